@@ -0,0 +1,17 @@
+use std::env;
+use std::process::Command;
+
+fn main() {
+    // Modules can only share Rust types with the server if they are built by the same compiler
+    // (see `abi::FACTORY_FINGERPRINT`).
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+
+    println!("cargo:rustc-env=MAMMOTH_RUSTC_VERSION={}", version.trim());
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
@@ -1,40 +1,441 @@
-use std::env;
+#![recursion_limit = "256"]
+
 use std::panic;
 
 use quote::quote;
 use syn;
-use syn::export::TokenStream;
+use syn::export::{TokenStream, TokenStream2};
+
+const CAPABILITIES: &[(&str, &str)] = &[
+    ("load", "Load"),
+    ("validation", "Validation"),
+    ("start", "Start"),
+    ("reload", "Reload"),
+    ("shutdown", "Shutdown"),
+    ("logging", "Logging")
+];
+
+/// Parses the capabilities listed in `capabilities(...)` into the variants of `Capability`.
+fn capabilities(list: &syn::MetaList) -> Vec<syn::Ident> {
+    list.nested.iter().map(|nested| {
+        let word = match nested {
+            syn::NestedMeta::Meta(syn::Meta::Word(word)) => word,
+            _ => panic!("Capabilities must be listed as `capabilities(validation, shutdown, ...)`.")
+        };
+        match CAPABILITIES.iter().find(|(name, _)| word == name) {
+            Some((_, variant)) => syn::Ident::new(variant, word.span()),
+            None => panic!("Unknown capability '{}'.", word)
+        }
+    }).collect()
+}
+
+const KINDS: &[(&str, &str)] = &[
+    ("handler", "Handler"),
+    ("middleware", "Middleware"),
+    ("service", "Service")
+];
+
+/// Parses the kind given in `kind(...)` into the name of the variant of `ModuleKind`, which is
+/// also the name of the corresponding sub-trait of `MammothInterface`.
+fn kind(list: &syn::MetaList) -> syn::Ident {
+    let word = match list.nested.iter().next() {
+        Some(syn::NestedMeta::Meta(syn::Meta::Word(word))) if list.nested.len() == 1 => word,
+        _ => panic!("The kind must be given as `kind(handler)`, `kind(middleware)` or `kind(service)`.")
+    };
+    match KINDS.iter().find(|(name, _)| word == name) {
+        Some((_, variant)) => syn::Ident::new(variant, word.span()),
+        None => panic!("Unknown kind '{}'.", word)
+    }
+}
+
+/// Parses the modules listed in `requires(...)` as names or string literals.
+fn requirements(list: &syn::MetaList) -> Vec<String> {
+    list.nested.iter().map(|nested| {
+        match nested {
+            syn::NestedMeta::Meta(syn::Meta::Word(word)) => word.to_string(),
+            syn::NestedMeta::Literal(syn::Lit::Str(name)) => name.value(),
+            _ => panic!("Required modules must be listed as `requires(mod_name, \"mod-name\", ...)`.")
+        }
+    }).collect()
+}
+
+/// Parses the string literal given in `key = "..."`.
+fn string_value(pair: &syn::MetaNameValue, example: &str) -> String {
+    match pair.lit {
+        syn::Lit::Str(ref value) => value.value(),
+        _ => panic!("The {} must be given as `{} = \"{}\"`.", pair.ident.to_string().replace('_', " "), pair.ident, example)
+    }
+}
+
+/// Argument of `mammoth_module`: `config = ConfigType`, whose value is a type rather than a
+/// literal, or any other argument.
+enum Argument {
+    Config(syn::Type),
+    Meta(syn::NestedMeta)
+}
+
+impl syn::parse::Parse for Argument {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        let fork = input.fork();
+        match fork.parse::<syn::Ident>() {
+            Ok(ref ident) if ident == "config" && fork.peek(syn::Token![=]) => {
+                input.parse::<syn::Ident>()?;
+                input.parse::<syn::Token![=]>()?;
+                input.parse().map(Argument::Config)
+            },
+            _ => input.parse().map(Argument::Meta)
+        }
+    }
+}
+
+/// Arguments of `mammoth_module`, separated by commas.
+struct Arguments(syn::punctuated::Punctuated<Argument, syn::Token![,]>);
+
+impl syn::parse::Parse for Arguments {
+    fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+        syn::punctuated::Punctuated::parse_terminated(input).map(Arguments)
+    }
+}
+
+/// Reports `error` at compile time, keeping the structure so that its uses do not fail as well.
+fn compile_error(error: syn::Error, ast: &syn::ItemStruct) -> TokenStream {
+    let error = error.to_compile_error();
+    let result = quote!{ #error #ast };
+    result.into()
+}
 
 #[proc_macro_attribute]
 pub fn mammoth_module(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let constructor: syn::Ident = syn::parse(attr).unwrap();
+    let args = syn::parse_macro_input!(attr as Arguments);
     let ast: syn::ItemStruct = syn::parse(item).unwrap();
-    let name = &ast.ident;
 
-    if env::var("MAMMOTH_MODULE").is_ok() {
-        panic!("Only one MammothInterface per library is allowed.");
-    } else {
-        env::set_var("MAMMOTH_MODULE", "impl");
+    let mut args = args.0.iter();
+    let constructor = match args.next() {
+        Some(Argument::Meta(syn::NestedMeta::Meta(syn::Meta::Word(constructor)))) => constructor,
+        _ => return compile_error(syn::Error::new_spanned(&ast.ident, "The first argument must be the constructor of the module."), &ast)
+    };
+    let mut capability_list = Vec::new();
+    let mut requirement_list = Vec::new();
+    let mut module_name = quote!{ env!("CARGO_CRATE_NAME") };
+    let mut description = quote!{ env!("CARGO_PKG_DESCRIPTION") };
+    let mut host_version = quote!{ None };
+    let mut schema = quote!{ None };
+    let mut shutdown = quote!{ None };
+    let mut config_type = quote!{ _ };
+    let mut kind_variant = None;
+    let mut instance = None;
+    for arg in args {
+        match arg {
+            Argument::Meta(syn::NestedMeta::Meta(syn::Meta::NameValue(ref pair))) if pair.ident == "name" => {
+                let name = string_value(pair, "mod_name");
+                if name.trim().is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                    panic!("Invalid module name '{}': module names can only contain ASCII letters, digits, '_' and '-'.", name);
+                }
+                module_name = quote!{ #name };
+            },
+            Argument::Meta(syn::NestedMeta::Meta(syn::Meta::NameValue(ref pair))) if pair.ident == "description" => {
+                let value = string_value(pair, "...");
+                description = quote!{ #value };
+            },
+            Argument::Meta(syn::NestedMeta::Meta(syn::Meta::NameValue(ref pair))) if pair.ident == "host_version" || pair.ident == "requires_host" => {
+                let requirement = string_value(pair, "^0.1");
+                if let Err(err) = semver::VersionReq::parse(&requirement) {
+                    panic!("Invalid host version requisite '{}': {}.", requirement, err);
+                }
+                host_version = quote!{ Some(#requirement) };
+            },
+            Argument::Meta(syn::NestedMeta::Meta(syn::Meta::List(ref list))) if list.ident == "capabilities" => {
+                capability_list.extend(capabilities(list));
+            },
+            Argument::Meta(syn::NestedMeta::Meta(syn::Meta::List(ref list))) if list.ident == "requires" => {
+                requirement_list.extend(requirements(list));
+            },
+            Argument::Meta(syn::NestedMeta::Meta(syn::Meta::List(ref list))) if list.ident == "kind" => {
+                kind_variant = Some(kind(list));
+            },
+            Argument::Meta(syn::NestedMeta::Meta(syn::Meta::NameValue(ref pair))) if pair.ident == "instance" => {
+                let value = string_value(pair, "MyModule<u32>");
+                match syn::parse_str::<syn::Type>(&value) {
+                    Ok(ty) => instance = Some(ty),
+                    Err(err) => panic!("Invalid instance type '{}': {}.", value, err)
+                }
+            },
+            Argument::Meta(syn::NestedMeta::Meta(syn::Meta::List(ref list))) if list.ident == "schema" => {
+                let function = match list.nested.iter().next() {
+                    Some(syn::NestedMeta::Meta(syn::Meta::Word(function))) if list.nested.len() == 1 => function,
+                    _ => panic!("The schema must be given as `schema(schema_fn)`.")
+                };
+                schema = quote!{ Some(#function()) };
+            },
+            Argument::Config(ref config) => {
+                config_type = quote!{ #config };
+            },
+            // Same as `config = ConfigType`.
+            Argument::Meta(syn::NestedMeta::Meta(syn::Meta::List(ref list))) if list.ident == "config" => {
+                let config = match list.nested.iter().next() {
+                    Some(syn::NestedMeta::Meta(syn::Meta::Word(config))) if list.nested.len() == 1 => config,
+                    _ => return compile_error(syn::Error::new_spanned(list, "The configuration type must be given as `config = ConfigType`."), &ast)
+                };
+                config_type = quote!{ #config };
+            },
+            Argument::Meta(syn::NestedMeta::Meta(syn::Meta::List(ref list))) if list.ident == "shutdown" => {
+                let function = match list.nested.iter().next() {
+                    Some(syn::NestedMeta::Meta(syn::Meta::Word(function))) if list.nested.len() == 1 => function,
+                    _ => panic!("The shutdown hook must be given as `shutdown(shutdown_fn)`.")
+                };
+                shutdown = quote!{ Some(#function) };
+            },
+            Argument::Meta(ref meta) => {
+                let error = syn::Error::new_spanned(meta, "Unexpected argument; expected `capabilities(...)`, `requires(...)`, `kind(...)`, `schema(...)`, `shutdown(...)`, `config = ConfigType`, `name = \"...\"`, `description = \"...\"`, `instance = \"...\"` or `host_version = \"...\"` (a.k.a. `requires_host`).");
+                return compile_error(error, &ast);
+            }
+        }
     }
 
+    // Generic structures are monomorphized through `instance`, which must name the structure.
+    let ident = &ast.ident;
+    let name = match instance {
+        Some(syn::Type::Path(ref ty)) if ty.qself.is_none() && ty.path.segments.last().map(|s| s.value().ident == *ident).unwrap_or(false) => quote!{ #ty },
+        Some(ref ty) => {
+            let error = syn::Error::new_spanned(ty, format!("The instance must be a concrete type of `{}`, e.g. `instance = \"{}<u32>\"`.", ident, ident));
+            return compile_error(error, &ast);
+        },
+        None if ast.generics.params.is_empty() => quote!{ #ident },
+        None => {
+            let error = syn::Error::new_spanned(&ast.generics, format!("Generic modules must be monomorphized through `instance`, e.g. `#[mammoth_module(constructor, instance = \"{}<u32>\")]`.", ident));
+            return compile_error(error, &ast);
+        }
+    };
+    let module_kind = match kind_variant {
+        Some(variant) => quote!{
+            fn implements<T: mammoth_setup::#variant>() {}
+            implements::<#name>();
+            let metadata = metadata.with_kind(mammoth_setup::metadata::ModuleKind::#variant);
+        },
+        None => quote!{}
+    };
+
+    let metadata = quote!{
+        extern "C" fn metadata(sink: mammoth_setup::abi::RawSink) {
+            mammoth_setup::abi::metadata(sink, || {
+                let metadata = mammoth_setup::metadata::ModuleMetadata::from_package(
+                    #module_name,
+                    #description,
+                    env!("CARGO_PKG_AUTHORS"),
+                    env!("CARGO_PKG_LICENSE"),
+                    &[#(mammoth_setup::metadata::Capability::#capability_list),*],
+                    &[#(#requirement_list),*]
+                );
+                #module_kind
+                metadata
+            })
+        }
+    };
+
+    let schema = quote!{
+        extern "C" fn config_schema(sink: mammoth_setup::abi::RawSink) {
+            mammoth_setup::abi::config_schema(sink, || #schema);
+        }
+    };
+
+    let host_version = quote!{
+        extern "C" fn host_version_req(sink: mammoth_setup::abi::RawSink) {
+            mammoth_setup::abi::host_version(sink, #host_version);
+        }
+    };
+
+    let construct = quote!{
+        extern "C" fn construct(config: mammoth_setup::abi::RawStr, error: mammoth_setup::abi::RawSink) -> *mut std::ffi::c_void {
+            mammoth_setup::abi::construct::<_, #config_type, _>(config, error, #constructor)
+        }
+    };
+
+    let vtable = quote!{
+        static VTABLE: mammoth_setup::abi::ModuleVTable = mammoth_setup::abi::ModuleVTable {
+            abi_version: mammoth_setup::abi::ABI_VERSION,
+            version: mammoth_setup::abi::version,
+            host_version_req,
+            metadata,
+            config_schema,
+            factory_fingerprint: mammoth_setup::abi::factory_fingerprint,
+            construct,
+            destroy: mammoth_setup::abi::destroy::<#name>,
+            register_logger: mammoth_setup::abi::register_logger::<#name>,
+            on_bus: mammoth_setup::abi::on_bus::<#name>,
+            on_sandbox: mammoth_setup::abi::on_sandbox::<#name>,
+            on_load: mammoth_setup::abi::on_load::<#name>,
+            on_validation: mammoth_setup::abi::on_validation::<#name>,
+            on_factory: mammoth_setup::abi::on_factory::<#name>,
+            on_start: mammoth_setup::abi::on_start::<#name>,
+            on_health: mammoth_setup::abi::on_health::<#name>,
+            on_metrics: mammoth_setup::abi::on_metrics::<#name>,
+            on_config_reload: mammoth_setup::abi::on_config_reload::<#name>,
+            on_request: mammoth_setup::abi::on_request::<#name>,
+            on_response: mammoth_setup::abi::on_response::<#name>,
+            on_shutdown: mammoth_setup::abi::on_shutdown::<#name>
+        };
+    };
+
+    // Exported macros live at the root of the crate wherever they are defined, hence a second
+    // module in the same library fails to compile with a "defined multiple times" error.
+    let guard = quote!{
+        #[doc(hidden)]
+        #[macro_export]
+        macro_rules! __mammoth_only_one_module_per_library { () => {} }
+    };
+
     let result = quote!{
-        trait __mammoth_interface: mammoth_setup::MammothInterface {}
+        #guard
 
         #[no_mangle]
-        pub extern fn __version() -> semver::Version {
-            mammoth_setup::version::version()
+        pub extern "C" fn __mammoth_module() -> *const mammoth_setup::abi::ModuleVTable {
+            #metadata
+            #schema
+            #host_version
+            #construct
+            #vtable
+
+            &VTABLE
         }
 
         #[no_mangle]
-        pub extern fn __construct(cfg: Option<toml::Value>) -> *mut mammoth_setup::MammothInterface {
-            let interface = Box::new(#constructor(cfg));
-            Box::into_raw(interface)
+        pub extern "C" fn __mammoth_shutdown() {
+            mammoth_setup::abi::shutdown(#shutdown);
         }
 
         #ast
+    };
+
+    result.into()
+}
+
+/// Finds the field storing the logger, i.e. the field marked as `#[logger]` or, if none, the field
+/// named `logger`.
+fn logger_field(ast: &syn::DeriveInput) -> &syn::Ident {
+    let fields = match ast.data {
+        syn::Data::Struct(syn::DataStruct { fields: syn::Fields::Named(ref fields), .. }) => &fields.named,
+        _ => panic!("`Log` can only be derived for structures with named fields.")
+    };
+    let marked: Vec<_> = fields.iter().filter(|field| field.attrs.iter().any(|attr| attr.path.is_ident("logger"))).collect();
+    let field = match marked.len() {
+        0 => fields.iter().find(|field| field.ident.iter().any(|ident| ident == "logger")),
+        1 => Some(marked[0]),
+        _ => panic!("Only one field can be marked as `#[logger]`.")
+    };
+    match field {
+        Some(field) => field.ident.as_ref().unwrap(),
+        None => panic!("`Log` requires a field of type `Option<AsyncLoggerReference>` named `logger` or marked as `#[logger]`.")
+    }
+}
+
+#[proc_macro_derive(Log, attributes(logger))]
+pub fn derive_log(item: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(item).unwrap();
+    let name = &ast.ident;
+    let field = logger_field(&ast);
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let result = quote!{
+        impl #impl_generics mammoth_setup::diagnostics::Log for #name #ty_generics #where_clause {
+            fn register_logger(&mut self, logger: mammoth_setup::diagnostics::AsyncLoggerReference) {
+                self.#field = Some(logger);
+            }
+
+            fn retrieve_logger(&self) -> Option<mammoth_setup::diagnostics::AsyncLoggerReference> {
+                self.#field.clone()
+            }
+        }
+    };
+
+    result.into()
+}
+
+const PATH_CHECKS: &[(&str, &str)] = &[
+    ("existing_dir", "ExistingDirectory"),
+    ("existing_file", "ExistingFile"),
+    ("file_path", "FilePath"),
+    ("writable_dir", "WritableDirectory")
+];
+
+const SEVERITIES: &[(&str, &str)] = &[
+    ("debug", "Debug"),
+    ("information", "Information"),
+    ("warning", "Warning"),
+    ("error", "Error"),
+    ("critical", "Critical")
+];
+
+/// Parses the checks listed in the `#[validate(...)]` attributes of `field` into calls to the
+/// corresponding validators.
+fn field_checks(field: &syn::Field) -> Vec<TokenStream2> {
+    let ident = field.ident.as_ref().unwrap();
+    let key = ident.to_string();
+    let mut checks = Vec::new();
 
-        impl __mammoth_interface for #name {}
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("validate")) {
+        let list = match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => list,
+            _ => panic!("Validations must be listed as `#[validate(existing_file, severity = \"warning\", ...)]`.")
+        };
+        let mut severity = syn::Ident::new("Error", list.ident.span());
+        let mut validators = Vec::new();
+        for nested in list.nested.iter() {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::Word(word)) => match PATH_CHECKS.iter().find(|(name, _)| word == name) {
+                    Some((_, variant)) => {
+                        let variant = syn::Ident::new(variant, word.span());
+                        validators.push(quote!{
+                            mammoth_setup::diagnostics::PathValidator(severity, mammoth_setup::diagnostics::PathValidatorKind::#variant)
+                        });
+                    },
+                    None => panic!("Unknown validation '{}'.", word)
+                },
+                syn::NestedMeta::Meta(syn::Meta::NameValue(ref pair)) if pair.ident == "severity" => {
+                    let value = string_value(pair, "warning");
+                    severity = match SEVERITIES.iter().find(|(name, _)| value.eq_ignore_ascii_case(name)) {
+                        Some((_, variant)) => syn::Ident::new(variant, pair.ident.span()),
+                        None => panic!("Unknown severity '{}'.", value)
+                    };
+                },
+                syn::NestedMeta::Meta(syn::Meta::NameValue(ref pair)) if pair.ident == "regex" => {
+                    let pattern = string_value(pair, "[a-z]+");
+                    validators.push(quote!{
+                        mammoth_setup::diagnostics::PatternValidator(severity, #key, #pattern)
+                    });
+                },
+                _ => panic!("Unexpected validation; expected `existing_dir`, `existing_file`, `file_path`, `writable_dir`, `regex = \"...\"` or `severity = \"...\"`.")
+            }
+        }
+        checks.extend(validators.into_iter().map(|validator| quote!{
+            {
+                let severity = mammoth_setup::error::severity::Severity::#severity;
+                mammoth_setup::diagnostics::Validator::validate(&#validator, logger, &item.#ident)?;
+            }
+        }));
+    }
+
+    checks
+}
+
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn derive_validate(item: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(item).unwrap();
+    let name = &ast.ident;
+    let fields = match ast.data {
+        syn::Data::Struct(syn::DataStruct { fields: syn::Fields::Named(ref fields), .. }) => &fields.named,
+        _ => panic!("`Validate` can only be derived for structures with named fields.")
+    };
+    let checks: Vec<_> = fields.iter().flat_map(field_checks).collect();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let result = quote!{
+        impl #impl_generics mammoth_setup::diagnostics::Validator<#name #ty_generics> for () #where_clause {
+            fn validate(&self, logger: &mut dyn mammoth_setup::diagnostics::Logger, item: &#name #ty_generics) -> Result<(), mammoth_setup::error::Error> {
+                #(#checks)*
+                Ok(())
+            }
+        }
     };
 
     result.into()
-}
\ No newline at end of file
+}
@@ -0,0 +1,58 @@
+use std::sync::{Arc, Mutex};
+
+use mammoth_macro::mammoth_module;
+use mammoth_setup::abi::ForeignModule;
+use mammoth_setup::bus::MessageBus;
+use mammoth_setup::prelude::*;
+
+fn constructor(_: Option<Value>) -> Module {
+    Module { bus: Mutex::new(None) }
+}
+
+#[mammoth_module(constructor)]
+pub struct Module {
+    bus: Mutex<Option<BusHandle>>
+}
+
+impl MammothInterface for Module {
+    fn on_bus(&self, bus: BusHandle) {
+        let replies = bus.clone();
+        bus.subscribe("ping", move |message| {
+            let value: i64 = message.payload().unwrap();
+            replies.publish("pong", &(value + 1)).unwrap();
+        });
+        *self.bus.lock().unwrap() = Some(bus);
+    }
+
+    fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Log for Module {
+    fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        None
+    }
+}
+
+#[test]
+fn test_bus_across_abi() {
+    let vtable = unsafe { &*__mammoth_module() };
+    let bus = MessageBus::new();
+    let handle = bus.handle();
+    let received: Arc<Mutex<Vec<i64>>> = Arc::new(Mutex::new(Vec::new()));
+
+    let sink = received.clone();
+    handle.subscribe("pong", move |message| sink.lock().unwrap().push(message.payload().unwrap()));
+
+    let module = unsafe { ForeignModule::new(vtable, None) }.unwrap();
+    module.on_bus(bus.handle());
+    assert_eq!(bus.subscribers("ping"), 1);
+    assert_eq!(handle.publish("ping", &41).unwrap(), 1);
+    assert_eq!(*received.lock().unwrap(), vec![42]);
+
+    drop(module);
+    assert_eq!(bus.subscribers("ping"), 0);
+}
@@ -0,0 +1,45 @@
+use mammoth_macro::mammoth_module;
+use mammoth_setup::abi;
+use mammoth_setup::config::schema::{ConfigSchema, FieldKind, FieldSchema, SchemaViolation};
+use mammoth_setup::prelude::*;
+
+fn constructor(_: Option<Value>) -> Module {
+    Module
+}
+
+fn schema() -> ConfigSchema {
+    ConfigSchema::new()
+        .field("realm", FieldSchema::required(FieldKind::String))
+        .field("timeout", FieldSchema::new(FieldKind::Integer))
+}
+
+#[mammoth_module(constructor, schema(schema))]
+pub struct Module;
+
+impl MammothInterface for Module {
+    fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Log for Module {
+    fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        None
+    }
+}
+
+#[test]
+fn test_config_schema() {
+    let vtable = unsafe { &*__mammoth_module() };
+    let exported = abi::module_config_schema(vtable).unwrap().unwrap();
+    assert_eq!(exported, schema());
+
+    let config: Value = toml::from_str("timeout = \"30s\"\nport = 8080").unwrap();
+    assert_eq!(exported.check(Some(&config)), vec![
+        SchemaViolation::MissingKey("config.realm".to_owned()),
+        SchemaViolation::WrongType("config.timeout".to_owned(), FieldKind::Integer),
+        SchemaViolation::UnknownKey("config.port".to_owned())
+    ]);
+}
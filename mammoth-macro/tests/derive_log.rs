@@ -0,0 +1,49 @@
+use std::sync::{Arc, RwLock};
+
+use mammoth_macro::{mammoth_module, Log};
+use mammoth_setup::error::event::Event;
+use mammoth_setup::prelude::*;
+
+fn constructor(_: Option<Value>) -> Module {
+    Module { name: "mod_logged".to_owned(), sink: None }
+}
+
+#[mammoth_module(constructor)]
+#[derive(Log)]
+pub struct Module {
+    pub name: String,
+    #[logger]
+    sink: Option<AsyncLoggerReference>
+}
+
+impl MammothInterface for Module {
+    fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[derive(Default, Log)]
+pub struct Component {
+    logger: Option<AsyncLoggerReference>
+}
+
+#[test]
+fn test_derive_log() {
+    let events = Arc::new(RwLock::new(Vec::<Event>::new()));
+    let mut module = constructor(None);
+    let mut component = Component::default();
+
+    assert!(module.retrieve_logger().is_none());
+    assert!(component.retrieve_logger().is_none());
+    module.log(Severity::Information, "Lost.");
+
+    module.register_logger(events.clone());
+    component.register_logger(events.clone());
+    module.log(Severity::Information, "Module.");
+    component.log(Severity::Warning, "Component.");
+
+    let events = events.read().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].description(), "Module.");
+    assert_eq!(events[1].severity(), Severity::Warning);
+}
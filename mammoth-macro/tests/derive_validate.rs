@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use mammoth_macro::Validate;
+use mammoth_setup::diagnostics::Validator;
+use mammoth_setup::error::event::Event;
+use mammoth_setup::prelude::*;
+
+#[derive(Validate)]
+pub struct Section {
+    #[validate(existing_file)]
+    pub certificate: PathBuf,
+    #[validate(existing_dir, severity = "warning")]
+    pub cache: PathBuf,
+    #[validate(regex = "[a-z][a-z0-9_]*")]
+    pub realm: String,
+    pub comment: String
+}
+
+fn section(certificate: &str, cache: &str, realm: &str) -> Section {
+    Section {
+        certificate: PathBuf::from(certificate),
+        cache: PathBuf::from(cache),
+        realm: realm.to_owned(),
+        comment: "Not validated.".to_owned()
+    }
+}
+
+#[test]
+fn test_derive_validate() {
+    let mut events: Vec<Event> = Vec::new();
+
+    assert!(().validate(&mut events, &section("Cargo.toml", "src", "mammoth_1")).is_ok());
+    assert!(events.is_empty());
+
+    assert!(().validate(&mut events, &section("Cargo.toml", "i_do_not_exist", "mammoth")).is_ok());
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].severity(), Severity::Warning);
+
+    match ().validate(&mut events, &section("i_do_not_exist.txt", "src", "mammoth")) {
+        Err(Error::FileNotFound(path)) => assert_eq!(path, PathBuf::from("i_do_not_exist.txt")),
+        _ => panic!("Should be 'FileNotFound' error.")
+    }
+    match ().validate(&mut events, &section("Cargo.toml", "src", "Mammoth")) {
+        Err(Error::InvalidValue(key)) => assert_eq!(key, "realm"),
+        _ => panic!("Should be 'InvalidValue' error.")
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt::Display;
+
+use mammoth_macro::mammoth_module;
+use mammoth_setup::abi::ForeignModule;
+use mammoth_setup::prelude::*;
+
+fn constructor(_: Option<Value>) -> Module<u32> {
+    Module { value: 73 }
+}
+
+#[mammoth_module(constructor, instance = "Module<u32>", kind(service))]
+pub struct Module<T> where T: Display {
+    pub value: T
+}
+
+impl<T> MammothInterface for Module<T> where T: Display + Send + Sync + 'static {
+    fn on_validation(&self, logger: &mut dyn Logger) -> Result<(), Error> {
+        logger.log(Severity::Information, &format!("Value: {}.", self.value));
+        Ok(())
+    }
+}
+
+impl<T> Service for Module<T> where T: Display + Send + Sync + 'static {}
+
+impl<T> Log for Module<T> where T: Display {
+    fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        None
+    }
+}
+
+#[test]
+fn test_generic_module() {
+    let vtable = unsafe { &*__mammoth_module() };
+    let module = unsafe { ForeignModule::new(vtable, None) }.unwrap();
+    let mut events: Vec<mammoth_setup::error::event::Event> = Vec::new();
+
+    assert!(module.on_validation(&mut events).is_ok());
+    assert_eq!(events[0].description(), "Value: 73.");
+}
@@ -0,0 +1,48 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mammoth_macro::mammoth_module;
+use mammoth_setup::abi::ForeignModule;
+use mammoth_setup::prelude::*;
+
+fn constructor(_: Option<Value>) -> Module {
+    Module { checks: AtomicUsize::new(0) }
+}
+
+#[mammoth_module(constructor)]
+pub struct Module {
+    checks: AtomicUsize
+}
+
+impl MammothInterface for Module {
+    fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_health(&self) -> HealthStatus {
+        match self.checks.fetch_add(1, Ordering::SeqCst) {
+            0 => HealthStatus::Healthy,
+            1 => HealthStatus::Degraded("cache disabled".to_owned()),
+            2 => HealthStatus::Unhealthy("database unreachable".to_owned()),
+            _ => panic!("health check failed")
+        }
+    }
+}
+
+impl Log for Module {
+    fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        None
+    }
+}
+
+#[test]
+fn test_health_across_abi() {
+    let vtable = unsafe { &*__mammoth_module() };
+    let module = unsafe { ForeignModule::new(vtable, None) }.unwrap();
+
+    assert_eq!(module.on_health(), HealthStatus::Healthy);
+    assert_eq!(module.on_health(), HealthStatus::Degraded("cache disabled".to_owned()));
+    assert_eq!(module.on_health(), HealthStatus::Unhealthy("database unreachable".to_owned()));
+    assert_eq!(module.on_health(), HealthStatus::Unhealthy("module panicked during health check".to_owned()));
+}
@@ -0,0 +1,39 @@
+use mammoth_macro::mammoth_module;
+use mammoth_setup::prelude::*;
+use semver::VersionReq;
+
+fn constructor(_: Option<Value>) -> Module {
+    Module
+}
+
+#[mammoth_module(constructor, host_version = ">=99.0.0")]
+pub struct Module;
+
+impl MammothInterface for Module {
+    fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Log for Module {
+    fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        None
+    }
+}
+
+#[test]
+fn test_host_version() {
+    let vtable = unsafe { &*__mammoth_module() };
+    let requirement = VersionReq::parse(">=99.0.0").unwrap();
+
+    assert_eq!(mammoth_setup::abi::host_version_req(vtable).unwrap(), requirement);
+    match mammoth_setup::abi::check_host_version(vtable) {
+        Err(Error::InvalidModuleVersion(ref version, ref req)) => {
+            assert_eq!(version, &mammoth_setup::version::version());
+            assert_eq!(req, &requirement);
+        },
+        _ => panic!("Should be 'InvalidModuleVersion' error.")
+    }
+}
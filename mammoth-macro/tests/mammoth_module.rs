@@ -10,7 +10,7 @@ fn constructor(cfg: Option<toml::Value>) -> Module {
     Module {x, y}
 }
 
-#[mammoth_module(constructor)]
+#[mammoth_module(constructor, kind(handler))]
 pub struct Module {
     pub x: i64,
     pub y: i64
@@ -22,6 +22,8 @@ impl MammothInterface for Module {
     }
 }
 
+impl Handler for Module {}
+
 impl Log for Module {
     fn register_logger(&mut self, _: AsyncLoggerReference) {
         unimplemented!()
@@ -38,13 +40,27 @@ fn test_constructor() {
     x = 73
     y = 121
     "#;
-    let cfg = Some(toml::from_str(t).unwrap());
-    let _ = __construct(cfg);
+    let cfg: toml::Value = toml::from_str(t).unwrap();
+    let vtable = unsafe { &*__mammoth_module() };
+    let _ = unsafe { mammoth_setup::abi::ForeignModule::new(vtable, Some(&cfg)) }.unwrap();
 }
 
 #[test]
 fn test_version() {
-    let v = __version();
+    let vtable = unsafe { &*__mammoth_module() };
+    let v = mammoth_setup::abi::module_version(vtable).unwrap();
 
+    assert_eq!(vtable.abi_version, mammoth_setup::abi::ABI_VERSION);
     assert!(mammoth_setup::version::compatible(&v));
-}
\ No newline at end of file
+    assert!(mammoth_setup::abi::host_version_req(vtable).unwrap().matches(&v));
+    mammoth_setup::abi::check_host_version(vtable).unwrap();
+    assert!(mammoth_setup::abi::module_config_schema(vtable).unwrap().is_none());
+}
+
+#[test]
+fn test_kind() {
+    let vtable = unsafe { &*__mammoth_module() };
+    let metadata = mammoth_setup::abi::module_metadata(vtable).unwrap();
+
+    assert_eq!(metadata.kind(), Some(mammoth_setup::metadata::ModuleKind::Handler));
+}
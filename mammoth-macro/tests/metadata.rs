@@ -0,0 +1,35 @@
+use mammoth_macro::mammoth_module;
+use mammoth_setup::prelude::*;
+use semver::VersionReq;
+
+fn constructor(_: Option<Value>) -> Module {
+    Module
+}
+
+#[mammoth_module(constructor, name = "mod_auth", description = "Authentication module.", requires_host = ">=0.0.1")]
+pub struct Module;
+
+impl MammothInterface for Module {
+    fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Log for Module {
+    fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        None
+    }
+}
+
+#[test]
+fn test_metadata_arguments() {
+    let vtable = unsafe { &*__mammoth_module() };
+    let metadata = mammoth_setup::abi::module_metadata(vtable).unwrap();
+
+    assert_eq!(metadata.name(), "mod_auth");
+    assert_eq!(metadata.description(), Some("Authentication module."));
+    assert_eq!(mammoth_setup::abi::host_version_req(vtable).unwrap(), VersionReq::parse(">=0.0.1").unwrap());
+    assert!(mammoth_setup::abi::check_host_version(vtable).is_ok());
+}
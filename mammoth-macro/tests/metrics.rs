@@ -0,0 +1,55 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mammoth_macro::mammoth_module;
+use mammoth_setup::abi::ForeignModule;
+use mammoth_setup::metrics::MetricKind;
+use mammoth_setup::prelude::*;
+
+fn constructor(_: Option<Value>) -> Module {
+    Module { scrapes: AtomicUsize::new(0) }
+}
+
+#[mammoth_module(constructor)]
+pub struct Module {
+    scrapes: AtomicUsize
+}
+
+impl MammothInterface for Module {
+    fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_metrics(&self, collector: &mut MetricsCollector) {
+        let scrapes = self.scrapes.fetch_add(1, Ordering::SeqCst) as u64;
+        if scrapes > 0 {
+            panic!("metrics unavailable");
+        }
+        collector.counter("scrapes_total", scrapes + 1);
+        collector.gauge_with("pool_usage", &[("pool", "db")], 0.75);
+    }
+}
+
+impl Log for Module {
+    fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        None
+    }
+}
+
+#[test]
+fn test_metrics_across_abi() {
+    let vtable = unsafe { &*__mammoth_module() };
+    let module = unsafe { ForeignModule::new(vtable, None) }.unwrap();
+
+    let mut collector = MetricsCollector::new();
+    module.on_metrics(&mut collector);
+    assert_eq!(collector.get("scrapes_total", &[]).unwrap().value(), 1.0);
+    let usage = collector.get("pool_usage", &[("pool", "db")]).unwrap();
+    assert_eq!(usage.kind(), MetricKind::Gauge);
+    assert_eq!(usage.value(), 0.75);
+
+    let mut collector = MetricsCollector::new();
+    module.on_metrics(&mut collector);
+    assert!(collector.is_empty());
+}
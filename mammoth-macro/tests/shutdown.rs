@@ -0,0 +1,45 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mammoth_macro::mammoth_module;
+use mammoth_setup::abi;
+use mammoth_setup::prelude::*;
+
+static SHUTDOWNS: AtomicUsize = AtomicUsize::new(0);
+
+fn constructor(_: Option<Value>) -> Module {
+    Module
+}
+
+fn on_unload() {
+    if SHUTDOWNS.fetch_add(1, Ordering::SeqCst) > 0 {
+        panic!("already shut down");
+    }
+}
+
+#[mammoth_module(constructor, shutdown(on_unload))]
+pub struct Module;
+
+impl MammothInterface for Module {
+    fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Log for Module {
+    fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        None
+    }
+}
+
+#[test]
+fn test_shutdown_export() {
+    __mammoth_shutdown();
+    assert_eq!(SHUTDOWNS.load(Ordering::SeqCst), 1);
+
+    __mammoth_shutdown();
+    assert_eq!(SHUTDOWNS.load(Ordering::SeqCst), 2);
+
+    abi::shutdown(None);
+}
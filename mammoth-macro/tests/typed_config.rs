@@ -0,0 +1,57 @@
+use mammoth_macro::mammoth_module;
+use mammoth_setup::abi::ForeignModule;
+use mammoth_setup::prelude::*;
+use serde_derive::Deserialize;
+
+#[derive(Deserialize)]
+pub struct Config {
+    x: i64,
+    #[serde(default)]
+    y: i64
+}
+
+fn constructor(cfg: Config) -> Module {
+    Module { x: cfg.x, y: cfg.y }
+}
+
+#[mammoth_module(constructor, config = Config)]
+pub struct Module {
+    pub x: i64,
+    pub y: i64
+}
+
+impl MammothInterface for Module {
+    fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Log for Module {
+    fn register_logger(&mut self, _: AsyncLoggerReference) {
+        unimplemented!()
+    }
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn test_typed_config() {
+    let vtable = unsafe { &*__mammoth_module() };
+    let cfg: toml::Value = toml::from_str("x = 73").unwrap();
+
+    assert!(unsafe { ForeignModule::new(vtable, Some(&cfg)) }.is_ok());
+}
+
+#[test]
+fn test_invalid_config() {
+    let vtable = unsafe { &*__mammoth_module() };
+    let cfg: toml::Value = toml::from_str("x = \"73\"").unwrap();
+
+    match unsafe { ForeignModule::new(vtable, Some(&cfg)) } {
+        Err(Error::Module(message)) => assert!(message.starts_with("invalid configuration")),
+        _ => panic!("Should be 'Module' error generated in module construction.")
+    }
+    assert!(unsafe { ForeignModule::new(vtable, None) }.is_err());
+}
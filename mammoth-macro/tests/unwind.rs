@@ -0,0 +1,52 @@
+use mammoth_macro::mammoth_module;
+use mammoth_setup::abi::{self, ForeignModule};
+use mammoth_setup::config::schema::ConfigSchema;
+use mammoth_setup::prelude::*;
+
+fn constructor(_: Option<Value>) -> Module {
+    Module
+}
+
+fn schema() -> ConfigSchema {
+    panic!("schema panicked")
+}
+
+#[mammoth_module(constructor, schema(schema))]
+pub struct Module;
+
+impl MammothInterface for Module {
+    fn on_validation(&self, logger: &mut dyn Logger) -> Result<(), Error> {
+        logger.log(Severity::Information, "Validating.");
+        Err(Error::InvalidValue("module".to_owned()))
+    }
+}
+
+impl Log for Module {
+    fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        None
+    }
+}
+
+struct PanickingLogger;
+
+impl Logger for PanickingLogger {
+    fn log(&mut self, _: Severity, _: &str) {
+        panic!("logger panicked")
+    }
+}
+
+#[test]
+fn test_unwind_guards() {
+    let vtable = unsafe { &*__mammoth_module() };
+
+    assert!(abi::module_config_schema(vtable).unwrap().is_none());
+    assert!(abi::module_metadata(vtable).is_ok());
+
+    let module = unsafe { ForeignModule::new(vtable, None) }.unwrap();
+    match module.on_validation(&mut PanickingLogger) {
+        Err(Error::Module(message)) => assert!(message.contains("module")),
+        _ => panic!("Should be 'Module' error generated in module validation.")
+    }
+}
@@ -1,7 +1,11 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use mammoth_setup::prelude::*;
 use mammoth_setup::error::severity::Severity;
 
-#[mammoth_module(constructor_fn)]
+static SHUTDOWNS: AtomicUsize = AtomicUsize::new(0);
+
+#[mammoth_module(constructor_fn, capabilities(load, validation, start, reload, shutdown, logging), shutdown(shutdown_fn))]
 pub struct TestModule {
     test: Option<Value>,
     logger: Option<AsyncLoggerReference>
@@ -14,6 +18,16 @@ fn constructor_fn(test: Option<Value>) -> TestModule {
     }
 }
 
+fn shutdown_fn() {
+    SHUTDOWNS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Obtains the number of times the shutdown hook of the library has been called.
+#[no_mangle]
+pub extern "C" fn __mod_test_shutdowns() -> usize {
+    SHUTDOWNS.load(Ordering::SeqCst)
+}
+
 impl MammothInterface for TestModule {
     fn on_load(&self) {
         self.log(Severity::Debug, "Test module loaded.");
@@ -36,6 +50,14 @@ impl MammothInterface for TestModule {
         }
     }
 
+    fn on_start(&self) {
+        self.log(Severity::Debug, "Test module started.");
+    }
+
+    fn on_config_reload(&self, config: &Value) {
+        self.log(Severity::Debug, &format!("Test module reloaded with configuration: {}.", config));
+    }
+
     fn on_shutdown(&self) {
         self.log(Severity::Debug, "Test module unloaded.");
     }
@@ -0,0 +1,1389 @@
+//! C-compatible interface between the server and the module libraries.
+//!
+//! Rust types such as `toml::Value`, `semver::Version` and trait objects have no stable layout, so
+//! passing them across the boundary of a dynamic library is undefined behavior unless both sides
+//! are built with the same compiler and the same crate versions. A module library therefore only
+//! exports the `__mammoth_module` function (see `ENTRY_POINT`), returning a `ModuleVTable` made of
+//! `#[repr(C)]` types and `extern "C"` functions, and the `__mammoth_shutdown` function (see
+//! `SHUTDOWN_POINT`), called right before the library is unloaded:
+//! - data flows from the server to the module as borrowed UTF-8 strings (`RawStr`), e.g. the
+//!   configuration of the module, the host it is constructed for and its environment, serialized
+//!   as TOML;
+//! - data flows from the module to the server through callbacks writing into buffers owned by the
+//!   server (`RawSink`), so that memory is never released by an allocator other than the one that
+//!   allocated it;
+//! - requests and responses (see `http`) flow as `RawHttpMessage`, made of their heads serialized
+//!   as TOML and their bodies as borrowed bytes (`RawBytes`);
+//! - messages of the bus (see `bus`) flow in both directions through `RawBus`, implemented by the
+//!   server, and `RawSubscriber`, implemented by the module and released through its own callback;
+//! - panics are caught by the shims and never unwind across the boundary, in both directions;
+//!   fallible hooks report their outcome through a status code (see `STATUS_OK`, `STATUS_ERROR`
+//!   and `STATUS_PANIC`) and write the reason of a failure into a `RawSink`;
+//! - severities, health statuses and the outcomes of the HTTP hooks flow as the numeric codes
+//!   defined by this module (see `SEVERITY_DEBUG`, `HEALTH_HEALTHY` and `HOOK_PASSED`).
+//!
+//! The only exception is the `ServiceConfig` of `actix-web` (see `MammothInterface::on_factory`),
+//! which is passed to a module only if it is built by the same compiler and against the same
+//! version of `mammoth-setup`, and thus of `actix-web` (see `FACTORY_FINGERPRINT`).
+//!
+//! The module side of the shims is generated by the `mammoth_module` attribute through the generic
+//! functions of this module; on the server side, `ForeignModule` wraps an instance of the module
+//! into a `MammothInterface`.
+//!
+//! Any change to the layout of the types of this module must increase `ABI_VERSION`.
+
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::ptr;
+use std::slice;
+use std::str;
+use std::sync::{Arc, Mutex, RwLock};
+
+#[cfg(feature = "actix")]
+use actix_web::web::ServiceConfig;
+use libloading::{Library, Symbol};
+use semver::{Version, VersionReq};
+use serde::Deserializer;
+use serde::de::{DeserializeOwned, Visitor};
+use serde::forward_to_deserialize_any;
+use toml::Value;
+
+use crate::MammothInterface;
+use crate::bus::{BusHandle, Message, SubscriptionId};
+use crate::config::HostIdentifier;
+use crate::config::schema::ConfigSchema;
+use crate::diagnostics::{AsyncLoggerReference, Log, Logger};
+use crate::diagnostics::report::HealthStatus;
+use crate::error::Error;
+use crate::error::severity::Severity;
+#[cfg(feature = "http")]
+use crate::http::{Request, Response};
+use crate::metadata::ModuleMetadata;
+use crate::metrics::MetricsCollector;
+use crate::sandbox::SandboxedFs;
+
+thread_local! {
+    static HOST: RefCell<Option<HostIdentifier>> = const { RefCell::new(None) };
+    static ENVIRONMENT: RefCell<Option<Value>> = const { RefCell::new(None) };
+}
+
+/// Version of the layout of the `ModuleVTable`; modules exporting a different version are
+/// rejected.
+pub const ABI_VERSION: u32 = 11;
+/// Status returned by a fallible hook of a module that succeeds.
+pub const STATUS_OK: u32 = 0;
+/// Status returned by a fallible hook of a module that fails; the reason is written into the
+/// error sink given to the hook.
+pub const STATUS_ERROR: u32 = 1;
+/// Status returned by a fallible hook of a module that panics; the panic is caught by the shim
+/// and a description is written into the error sink given to the hook.
+pub const STATUS_PANIC: u32 = 2;
+/// Code of `Severity::Debug` passed to a `RawLogger`.
+pub const SEVERITY_DEBUG: u8 = 0;
+/// Code of `Severity::Information` passed to a `RawLogger`.
+pub const SEVERITY_INFORMATION: u8 = 1;
+/// Code of `Severity::Warning` passed to a `RawLogger`.
+pub const SEVERITY_WARNING: u8 = 2;
+/// Code of `Severity::Error` passed to a `RawLogger`.
+pub const SEVERITY_ERROR: u8 = 3;
+/// Code of `Severity::Critical` passed to a `RawLogger`; unknown codes are read as critical.
+pub const SEVERITY_CRITICAL: u8 = 4;
+/// Status returned by `on_health` for `HealthStatus::Healthy`.
+pub const HEALTH_HEALTHY: u32 = 0;
+/// Status returned by `on_health` for `HealthStatus::Degraded`.
+pub const HEALTH_DEGRADED: u32 = 1;
+/// Status returned by `on_health` for `HealthStatus::Unhealthy`, or if the module panics; unknown
+/// statuses are read as unhealthy.
+pub const HEALTH_UNHEALTHY: u32 = 2;
+/// Status returned by `on_request` and `on_response` of a module built without the `http`
+/// feature, which leaves the message untouched.
+pub const HOOK_SKIPPED: u32 = 0;
+/// Status returned by `on_request` if the request is passed on, and by `on_response` if the
+/// response is written back; the message written into the sink replaces the original one.
+pub const HOOK_PASSED: u32 = 1;
+/// Status returned by `on_request` if the module answers the request itself.
+pub const HOOK_ANSWERED: u32 = 2;
+/// Status returned by `on_request` and `on_response` if the message cannot be decoded or the
+/// module panics; the server answers with status 500.
+pub const HOOK_FAILED: u32 = 3;
+/// Name of the function exported by the module libraries.
+pub const ENTRY_POINT: &[u8] = b"__mammoth_module";
+/// Name of the function exported by the module libraries to be notified before they are unloaded.
+pub const SHUTDOWN_POINT: &[u8] = b"__mammoth_shutdown";
+/// Identifies the layout of the Rust types shared with the modules through `on_factory`; modules
+/// exporting a different fingerprint do not register their services.
+#[cfg(feature = "actix")]
+pub const FACTORY_FINGERPRINT: &str = concat!("mammoth-setup ", env!("CARGO_PKG_VERSION"), "; ", env!("MAMMOTH_RUSTC_VERSION"));
+
+/// Borrowed UTF-8 string passed across the boundary.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawStr {
+    ptr: *const u8,
+    len: usize
+}
+
+impl RawStr {
+    /// Creates a new `RawStr` borrowing `s`.
+    pub fn new(s: &str) -> RawStr {
+        RawStr {
+            ptr: s.as_ptr(),
+            len: s.len()
+        }
+    }
+
+    /// Obtains the borrowed string; invalid UTF-8 results in an empty string.
+    ///
+    /// # Safety
+    /// The string must be still alive, i.e. this function can be used only in the callee.
+    pub unsafe fn as_str<'a>(self) -> &'a str {
+        if self.ptr.is_null() {
+            ""
+        } else {
+            str::from_utf8(slice::from_raw_parts(self.ptr, self.len)).unwrap_or("")
+        }
+    }
+}
+
+/// Callback through which the callee writes a string into a buffer owned by the caller.
+#[repr(C)]
+pub struct RawSink {
+    ctx: *mut c_void,
+    write: extern "C" fn(*mut c_void, RawStr)
+}
+
+impl RawSink {
+    /// Creates a new `RawSink` appending to `buffer`.
+    pub fn new(buffer: &mut String) -> RawSink {
+        RawSink {
+            ctx: buffer as *mut String as *mut c_void,
+            write: write_string
+        }
+    }
+
+    /// Writes `s` into the buffer of the caller.
+    pub fn write(&self, s: &str) {
+        (self.write)(self.ctx, RawStr::new(s));
+    }
+}
+
+extern "C" fn write_string(ctx: *mut c_void, s: RawStr) {
+    let buffer = unsafe { &mut *(ctx as *mut String) };
+    buffer.push_str(unsafe { s.as_str() });
+}
+
+/// Borrowed bytes passed across the boundary.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawBytes {
+    ptr: *const u8,
+    len: usize
+}
+
+impl RawBytes {
+    /// Creates a new `RawBytes` borrowing `bytes`.
+    pub fn new(bytes: &[u8]) -> RawBytes {
+        RawBytes {
+            ptr: bytes.as_ptr(),
+            len: bytes.len()
+        }
+    }
+
+    /// Obtains the borrowed bytes.
+    ///
+    /// # Safety
+    /// The bytes must be still alive, i.e. this function can be used only in the callee.
+    pub unsafe fn as_slice<'a>(self) -> &'a [u8] {
+        if self.ptr.is_null() {
+            &[]
+        } else {
+            slice::from_raw_parts(self.ptr, self.len)
+        }
+    }
+}
+
+/// Borrowed request or response passed across the boundary, given its head serialized as TOML
+/// and its body.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawHttpMessage {
+    head: RawStr,
+    body: RawBytes
+}
+
+impl RawHttpMessage {
+    /// Creates a new `RawHttpMessage` borrowing `head` and `body`.
+    pub fn new(head: &str, body: &[u8]) -> RawHttpMessage {
+        RawHttpMessage {
+            head: RawStr::new(head),
+            body: RawBytes::new(body)
+        }
+    }
+
+    /// Obtains the borrowed head.
+    ///
+    /// # Safety
+    /// See `RawStr::as_str`.
+    pub unsafe fn head<'a>(self) -> &'a str {
+        self.head.as_str()
+    }
+    /// Obtains the borrowed body.
+    ///
+    /// # Safety
+    /// See `RawBytes::as_slice`.
+    pub unsafe fn body<'a>(self) -> &'a [u8] {
+        self.body.as_slice()
+    }
+}
+
+/// Callback through which the callee writes a request or a response into a buffer owned by the
+/// caller.
+#[repr(C)]
+pub struct RawHttpSink {
+    ctx: *mut c_void,
+    write: extern "C" fn(*mut c_void, RawHttpMessage)
+}
+
+impl RawHttpSink {
+    /// Creates a new `RawHttpSink` appending the head and the body to `buffer`.
+    pub fn new(buffer: &mut (String, Vec<u8>)) -> RawHttpSink {
+        RawHttpSink {
+            ctx: buffer as *mut (String, Vec<u8>) as *mut c_void,
+            write: write_http
+        }
+    }
+
+    /// Writes `head` and `body` into the buffer of the caller.
+    pub fn write(&self, head: &str, body: &[u8]) {
+        (self.write)(self.ctx, RawHttpMessage::new(head, body));
+    }
+}
+
+extern "C" fn write_http(ctx: *mut c_void, message: RawHttpMessage) {
+    let buffer = unsafe { &mut *(ctx as *mut (String, Vec<u8>)) };
+    buffer.0.push_str(unsafe { message.head() });
+    buffer.1.extend_from_slice(unsafe { message.body() });
+}
+
+/// Callback through which a module writes into a logger of the server.
+#[repr(C)]
+pub struct RawLogger {
+    ctx: *mut c_void,
+    log: extern "C" fn(*mut c_void, u8, RawStr)
+}
+
+// The logger is used only for the duration of the call it is passed to, or, if shared, while the
+// server keeps the reference it points to (see `ForeignModule::register_logger`); shared loggers
+// are synchronized through their lock.
+unsafe impl Send for RawLogger {}
+unsafe impl Sync for RawLogger {}
+
+impl RawLogger {
+    /// Creates a new `RawLogger` writing into `logger`.
+    pub fn new(logger: &mut &mut dyn Logger) -> RawLogger {
+        RawLogger {
+            ctx: logger as *mut &mut dyn Logger as *mut c_void,
+            log: log_event
+        }
+    }
+    /// Creates a new `RawLogger` writing into the shared `logger`, which the module may keep.
+    pub fn shared(logger: &AsyncLoggerReference) -> RawLogger {
+        RawLogger {
+            ctx: logger as *const AsyncLoggerReference as *mut c_void,
+            log: log_shared
+        }
+    }
+}
+
+impl Logger for RawLogger {
+    fn log(&mut self, sev: Severity, desc: &str) {
+        (self.log)(self.ctx, severity_code(sev), RawStr::new(desc));
+    }
+}
+
+extern "C" fn log_event(ctx: *mut c_void, sev: u8, desc: RawStr) {
+    let logger = unsafe { &mut *(ctx as *mut &mut dyn Logger) };
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| logger.log(severity_from_code(sev), unsafe { desc.as_str() })));
+}
+
+extern "C" fn log_shared(ctx: *mut c_void, sev: u8, desc: RawStr) {
+    let logger = unsafe { &*(ctx as *const AsyncLoggerReference) };
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| if let Ok(mut logger) = logger.write() {
+        logger.log(severity_from_code(sev), unsafe { desc.as_str() });
+    }));
+}
+
+/// Bus of the server, through which a module publishes and subscribes to messages.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RawBus {
+    ctx: *const c_void,
+    publish: extern "C" fn(*const c_void, RawStr, RawStr) -> u32,
+    subscribe: extern "C" fn(*const c_void, RawStr, RawSubscriber) -> u64,
+    unsubscribe: extern "C" fn(*const c_void, u64) -> u32
+}
+
+// The handle the bus forwards to is owned by the `ForeignModule`, which outlives the module.
+unsafe impl Send for RawBus {}
+unsafe impl Sync for RawBus {}
+
+impl RawBus {
+    /// Creates a new `RawBus` forwarding to `handle`, which must outlive it.
+    pub fn new(handle: &BusHandle) -> RawBus {
+        RawBus {
+            ctx: handle as *const BusHandle as *const c_void,
+            publish: bus_publish,
+            subscribe: bus_subscribe,
+            unsubscribe: bus_unsubscribe
+        }
+    }
+
+    pub(crate) fn publish(&self, message: &Message) -> usize {
+        (self.publish)(self.ctx, RawStr::new(message.topic()), RawStr::new(message.raw_payload())) as usize
+    }
+    pub(crate) fn subscribe(&self, topic: &str, subscriber: RawSubscriber) -> SubscriptionId {
+        SubscriptionId::from_raw((self.subscribe)(self.ctx, RawStr::new(topic), subscriber))
+    }
+    pub(crate) fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        (self.unsubscribe)(self.ctx, id.into_raw()) != 0
+    }
+}
+
+extern "C" fn bus_publish(ctx: *const c_void, topic: RawStr, payload: RawStr) -> u32 {
+    let handle = unsafe { &*(ctx as *const BusHandle) };
+    let message = Message::from_raw(unsafe { topic.as_str() }, unsafe { payload.as_str() }.to_owned());
+    panic::catch_unwind(AssertUnwindSafe(|| handle.publish_message(&message) as u32)).unwrap_or(0)
+}
+
+extern "C" fn bus_subscribe(ctx: *const c_void, topic: RawStr, subscriber: RawSubscriber) -> u64 {
+    let handle = unsafe { &*(ctx as *const BusHandle) };
+    let topic = unsafe { topic.as_str() };
+    panic::catch_unwind(AssertUnwindSafe(|| handle.subscribe(topic, move |message| subscriber.deliver(message)).into_raw()))
+        .unwrap_or(0)
+}
+
+extern "C" fn bus_unsubscribe(ctx: *const c_void, id: u64) -> u32 {
+    let handle = unsafe { &*(ctx as *const BusHandle) };
+    panic::catch_unwind(AssertUnwindSafe(|| handle.unsubscribe(SubscriptionId::from_raw(id)) as u32)).unwrap_or(0)
+}
+
+/// Callback of a module subscribed to a topic of the bus; released by the module when dropped.
+#[repr(C)]
+pub struct RawSubscriber {
+    ctx: *mut c_void,
+    deliver: extern "C" fn(*mut c_void, RawStr, RawStr),
+    release: extern "C" fn(*mut c_void)
+}
+
+// Callbacks are required to be `Send` and `Sync` (see `BusHandle::subscribe`).
+unsafe impl Send for RawSubscriber {}
+unsafe impl Sync for RawSubscriber {}
+
+impl RawSubscriber {
+    /// Creates a new `RawSubscriber` calling `callback`.
+    pub fn new<F>(callback: F) -> RawSubscriber
+        where
+            F: Fn(&Message) + Send + Sync + 'static
+    {
+        let callback: Box<Box<crate::bus::Callback>> = Box::new(Box::new(callback));
+        RawSubscriber {
+            ctx: Box::into_raw(callback) as *mut c_void,
+            deliver: deliver_message,
+            release: release_subscriber
+        }
+    }
+
+    fn deliver(&self, message: &Message) {
+        (self.deliver)(self.ctx, RawStr::new(message.topic()), RawStr::new(message.raw_payload()));
+    }
+}
+
+impl Drop for RawSubscriber {
+    fn drop(&mut self) {
+        (self.release)(self.ctx);
+    }
+}
+
+extern "C" fn deliver_message(ctx: *mut c_void, topic: RawStr, payload: RawStr) {
+    let callback = unsafe { &*(ctx as *const Box<crate::bus::Callback>) };
+    let message = Message::from_raw(unsafe { topic.as_str() }, unsafe { payload.as_str() }.to_owned());
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| callback(&message)));
+}
+
+extern "C" fn release_subscriber(ctx: *mut c_void) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(unsafe { Box::from_raw(ctx as *mut Box<crate::bus::Callback>) })));
+}
+
+#[doc(hidden)]
+fn severity_code(sev: Severity) -> u8 {
+    match sev {
+        Severity::Debug => SEVERITY_DEBUG,
+        Severity::Information => SEVERITY_INFORMATION,
+        Severity::Warning => SEVERITY_WARNING,
+        Severity::Error => SEVERITY_ERROR,
+        Severity::Critical => SEVERITY_CRITICAL
+    }
+}
+
+#[doc(hidden)]
+fn severity_from_code(code: u8) -> Severity {
+    match code {
+        SEVERITY_DEBUG => Severity::Debug,
+        SEVERITY_INFORMATION => Severity::Information,
+        SEVERITY_WARNING => Severity::Warning,
+        SEVERITY_ERROR => Severity::Error,
+        _ => Severity::Critical
+    }
+}
+
+/// Table of the functions of a module library.
+///
+/// Instances are opaque pointers created by `construct` and released by `destroy`.
+#[repr(C)]
+pub struct ModuleVTable {
+    /// Version of the layout of this structure (see `ABI_VERSION`).
+    pub abi_version: u32,
+    /// Writes the version of `mammoth-setup` the module is built with.
+    pub version: extern "C" fn(RawSink),
+    /// Writes the requisite that the version of the server must respect to load the module.
+    pub host_version_req: extern "C" fn(RawSink),
+    /// Writes the metadata of the module, serialized as TOML.
+    pub metadata: extern "C" fn(RawSink),
+    /// Writes the schema of the configuration of the module, serialized as TOML; writes nothing
+    /// if the module does not describe its configuration.
+    pub config_schema: extern "C" fn(RawSink),
+    /// Writes the fingerprint of the module (see `FACTORY_FINGERPRINT`); writes nothing if the
+    /// module is built without the `actix` feature.
+    pub factory_fingerprint: extern "C" fn(RawSink),
+    /// Constructs an instance given its configuration, host and environment (see
+    /// `encode_context`); returns a null pointer on failure, writing the reason into the sink.
+    pub construct: extern "C" fn(RawStr, RawSink) -> *mut c_void,
+    /// Releases an instance.
+    pub destroy: extern "C" fn(*mut c_void),
+    /// Calls `Log::register_logger` given a shared logger, which the instance may keep; must be
+    /// called before the instance is shared.
+    pub register_logger: extern "C" fn(*mut c_void, RawLogger),
+    /// Calls `MammothInterface::on_bus`.
+    pub on_bus: extern "C" fn(*const c_void, RawBus),
+    /// Calls `MammothInterface::on_sandbox` given the allowed paths, serialized as TOML.
+    pub on_sandbox: extern "C" fn(*const c_void, RawStr),
+    /// Calls `MammothInterface::on_load`.
+    pub on_load: extern "C" fn(*const c_void),
+    /// Calls `MammothInterface::on_validation`, writing the error (if any) into the sink; returns
+    /// zero on success.
+    pub on_validation: extern "C" fn(*const c_void, RawLogger, RawSink) -> u32,
+    /// Calls `MammothInterface::on_factory` given a pointer to the `ServiceConfig` of the server;
+    /// must be called only if the fingerprint of the module matches the one of the server.
+    pub on_factory: extern "C" fn(*const c_void, *mut c_void),
+    /// Calls `MammothInterface::on_start`.
+    pub on_start: extern "C" fn(*const c_void),
+    /// Calls `MammothInterface::on_health`, writing the reason of the status (if any) into the
+    /// sink; returns `HEALTH_HEALTHY`, `HEALTH_DEGRADED` or `HEALTH_UNHEALTHY`.
+    pub on_health: extern "C" fn(*const c_void, RawSink) -> u32,
+    /// Calls `MammothInterface::on_metrics`, writing the collected samples into the sink,
+    /// serialized as TOML.
+    pub on_metrics: extern "C" fn(*const c_void, RawSink),
+    /// Calls `MammothInterface::on_config_reload` given the configuration (see `encode_config`).
+    pub on_config_reload: extern "C" fn(*const c_void, RawStr),
+    /// Calls `MammothInterface::on_request` given the request, writing the request as left by the
+    /// module into the first sink and its response, if any, into the second one; returns
+    /// `HOOK_PASSED` if the request is passed on, `HOOK_ANSWERED` if it is answered, `HOOK_FAILED`
+    /// on failure and `HOOK_SKIPPED` if the module is built without the `http` feature.
+    pub on_request: extern "C" fn(*const c_void, RawHttpMessage, RawHttpSink, RawHttpSink) -> u32,
+    /// Calls `MammothInterface::on_response` given the request and its response, writing the
+    /// response as left by the module into the sink; returns `HOOK_PASSED` on success,
+    /// `HOOK_FAILED` on failure and `HOOK_SKIPPED` if the module is built without the `http`
+    /// feature.
+    pub on_response: extern "C" fn(*const c_void, RawHttpMessage, RawHttpMessage, RawHttpSink) -> u32,
+    /// Calls `MammothInterface::on_shutdown`.
+    pub on_shutdown: extern "C" fn(*const c_void)
+}
+
+/// Serializes the configuration of a module as a TOML document with a single `config` key; no
+/// configuration results in an empty string.
+pub fn encode_config(config: Option<&Value>) -> String {
+    encode_context(config, None, None)
+}
+
+/// Serializes the configuration of a module, the host it is constructed for (see
+/// `Instancing::PerHost`) and the `[environment]` table effective for that host (see
+/// `ConfigurationFile::environment_for`) as a TOML document with the `config`, `host` and
+/// `environment` keys; none of them results in an empty string.
+pub fn encode_context(config: Option<&Value>, host: Option<&HostIdentifier>, environment: Option<&Value>) -> String {
+    let mut table = toml::value::Table::new();
+    if let Some(value) = config {
+        table.insert("config".to_owned(), value.clone());
+    }
+    if let Some(host) = host.and_then(|h| Value::try_from(h).ok()) {
+        table.insert("host".to_owned(), host);
+    }
+    if let Some(environment) = environment {
+        table.insert("environment".to_owned(), environment.clone());
+    }
+
+    if table.is_empty() {
+        String::new()
+    } else {
+        toml::to_string(&table).unwrap_or_default()
+    }
+}
+
+/// Deserializes a configuration serialized by `encode_config`.
+pub fn decode_config(config: &str) -> Option<Value> {
+    if config.is_empty() {
+        return None;
+    }
+    toml::from_str::<toml::value::Table>(config).ok()
+        .and_then(|mut table| table.remove("config"))
+}
+
+/// Deserializes the host serialized by `encode_context`, if any.
+pub fn decode_host(context: &str) -> Option<HostIdentifier> {
+    toml::from_str::<toml::value::Table>(context).ok()
+        .and_then(|mut table| table.remove("host"))
+        .and_then(|host| host.try_into().ok())
+}
+
+/// Deserializes the environment serialized by `encode_context`, if any.
+pub fn decode_environment(context: &str) -> Option<Value> {
+    toml::from_str::<toml::value::Table>(context).ok()
+        .and_then(|mut table| table.remove("environment"))
+}
+
+/// Obtains the host the module is being constructed for, if called by the constructor of a
+/// per-host instance (see `Instancing::PerHost`); `None` otherwise.
+pub fn current_host() -> Option<HostIdentifier> {
+    HOST.with(|host| host.borrow().clone())
+}
+
+/// Obtains the `[environment]` table of the configuration, merged with the `[host.environment]`
+/// table of the host the module is loaded for, if called by the constructor of a module; `None`
+/// otherwise or if the configuration has no environment.
+///
+/// This lets modules read deployment settings shared with the other modules, e.g.
+/// ```rust,ignore
+/// fn new(config: Config) -> MyModule {
+///     let region = abi::current_environment()
+///         .and_then(|env| env.get("region").and_then(|r| r.as_str().map(str::to_owned)));
+///     // ...
+/// }
+/// ```
+pub fn current_environment() -> Option<Value> {
+    ENVIRONMENT.with(|environment| environment.borrow().clone())
+}
+
+/// Calls `f` so that `current_host` returns `host` until it returns.
+#[doc(hidden)]
+pub fn with_host<R, F>(host: Option<HostIdentifier>, f: F) -> R
+    where
+        F: FnOnce() -> R
+{
+    let previous = HOST.with(|current| current.replace(host));
+    let result = f();
+    HOST.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// Calls `f` so that `current_environment` returns `environment` until it returns.
+#[doc(hidden)]
+pub fn with_environment<R, F>(environment: Option<Value>, f: F) -> R
+    where
+        F: FnOnce() -> R
+{
+    let previous = ENVIRONMENT.with(|current| current.replace(environment));
+    let result = f();
+    ENVIRONMENT.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// Deserializes the configuration of a module into the type taken by its constructor.
+///
+/// A missing configuration is deserialized as `None` by optional types (e.g. `Option<Value>`,
+/// the raw configuration) and as an empty table otherwise, so that structures whose fields all
+/// have defaults can be used even if no configuration is given.
+pub fn deserialize_config<C>(config: Option<Value>) -> Result<C, Error>
+    where
+        C: DeserializeOwned
+{
+    Ok(C::deserialize(ConfigDeserializer(config))?)
+}
+
+struct ConfigDeserializer(Option<Value>);
+
+impl ConfigDeserializer {
+    fn into_value(self) -> Value {
+        self.0.unwrap_or_else(|| Value::Table(Default::default()))
+    }
+}
+
+impl<'de> Deserializer<'de> for ConfigDeserializer {
+    type Error = toml::de::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.into_value().deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Some(value) => value.deserialize_option(visitor),
+            None => visitor.visit_none()
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        self.into_value().deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        self.into_value().deserialize_newtype_struct(name, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string seq
+        bytes byte_buf map unit_struct tuple_struct struct
+        tuple ignored_any identifier unit
+    }
+}
+
+/// Calls the shutdown hook of `library` (see `SHUTDOWN_POINT`), if it exports one.
+///
+/// Unlike `MammothInterface::on_shutdown`, the hook does not depend on any instance of the module,
+/// so it runs even if the instances were never constructed or their destruction was abandoned
+/// (see `loaded::watchdog`).
+pub fn library_shutdown(library: &Library) {
+    let hook = unsafe { library.get::<extern "C" fn()>(SHUTDOWN_POINT) };
+    if let Ok(hook) = hook {
+        hook();
+    }
+}
+
+/// Obtains the function table of `library`, checking its ABI version.
+pub fn vtable(library: &Library) -> Result<&ModuleVTable, Error> {
+    let vtable = unsafe {
+        let entry: Symbol<extern "C" fn() -> *const ModuleVTable> = library.get(ENTRY_POINT)?;
+        entry()
+    };
+    if vtable.is_null() {
+        return Err(Error::InvalidModuleAbi(0));
+    }
+
+    let vtable = unsafe { &*vtable };
+    if vtable.abi_version != ABI_VERSION {
+        return Err(Error::InvalidModuleAbi(vtable.abi_version));
+    }
+
+    Ok(vtable)
+}
+
+/// Obtains the version of `mammoth-setup` a module is built with.
+pub fn module_version(vtable: &ModuleVTable) -> Result<Version, Error> {
+    let mut buffer = String::new();
+    (vtable.version)(RawSink::new(&mut buffer));
+
+    Version::parse(&buffer).map_err(|err| Error::Generic(Box::new(err)))
+}
+
+/// Obtains the requisite that the version of the server must respect to load a module.
+pub fn host_version_req(vtable: &ModuleVTable) -> Result<VersionReq, Error> {
+    let mut buffer = String::new();
+    (vtable.host_version_req)(RawSink::new(&mut buffer));
+
+    VersionReq::parse(&buffer).map_err(|err| Error::Generic(Box::new(err)))
+}
+
+/// Checks that the version of the server respects the requisite of a module.
+///
+/// Fails with `Error::InvalidModuleVersion`, containing the version of the server and the
+/// requisite of the module, if the module refuses to be loaded.
+pub fn check_host_version(vtable: &ModuleVTable) -> Result<(), Error> {
+    let requirement = host_version_req(vtable)?;
+    let version = crate::version::version();
+
+    if requirement.matches(&version) {
+        Ok(())
+    } else {
+        Err(Error::InvalidModuleVersion(version, requirement))
+    }
+}
+
+/// Obtains the metadata of a module.
+pub fn module_metadata(vtable: &ModuleVTable) -> Result<ModuleMetadata, Error> {
+    let mut buffer = String::new();
+    (vtable.metadata)(RawSink::new(&mut buffer));
+
+    Ok(toml::from_str(&buffer)?)
+}
+
+/// Obtains the fingerprint of a module (see `FACTORY_FINGERPRINT`); the fingerprint is empty if
+/// the module is built without the `actix` feature.
+pub fn module_factory_fingerprint(vtable: &ModuleVTable) -> String {
+    let mut buffer = String::new();
+    (vtable.factory_fingerprint)(RawSink::new(&mut buffer));
+
+    buffer
+}
+
+/// Obtains the schema of the configuration of a module, if it provides one.
+pub fn module_config_schema(vtable: &ModuleVTable) -> Result<Option<ConfigSchema>, Error> {
+    let mut buffer = String::new();
+    (vtable.config_schema)(RawSink::new(&mut buffer));
+
+    if buffer.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(toml::from_str(&buffer)?))
+    }
+}
+
+/// Instance of a module living in a library, seen by the server as a `MammothInterface`.
+///
+/// The library containing the module must outlive the instance.
+pub struct ForeignModule {
+    vtable: *const ModuleVTable,
+    instance: *mut c_void,
+    // Handles given to the module through `on_bus`, dropped after the instance; boxed, since the
+    // module keeps pointers to them.
+    #[allow(clippy::vec_box)]
+    buses: Mutex<Vec<Box<BusHandle>>>,
+    // Loggers given to the module through `register_logger`, dropped after the instance; boxed
+    // for the same reason.
+    #[allow(clippy::vec_box)]
+    loggers: Vec<Box<AsyncLoggerReference>>
+}
+
+// Modules implement `MammothInterface`, which requires `Send` and `Sync`.
+unsafe impl Send for ForeignModule {}
+unsafe impl Sync for ForeignModule {}
+
+impl ForeignModule {
+    /// Constructs an instance of the module described by `vtable` given its configuration.
+    ///
+    /// # Safety
+    /// `vtable` must be obtained through `vtable` from a library that outlives the instance.
+    pub unsafe fn new(vtable: &ModuleVTable, config: Option<&Value>) -> Result<ForeignModule, Error> {
+        ForeignModule::for_host(vtable, config, None)
+    }
+    /// Constructs an instance of the module described by `vtable` given its configuration and the
+    /// host it is constructed for, if any (see `current_host`).
+    ///
+    /// # Safety
+    /// `vtable` must be obtained through `vtable` from a library that outlives the instance.
+    pub unsafe fn for_host(vtable: &ModuleVTable, config: Option<&Value>, host: Option<&HostIdentifier>) -> Result<ForeignModule, Error> {
+        ForeignModule::with_context(vtable, config, host, None)
+    }
+    /// Constructs an instance of the module described by `vtable` given its configuration, the
+    /// host it is constructed for, if any, and its environment (see `current_environment`).
+    ///
+    /// # Safety
+    /// `vtable` must be obtained through `vtable` from a library that outlives the instance.
+    pub unsafe fn with_context(vtable: &ModuleVTable, config: Option<&Value>, host: Option<&HostIdentifier>, environment: Option<&Value>) -> Result<ForeignModule, Error> {
+        let config = encode_context(config, host, environment);
+        let mut message = String::new();
+        let instance = (vtable.construct)(RawStr::new(&config), RawSink::new(&mut message));
+        if instance.is_null() {
+            if message.is_empty() {
+                message.push_str("module construction failed");
+            }
+            return Err(Error::Module(message));
+        }
+
+        Ok(ForeignModule {
+            vtable,
+            instance,
+            buses: Mutex::new(Vec::new()),
+            loggers: Vec::new()
+        })
+    }
+
+    fn vtable(&self) -> &ModuleVTable {
+        unsafe { &*self.vtable }
+    }
+}
+
+impl Drop for ForeignModule {
+    fn drop(&mut self) {
+        (self.vtable().destroy)(self.instance);
+    }
+}
+
+impl Log for ForeignModule {
+    fn register_logger(&mut self, logger: AsyncLoggerReference) {
+        let logger = Box::new(logger);
+        let raw = RawLogger::shared(&logger);
+        self.loggers.push(logger);
+        (self.vtable().register_logger)(self.instance, raw);
+    }
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        self.loggers.last().map(|logger| AsyncLoggerReference::clone(logger))
+    }
+}
+
+impl MammothInterface for ForeignModule {
+    fn on_bus(&self, bus: BusHandle) {
+        let bus = Box::new(bus);
+        let raw = RawBus::new(&bus);
+        self.buses.lock().unwrap().push(bus);
+        (self.vtable().on_bus)(self.instance, raw);
+    }
+
+    fn on_sandbox(&self, sandbox: SandboxedFs) {
+        let sandbox = toml::to_string(&sandbox).unwrap_or_default();
+        (self.vtable().on_sandbox)(self.instance, RawStr::new(&sandbox));
+    }
+
+    fn on_load(&self) {
+        (self.vtable().on_load)(self.instance);
+    }
+
+    fn on_validation(&self, mut logger: &mut dyn Logger) -> Result<(), Error> {
+        let mut message = String::new();
+        let status = (self.vtable().on_validation)(self.instance, RawLogger::new(&mut logger), RawSink::new(&mut message));
+
+        if status == STATUS_OK {
+            Ok(())
+        } else {
+            Err(Error::Module(message))
+        }
+    }
+
+    fn on_start(&self) {
+        (self.vtable().on_start)(self.instance);
+    }
+
+    fn on_health(&self) -> HealthStatus {
+        let mut reason = String::new();
+        match (self.vtable().on_health)(self.instance, RawSink::new(&mut reason)) {
+            HEALTH_HEALTHY => HealthStatus::Healthy,
+            HEALTH_DEGRADED => HealthStatus::Degraded(reason),
+            _ => HealthStatus::Unhealthy(reason)
+        }
+    }
+
+    fn on_metrics(&self, collector: &mut MetricsCollector) {
+        let mut samples = String::new();
+        (self.vtable().on_metrics)(self.instance, RawSink::new(&mut samples));
+        if let Ok(samples) = toml::from_str::<MetricsCollector>(&samples) {
+            collector.merge(samples, &[]);
+        }
+    }
+
+    fn on_config_reload(&self, config: &Value) {
+        let config = encode_config(Some(config));
+        (self.vtable().on_config_reload)(self.instance, RawStr::new(&config));
+    }
+
+    #[cfg(feature = "actix")]
+    fn on_factory(&self, cfg: &mut ServiceConfig) {
+        if module_factory_fingerprint(self.vtable()) == FACTORY_FINGERPRINT {
+            (self.vtable().on_factory)(self.instance, cfg as *mut ServiceConfig as *mut c_void);
+        }
+    }
+
+    #[cfg(feature = "http")]
+    fn on_request(&self, request: &mut Request) -> Option<Response> {
+        let head = request.encode_head();
+        let mut passed = (String::new(), Vec::new());
+        let mut answer = (String::new(), Vec::new());
+        let status = (self.vtable().on_request)(self.instance, RawHttpMessage::new(&head, request.body()), RawHttpSink::new(&mut passed), RawHttpSink::new(&mut answer));
+
+        match status {
+            HOOK_SKIPPED => None,
+            HOOK_PASSED | HOOK_ANSWERED => {
+                if let Ok(passed) = Request::decode(&passed.0, passed.1) {
+                    *request = passed;
+                }
+                match status {
+                    HOOK_ANSWERED => Some(Response::decode(&answer.0, answer.1).unwrap_or_else(|_| Response::new(500))),
+                    _ => None
+                }
+            },
+            _ => Some(Response::new(500))
+        }
+    }
+
+    #[cfg(feature = "http")]
+    fn on_response(&self, request: &Request, response: &mut Response) {
+        let (request_head, response_head) = (request.encode_head(), response.encode_head());
+        let mut rewritten = (String::new(), Vec::new());
+        let status = (self.vtable().on_response)(self.instance, RawHttpMessage::new(&request_head, request.body()), RawHttpMessage::new(&response_head, response.body()), RawHttpSink::new(&mut rewritten));
+
+        match status {
+            HOOK_SKIPPED => {},
+            HOOK_PASSED => if let Ok(rewritten) = Response::decode(&rewritten.0, rewritten.1) {
+                *response = rewritten;
+            },
+            _ => *response = Response::new(500)
+        }
+    }
+
+    fn on_shutdown(&self) {
+        (self.vtable().on_shutdown)(self.instance);
+    }
+}
+
+// Module side of the shims, used by the code generated by the `mammoth_module` attribute.
+
+/// Writes the version of `mammoth-setup` into `sink`.
+#[doc(hidden)]
+pub extern "C" fn version(sink: RawSink) {
+    sink.write(&crate::version::version().to_string());
+}
+
+/// Writes into `sink` the requisite that the version of the server must respect, i.e.
+/// `requirement` or, if `None`, the versions compatible with the version of `mammoth-setup` the
+/// module is built with (e.g. `~0.0.1`).
+#[doc(hidden)]
+pub fn host_version(sink: RawSink, requirement: Option<&str>) {
+    match requirement {
+        Some(requirement) => sink.write(requirement),
+        None => sink.write(&format!("~{}", crate::version::version()))
+    }
+}
+
+/// Writes the metadata built by `metadata` into `sink`; nothing is written if it panics, so that
+/// the server fails to read the metadata.
+#[doc(hidden)]
+pub fn metadata<F>(sink: RawSink, metadata: F)
+    where
+        F: FnOnce() -> ModuleMetadata
+{
+    if let Ok(Ok(metadata)) = panic::catch_unwind(AssertUnwindSafe(|| toml::to_string(&metadata()))) {
+        sink.write(&metadata);
+    }
+}
+
+/// Writes the schema built by `schema` into `sink`, if any; nothing is written if it panics.
+#[doc(hidden)]
+pub fn config_schema<F>(sink: RawSink, schema: F)
+    where
+        F: FnOnce() -> Option<ConfigSchema>
+{
+    let schema = panic::catch_unwind(AssertUnwindSafe(schema)).ok().and_then(|s| s);
+    if let Some(schema) = schema.and_then(|s| toml::to_string(&s).ok()) {
+        sink.write(&schema);
+    }
+}
+
+/// Writes the fingerprint of the module into `sink` (see `FACTORY_FINGERPRINT`).
+#[doc(hidden)]
+#[cfg_attr(not(feature = "actix"), allow(unused_variables))]
+pub extern "C" fn factory_fingerprint(sink: RawSink) {
+    #[cfg(feature = "actix")]
+    sink.write(FACTORY_FINGERPRINT);
+}
+
+/// Constructs an instance through `constructor`, given its configuration deserialized as by
+/// `deserialize_config`; returns a null pointer, writing the reason into `error`, if the
+/// configuration is invalid or the constructor panics.
+#[doc(hidden)]
+pub fn construct<T, C, F>(config: RawStr, error: RawSink, constructor: F) -> *mut c_void
+    where
+        T: MammothInterface,
+        C: DeserializeOwned,
+        F: FnOnce(C) -> T
+{
+    let context = unsafe { config.as_str() };
+    let host = decode_host(context);
+    let environment = decode_environment(context);
+    let config = match deserialize_config(decode_config(context)) {
+        Ok(config) => config,
+        Err(err) => {
+            error.write(&format!("invalid configuration: {}", err));
+            return ptr::null_mut();
+        }
+    };
+
+    let instance = || with_environment(environment, || Box::into_raw(Box::new(constructor(config))) as *mut c_void);
+    match panic::catch_unwind(AssertUnwindSafe(|| with_host(host, instance))) {
+        Ok(instance) => instance,
+        Err(_) => {
+            error.write("module panicked during construction");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Calls the shutdown hook of the library, if any, catching its panics.
+#[doc(hidden)]
+pub fn shutdown(hook: Option<fn()>) {
+    if let Some(hook) = hook {
+        let _ = panic::catch_unwind(hook);
+    }
+}
+
+/// Releases an instance created by `construct`.
+#[doc(hidden)]
+pub extern "C" fn destroy<T: MammothInterface>(instance: *mut c_void) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(unsafe { Box::from_raw(instance as *mut T) })));
+}
+
+#[doc(hidden)]
+fn call<T: MammothInterface, F: FnOnce(&T)>(instance: *const c_void, f: F) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| f(unsafe { &*(instance as *const T) })));
+}
+
+#[doc(hidden)]
+pub extern "C" fn register_logger<T: MammothInterface>(instance: *mut c_void, logger: RawLogger) {
+    let logger: AsyncLoggerReference = Arc::new(RwLock::new(logger));
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| unsafe { &mut *(instance as *mut T) }.register_logger(logger)));
+}
+
+#[doc(hidden)]
+pub extern "C" fn on_bus<T: MammothInterface>(instance: *const c_void, bus: RawBus) {
+    call::<T, _>(instance, |module| module.on_bus(BusHandle::foreign(bus)));
+}
+
+#[doc(hidden)]
+pub extern "C" fn on_sandbox<T: MammothInterface>(instance: *const c_void, sandbox: RawStr) {
+    let sandbox = toml::from_str::<SandboxedFs>(unsafe { sandbox.as_str() }).unwrap_or_default();
+    call::<T, _>(instance, |module| module.on_sandbox(sandbox));
+}
+
+#[doc(hidden)]
+pub extern "C" fn on_load<T: MammothInterface>(instance: *const c_void) {
+    call::<T, _>(instance, |module| module.on_load());
+}
+
+#[doc(hidden)]
+pub extern "C" fn on_validation<T: MammothInterface>(instance: *const c_void, mut logger: RawLogger, error: RawSink) -> u32 {
+    let module = unsafe { &*(instance as *const T) };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| module.on_validation(&mut logger))) {
+        Ok(Ok(())) => STATUS_OK,
+        Ok(Err(err)) => {
+            error.write(&err.to_string());
+            STATUS_ERROR
+        },
+        Err(_) => {
+            error.write("module panicked during validation");
+            STATUS_PANIC
+        }
+    }
+}
+
+#[doc(hidden)]
+#[cfg_attr(not(feature = "actix"), allow(unused_variables))]
+pub extern "C" fn on_factory<T: MammothInterface>(instance: *const c_void, cfg: *mut c_void) {
+    #[cfg(feature = "actix")]
+    call::<T, _>(instance, |module| module.on_factory(unsafe { &mut *(cfg as *mut ServiceConfig) }));
+}
+
+#[doc(hidden)]
+pub extern "C" fn on_start<T: MammothInterface>(instance: *const c_void) {
+    call::<T, _>(instance, |module| module.on_start());
+}
+
+#[doc(hidden)]
+pub extern "C" fn on_health<T: MammothInterface>(instance: *const c_void, reason: RawSink) -> u32 {
+    let module = unsafe { &*(instance as *const T) };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| module.on_health())) {
+        Ok(HealthStatus::Healthy) => HEALTH_HEALTHY,
+        Ok(HealthStatus::Degraded(message)) => {
+            reason.write(&message);
+            HEALTH_DEGRADED
+        },
+        Ok(HealthStatus::Unhealthy(message)) => {
+            reason.write(&message);
+            HEALTH_UNHEALTHY
+        },
+        Err(_) => {
+            reason.write("module panicked during health check");
+            HEALTH_UNHEALTHY
+        }
+    }
+}
+
+#[doc(hidden)]
+pub extern "C" fn on_metrics<T: MammothInterface>(instance: *const c_void, samples: RawSink) {
+    call::<T, _>(instance, |module| {
+        let mut collector = MetricsCollector::new();
+        module.on_metrics(&mut collector);
+        if let Ok(serialized) = toml::to_string(&collector) {
+            samples.write(&serialized);
+        }
+    });
+}
+
+#[doc(hidden)]
+pub extern "C" fn on_config_reload<T: MammothInterface>(instance: *const c_void, config: RawStr) {
+    let config = decode_config(unsafe { config.as_str() })
+        .unwrap_or_else(|| Value::Table(Default::default()));
+    call::<T, _>(instance, |module| module.on_config_reload(&config));
+}
+
+#[doc(hidden)]
+#[cfg_attr(not(feature = "http"), allow(unused_variables))]
+pub extern "C" fn on_request<T: MammothInterface>(instance: *const c_void, request: RawHttpMessage, passed: RawHttpSink, answer: RawHttpSink) -> u32 {
+    #[cfg(feature = "http")]
+    {
+        let module = unsafe { &*(instance as *const T) };
+        let mut request = match Request::decode(unsafe { request.head() }, unsafe { request.body() }.to_vec()) {
+            Ok(request) => request,
+            Err(_) => return HOOK_FAILED
+        };
+
+        match panic::catch_unwind(AssertUnwindSafe(|| module.on_request(&mut request))) {
+            Ok(response) => {
+                passed.write(&request.encode_head(), request.body());
+                match response {
+                    Some(response) => {
+                        answer.write(&response.encode_head(), response.body());
+                        HOOK_ANSWERED
+                    },
+                    None => HOOK_PASSED
+                }
+            },
+            Err(_) => HOOK_FAILED
+        }
+    }
+    #[cfg(not(feature = "http"))]
+    { HOOK_SKIPPED }
+}
+
+#[doc(hidden)]
+#[cfg_attr(not(feature = "http"), allow(unused_variables))]
+pub extern "C" fn on_response<T: MammothInterface>(instance: *const c_void, request: RawHttpMessage, response: RawHttpMessage, rewritten: RawHttpSink) -> u32 {
+    #[cfg(feature = "http")]
+    {
+        let module = unsafe { &*(instance as *const T) };
+        let decoded = Request::decode(unsafe { request.head() }, unsafe { request.body() }.to_vec())
+            .and_then(|request| Ok((request, Response::decode(unsafe { response.head() }, unsafe { response.body() }.to_vec())?)));
+        let (request, mut response) = match decoded {
+            Ok(decoded) => decoded,
+            Err(_) => return HOOK_FAILED
+        };
+
+        match panic::catch_unwind(AssertUnwindSafe(|| module.on_response(&request, &mut response))) {
+            Ok(()) => {
+                rewritten.write(&response.encode_head(), response.body());
+                HOOK_PASSED
+            },
+            Err(_) => HOOK_FAILED
+        }
+    }
+    #[cfg(not(feature = "http"))]
+    { HOOK_SKIPPED }
+}
+
+#[doc(hidden)]
+pub extern "C" fn on_shutdown<T: MammothInterface>(instance: *const c_void) {
+    call::<T, _>(instance, |module| module.on_shutdown());
+}
+
+#[cfg(test)]
+mod test {
+    use toml::Value;
+
+    use crate::abi::{current_environment, current_host, decode_config, decode_environment, decode_host, deserialize_config, encode_config, encode_context, severity_code, severity_from_code, with_environment, with_host, RawLogger, RawSink, SEVERITY_CRITICAL};
+    use crate::config::HostIdentifier;
+    use crate::diagnostics::Logger;
+    use crate::error::event::Event;
+    use crate::error::severity::Severity;
+
+    #[test]
+    /// Tests the serialization of the configuration passed to the modules.
+    fn test_config() {
+        let table: Value = toml::from_str("x = 73\ny = { z = \"abc\" }").unwrap();
+
+        assert_eq!(decode_config(&encode_config(None)), None);
+        assert_eq!(decode_config(&encode_config(Some(&Value::from("test_error")))), Some(Value::from("test_error")));
+        assert_eq!(decode_config(&encode_config(Some(&table))), Some(table.clone()));
+    }
+
+    #[test]
+    /// Tests the serialization of the host passed to per-host instances.
+    fn test_host_context() {
+        let host = HostIdentifier::new(8080, Some("example.com"));
+        let table: Value = toml::from_str("x = 73").unwrap();
+
+        let context = encode_context(Some(&table), Some(&host), None);
+        assert_eq!(decode_config(&context), Some(table));
+        assert_eq!(decode_host(&context), Some(host.clone()));
+        assert_eq!(decode_environment(&context), None);
+        assert_eq!(decode_host(&encode_context(None, Some(&host), None)), Some(host.clone()));
+        assert_eq!(decode_host(&encode_config(None)), None);
+
+        assert_eq!(current_host(), None);
+        assert_eq!(with_host(Some(host.clone()), current_host), Some(host));
+        assert_eq!(current_host(), None);
+    }
+
+    #[test]
+    /// Tests the serialization of the environment passed to the constructors.
+    fn test_environment_context() {
+        let environment: Value = toml::from_str("region = \"eu-west\"\n[db]\nurl = \"postgres://db\"").unwrap();
+        let table: Value = toml::from_str("x = 73").unwrap();
+
+        let context = encode_context(Some(&table), None, Some(&environment));
+        assert_eq!(decode_config(&context), Some(table));
+        assert_eq!(decode_host(&context), None);
+        assert_eq!(decode_environment(&context), Some(environment.clone()));
+        assert_eq!(decode_environment(&encode_config(None)), None);
+
+        assert_eq!(current_environment(), None);
+        assert_eq!(with_environment(Some(environment.clone()), current_environment), Some(environment));
+        assert_eq!(current_environment(), None);
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct TypedConfig {
+        x: i64,
+        #[serde(default)]
+        y: Option<String>
+    }
+
+    #[derive(Debug, Default, Deserialize, PartialEq)]
+    #[serde(default)]
+    struct DefaultConfig {
+        x: i64
+    }
+
+    #[test]
+    /// Tests the deserialization of the configuration into the type taken by the constructor.
+    fn test_deserialize_config() {
+        let table: Value = toml::from_str("x = 73").unwrap();
+
+        assert_eq!(deserialize_config::<Option<Value>>(None).unwrap(), None);
+        assert_eq!(deserialize_config::<Option<Value>>(Some(table.clone())).unwrap(), Some(table.clone()));
+        assert_eq!(deserialize_config::<TypedConfig>(Some(table.clone())).unwrap(), TypedConfig { x: 73, y: None });
+        assert_eq!(deserialize_config::<Option<TypedConfig>>(None).unwrap(), None);
+        assert_eq!(deserialize_config::<DefaultConfig>(None).unwrap(), DefaultConfig { x: 0 });
+        assert_eq!(deserialize_config::<String>(Some(Value::from("test_error"))).unwrap(), "test_error");
+
+        assert!(deserialize_config::<TypedConfig>(None).is_err());
+        assert!(deserialize_config::<TypedConfig>(Some(Value::from(42))).is_err());
+    }
+
+    #[test]
+    /// Tests the callbacks passed to the modules.
+    fn test_callbacks() {
+        let mut buffer = String::new();
+        RawSink::new(&mut buffer).write("abc");
+        RawSink::new(&mut buffer).write("def");
+        assert_eq!(buffer, "abcdef");
+
+        let mut events: Vec<Event> = Vec::new();
+        let mut logger: &mut dyn Logger = &mut events;
+        RawLogger::new(&mut logger).log(Severity::Warning, "from the module");
+        assert_eq!(events.len(), 1);
+        assert!(events[0].to_string().contains("from the module"));
+
+        for &sev in [Severity::Debug, Severity::Information, Severity::Warning, Severity::Error, Severity::Critical].iter() {
+            assert_eq!(severity_from_code(severity_code(sev)), sev);
+        }
+        assert_eq!(severity_from_code(SEVERITY_CRITICAL + 1), Severity::Critical);
+    }
+
+    #[test]
+    #[cfg(feature = "actix")]
+    /// Tests the fingerprint exported by the modules.
+    fn test_factory_fingerprint() {
+        let mut buffer = String::new();
+        crate::abi::factory_fingerprint(RawSink::new(&mut buffer));
+        assert_eq!(buffer, crate::abi::FACTORY_FINGERPRINT);
+        assert!(buffer.starts_with(&format!("mammoth-setup {}; rustc ", crate::version::version())));
+    }
+
+    #[cfg(feature = "http")]
+    mod http {
+        use std::ffi::c_void;
+
+        use toml::Value;
+
+        use crate::MammothInterface;
+        use crate::abi::{construct, destroy, on_bus, on_config_reload, on_factory, on_health, on_load, on_metrics, on_request, on_response, on_sandbox, on_shutdown, on_start, on_validation, register_logger, version, ForeignModule, ModuleVTable, RawSink, RawStr, ABI_VERSION};
+        use crate::diagnostics::{AsyncLoggerReference, Log, Logger};
+        use crate::error::Error;
+        use crate::http::{Method, Request, Response};
+
+        struct Gate;
+
+        impl Log for Gate {
+            fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+            fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+                None
+            }
+        }
+
+        impl MammothInterface for Gate {
+            fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+                Ok(())
+            }
+
+            fn on_request(&self, request: &mut Request) -> Option<Response> {
+                if request.path() == "/panic" {
+                    panic!("failing module");
+                }
+                request.headers_mut().insert("X-Gate", "passed");
+                match request.headers().get("Authorization") {
+                    Some(_) => None,
+                    None => Some(Response::with_body(b"denied".to_vec(), "text/plain"))
+                }
+            }
+
+            fn on_response(&self, _: &Request, response: &mut Response) {
+                response.headers_mut().insert("X-Gate", "seen");
+            }
+        }
+
+        extern "C" fn construct_gate(config: RawStr, error: RawSink) -> *mut c_void {
+            construct(config, error, |_: Option<Value>| Gate)
+        }
+
+        extern "C" fn write_nothing(_: RawSink) {}
+
+        static GATE: ModuleVTable = ModuleVTable {
+            abi_version: ABI_VERSION,
+            version,
+            host_version_req: write_nothing,
+            metadata: write_nothing,
+            config_schema: write_nothing,
+            factory_fingerprint: write_nothing,
+            construct: construct_gate,
+            destroy: destroy::<Gate>,
+            register_logger: register_logger::<Gate>,
+            on_bus: on_bus::<Gate>,
+            on_sandbox: on_sandbox::<Gate>,
+            on_load: on_load::<Gate>,
+            on_validation: on_validation::<Gate>,
+            on_factory: on_factory::<Gate>,
+            on_start: on_start::<Gate>,
+            on_health: on_health::<Gate>,
+            on_metrics: on_metrics::<Gate>,
+            on_config_reload: on_config_reload::<Gate>,
+            on_request: on_request::<Gate>,
+            on_response: on_response::<Gate>,
+            on_shutdown: on_shutdown::<Gate>
+        };
+
+        #[test]
+        /// Tests the request and response hooks across the boundary.
+        fn test_http_hooks() {
+            let module = unsafe { ForeignModule::new(&GATE, None) }.unwrap();
+
+            let mut request = Request::new(Method::Get, "/private?page=2");
+            request.headers_mut().insert("Authorization", "Basic YWRtaW46YWRtaW4=");
+            request.set_body(vec![0, 255]);
+            assert_eq!(module.on_request(&mut request), None);
+            assert_eq!(request.headers().get("X-Gate"), Some("passed"));
+            assert_eq!(request.query(), Some("page=2"));
+            assert_eq!(request.body(), &[0, 255]);
+
+            let mut request = Request::new(Method::Get, "/private");
+            let mut response = module.on_request(&mut request).unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(response.body(), b"denied");
+            module.on_response(&request, &mut response);
+            assert_eq!(response.headers().get("X-Gate"), Some("seen"));
+            assert_eq!(response.headers().get("Content-Type"), Some("text/plain"));
+
+            let mut request = Request::new(Method::Get, "/panic");
+            assert_eq!(module.on_request(&mut request).unwrap().status(), 500);
+        }
+    }
+}
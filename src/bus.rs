@@ -0,0 +1,306 @@
+//! Topic-based publish/subscribe bus through which the loaded modules interact with each other.
+//!
+//! Every `LoadedModuleSet` owns a `MessageBus`; each module receives a `BusHandle` to it when
+//! loaded (see `MammothInterface::on_bus`), e.g.
+//! ```rust,ignore
+//! fn on_bus(&self, bus: BusHandle) {
+//!     bus.subscribe("auth.login", |message| {
+//!         if let Ok(user) = message.payload::<String>() {
+//!             // ...
+//!         }
+//!     });
+//!     *self.bus.lock().unwrap() = Some(bus);
+//! }
+//! ```
+//! Payloads are serialized as TOML, so that messages can cross the boundary of the module
+//! libraries (see `abi::RawBus`) and modules only have to agree on the shape of the data.
+//!
+//! Messages are delivered synchronously, in subscription order, to the subscribers of the exact
+//! topic. Subscriptions are bound to the handle they are made through: once every clone of a
+//! handle is dropped (e.g. when its module is unloaded), its subscriptions are removed.
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use toml::Value;
+
+use crate::abi::{RawBus, RawSubscriber};
+use crate::error::Error;
+
+/// Function that receives the messages published on a topic.
+pub type Callback = dyn Fn(&Message) + Send + Sync;
+
+/// Identifier of a subscription, used to cancel it (see `BusHandle::unsubscribe`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+impl SubscriptionId {
+    pub(crate) fn from_raw(id: u64) -> SubscriptionId {
+        SubscriptionId(id)
+    }
+    pub(crate) fn into_raw(self) -> u64 {
+        self.0
+    }
+}
+
+/// Structure that contains a message published on the bus.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    topic: String,
+    payload: String
+}
+
+impl Message {
+    /// Creates a new `Message` structure given its topic and payload.
+    ///
+    /// Fails if the payload cannot be serialized as TOML.
+    pub fn new<T>(topic: &str, payload: &T) -> Result<Message, Error>
+        where
+            T: Serialize
+    {
+        let mut table = toml::value::Table::new();
+        table.insert("payload".to_owned(), Value::try_from(payload).map_err(|err| Error::Generic(Box::new(err)))?);
+
+        Ok(Message::from_raw(topic, toml::to_string(&table).map_err(|err| Error::Generic(Box::new(err)))?))
+    }
+    /// Creates a new `Message` structure given its topic and serialized payload.
+    pub(crate) fn from_raw(topic: &str, payload: String) -> Message {
+        Message {
+            topic: topic.to_owned(),
+            payload
+        }
+    }
+
+    /// Obtains the topic of the message.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+    /// Obtains the payload of the message, serialized as TOML.
+    pub fn raw_payload(&self) -> &str {
+        &self.payload
+    }
+    /// Deserializes the payload of the message.
+    pub fn payload<T>(&self) -> Result<T, Error>
+        where
+            T: DeserializeOwned
+    {
+        let mut table: toml::value::Table = toml::from_str(&self.payload)?;
+        let payload = table.remove("payload").ok_or_else(|| Error::InvalidValue("payload".to_owned()))?;
+
+        Ok(payload.try_into()?)
+    }
+}
+
+struct Subscriber {
+    id: u64,
+    client: u64,
+    topic: String,
+    callback: Arc<Callback>
+}
+
+#[derive(Default)]
+struct BusState {
+    next_id: AtomicU64,
+    subscribers: RwLock<Vec<Subscriber>>
+}
+
+impl BusState {
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    fn publish(&self, message: &Message) -> usize {
+        let callbacks: Vec<Arc<Callback>> = self.subscribers.read().unwrap().iter()
+            .filter(|s| s.topic == message.topic)
+            .map(|s| s.callback.clone())
+            .collect();
+
+        for callback in callbacks.iter() {
+            // A failing subscriber must not prevent the delivery to the other ones.
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| callback(message)));
+        }
+
+        callbacks.len()
+    }
+}
+
+/// Structure that contains the bus shared by the modules of a `LoadedModuleSet`.
+#[derive(Default)]
+pub struct MessageBus {
+    state: Arc<BusState>
+}
+
+impl MessageBus {
+    /// Creates a new `MessageBus` structure without subscribers.
+    pub fn new() -> MessageBus {
+        MessageBus {
+            state: Arc::new(BusState::default())
+        }
+    }
+
+    /// Creates a new handle to the bus, owning the subscriptions made through it.
+    pub fn handle(&self) -> BusHandle {
+        BusHandle {
+            transport: Transport::Local(Arc::new(Client {
+                id: self.state.next_id(),
+                state: self.state.clone()
+            }))
+        }
+    }
+    /// Delivers `message` to the subscribers of its topic, returning their number.
+    pub fn publish(&self, message: &Message) -> usize {
+        self.state.publish(message)
+    }
+    /// Obtains the number of subscribers of `topic`.
+    pub fn subscribers(&self, topic: &str) -> usize {
+        self.state.subscribers.read().unwrap().iter()
+            .filter(|s| s.topic == topic)
+            .count()
+    }
+}
+
+struct Client {
+    id: u64,
+    state: Arc<BusState>
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let removed: Vec<Subscriber> = {
+            let mut subscribers = self.state.subscribers.write().unwrap();
+            let (removed, kept) = subscribers.drain(..).partition(|s| s.client == self.id);
+            *subscribers = kept;
+            removed
+        };
+        // Callbacks are released outside of the lock, since they may belong to a module library.
+        drop(removed);
+    }
+}
+
+#[derive(Clone)]
+enum Transport {
+    Local(Arc<Client>),
+    Foreign(RawBus)
+}
+
+/// Handle through which a module publishes and subscribes to messages.
+#[derive(Clone)]
+pub struct BusHandle {
+    transport: Transport
+}
+
+impl BusHandle {
+    /// Creates a new `BusHandle` structure forwarding to the bus of the server across the
+    /// boundary of a module library.
+    pub(crate) fn foreign(bus: RawBus) -> BusHandle {
+        BusHandle {
+            transport: Transport::Foreign(bus)
+        }
+    }
+
+    /// Publishes `payload` on `topic`, returning the number of subscribers it is delivered to.
+    ///
+    /// Fails if the payload cannot be serialized as TOML.
+    pub fn publish<T>(&self, topic: &str, payload: &T) -> Result<usize, Error>
+        where
+            T: Serialize
+    {
+        Ok(self.publish_message(&Message::new(topic, payload)?))
+    }
+    /// Publishes `message`, returning the number of subscribers it is delivered to.
+    pub fn publish_message(&self, message: &Message) -> usize {
+        match self.transport {
+            Transport::Local(ref client) => client.state.publish(message),
+            Transport::Foreign(ref bus) => bus.publish(message)
+        }
+    }
+    /// Subscribes `callback` to the messages published on `topic`.
+    pub fn subscribe<F>(&self, topic: &str, callback: F) -> SubscriptionId
+        where
+            F: Fn(&Message) + Send + Sync + 'static
+    {
+        match self.transport {
+            Transport::Local(ref client) => {
+                let id = client.state.next_id();
+                client.state.subscribers.write().unwrap().push(Subscriber {
+                    id,
+                    client: client.id,
+                    topic: topic.to_owned(),
+                    callback: Arc::new(callback)
+                });
+                SubscriptionId(id)
+            },
+            Transport::Foreign(ref bus) => bus.subscribe(topic, RawSubscriber::new(callback))
+        }
+    }
+    /// Cancels the subscription `id` made through this handle, returning `true` if it existed.
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        match self.transport {
+            Transport::Local(ref client) => {
+                let removed = {
+                    let mut subscribers = client.state.subscribers.write().unwrap();
+                    let position = subscribers.iter().position(|s| s.id == id.0 && s.client == client.id);
+                    position.map(|i| subscribers.remove(i))
+                };
+                removed.is_some()
+            },
+            Transport::Foreign(ref bus) => bus.unsubscribe(id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use crate::bus::{Message, MessageBus};
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Login {
+        user: String,
+        attempts: u32
+    }
+
+    #[test]
+    /// Tests the serialization of the payloads.
+    fn test_message() {
+        let login = Login { user: "admin".to_owned(), attempts: 3 };
+        let message = Message::new("auth.login", &login).unwrap();
+
+        assert_eq!(message.topic(), "auth.login");
+        assert_eq!(message.payload::<Login>().unwrap(), login);
+        assert_eq!(Message::new("counter", &42).unwrap().payload::<i64>().unwrap(), 42);
+        assert!(message.payload::<String>().is_err());
+    }
+
+    #[test]
+    /// Tests publishing and subscribing through the handles of a bus.
+    fn test_publish_subscribe() {
+        let bus = MessageBus::new();
+        let publisher = bus.handle();
+        let subscriber = bus.handle();
+        let received: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let sink = received.clone();
+        let id = subscriber.subscribe("greetings", move |m| sink.lock().unwrap().push(m.payload().unwrap()));
+        subscriber.subscribe("other", |_| panic!("unexpected topic"));
+        subscriber.subscribe("greetings", |_| panic!("failing subscriber"));
+
+        assert_eq!(publisher.publish("greetings", &"hello").unwrap(), 2);
+        assert_eq!(*received.lock().unwrap(), vec!["hello"]);
+
+        assert!(!publisher.unsubscribe(id));
+        assert!(subscriber.unsubscribe(id));
+        assert_eq!(bus.subscribers("greetings"), 1);
+
+        let clone = subscriber.clone();
+        drop(subscriber);
+        assert_eq!(bus.subscribers("other"), 1);
+        drop(clone);
+        assert_eq!(bus.subscribers("other"), 0);
+        assert_eq!(publisher.publish("greetings", &"hello").unwrap(), 0);
+    }
+}
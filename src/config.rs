@@ -1,15 +1,45 @@
 //! The `ConfigurationFile` structure contains the configuration for the entire Mammoth application.
 
+#[cfg(feature = "acme")]
+pub mod acme;
+pub mod auth;
+pub mod cache;
+pub mod compression;
+pub mod glob;
 pub mod host;
 pub mod mammoth;
+pub mod migrations;
 pub mod port;
+pub mod proxy;
+pub mod rate_limit;
+pub mod redirect;
+pub mod rlimits;
+pub mod schema;
+#[cfg(feature = "self_signed")]
+pub mod self_signed;
 pub mod module;
+pub mod observability;
+pub mod path;
+pub mod set;
+pub mod shard;
+pub mod size;
+pub mod socket;
+pub mod status;
+pub mod tls;
+pub mod upstream;
 
 pub use self::host::Host;
 pub use self::host::HostIdentifier;
 pub use self::mammoth::Mammoth;
-pub use self::module::Module;
+pub use self::migrations::DeprecatedKey;
+pub use self::module::{EffectiveModule, Module};
+pub use self::path::ConfigPath;
+pub use self::set::ConfigurationSet;
+pub use self::shard::ShardPlan;
+pub use self::tls::TlsProfile;
+pub use self::upstream::Upstream;
 
+use std::collections::BTreeMap;
 use std::io::Read;
 use std::fs::File;
 use std::marker::PhantomData;
@@ -17,8 +47,12 @@ use std::path::Path;
 
 use toml::Value;
 
+use crate::config::module::{chain_order, load_order, merge_config, ModuleGroup, ModuleName, ModuleValidator};
+use crate::metadata::ModuleKind;
 use crate::diagnostics::{IdValidator, Logger, Validator};
 use crate::error::Error;
+use crate::error::event::Event;
+use crate::loaded::library::LoadedModuleSet;
 use crate::error::severity::Severity;
 
 /// Structure that contains all the configuration for the Mammoth application.
@@ -29,11 +63,23 @@ pub struct ConfigurationFile {
     hosts: Vec<Host>,
     #[serde(rename = "mod", default = "default_mods")]
     mods: Vec<Module>,
-    environment: Option<Value>
+    #[serde(rename = "upstream", default = "default_upstreams")]
+    upstreams: Vec<Upstream>,
+    #[serde(default = "default_tls")]
+    tls: BTreeMap<String, TlsProfile>,
+    #[serde(rename = "module_group", default)]
+    module_groups: BTreeMap<String, ModuleGroup>,
+    environment: Option<Value>,
+    #[serde(skip)]
+    deprecations: Vec<DeprecatedKey>
 }
 
 #[doc(hidden)]
 fn default_mods() -> Vec<Module> { Vec::new() }
+#[doc(hidden)]
+fn default_upstreams() -> Vec<Upstream> { Vec::new() }
+#[doc(hidden)]
+fn default_tls() -> BTreeMap<String, TlsProfile> { BTreeMap::new() }
 
 impl ConfigurationFile {
     /// Creates a `ConfigurationFile` structure given a TOML file.
@@ -46,11 +92,69 @@ impl ConfigurationFile {
 
         file.read_to_string(&mut contents)?;
 
-        Ok(toml::from_str(&contents)?)
+        ConfigurationFile::from_str(&contents)
     }
     /// Creates a `ConfigurationFile` structure given a TOML string.
     pub fn from_str(contents: &str) -> Result<ConfigurationFile, Error> {
-        Ok(toml::from_str(contents)?)
+        ConfigurationFile::from_value(toml::from_str(contents)?)
+    }
+    /// Creates a `ConfigurationFile` structure given a TOML value.
+    pub fn from_value(value: Value) -> Result<ConfigurationFile, Error> {
+        #[cfg(feature = "chaos")]
+        crate::testing::chaos::check(crate::testing::chaos::FailurePoint::ConfigParse)?;
+
+        let deprecations = migrations::check(&value);
+        let mut configuration: ConfigurationFile = value.try_into()?;
+        configuration.deprecations = deprecations;
+        configuration.resolve_tls();
+        #[cfg(feature = "acme")]
+        configuration.resolve_acme();
+
+        Ok(configuration)
+    }
+    /// Generates the certificates of the bindings declared with `self_signed = true`, for the
+    /// hostname of their host (or `localhost`; see `Binding::generate_self_signed`).
+    ///
+    /// Parsing a configuration does not generate them: the runtime calls this before binding the
+    /// ports, so that configurations parsed only to be validated or inspected do not write keys.
+    #[cfg(feature = "self_signed")]
+    pub fn generate_self_signed(&mut self) -> Result<(), Error> {
+        for host in self.hosts.iter_mut().filter(|h| h.binding().self_signed()) {
+            let hostname = host.name().unwrap_or("localhost").to_owned();
+            host.binding_mut().generate_self_signed(&hostname)?;
+        }
+
+        Ok(())
+    }
+    /// Applies the referenced TLS profiles to the bindings of the hosts.
+    ///
+    /// Bindings referencing an unknown profile are left without certificate and key, and are
+    /// reported by the validator.
+    fn resolve_tls(&mut self) {
+        let profiles = &self.tls;
+        for host in self.hosts.iter_mut() {
+            let profile = host.binding().tls_profile().and_then(|name| profiles.get(name));
+            if let Some(profile) = profile {
+                host.binding_mut().apply_tls_profile(profile);
+            }
+        }
+    }
+    /// Points the certificate and the key of the ACME bindings to the ACME cache directory.
+    #[cfg(feature = "acme")]
+    fn resolve_acme(&mut self) {
+        let acme = match self.mammoth.acme() {
+            Some(acme) => acme.clone(),
+            None => return
+        };
+        for host in self.hosts.iter_mut().filter(|h| h.binding().acme()) {
+            if let Some(domain) = host.name().map(|n| n.to_owned()) {
+                host.binding_mut().set_acme_paths(&acme, &domain);
+            }
+        }
+    }
+    /// Obtains the list of the deprecated keys found when the configuration was parsed.
+    pub fn deprecations(&self) -> Vec<&DeprecatedKey> {
+        self.deprecations.iter().collect()
     }
     /// Obtains the underlying `Mammoth` structure.
     pub fn mammoth(&self) -> &Mammoth {
@@ -80,10 +184,128 @@ impl ConfigurationFile {
         self.hosts.iter().position(|h| h.is(&id)).is_some()
     }
 
+    /// Obtains a vector of references to the upstream groups.
+    pub fn upstreams(&self) -> Vec<&Upstream> {
+        self.upstreams.iter().collect()
+    }
+    /// Obtains the upstream group named `name`, if any.
+    pub fn upstream(&self, name: &str) -> Option<&Upstream> {
+        self.upstreams.iter().find(|u| u.name() == name)
+    }
+    /// Adds an upstream group.
+    pub fn add_upstream(&mut self, upstream: Upstream) {
+        self.upstreams.push(upstream);
+    }
+    /// Removes an upstream group by its `name`.
+    pub fn remove_upstream(&mut self, name: &str) {
+        self.upstreams.retain(|u| u.name() != name);
+    }
+
+    /// Obtains the TLS profiles, by name.
+    pub fn tls_profiles(&self) -> &BTreeMap<String, TlsProfile> {
+        &self.tls
+    }
+    /// Obtains the TLS profile named `name`, if any.
+    pub fn tls_profile(&self, name: &str) -> Option<&TlsProfile> {
+        self.tls.get(name)
+    }
+    /// Adds a TLS profile named `name`, replacing the previous one with the same name, if any.
+    ///
+    /// The bindings referencing the profile are updated accordingly.
+    pub fn add_tls_profile(&mut self, name: &str, profile: TlsProfile) {
+        self.tls.insert(name.to_owned(), profile);
+        self.resolve_tls();
+    }
+
+    /// Obtains the module groups, by name.
+    pub fn module_groups(&self) -> &BTreeMap<String, ModuleGroup> {
+        &self.module_groups
+    }
+    /// Obtains the module group named `name`, if any.
+    pub fn module_group(&self, name: &str) -> Option<&ModuleGroup> {
+        self.module_groups.get(name)
+    }
+    /// Adds a module group named `name`, replacing the previous one with the same name, if any.
+    pub fn add_module_group(&mut self, name: &str, group: ModuleGroup) {
+        self.module_groups.insert(name.to_owned(), group);
+    }
+    /// Removes the module group named `name`.
+    pub fn remove_module_group(&mut self, name: &str) {
+        self.module_groups.remove(name);
+    }
+    /// Expands the module groups of `host`, obtaining the global modules as seen by the host and
+    /// the modules enabled on the host through its groups only.
+    ///
+    /// Group members defined globally keep their global definition, but are enabled on the host
+    /// even if they are globally disabled (i.e. opt-in modules); the other members are taken once,
+    /// in the order in which the groups are referenced, unless the host defines them itself.
+    /// References to unknown groups are ignored (see the validation of the configuration).
+    fn group_mods(&self, host: &Host) -> (Vec<Module>, Vec<Module>) {
+        let names: Vec<&ModuleName> = host.groups().iter()
+            .filter_map(|group| self.module_groups.get(group))
+            .flat_map(|group| group.modules().iter())
+            .collect();
+        let global: Vec<Module> = self.mods().into_iter()
+            .map(|m| {
+                let mut module = m.clone();
+                if names.contains(&m.module_name()) { module.enable(); }
+                module
+            })
+            .collect();
+        let defined: Vec<&Module> = host.mods().into_iter().chain(global.iter()).collect();
+        let mut mods: Vec<Module> = Vec::new();
+        for name in names {
+            if !defined.iter().any(|m| m.module_name() == name) && !mods.iter().any(|m| m.module_name() == name) {
+                mods.push(Module::with_name(name.clone()));
+            }
+        }
+        (global, mods)
+    }
+
+    /// Obtains the plans of the listener sockets, one for each port in use.
+    ///
+    /// Hosts sharing a port share its listeners: the plan is taken from the first of these hosts.
+    pub fn shard_plans(&self) -> Vec<ShardPlan> {
+        let mut plans: Vec<ShardPlan> = Vec::new();
+        for host in self.hosts.iter() {
+            if !plans.iter().any(|p| p.port() == host.binding().port()) {
+                plans.push(host.binding().shard_plan());
+            }
+        }
+        plans
+    }
+
+    /// Obtains the global `[environment]` table, if any.
+    pub fn environment(&self) -> Option<&Value> {
+        self.environment.as_ref()
+    }
+    /// Sets the global `[environment]` table.
+    pub fn set_environment(&mut self, environment: Option<Value>) {
+        self.environment = environment;
+    }
+    /// Obtains the environment passed to the constructors of the modules of the host identified
+    /// by `id` (see `abi::current_environment`): the global `[environment]` table deep-merged with
+    /// the `[host.environment]` table of the host, whose values take precedence.
+    ///
+    /// If no host matches `id`, this function returns the global table.
+    pub fn environment_for(&self, id: &HostIdentifier) -> Option<Value> {
+        let host = self.hosts.iter().find(|h| h.is(id)).and_then(|h| h.environment());
+        match (self.environment.as_ref(), host) {
+            (Some(global), Some(host)) => {
+                let mut environment = global.clone();
+                merge_config(&mut environment, host);
+                Some(environment)
+            },
+            (global, host) => host.or(global).cloned()
+        }
+    }
+
     /// Obtains a vector of references to the underlying `Module` structures defining module
-    /// configuration for all hosts.
+    /// configuration for all hosts, sorted by priority (see `Module::priority`).
     pub fn mods(&self) -> Vec<&Module> {
-        self.mods.iter().collect()
+        let mut mods: Vec<&Module> = self.mods.iter().collect();
+        mods.sort_by_key(|m| m.priority());
+        mods
     }
     /// Obtains a vector of mutable references to the underlying `Module` structures defining module
     /// configuration for all hosts.
@@ -102,23 +324,236 @@ impl ConfigurationFile {
     pub fn has_module(&self, name: &str) -> bool {
         self.mods.iter().position(|m| m.name() == name).is_some()
     }
+    /// Obtains the modules effectively enabled for the host identified by `id`, combining the
+    /// global modules with the modules of the host as specified by `[mammoth].module_conflicts`
+    /// (see `EffectiveModule::combine_with`).
+    ///
+    /// Profiles loaded through a `ConfigurationSet` are already merged into the configuration.
+    /// If no host matches `id`, this function returns an empty vector.
+    pub fn modules_for(&self, id: &HostIdentifier) -> Vec<EffectiveModule> {
+        match self.hosts.iter().find(|h| h.is(id)) {
+            Some(host) => {
+                let (global, grouped) = self.group_mods(host);
+                let global: Vec<&Module> = global.iter().collect();
+                let mut mods = host.mods();
+                mods.extend(grouped.iter());
+                EffectiveModule::combine_with(&global, &mods, self.mammoth().module_conflicts())
+            },
+            None => Vec::new()
+        }
+    }
+    /// Obtains the modules effectively enabled for the host identified by `id` as by
+    /// `modules_for`.
+    ///
+    /// Fails with `Error::ModuleConflict` if the host redefines a global module while
+    /// `[mammoth].module_conflicts` is `error` (see `EffectiveModule::resolve`).
+    pub fn resolve_modules_for(&self, id: &HostIdentifier) -> Result<Vec<EffectiveModule>, Error> {
+        match self.hosts.iter().find(|h| h.is(id)) {
+            Some(host) => {
+                let (global, grouped) = self.group_mods(host);
+                let global: Vec<&Module> = global.iter().collect();
+                let mut mods = host.mods();
+                mods.extend(grouped.iter());
+                EffectiveModule::resolve(&global, &mods, self.mammoth().module_conflicts())
+            },
+            None => Ok(Vec::new())
+        }
+    }
+    /// Obtains the middleware modules effectively enabled for the host identified by `id`, in the
+    /// order in which they wrap its requests (see `config::module::chain_order`).
+    ///
+    /// The kind of every module is the one specified in the configuration or else the one declared
+    /// by its library (see `Module::kind_in`). Fails with `Error::MiddlewareCycle` if the `before`
+    /// and `after` constraints of the modules form a cycle.
+    pub fn middleware_chain_for(&self, id: &HostIdentifier) -> Result<Vec<Module>, Error> {
+        let mods_dir = self.mammoth().mods_dir().unwrap_or_else(|| Path::new(""));
+        let modules = self.modules_for(id);
+        let middleware: Vec<&Module> = modules.iter()
+            .map(|e| e.module())
+            .filter(|m| m.kind_in(mods_dir, self.mammoth().library_names()) == ModuleKind::Middleware)
+            .collect();
+
+        Ok(chain_order(&middleware)?.into_iter().cloned().collect())
+    }
+    /// Obtains the modules effectively enabled for the host identified by `id` through which its
+    /// requests go (see `LoadedModuleSet::handle_request`): its middleware chain, followed by its
+    /// handler modules in loading order.
+    ///
+    /// Fails as `middleware_chain_for` does.
+    pub fn request_chain_for(&self, id: &HostIdentifier) -> Result<Vec<Module>, Error> {
+        let mods_dir = self.mammoth().mods_dir().unwrap_or_else(|| Path::new(""));
+        let mut chain = self.middleware_chain_for(id)?;
+        chain.extend(self.modules_for(id).into_iter()
+            .map(EffectiveModule::into_module)
+            .filter(|m| m.kind_in(mods_dir, self.mammoth().library_names()) == ModuleKind::Handler));
+
+        Ok(chain)
+    }
+    /// Loads the modules effectively enabled for the host identified by `id` into `mod_set`,
+    /// checking their versions against the compatibility policy of the configuration and loading
+    /// every module after the modules it requires.
+    ///
+    /// Shared modules already loaded for another host are not loaded again, and optional modules
+    /// that fail to load are skipped with a warning logged into `logger` (see
+    /// `LoadedModuleSet::load_for_host_with`).
+    pub fn load_modules_for(&self, id: &HostIdentifier, mod_set: &mut LoadedModuleSet, logger: &mut dyn Logger) -> Result<(), Error> {
+        mod_set.set_policy(self.mammoth().compatibility().clone());
+        mod_set.set_library_names(self.mammoth().library_names().clone());
+        mod_set.set_module_policy(self.mammoth().module_policy().clone());
+        mod_set.set_timeout(self.mammoth().module_timeout());
+        mod_set.set_environment(self.environment_for(id));
+        let modules = self.resolve_modules_for(id)?;
+        let modules: Vec<&Module> = modules.iter().map(|e| e.module()).collect();
+
+        mod_set.load_for_host_with(id, &modules, logger)
+    }
+    /// Notifies the modules of `mod_set` that the configuration is reloaded, passing them the
+    /// configuration of the modules effectively enabled for the host identified by `id`.
+    pub fn reload_modules_for(&self, id: &HostIdentifier, mod_set: &LoadedModuleSet) {
+        let modules = self.modules_for(id);
+        let modules: Vec<&Module> = modules.iter().map(|e| e.module()).collect();
+
+        mod_set.reload_config_for(id, &modules);
+    }
+}
+
+/// Checks that the module `module` of the host `host` is allowed by the module policy of the
+/// configuration `item` and that it can be constructed from its configuration (see
+/// `ModuleValidator`).
+fn validate_module(logger: &mut dyn Logger, item: &ConfigurationFile, host: &HostIdentifier, module: &Module, mods_dir: &Path, environment: Option<&Value>) -> Result<(), Error> {
+    let path = if module.is_static() {
+        None
+    } else {
+        Some(module.library_in(mods_dir, item.mammoth().library_names()))
+    };
+    if let Err(err) = item.mammoth().module_policy().check(module.name(), path.as_ref().map(|p| p.path())) {
+        let desc = format!("Module '{}' of host '{}' is not allowed by [mammoth].module_policy.", module.name(), host);
+        logger.log(Severity::Critical, &desc);
+        Err(err)?;
+    }
+    ModuleValidator(mods_dir, item.mammoth().compatibility(), item.mammoth().library_names(), item.mammoth().module_timeout(), environment)
+        .validate(logger, module)
 }
 
 impl Validator<ConfigurationFile> for () {
     fn validate(&self, logger: &mut Logger, item: &ConfigurationFile) -> Result<(), Error> {
+        for deprecated in item.deprecations() {
+            logger.log(Severity::Warning, &deprecated.to_string());
+        }
+
         ().validate(logger, item.mammoth())?;
 
+        for (name, profile) in item.tls_profiles() {
+            if !item.hosts().into_iter().any(|h| h.binding().tls_profile() == Some(name.as_str())) {
+                let desc = format!("TLS profile '{}' is not used by any host.", name);
+                logger.log(Severity::Warning, &desc);
+            }
+            ().validate(logger, profile)?;
+        }
+        for host in item.hosts() {
+            if let Some(name) = host.binding().tls_profile() {
+                if item.tls_profile(name).is_none() {
+                    let desc = format!("Host '{}' references the unknown TLS profile '{}'.", host.identifier(), name);
+                    logger.log(Severity::Error, &desc);
+                    Err(Error::InvalidValue("listen.tls".to_owned()))?;
+                }
+            }
+        }
+
+        #[cfg(feature = "acme")]
+        for host in item.hosts().into_iter().filter(|h| h.binding().acme()) {
+            if item.mammoth().acme().is_none() {
+                let desc = format!("Port {} uses ACME but [mammoth.acme] is not specified.", host.binding().port());
+                logger.log(Severity::Critical, &desc);
+                Err(Error::InvalidValue("mammoth.acme".to_owned()))?;
+            }
+            if host.name().is_none() {
+                let desc = format!("Port {} uses ACME but its host has no hostname.", host.binding().port());
+                logger.log(Severity::Critical, &desc);
+                Err(Error::InvalidValue("hostname".to_owned()))?;
+            }
+        }
+
         if item.hosts().is_empty() {
             logger.log(Severity::Critical, "No host specified.");
             Err(Error::NoHost)?;
         }
 
+        for plan in item.shard_plans() {
+            let conflict = item.hosts().into_iter()
+                .filter(|h| h.binding().port() == plan.port())
+                .any(|h| h.binding().shard_plan() != plan);
+            if conflict {
+                let desc = format!("Hosts listening on port {} specify different shards or pin_to_cores.", plan.port());
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("shards".to_owned()))?;
+            }
+        }
+
+        IdValidator(Severity::Error, (), PhantomData)
+            .validate(logger, &item.upstreams())?;
+        for host in item.hosts() {
+            for rule in host.proxy_rules() {
+                if item.upstream(rule.upstream()).is_none() {
+                    let desc = format!("Proxy rule '{}' of host '{}' references the unknown upstream '{}'.", rule.path(), host.identifier(), rule.upstream());
+                    logger.log(Severity::Error, &desc);
+                    Err(Error::InvalidValue("proxy.upstream".to_owned()))?;
+                }
+            }
+            for group in host.groups() {
+                if item.module_group(group).is_none() {
+                    let desc = format!("Host '{}' references the unknown module group '{}'.", host.identifier(), group);
+                    logger.log(Severity::Error, &desc);
+                    Err(Error::InvalidValue("host.groups".to_owned()))?;
+                }
+            }
+        }
+
+        for host in item.hosts() {
+            if let Err(err) = item.resolve_modules_for(&host.identifier()) {
+                let desc = format!("Invalid modules for host '{}': {} ([mammoth].module_conflicts is '{}').", host.identifier(), err, item.mammoth().module_conflicts());
+                logger.log(Severity::Critical, &desc);
+                Err(err)?;
+            }
+        }
+
         let mods_dir = item.mammoth().mods_dir();
         if let Some(mods_dir) = mods_dir {
-            IdValidator(Severity::Critical, mods_dir.to_path_buf(), PhantomData)
+            IdValidator(Severity::Critical, (), PhantomData)
                 .validate(logger, &item.mods())?;
-            IdValidator(Severity::Critical, mods_dir.to_path_buf(), PhantomData)
+            IdValidator(Severity::Critical, (), PhantomData)
                 .validate(logger, &item.hosts())?;
+
+            for host in item.hosts() {
+                let modules = item.modules_for(&host.identifier());
+                let environment = item.environment_for(&host.identifier());
+                for effective in modules.iter() {
+                    let module = effective.module();
+                    if module.is_required() {
+                        validate_module(logger, item, &host.identifier(), module, mods_dir, environment.as_ref())?;
+                        continue;
+                    }
+                    let mut events: Vec<Event> = Vec::new();
+                    if let Err(err) = validate_module(&mut events, item, &host.identifier(), module, mods_dir, environment.as_ref()) {
+                        for event in events {
+                            logger.log(event.severity().min(Severity::Warning), event.description());
+                        }
+                        let desc = format!("Optional module '{}' of host '{}' is invalid and will be skipped: {}.", module.name(), host.identifier(), err);
+                        logger.log(Severity::Warning, &desc);
+                    }
+                }
+                let modules: Vec<&Module> = modules.iter().map(|e| e.module()).collect();
+                if let Err(err) = load_order(&modules, |m| m.requirements_in(mods_dir, item.mammoth().library_names())) {
+                    let desc = format!("Invalid module dependencies for host '{}': {}.", host.identifier(), err);
+                    logger.log(Severity::Critical, &desc);
+                    Err(err)?;
+                }
+                if let Err(err) = item.middleware_chain_for(&host.identifier()) {
+                    let desc = format!("Invalid middleware chain for host '{}': {}.", host.identifier(), err);
+                    logger.log(Severity::Critical, &desc);
+                    Err(err)?;
+                }
+            }
         } else {
             if !item.mods().is_empty() {
                 logger.log(Severity::Critical, "Enabled modules without specifying modules directory.");
@@ -132,10 +567,48 @@ impl Validator<ConfigurationFile> for () {
 
 #[cfg(test)]
 mod tests {
-    use crate::config::{ConfigurationFile, HostIdentifier};
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    use semver::VersionReq;
+    use toml::Value;
+
+    use crate::MammothInterface;
+    use crate::abi;
+    use crate::config::{ConfigurationFile, HostIdentifier, Module, Upstream};
+    use crate::config::module::{LoadPolicy, ModuleConflict};
+    use crate::config::port::TlsVersion;
     use crate::error::Error;
     use crate::error::event::Event;
+    use crate::error::severity::Severity;
     use crate::diagnostics::Validator;
+    use crate::diagnostics::{AsyncLoggerReference, Log, Logger};
+    use crate::loaded::library::LoadedModuleSet;
+    use crate::loaded::registry::StaticModuleRegistry;
+    use crate::version::CompatibilityPolicy;
+
+    static ENVIRONMENT: Mutex<Option<Value>> = Mutex::new(None);
+
+    struct EnvironmentModule;
+
+    impl Log for EnvironmentModule {
+        fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            None
+        }
+    }
+
+    impl MammothInterface for EnvironmentModule {
+        fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn environment_aware(_: Option<&Value>) -> Box<dyn MammothInterface> {
+        *ENVIRONMENT.lock().unwrap() = abi::current_environment();
+        Box::new(EnvironmentModule)
+    }
 
     #[test]
     /// Tests a common configuration file.
@@ -155,11 +628,209 @@ mod tests {
         let err = ().validate(&mut events, &configuration).unwrap_err();
 
         match err {
-            Error::Unknown => {},
+            Error::Module(ref message) if message == &Error::Unknown.to_string() => {},
             _ => { panic!("Should be 'Unknown' error generated in module validation."); }
         }
     }
 
+    #[test]
+    /// Tests the module compatibility policy.
+    fn test_compatibility() {
+        let toml = r##"
+        [mammoth]
+        mods_dir = "./target/debug/"
+            [mammoth.compatibility]
+            requirement = ">=1.0.0"
+                [mammoth.compatibility.modules.mod_test]
+                allow = ["0.0.1"]
+
+        [[host]]
+        listen = 8080
+
+        [[mod]]
+        name = "mod_test"
+        "##;
+        let mut configuration = ConfigurationFile::from_str(toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        ().validate(&mut events, &configuration).unwrap();
+        assert!(events.iter().any(|e| e.to_string().contains("loaded by compatibility policy")));
+
+        configuration.mammoth_mut().set_compatibility(CompatibilityPolicy::new(VersionReq::parse(">=1.0.0").unwrap()));
+        match ().validate(&mut events, &configuration) {
+            Err(Error::InvalidModuleVersion(_, _)) => {},
+            _ => panic!("Should be 'InvalidModuleVersion' error.")
+        }
+    }
+
+    #[test]
+    /// Tests the TLS profiles shared by several hosts.
+    fn test_tls_profiles() {
+        let toml = r##"
+        [mammoth]
+
+        [tls.wildcard]
+        cert = "./tests/test_cert.pem"
+        key = "./tests/test_key.pem"
+        tls_min_version = "1.2"
+        session_cache = 1024
+
+        [[host]]
+        hostname = "www.example.com"
+        listen = { port = 8443, tls = "wildcard" }
+
+        [[host]]
+        hostname = "api.example.com"
+        listen = { port = 8443, tls = "wildcard", tls_min_version = "1.3" }
+        "##;
+        let mut configuration = ConfigurationFile::from_str(toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        ().validate(&mut events, &configuration).unwrap();
+        for host in configuration.hosts() {
+            let binding = host.binding();
+            assert!(binding.secure());
+            assert_eq!(binding.tls_profile(), Some("wildcard"));
+            assert_eq!(binding.cert(), Some(Path::new("./tests/test_cert.pem")));
+            assert_eq!(binding.session_cache(), Some(1024));
+            assert!(binding.ssl_acceptor().is_ok());
+        }
+        assert_eq!(configuration.hosts()[0].binding().tls_min_version(), Some(TlsVersion::Tls12));
+        assert_eq!(configuration.hosts()[1].binding().tls_min_version(), Some(TlsVersion::Tls13));
+
+        configuration.hosts_mut()[1].binding_mut().set_tls_profile("missing");
+        match ().validate(&mut events, &configuration) {
+            Err(Error::InvalidValue(ref key)) if key == "listen.tls" => {},
+            _ => panic!("Should be 'InvalidValue' error.")
+        }
+
+        let toml = r##"
+        [mammoth]
+
+        [[host]]
+        listen = { port = 8443, tls = "wildcard", cert = "./tests/test_cert.pem" }
+        "##;
+        assert!(ConfigurationFile::from_str(toml).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "acme")]
+    /// Tests the resolution of the certificates of the ACME bindings.
+    fn test_acme() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml = format!(r##"
+        [mammoth]
+            [mammoth.acme]
+            email = "admin@example.com"
+            cache_dir = "{}"
+
+        [[host]]
+        hostname = "example.com"
+        listen = {{ port = 443, acme = true }}
+        "##, dir.path().display());
+        let mut configuration = ConfigurationFile::from_str(&toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(configuration.hosts()[0].binding().cert(), Some(dir.path().join("example.com/cert.pem").as_path()));
+        ().validate(&mut events, &configuration).unwrap();
+
+        configuration.hosts_mut()[0].clear_name();
+        match ().validate(&mut events, &configuration) {
+            Err(Error::InvalidValue(_)) => {},
+            _ => panic!("Should be 'InvalidValue' error.")
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "self_signed")]
+    /// Tests that self-signed certificates are generated on request only, not while parsing.
+    fn test_self_signed() {
+        let toml = r#"
+        [mammoth]
+
+        [[host]]
+        hostname = "localhost"
+        listen = { port = 8443, self_signed = true }
+        "#;
+        let mut configuration = ConfigurationFile::from_str(toml).unwrap();
+
+        assert!(configuration.hosts()[0].binding().cert().is_none());
+        configuration.generate_self_signed().unwrap();
+        let cert = configuration.hosts()[0].binding().cert().unwrap().to_path_buf();
+        assert!(cert.is_file());
+        std::fs::remove_dir_all(cert.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    /// Tests the plans of the listener sockets.
+    fn test_shard_plans() {
+        let toml = r##"
+        [mammoth]
+
+        [[host]]
+        hostname = "localhost"
+        listen = { port = 8080, shards = 2 }
+
+        [[host]]
+        listen = { port = 8080, shards = 2 }
+
+        [[host]]
+        listen = 8088
+        "##;
+        let mut configuration = ConfigurationFile::from_str(toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+        let plans = configuration.shard_plans();
+
+        assert_eq!(plans.len(), 2);
+        assert_eq!(plans[0].listeners().len(), 2);
+        assert_eq!(plans[1].listeners().len(), 1);
+        ().validate(&mut events, &configuration).unwrap();
+
+        configuration.hosts_mut()[1].binding_mut().set_shards(4);
+        match ().validate(&mut events, &configuration) {
+            Err(Error::InvalidValue(_)) => {},
+            _ => panic!("Should be 'InvalidValue' error.")
+        }
+    }
+
+    #[test]
+    /// Tests the upstream groups and the proxy rules referencing them.
+    fn test_upstreams() {
+        let toml = r##"
+        [mammoth]
+
+        [[host]]
+        listen = 8080
+            [[host.proxy]]
+            path = "/api/**"
+            upstream = "api"
+
+        [[upstream]]
+        name = "api"
+        backends = ["127.0.0.1:8000", "127.0.0.1:8001"]
+        health_check = 10
+        "##;
+        let mut configuration = ConfigurationFile::from_str(toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(configuration.upstreams().len(), 1);
+        assert_eq!(configuration.hosts()[0].proxy_rule_for("/api/users").unwrap().upstream(), "api");
+        assert!(configuration.hosts()[0].proxy_rule_for("/index.html").is_none());
+        ().validate(&mut events, &configuration).unwrap();
+
+        configuration.add_upstream(Upstream::new("api", &["127.0.0.1:9000"]));
+        match ().validate(&mut events, &configuration) {
+            Err(Error::DuplicateItem(_)) => {},
+            _ => panic!("Should be 'DuplicateItem' error.")
+        }
+
+        configuration.remove_upstream("api");
+        match ().validate(&mut events, &configuration) {
+            Err(Error::InvalidValue(_)) => {},
+            _ => panic!("Should be 'InvalidValue' error.")
+        }
+    }
+
     #[test]
     /// Tests a minimal configuration TOML.
     fn test_config_minimal() {
@@ -233,6 +904,304 @@ mod tests {
         assert!(!configuration.has_host(HostIdentifier::new(8088, None)));
     }
 
+    #[test]
+    /// Tests the `modules_for` function.
+    fn test_modules_for() {
+        let toml = r##"
+        [mammoth]
+        mods_dir = "./mods/"
+
+        [[host]]
+        listen = 8080
+
+        [[host]]
+        listen = 8088
+            [[host.mod]]
+            name = "mod_test"
+            enabled = false
+
+            [[host.mod]]
+            name = "mod_host"
+
+        [[mod]]
+        name = "mod_test"
+        "##;
+        let configuration = ConfigurationFile::from_str(toml).unwrap();
+
+        let modules = configuration.modules_for(&HostIdentifier::new(8080, None));
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].module().name(), "mod_test");
+
+        let modules = configuration.modules_for(&HostIdentifier::new(8088, None));
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].module().name(), "mod_host");
+
+        assert!(configuration.modules_for(&HostIdentifier::new(8443, None)).is_empty());
+    }
+
+    #[test]
+    /// Tests the module policy.
+    fn test_module_policy() {
+        let toml = r##"
+        [mammoth]
+        mods_dir = "./target/debug/"
+        module_policy = { allow = ["mod_*"], deny = ["**/debug/*mod_test*"] }
+
+        [[host]]
+        listen = 8080
+
+        [[mod]]
+        name = "mod_test"
+        "##;
+        let mut configuration = ConfigurationFile::from_str(toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        match ().validate(&mut events, &configuration) {
+            Err(Error::ModuleDenied(name)) => assert_eq!(name, "mod_test"),
+            _ => panic!("Should be 'ModuleDenied' error.")
+        }
+        let mut mod_set = LoadedModuleSet::new("./target/debug/");
+        assert!(configuration.load_modules_for(&HostIdentifier::new(8080, None), &mut mod_set, &mut events).is_err());
+        assert!(mod_set.module_names().is_empty());
+
+        configuration.mammoth_mut().set_module_policy(LoadPolicy::new());
+        ().validate(&mut events, &configuration).unwrap();
+    }
+
+    #[test]
+    /// Tests the expansion of the module groups of the hosts.
+    fn test_module_groups() {
+        let toml = r##"
+        [mammoth]
+        mods_dir = "./target/debug/"
+
+        [[host]]
+        listen = 8080
+        groups = ["observability", "testing"]
+            [[host.mod]]
+            name = "mod_metrics"
+            priority = -1
+
+        [[host]]
+        listen = 8088
+
+        [[mod]]
+        name = "mod_test"
+
+        [module_group.observability]
+        modules = ["mod_metrics", "mod_tracing", "mod_test"]
+
+        [module_group.testing]
+        modules = ["mod_tracing", "mod_fixtures"]
+        "##;
+        let mut configuration = ConfigurationFile::from_str(toml).unwrap();
+        let first = HostIdentifier::new(8080, None);
+        let second = HostIdentifier::new(8088, None);
+
+        let modules = configuration.modules_for(&first);
+        let names: Vec<&str> = modules.iter().map(|e| e.module().name()).collect();
+        assert_eq!(names, vec!["mod_metrics", "mod_test", "mod_tracing", "mod_fixtures"]);
+        assert_eq!(modules[0].module().priority(), -1);
+        assert_eq!(configuration.modules_for(&second).len(), 1);
+        assert_eq!(configuration.module_group("testing").unwrap().modules().len(), 2);
+
+        // Configured modules that are globally disabled are enabled through the groups only.
+        let mut opt_in = Module::with_config("mod_tracing", false, Value::from("traced")).unwrap();
+        opt_in.set_priority(5);
+        configuration.add_mod(opt_in);
+        let modules = configuration.modules_for(&first);
+        let tracing = modules.iter().find(|e| e.module().name() == "mod_tracing").unwrap();
+        assert_eq!(tracing.module().priority(), 5);
+        assert_eq!(tracing.module().effective_config(), Some(Value::from("traced")));
+        assert_eq!(modules.len(), 4);
+        assert!(configuration.modules_for(&second).iter().all(|e| e.module().name() != "mod_tracing"));
+        assert_eq!(configuration.resolve_modules_for(&first).unwrap().len(), 4);
+
+        configuration.remove_module_group("testing");
+        let mut events: Vec<Event> = Vec::new();
+        match ().validate(&mut events, &configuration) {
+            Err(Error::InvalidValue(key)) => assert_eq!(key, "host.groups"),
+            _ => panic!("Should be 'InvalidValue' error.")
+        }
+    }
+
+    #[test]
+    /// Tests optional modules failing validation and loading.
+    fn test_optional_modules() {
+        let toml = r##"
+        [mammoth]
+        mods_dir = "./target/debug/"
+
+        [[host]]
+        listen = 8080
+
+        [[mod]]
+        name = "mod_missing"
+        required = false
+
+        [[mod]]
+        name = "mod_test"
+        required = false
+        "##;
+        let mut configuration = ConfigurationFile::from_str(toml).unwrap();
+        let host = HostIdentifier::new(8080, None);
+        let mut events: Vec<Event> = Vec::new();
+
+        ().validate(&mut events, &configuration).unwrap();
+        assert!(!events.is_empty());
+        assert!(events.iter().all(|e| e.severity() <= Severity::Warning));
+        let mut mod_set = LoadedModuleSet::new("./target/debug/");
+        let mut events: Vec<Event> = Vec::new();
+        configuration.load_modules_for(&host, &mut mod_set, &mut events).unwrap();
+        assert_eq!(mod_set.module_names(), vec!["mod_test"]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].severity(), Severity::Warning);
+
+        // The library of a module failing after being loaded is unloaded too.
+        configuration.mammoth_mut().set_compatibility(CompatibilityPolicy::new(VersionReq::parse(">=1.0.0").unwrap()));
+        let mut mod_set = LoadedModuleSet::new("./target/debug/");
+        let mut events: Vec<Event> = Vec::new();
+        configuration.load_modules_for(&host, &mut mod_set, &mut events).unwrap();
+        assert!(mod_set.module_names().is_empty());
+        assert!(mod_set.library_paths().is_empty());
+        assert_eq!(events.len(), 2);
+
+        configuration.mods_mut()[0].set_required(true);
+        assert!(().validate(&mut events, &configuration).is_err());
+        assert!(configuration.load_modules_for(&host, &mut mod_set, &mut events).is_err());
+    }
+
+    #[test]
+    /// Tests the environment passed to the constructors of the modules.
+    fn test_environment() {
+        let toml = r##"
+        [mammoth]
+        mods_dir = "./target/debug/"
+
+        [[host]]
+        listen = 8080
+
+        [[host]]
+        listen = 8088
+            [host.environment]
+            region = "us-east"
+            [host.environment.db]
+            pool = 4
+
+            [[host.mod]]
+            name = "mod_environment"
+
+        [environment]
+        region = "eu-west"
+        [environment.db]
+        url = "postgres://db"
+        "##;
+        let configuration = ConfigurationFile::from_str(toml).unwrap();
+        let first = HostIdentifier::new(8080, None);
+        let second = HostIdentifier::new(8088, None);
+
+        assert_eq!(configuration.environment_for(&first).as_ref(), configuration.environment());
+        let environment = configuration.environment_for(&second).unwrap();
+        assert_eq!(environment["region"].as_str(), Some("us-east"));
+        assert_eq!(environment["db"]["url"].as_str(), Some("postgres://db"));
+        assert_eq!(environment["db"]["pool"].as_integer(), Some(4));
+
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_environment", environment_aware).unwrap();
+        let mut mod_set = LoadedModuleSet::new("./target/debug/");
+        let mut events: Vec<Event> = Vec::new();
+        configuration.load_modules_for(&second, &mut mod_set, &mut events).unwrap();
+        assert_eq!(ENVIRONMENT.lock().unwrap().as_ref(), Some(&environment));
+        assert_eq!(abi::current_environment(), None);
+        mod_set.unload("mod_environment").unwrap();
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_environment");
+    }
+
+    #[test]
+    /// Tests the conflict policy between global and host-level modules.
+    fn test_module_conflicts() {
+        let toml = r##"
+        [mammoth]
+        mods_dir = "./target/debug/"
+        module_conflicts = "error"
+
+        [[host]]
+        listen = 8080
+            [[host.mod]]
+            name = "mod_test"
+
+        [[mod]]
+        name = "mod_test"
+        config = 42
+        "##;
+        let mut configuration = ConfigurationFile::from_str(toml).unwrap();
+        let host = HostIdentifier::new(8080, None);
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(configuration.mammoth().module_conflicts(), ModuleConflict::Error);
+        match ().validate(&mut events, &configuration) {
+            Err(Error::ModuleConflict(name)) => assert_eq!(name, "mod_test"),
+            _ => panic!("Should be 'ModuleConflict' error.")
+        }
+        let mut mod_set = LoadedModuleSet::new("./target/debug/");
+        assert!(configuration.load_modules_for(&host, &mut mod_set, &mut events).is_err());
+
+        configuration.mammoth_mut().set_module_conflicts(ModuleConflict::Override);
+        ().validate(&mut events, &configuration).unwrap();
+        assert_eq!(configuration.modules_for(&host)[0].module().config(), None);
+        configuration.mammoth_mut().set_module_conflicts(ModuleConflict::Merge);
+        assert!(configuration.modules_for(&host)[0].module().effective_config().is_some());
+    }
+
+    #[test]
+    /// Tests the middleware chain of the hosts.
+    fn test_middleware_chain() {
+        let toml = r##"
+        [mammoth]
+        mods_dir = "./mods/"
+
+        [[host]]
+        listen = 8080
+
+        [[host]]
+        listen = 8088
+            [[host.mod]]
+            name = "mod_log"
+            after = ["mod_compress"]
+
+        [[mod]]
+        name = "mod_compress"
+        kind = "middleware"
+        after = ["mod_auth"]
+
+        [[mod]]
+        name = "mod_stats"
+        kind = "handler"
+
+        [[mod]]
+        name = "mod_auth"
+        kind = "middleware"
+
+        [[mod]]
+        name = "mod_log"
+        kind = "middleware"
+        before = ["mod_auth"]
+        "##;
+        let configuration = ConfigurationFile::from_str(toml).unwrap();
+
+        let chain = configuration.middleware_chain_for(&HostIdentifier::new(8080, None)).unwrap();
+        let names: Vec<&str> = chain.iter().map(|m| m.name()).collect();
+        assert_eq!(names, vec!["mod_log", "mod_auth", "mod_compress"]);
+        let chain = configuration.request_chain_for(&HostIdentifier::new(8080, None)).unwrap();
+        let names: Vec<&str> = chain.iter().map(|m| m.name()).collect();
+        assert_eq!(names, vec!["mod_log", "mod_auth", "mod_compress", "mod_stats"]);
+
+        match configuration.middleware_chain_for(&HostIdentifier::new(8088, None)) {
+            Err(Error::MiddlewareCycle(cycle)) => assert_eq!(cycle.len(), 4),
+            _ => panic!("Should be 'MiddlewareCycle' error.")
+        }
+    }
+
     #[test]
     /// Tests the `has_module` and `remove_mod` functions.
     fn test_mods() {
@@ -0,0 +1,239 @@
+//! The `Acme` structure contains the settings used to obtain certificates through the ACME
+//! protocol (e.g. from Let's Encrypt); it is only available with the `acme` feature.
+//!
+//! Bindings declared with `acme = true` do not specify `cert` and `key`: their certificate and key
+//! are stored in `cache_dir`, in a subdirectory named after the hostname of the host, and are
+//! renewed `renew_before` days before they expire.
+//! The protocol exchange is performed by an `AcmeClient` provided by the runtime, so that this
+//! crate does not depend on an HTTP stack.
+//!
+//! # Example
+//! ```toml
+//! [mammoth.acme]
+//! email = "admin@example.com"
+//! cache_dir = "/var/lib/mammoth/acme"
+//!
+//! [[host]]
+//! hostname = "example.com"
+//! listen = { port = 443, acme = true }
+//! ```
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use openssl::asn1::Asn1Time;
+use openssl::pkey::PKey;
+use openssl::x509::X509;
+
+use crate::diagnostics::{Logger, PathValidator, PathValidatorKind, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Directory URL of the Let's Encrypt production environment.
+pub const LETS_ENCRYPT_DIRECTORY: &str = "https://acme-v02.api.letsencrypt.org/directory";
+
+/// Structure that defines the ACME settings.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Acme {
+    email: String,
+    #[serde(default = "default_directory_url")]
+    directory_url: String,
+    cache_dir: PathBuf,
+    #[serde(default = "default_renew_before")]
+    renew_before: u32
+}
+
+#[doc(hidden)]
+fn default_directory_url() -> String { LETS_ENCRYPT_DIRECTORY.to_owned() }
+#[doc(hidden)]
+fn default_renew_before() -> u32 { 30 }
+
+/// Structure that contains a certificate issued through ACME.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IssuedCertificate {
+    /// PEM-encoded certificate, followed by its intermediate certificates.
+    pub chain: Vec<u8>,
+    /// PEM-encoded private key.
+    pub key: Vec<u8>
+}
+
+/// Trait implemented by the clients performing the ACME orders.
+pub trait AcmeClient {
+    /// Orders a certificate for `domain` from the directory of `acme`, answering the challenges.
+    fn order(&self, acme: &Acme, domain: &str) -> Result<IssuedCertificate, Error>;
+}
+
+impl Acme {
+    /// Creates a new `Acme` structure using Let's Encrypt with the given contact `email`, storing
+    /// the certificates into `cache_dir`.
+    pub fn new<P>(email: &str, cache_dir: P) -> Acme
+        where
+            P: AsRef<Path>
+    {
+        Acme {
+            email: email.to_owned(),
+            directory_url: default_directory_url(),
+            cache_dir: cache_dir.as_ref().to_path_buf(),
+            renew_before: default_renew_before()
+        }
+    }
+
+    /// Obtains the contact email of the account.
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+    /// Obtains the URL of the ACME directory.
+    pub fn directory_url(&self) -> &str {
+        &self.directory_url
+    }
+    /// Sets the URL of the ACME directory (e.g. a staging environment).
+    pub fn set_directory_url(&mut self, url: &str) {
+        self.directory_url = url.to_owned();
+    }
+    /// Obtains the directory in which the certificates are stored.
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+    /// Obtains how many days before their expiration the certificates are renewed.
+    pub fn renew_before(&self) -> u32 {
+        self.renew_before
+    }
+    /// Sets how many days before their expiration the certificates are renewed.
+    pub fn set_renew_before(&mut self, days: u32) {
+        self.renew_before = days;
+    }
+
+    /// Obtains the path of the certificate of `domain`.
+    pub fn cert_path(&self, domain: &str) -> PathBuf {
+        self.cache_dir.join(domain).join("cert.pem")
+    }
+    /// Obtains the path of the private key of `domain`.
+    pub fn key_path(&self, domain: &str) -> PathBuf {
+        self.cache_dir.join(domain).join("key.pem")
+    }
+    /// Returns `true` if the certificate of `domain` is missing, unreadable, or expires within
+    /// `renew_before` days.
+    pub fn needs_renewal(&self, domain: &str) -> Result<bool, Error> {
+        let cert = match fs::read(self.cert_path(domain)) {
+            Ok(pem) => match X509::from_pem(&pem) {
+                Ok(cert) => cert,
+                Err(_) => return Ok(true)
+            },
+            Err(_) => return Ok(true)
+        };
+        let threshold = Asn1Time::days_from_now(self.renew_before)?;
+
+        Ok(cert.not_after() < threshold)
+    }
+    /// Orders a new certificate for `domain` through `client` if it needs to be renewed, storing
+    /// it along with its key; returns `true` if the certificate has been renewed.
+    pub fn renew(&self, client: &dyn AcmeClient, domain: &str) -> Result<bool, Error> {
+        if !self.needs_renewal(domain)? {
+            return Ok(false);
+        }
+
+        let issued = client.order(self, domain)?;
+        X509::from_pem(&issued.chain)?;
+        PKey::private_key_from_pem(&issued.key)?;
+
+        fs::create_dir_all(self.cache_dir.join(domain))?;
+        write_replacing(&self.key_path(domain), &issued.key)?;
+        write_replacing(&self.cert_path(domain), &issued.chain)?;
+
+        Ok(true)
+    }
+}
+
+/// Writes `contents` into a temporary file and moves it onto `path`, so that readers never see a
+/// partially written file.
+fn write_replacing(path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let mut temporary = path.to_path_buf().into_os_string();
+    temporary.push(".tmp");
+
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)?;
+
+    Ok(())
+}
+
+impl Validator<Acme> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &Acme) -> Result<(), Error> {
+        let at = item.email.find('@');
+        if !matches!(at, Some(i) if i > 0 && i < item.email.len() - 1) {
+            let desc = format!("Invalid ACME contact email: '{}'.", item.email);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("acme.email".to_owned()))?;
+        }
+        if !item.directory_url.starts_with("https://") {
+            let desc = format!("ACME directory URL must use HTTPS: '{}'.", item.directory_url);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("acme.directory_url".to_owned()))?;
+        }
+        PathValidator(Severity::Error, PathValidatorKind::WritableDirectory)
+            .validate(logger, &item.cache_dir)?;
+        if item.renew_before == 0 || item.renew_before >= 90 {
+            let desc = format!("ACME certificates renewed {} days before expiration; ACME certificates usually last 90 days.", item.renew_before);
+            logger.log(Severity::Warning, &desc);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::config::acme::{Acme, AcmeClient, IssuedCertificate};
+    use crate::diagnostics::Validator;
+    use crate::error::Error;
+    use crate::error::event::Event;
+
+    struct FixtureClient;
+
+    impl AcmeClient for FixtureClient {
+        fn order(&self, _: &Acme, _: &str) -> Result<IssuedCertificate, Error> {
+            Ok(IssuedCertificate {
+                chain: fs::read("./tests/test_leaf_cert.pem")?,
+                key: fs::read("./tests/test_leaf_key.pem")?
+            })
+        }
+    }
+
+    #[test]
+    /// Tests deserialization and validation of the ACME settings.
+    fn test_acme() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut acme: Acme = toml::from_str(&format!(r#"
+        email = "admin@example.com"
+        cache_dir = "{}"
+        "#, dir.path().display())).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(acme.directory_url(), super::LETS_ENCRYPT_DIRECTORY);
+        assert_eq!(acme.renew_before(), 30);
+        assert_eq!(acme.cert_path("example.com"), dir.path().join("example.com/cert.pem"));
+        assert!(().validate(&mut events, &acme).is_ok());
+
+        acme.set_directory_url("http://localhost/directory");
+        assert!(().validate(&mut events, &acme).is_err());
+
+        let acme = Acme::new("admin", dir.path());
+        assert!(().validate(&mut events, &acme).is_err());
+    }
+
+    #[test]
+    /// Tests the renewal of a certificate.
+    fn test_renew() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut acme = Acme::new("admin@example.com", dir.path());
+
+        assert!(acme.needs_renewal("example.com").unwrap());
+        assert!(acme.renew(&FixtureClient, "example.com").unwrap());
+        assert!(acme.key_path("example.com").is_file());
+        assert!(!acme.needs_renewal("example.com").unwrap());
+        assert!(!acme.renew(&FixtureClient, "example.com").unwrap());
+
+        acme.set_renew_before(365 * 200);
+        assert!(acme.needs_renewal("example.com").unwrap());
+    }
+}
@@ -0,0 +1,335 @@
+//! The `Auth` structure contains an HTTP basic authentication rule of a host.
+//!
+//! Every request whose path starts with the `path` prefix must carry the credentials of one of
+//! the users of the rule, read from an `htpasswd` file, given inline in `users`, or both (inline
+//! users take precedence).
+//! Passwords are stored hashed, in one of the following schemes:
+//! - `$apr1$...`: Apache MD5 (the default scheme of `htpasswd`);
+//! - `{SHA}...`: base64-encoded SHA-1 (`htpasswd -s`);
+//! - `{PLAIN}...`: plain text, discouraged.
+//!
+//! # Example
+//! ```toml
+//! [[host.auth]]
+//! path = "/admin"
+//! realm = "Administration"
+//! htpasswd = "/etc/mammoth/.htpasswd"
+//! users = { alice = "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=" }
+//! ```
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use openssl::base64;
+use openssl::hash::{hash, Hasher, MessageDigest};
+use openssl::memcmp;
+
+use crate::diagnostics::{Logger, OptionValidator, PathValidator, PathValidatorKind, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Alphabet used by the `$apr1$` scheme to encode salts and hashes.
+const APR1_ALPHABET: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Structure that defines an HTTP basic authentication rule.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Auth {
+    path: String,
+    #[serde(default = "default_realm")]
+    realm: String,
+    htpasswd: Option<PathBuf>,
+    #[serde(default = "default_users")]
+    users: BTreeMap<String, String>
+}
+
+#[doc(hidden)]
+fn default_realm() -> String { "Restricted".to_owned() }
+#[doc(hidden)]
+fn default_users() -> BTreeMap<String, String> { BTreeMap::new() }
+
+/// Hashing scheme of a stored password.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PasswordScheme {
+    /// Apache MD5 (`$apr1$`).
+    Apr1,
+    /// Base64-encoded SHA-1 (`{SHA}`).
+    Sha1,
+    /// Plain text (`{PLAIN}`).
+    Plain
+}
+
+impl PasswordScheme {
+    /// Obtains the scheme of the stored password `hash`, or `None` if the scheme is not supported
+    /// (e.g. bcrypt or system `crypt`).
+    pub fn of(hash: &str) -> Option<PasswordScheme> {
+        if hash.starts_with("$apr1$") {
+            Some(PasswordScheme::Apr1)
+        } else if hash.starts_with("{SHA}") {
+            Some(PasswordScheme::Sha1)
+        } else if hash.starts_with("{PLAIN}") {
+            Some(PasswordScheme::Plain)
+        } else {
+            None
+        }
+    }
+}
+
+impl Auth {
+    /// Creates a new `Auth` structure protecting the paths starting with `path`, without users.
+    pub fn new(path: &str) -> Auth {
+        Auth {
+            path: path.to_owned(),
+            realm: default_realm(),
+            htpasswd: None,
+            users: BTreeMap::new()
+        }
+    }
+
+    /// Obtains the protected path prefix.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    /// Obtains the realm reported to the clients.
+    pub fn realm(&self) -> &str {
+        &self.realm
+    }
+    /// Sets the realm reported to the clients.
+    pub fn set_realm(&mut self, realm: &str) {
+        self.realm = realm.to_owned();
+    }
+    /// Obtains the path of the `htpasswd` file, if any.
+    pub fn htpasswd(&self) -> Option<&Path> {
+        self.htpasswd.as_deref()
+    }
+    /// Sets the path of the `htpasswd` file.
+    pub fn set_htpasswd<P>(&mut self, path: P)
+        where
+            P: AsRef<Path>
+    {
+        self.htpasswd = Some(path.as_ref().to_path_buf());
+    }
+    /// Removes the `htpasswd` file.
+    pub fn clear_htpasswd(&mut self) {
+        self.htpasswd = None;
+    }
+    /// Obtains the inline users along with their hashed passwords.
+    pub fn users(&self) -> &BTreeMap<String, String> {
+        &self.users
+    }
+    /// Adds an inline user with the hashed password `hash`.
+    pub fn add_user(&mut self, user: &str, hash: &str) {
+        self.users.insert(user.to_owned(), hash.to_owned());
+    }
+    /// Removes an inline user.
+    pub fn remove_user(&mut self, user: &str) {
+        self.users.remove(user);
+    }
+
+    /// Returns `true` if the rule protects `path`, i.e. if `path` is the prefix or is contained
+    /// in it.
+    pub fn matches(&self, path: &str) -> bool {
+        let prefix = self.path.trim_end_matches('/');
+        match path.strip_prefix(prefix) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/') || prefix.is_empty(),
+            None => false
+        }
+    }
+    /// Obtains every user of the rule, reading the `htpasswd` file if any.
+    pub fn credentials(&self) -> Result<BTreeMap<String, String>, Error> {
+        let mut credentials = match self.htpasswd {
+            Some(ref path) => parse_htpasswd(&fs::read_to_string(path)?),
+            None => BTreeMap::new()
+        };
+        credentials.extend(self.users.iter().map(|(u, h)| (u.clone(), h.clone())));
+        Ok(credentials)
+    }
+    /// Returns `true` if `pass` is the password of `user`.
+    ///
+    /// Fails if the `htpasswd` file cannot be read.
+    pub fn verify(&self, user: &str, pass: &str) -> Result<bool, Error> {
+        let hash = match self.users.get(user) {
+            Some(hash) => Some(hash.clone()),
+            None => match self.htpasswd {
+                Some(ref path) => parse_htpasswd(&fs::read_to_string(path)?).remove(user),
+                None => None
+            }
+        };
+
+        Ok(match hash {
+            Some(hash) => verify_password(&hash, pass)?,
+            None => false
+        })
+    }
+}
+
+/// Parses the content of an `htpasswd` file into a map from the users to their hashed passwords.
+///
+/// Empty lines, comments and lines without a `:` separator are ignored.
+pub fn parse_htpasswd(content: &str) -> BTreeMap<String, String> {
+    content.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let mut split = l.splitn(2, ':');
+            match (split.next(), split.next()) {
+                (Some(user), Some(hash)) if !user.is_empty() => Some((user.to_owned(), hash.to_owned())),
+                _ => None
+            }
+        })
+        .collect()
+}
+
+/// Returns `true` if `pass` corresponds to the stored password `hash`.
+///
+/// Passwords stored with an unsupported scheme never match.
+pub fn verify_password(hash: &str, pass: &str) -> Result<bool, Error> {
+    let computed = match PasswordScheme::of(hash) {
+        Some(PasswordScheme::Apr1) => {
+            let salt = hash["$apr1$".len()..].split('$').next().unwrap_or("");
+            apr1(pass.as_bytes(), salt.as_bytes())?
+        },
+        Some(PasswordScheme::Sha1) => {
+            let digest = openssl::sha::sha1(pass.as_bytes());
+            format!("{{SHA}}{}", base64::encode_block(&digest))
+        },
+        Some(PasswordScheme::Plain) => format!("{{PLAIN}}{}", pass),
+        None => return Ok(false)
+    };
+
+    Ok(computed.len() == hash.len() && memcmp::eq(computed.as_bytes(), hash.as_bytes()))
+}
+
+/// Computes the `$apr1$` hash of `pass` with the given `salt` (at most 8 characters are used).
+fn apr1(pass: &[u8], salt: &[u8]) -> Result<String, Error> {
+    let salt = &salt[..salt.len().min(8)];
+    let md5 = MessageDigest::md5();
+
+    let alternate = hash(md5, &[pass, salt, pass].concat())?;
+    let mut hasher = Hasher::new(md5)?;
+    hasher.update(pass)?;
+    hasher.update(b"$apr1$")?;
+    hasher.update(salt)?;
+    for chunk in (0..pass.len()).step_by(16) {
+        hasher.update(&alternate[..(pass.len() - chunk).min(16)])?;
+    }
+    let mut i = pass.len();
+    while i > 0 {
+        if i & 1 == 1 { hasher.update(&[0])?; } else { hasher.update(&pass[..1])?; }
+        i >>= 1;
+    }
+    let mut digest = hasher.finish()?.to_vec();
+
+    for round in 0..1000 {
+        let mut hasher = Hasher::new(md5)?;
+        if round & 1 == 1 { hasher.update(pass)?; } else { hasher.update(&digest)?; }
+        if round % 3 != 0 { hasher.update(salt)?; }
+        if round % 7 != 0 { hasher.update(pass)?; }
+        if round & 1 == 1 { hasher.update(&digest)?; } else { hasher.update(pass)?; }
+        digest = hasher.finish()?.to_vec();
+    }
+
+    let mut encoded = format!("$apr1${}$", String::from_utf8_lossy(salt));
+    let groups = [(0, 6, 12), (1, 7, 13), (2, 8, 14), (3, 9, 15), (4, 10, 5)];
+    for &(a, b, c) in groups.iter() {
+        let value = (u32::from(digest[a]) << 16) | (u32::from(digest[b]) << 8) | u32::from(digest[c]);
+        encode_apr1(&mut encoded, value, 4);
+    }
+    encode_apr1(&mut encoded, u32::from(digest[11]), 2);
+
+    Ok(encoded)
+}
+
+/// Appends the `count` least significant 6-bit groups of `value` to `output`.
+fn encode_apr1(output: &mut String, mut value: u32, count: usize) {
+    for _ in 0..count {
+        output.push(APR1_ALPHABET[(value & 0x3f) as usize] as char);
+        value >>= 6;
+    }
+}
+
+impl Validator<Auth> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &Auth) -> Result<(), Error> {
+        if !item.path.starts_with('/') {
+            let desc = format!("Authentication path '{}' must start with '/'.", item.path);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("auth.path".to_owned()))?;
+        }
+        if item.htpasswd.is_none() && item.users.is_empty() {
+            let desc = format!("Authentication rule for '{}' specifies neither 'htpasswd' nor 'users'.", item.path);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("auth".to_owned()))?;
+        }
+        OptionValidator(PathValidator(Severity::Error, PathValidatorKind::ExistingFile))
+            .validate(logger, &item.htpasswd)?;
+
+        for (user, hash) in item.credentials()? {
+            match PasswordScheme::of(&hash) {
+                Some(PasswordScheme::Plain) => {
+                    let desc = format!("Password of user '{}' for '{}' is stored in plain text.", user, item.path);
+                    logger.log(Severity::Warning, &desc);
+                },
+                Some(_) => {},
+                None => {
+                    let desc = format!("Password of user '{}' for '{}' uses an unsupported scheme (use '$apr1$' or '{{SHA}}').", user, item.path);
+                    logger.log(Severity::Error, &desc);
+                    Err(Error::InvalidValue("auth.users".to_owned()))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use crate::config::auth::{Auth, verify_password};
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests the supported password schemes.
+    fn test_verify_password() {
+        assert!(verify_password("$apr1$abcdefgh$FBwExRW4dCc8aL.OvjpIE1", "password").unwrap());
+        assert!(!verify_password("$apr1$abcdefgh$FBwExRW4dCc8aL.OvjpIE1", "Password").unwrap());
+        assert!(verify_password("{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=", "password").unwrap());
+        assert!(!verify_password("{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=", "").unwrap());
+        assert!(verify_password("{PLAIN}password", "password").unwrap());
+        assert!(!verify_password("$2y$05$invalid", "password").unwrap());
+    }
+
+    #[test]
+    /// Tests the path matching and the verification of inline and `htpasswd` users.
+    fn test_auth() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "# users\nbob:$apr1$abcdefgh$FBwExRW4dCc8aL.OvjpIE1\nalice:{{PLAIN}}old").unwrap();
+
+        let mut auth: Auth = toml::from_str(r#"
+        path = "/admin"
+        users = { alice = "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=" }
+        "#).unwrap();
+        auth.set_htpasswd(file.path());
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(auth.matches("/admin"));
+        assert!(auth.matches("/admin/users"));
+        assert!(!auth.matches("/administrator"));
+        assert!(!auth.matches("/"));
+
+        assert!(auth.verify("alice", "password").unwrap());
+        assert!(!auth.verify("alice", "old").unwrap());
+        assert!(auth.verify("bob", "password").unwrap());
+        assert!(!auth.verify("carol", "password").unwrap());
+        assert!(().validate(&mut events, &auth).is_ok());
+
+        auth.add_user("carol", "$2y$05$invalid");
+        assert!(().validate(&mut events, &auth).is_err());
+
+        let mut auth = Auth::new("/private");
+        assert!(().validate(&mut events, &auth).is_err());
+        auth.set_htpasswd("./does/not/exist");
+        assert!(().validate(&mut events, &auth).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! The `CacheRule` structure contains the cache policy of the static files matching a path
+//! pattern.
+use crate::config::glob::Glob;
+use crate::diagnostics::{Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Structure that defines the `Cache-Control` policy of the files matching a path pattern.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct CacheRule {
+    path: Glob,
+    max_age: Option<u64>,
+    #[serde(default)]
+    immutable: bool,
+    #[serde(default)]
+    no_store: bool
+}
+
+impl CacheRule {
+    /// Creates a new `CacheRule` structure for the paths matching `path`, cached for `max_age`
+    /// seconds.
+    pub fn new(path: Glob, max_age: u64) -> CacheRule {
+        CacheRule {
+            path,
+            max_age: Some(max_age),
+            immutable: false,
+            no_store: false
+        }
+    }
+    /// Creates a new `CacheRule` structure that forbids caching the paths matching `path`.
+    pub fn no_store(path: Glob) -> CacheRule {
+        CacheRule {
+            path,
+            max_age: None,
+            immutable: false,
+            no_store: true
+        }
+    }
+    /// Obtains the path pattern of the rule.
+    pub fn path(&self) -> &Glob {
+        &self.path
+    }
+    /// Obtains the maximum age in seconds, if any.
+    pub fn max_age(&self) -> Option<u64> {
+        self.max_age
+    }
+    /// Returns `true` if the matching files never change and `false` otherwise.
+    pub fn immutable(&self) -> bool {
+        self.immutable
+    }
+    /// Sets whether the matching files never change.
+    pub fn set_immutable(&mut self, immutable: bool) {
+        self.immutable = immutable;
+    }
+    /// Returns `true` if the matching files must not be cached and `false` otherwise.
+    pub fn is_no_store(&self) -> bool {
+        self.no_store
+    }
+    /// Returns `true` if the rule applies to `path` and `false` otherwise.
+    pub fn matches(&self, path: &str) -> bool {
+        self.path.matches(path)
+    }
+    /// Obtains the value of the `Cache-Control` header described by the rule.
+    pub fn header_value(&self) -> String {
+        if self.no_store { return "no-store".to_owned(); }
+
+        let mut directives = vec!["public".to_owned()];
+        if let Some(max_age) = self.max_age {
+            directives.push(format!("max-age={}", max_age));
+        }
+        if self.immutable {
+            directives.push("immutable".to_owned());
+        }
+
+        directives.join(", ")
+    }
+}
+
+impl Validator<CacheRule> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &CacheRule) -> Result<(), Error> {
+        if item.no_store && (item.max_age.is_some() || item.immutable) {
+            let desc = format!("Cache rule '{}' specifies 'no_store' along with 'max_age' or 'immutable'.", item.path);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("cache.no_store".to_owned()))?;
+        }
+        if item.immutable && item.max_age.is_none() {
+            let desc = format!("Cache rule '{}' is immutable but has no 'max_age'.", item.path);
+            logger.log(Severity::Warning, &desc);
+        }
+        if !item.no_store && item.max_age.is_none() && !item.immutable {
+            let desc = format!("Cache rule '{}' has no effect.", item.path);
+            logger.log(Severity::Warning, &desc);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::cache::CacheRule;
+    use crate::config::glob::Glob;
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests deserialization and the `Cache-Control` header value.
+    fn test_cache_rule() {
+        let rule: CacheRule = toml::from_str(r#"
+        path = "/static/**"
+        max_age = 31536000
+        immutable = true
+        "#).unwrap();
+
+        assert!(rule.matches("/static/js/app.js"));
+        assert!(!rule.matches("/index.html"));
+        assert_eq!(rule.header_value(), "public, max-age=31536000, immutable");
+
+        let rule = CacheRule::no_store(Glob::new("/api/**").unwrap());
+        assert_eq!(rule.header_value(), "no-store");
+
+        assert!(toml::from_str::<CacheRule>(r#"path = "/static/[""#).is_err());
+    }
+
+    #[test]
+    /// Tests validation.
+    fn test_validate() {
+        let mut events: Vec<Event> = Vec::new();
+        let mut rule = CacheRule::new(Glob::new("/static/**").unwrap(), 3600);
+
+        assert!(().validate(&mut events, &rule).is_ok());
+        assert!(events.is_empty());
+
+        rule.set_immutable(true);
+        assert!(().validate(&mut events, &rule).is_ok());
+
+        let mut rule = CacheRule::no_store(Glob::new("/api/**").unwrap());
+        assert!(().validate(&mut events, &rule).is_ok());
+        rule.set_immutable(true);
+        assert!(().validate(&mut events, &rule).is_err());
+    }
+}
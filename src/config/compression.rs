@@ -0,0 +1,203 @@
+//! The `Compression` structure contains the compression settings of the static files of a host.
+use crate::config::size::ByteSize;
+use crate::diagnostics::{Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Structure that defines how the static files of a host are compressed.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Compression {
+    #[serde(default = "default_gzip")]
+    gzip: bool,
+    #[serde(default)]
+    brotli: bool,
+    #[serde(default = "default_min_size")]
+    min_size: ByteSize,
+    #[serde(default = "default_types")]
+    types: Vec<String>
+}
+
+#[doc(hidden)]
+fn default_gzip() -> bool { true }
+#[doc(hidden)]
+fn default_min_size() -> ByteSize { ByteSize::new(1024) }
+#[doc(hidden)]
+fn default_types() -> Vec<String> {
+    [
+        "text/html",
+        "text/css",
+        "text/plain",
+        "application/javascript",
+        "application/json",
+        "image/svg+xml"
+    ].iter().map(|t| (*t).to_owned()).collect()
+}
+
+impl Compression {
+    /// Creates a new `Compression` structure with the default settings, i.e. gzip compression of
+    /// the common text types for files of at least 1KB.
+    pub fn new() -> Compression {
+        Compression {
+            gzip: default_gzip(),
+            brotli: false,
+            min_size: default_min_size(),
+            types: default_types()
+        }
+    }
+
+    /// Returns `true` if gzip compression is enabled and `false` otherwise.
+    pub fn gzip(&self) -> bool {
+        self.gzip
+    }
+    /// Enables or disables gzip compression.
+    pub fn set_gzip(&mut self, enabled: bool) {
+        self.gzip = enabled;
+    }
+    /// Returns `true` if brotli compression is enabled and `false` otherwise.
+    pub fn brotli(&self) -> bool {
+        self.brotli
+    }
+    /// Enables or disables brotli compression.
+    pub fn set_brotli(&mut self, enabled: bool) {
+        self.brotli = enabled;
+    }
+    /// Obtains the minimum size of a file to be compressed.
+    pub fn min_size(&self) -> ByteSize {
+        self.min_size
+    }
+    /// Sets the minimum size of a file to be compressed.
+    pub fn set_min_size<S>(&mut self, size: S)
+        where
+            S: Into<ByteSize>
+    {
+        self.min_size = size.into();
+    }
+    /// Obtains the list of the compressible MIME types.
+    pub fn types(&self) -> Vec<&str> {
+        self.types.iter().map(|t| t.as_str()).collect()
+    }
+    /// Adds a compressible MIME type.
+    pub fn add_type(&mut self, mime: &str) {
+        self.types.push(mime.to_owned());
+    }
+    /// Removes a compressible MIME type.
+    pub fn remove_type(&mut self, mime: &str) {
+        self.types.retain(|t| t != mime);
+    }
+    /// Returns `true` if a file of the given MIME type and `size` should be compressed and `false`
+    /// otherwise.
+    ///
+    /// Types are compared case-insensitively, ignoring parameters such as `charset`; a type ending
+    /// with `/*` matches every subtype.
+    pub fn is_compressible(&self, mime: &str, size: u64) -> bool {
+        if !self.gzip && !self.brotli { return false; }
+        if size < self.min_size.bytes() { return false; }
+
+        let mime = mime.split(';').next().unwrap_or("").trim().to_lowercase();
+        self.types.iter().any(|t| {
+            let t = t.to_lowercase();
+            match t.strip_suffix("/*") {
+                Some(prefix) => mime.starts_with(prefix) && mime[prefix.len()..].starts_with('/'),
+                None => t == mime
+            }
+        })
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::new()
+    }
+}
+
+/// Returns `true` if `mime` is a valid MIME type (e.g. `text/html` or `text/*`) and `false`
+/// otherwise.
+fn is_valid_mime(mime: &str) -> bool {
+    let token = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c));
+    let mut parts = mime.splitn(2, '/');
+
+    match (parts.next(), parts.next()) {
+        (Some(kind), Some(subtype)) => token(kind) && (subtype == "*" || token(subtype)),
+        _ => false
+    }
+}
+
+impl Validator<Compression> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &Compression) -> Result<(), Error> {
+        if !item.gzip() && !item.brotli() {
+            logger.log(Severity::Warning, "Compression settings specified with neither 'gzip' nor 'brotli' enabled.");
+        }
+        if item.types.is_empty() {
+            logger.log(Severity::Warning, "Compression settings specified without compressible types.");
+        }
+        for mime in item.types() {
+            if !is_valid_mime(mime) {
+                let desc = format!("Invalid compressible type: '{}'.", mime);
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("compression.types".to_owned()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::compression::Compression;
+    use crate::config::size::ByteSize;
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests deserialization and defaults.
+    fn test_deserialize() {
+        let compression: Compression = toml::from_str(r#"
+        brotli = true
+        min_size = "4KB"
+        types = ["text/*", "application/json"]
+        "#).unwrap();
+
+        assert!(compression.gzip());
+        assert!(compression.brotli());
+        assert_eq!(compression.min_size(), ByteSize::new(4096));
+        assert_eq!(compression.types(), vec!["text/*", "application/json"]);
+
+        let compression: Compression = toml::from_str("").unwrap();
+        assert_eq!(compression, Compression::new());
+    }
+
+    #[test]
+    /// Tests the `is_compressible` function.
+    fn test_is_compressible() {
+        let mut compression = Compression::new();
+
+        assert!(compression.is_compressible("text/html; charset=utf-8", 2048));
+        assert!(!compression.is_compressible("text/html", 512));
+        assert!(!compression.is_compressible("image/png", 2048));
+
+        compression.add_type("image/*");
+        assert!(compression.is_compressible("image/png", 2048));
+        assert!(!compression.is_compressible("imagex/png", 2048));
+
+        compression.set_gzip(false);
+        assert!(!compression.is_compressible("text/html", 2048));
+    }
+
+    #[test]
+    /// Tests validation.
+    fn test_validate() {
+        let mut compression = Compression::new();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(().validate(&mut events, &compression).is_ok());
+        assert!(events.is_empty());
+
+        compression.set_gzip(false);
+        assert!(().validate(&mut events, &compression).is_ok());
+        assert_eq!(events.len(), 1);
+
+        compression.add_type("not a type");
+        assert!(().validate(&mut events, &compression).is_err());
+    }
+}
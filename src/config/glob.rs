@@ -0,0 +1,178 @@
+//! The `Glob` structure contains a path pattern, such as `/static/**/*.js`.
+//!
+//! Patterns support the following syntax:
+//! - `?` matches any single character except `/`;
+//! - `*` matches any sequence of characters except `/`;
+//! - `**` matches any sequence of characters, including `/`;
+//! - `[abc]`, `[a-z]` and `[!abc]` match any single character in (or not in) the given set;
+//! - `{a,b}` matches any of the comma-separated alternatives.
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use regex::Regex;
+use serde::de::{Deserialize, Deserializer, Error as DeError, Unexpected, Visitor};
+
+use crate::error::Error;
+
+/// Structure that defines a path pattern.
+#[derive(Clone, Debug)]
+pub struct Glob {
+    pattern: String,
+    regex: Regex
+}
+
+impl Glob {
+    /// Creates a new `Glob` structure given its `pattern`.
+    pub fn new(pattern: &str) -> Result<Glob, Error> {
+        let regex = Regex::new(&to_regex(pattern)?)
+            .map_err(|_| Error::InvalidValue(pattern.to_owned()))?;
+
+        Ok(Glob {
+            pattern: pattern.to_owned(),
+            regex
+        })
+    }
+    /// Obtains the pattern.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+    /// Returns `true` if the `path` matches the pattern and `false` otherwise.
+    pub fn matches(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+}
+
+/// Translates a glob `pattern` into an anchored regular expression.
+fn to_regex(pattern: &str) -> Result<String, Error> {
+    let invalid = || Error::InvalidValue(pattern.to_owned());
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    let mut alternatives = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => if chars.peek() == Some(&'*') {
+                chars.next();
+                regex.push_str(".*");
+            } else {
+                regex.push_str("[^/]*");
+            },
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                regex.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    regex.push('^');
+                }
+                let mut empty = true;
+                loop {
+                    match chars.next() {
+                        Some(']') if !empty => break,
+                        Some('\\') | Some('[') | Some('^') => return Err(invalid()),
+                        Some(c) => {
+                            regex.push(c);
+                            empty = false;
+                        },
+                        None => return Err(invalid())
+                    }
+                }
+                regex.push(']');
+            },
+            '{' => {
+                alternatives += 1;
+                regex.push_str("(?:");
+            },
+            ',' if alternatives > 0 => regex.push('|'),
+            '}' if alternatives > 0 => {
+                alternatives -= 1;
+                regex.push(')');
+            },
+            ']' | '}' => return Err(invalid()),
+            c => regex.push_str(&regex::escape(&c.to_string()))
+        }
+    }
+    if alternatives > 0 { return Err(invalid()); }
+
+    regex.push('$');
+    Ok(regex)
+}
+
+impl PartialEq for Glob {
+    fn eq(&self, other: &Glob) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl Display for Glob {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+impl FromStr for Glob {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Glob, Error> {
+        Glob::new(s)
+    }
+}
+
+/// Visitor for `Glob` deserialization.
+struct GlobVisitor;
+
+impl<'de> Visitor<'de> for GlobVisitor {
+    type Value = Glob;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, r#"a valid glob pattern such as "/static/**/*.js""#)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Glob, E> where
+        E: DeError {
+        Glob::new(v).map_err(|_| DeError::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Glob {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+        D: Deserializer<'de> {
+        deserializer.deserialize_str(GlobVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::glob::Glob;
+
+    #[test]
+    /// Tests matching of the glob patterns.
+    fn test_matches() {
+        let glob = Glob::new("/static/*.js").unwrap();
+        assert!(glob.matches("/static/app.js"));
+        assert!(!glob.matches("/static/js/app.js"));
+        assert!(!glob.matches("/static/app.jsx"));
+
+        let glob = Glob::new("/static/**/*.{js,css}").unwrap();
+        assert!(glob.matches("/static/js/app.js"));
+        assert!(glob.matches("/static/a/b/style.css"));
+        assert!(!glob.matches("/static/a/b/index.html"));
+
+        let glob = Glob::new("/img/?[0-9][!a-z].png").unwrap();
+        assert!(glob.matches("/img/a12.png"));
+        assert!(!glob.matches("/img/a1b.png"));
+
+        assert!(Glob::new("/a.b").unwrap().matches("/a.b"));
+        assert!(!Glob::new("/a.b").unwrap().matches("/axb"));
+    }
+
+    #[test]
+    /// Tests invalid glob patterns.
+    fn test_invalid() {
+        assert!(Glob::new("/static/[abc").is_err());
+        assert!(Glob::new("/static/[]").is_err());
+        assert!(Glob::new("/static/{a,b").is_err());
+        assert!(Glob::new("/static/a}").is_err());
+        assert!(toml::from_str::<std::collections::BTreeMap<String, Glob>>(r#"a = "/[""#).is_err());
+    }
+}
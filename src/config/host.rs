@@ -6,22 +6,38 @@
 //! but only the port/hostname pair.
 //!
 //! Only one host is allowed per port/hostname pair.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 
 use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use serde::de;
+use toml::Value;
 
+use crate::config::auth::Auth;
+use crate::config::cache::CacheRule;
+use crate::config::compression::Compression;
 use crate::config::module::Module;
+use crate::config::observability::Observability;
+use crate::config::path::{ConfigPath, ConfigPathValidator};
 use crate::config::port::Binding;
-use crate::diagnostics::{Id, IdValidator, Logger, PathValidator, PathValidatorKind, Validator};
+use crate::config::proxy::ProxyRule;
+use crate::config::rate_limit::RateLimit;
+use crate::config::redirect::Redirect;
+use crate::config::size::ByteSize;
+use crate::config::status::StatusPage;
+use crate::diagnostics::{Id, IdValidator, Logger, OptionValidator, PathValidator, PathValidatorKind, Validator};
 use crate::error::Error;
 use crate::error::severity::Severity;
 
-const REGEX_NAME_ADDRESS_STRING: &str = r#"^(([a-zA-Z0-9]|[a-zA-Z0-9][a-zA-Z0-9\-]*[a-zA-Z0-9])\.)*([A-Za-z0-9]|[A-Za-z0-9][A-Za-z0-9\-]*[A-Za-z0-9])$"#;
-const REGEX_IP_ADDRESS_STRING: &str = r#"^(([0-9]|[1-9][0-9]|1[0-9]{2}|2[0-4][0-9]|25[0-5])\.){3}([0-9]|[1-9][0-9]|1[0-9]{2}|2[0-4][0-9]|25[0-5])$"#;
+pub(crate) const REGEX_NAME_ADDRESS_STRING: &str = r#"^(([a-zA-Z0-9]|[a-zA-Z0-9][a-zA-Z0-9\-]*[a-zA-Z0-9])\.)*([A-Za-z0-9]|[A-Za-z0-9][A-Za-z0-9\-]*[A-Za-z0-9])$"#;
+pub(crate) const REGEX_IP_ADDRESS_STRING: &str = r#"^(([0-9]|[1-9][0-9]|1[0-9]{2}|2[0-4][0-9]|25[0-5])\.){3}([0-9]|[1-9][0-9]|1[0-9]{2}|2[0-4][0-9]|25[0-5])$"#;
 
 /// Structure that uniquely identifies an `Host` structure within a vector of hosts.
-#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct HostIdentifier {
     hostname: Option<String>,
     port: u16
@@ -32,14 +48,62 @@ pub struct HostIdentifier {
 pub struct Host {
     hostname: Option<String>,
     listen: Binding,
-    static_dir: Option<PathBuf>,
+    static_dir: Option<ConfigPath>,
+    #[serde(default)]
+    autoindex: bool,
+    #[serde(default = "default_index")]
+    index: Vec<String>,
+    max_body_size: Option<ByteSize>,
+    compression: Option<Compression>,
+    #[serde(default = "default_error_pages", deserialize_with = "deserialize_error_pages")]
+    error_pages: BTreeMap<u16, PathBuf>,
+    #[serde(default = "default_cache")]
+    cache: Vec<CacheRule>,
+    #[serde(default = "default_redirect", rename = "redirect")]
+    redirects: Vec<Redirect>,
+    #[serde(default = "default_proxy", rename = "proxy")]
+    proxies: Vec<ProxyRule>,
+    #[serde(default = "default_auth")]
+    auth: Vec<Auth>,
+    observability: Option<Observability>,
+    rate_limit: Option<RateLimit>,
+    status_page: Option<StatusPage>,
     #[serde(default = "default_mod", rename = "mod")]
-    mods: Vec<Module>
+    mods: Vec<Module>,
+    #[serde(default)]
+    groups: Vec<String>,
+    environment: Option<Value>
 }
 
+#[doc(hidden)]
+fn default_index() -> Vec<String> { vec!["index.html".to_owned(), "index.htm".to_owned()] }
+#[doc(hidden)]
+fn default_error_pages() -> BTreeMap<u16, PathBuf> { BTreeMap::new() }
+#[doc(hidden)]
+fn default_cache() -> Vec<CacheRule> { Vec::new() }
+#[doc(hidden)]
+fn default_redirect() -> Vec<Redirect> { Vec::new() }
+#[doc(hidden)]
+fn default_proxy() -> Vec<ProxyRule> { Vec::new() }
+#[doc(hidden)]
+fn default_auth() -> Vec<Auth> { Vec::new() }
 #[doc(hidden)]
 fn default_mod() -> Vec<Module> { Vec::new() }
 
+/// Deserializes the error pages, whose keys are written as strings in TOML.
+fn deserialize_error_pages<'de, D>(deserializer: D) -> Result<BTreeMap<u16, PathBuf>, D::Error>
+    where
+        D: Deserializer<'de>
+{
+    BTreeMap::<String, PathBuf>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(code, path)| match code.parse() {
+            Ok(code) => Ok((code, path)),
+            Err(_) => Err(de::Error::custom(format!("invalid status code '{}'", code)))
+        })
+        .collect()
+}
+
 impl HostIdentifier {
     /// Creates a new `HostIdentifier` structure containing the port and the host name, if any.
     pub fn new(port: u16, name: Option<&str>) -> HostIdentifier {
@@ -62,6 +126,12 @@ impl HostIdentifier {
     }
 }
 
+impl Display for HostIdentifier {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.name().unwrap_or("*"), self.port)
+    }
+}
+
 impl Host {
     /// Creates a new `Host` structure with a binding on the specified `port`.
     pub fn new(port: u16) -> Host {
@@ -69,7 +139,21 @@ impl Host {
             hostname: None,
             listen: Binding::new(port),
             static_dir: None,
-            mods: Vec::new()
+            autoindex: false,
+            index: default_index(),
+            max_body_size: None,
+            compression: None,
+            error_pages: BTreeMap::new(),
+            cache: Vec::new(),
+            redirects: Vec::new(),
+            proxies: Vec::new(),
+            auth: Vec::new(),
+            observability: None,
+            rate_limit: None,
+            status_page: None,
+            mods: Vec::new(),
+            groups: Vec::new(),
+            environment: None
         }
     }
     /// Creates a new `Host` structure with a secure binding on the specified `port` and the
@@ -80,10 +164,8 @@ impl Host {
             Q: AsRef<Path>
     {
         Host {
-            hostname: None,
             listen: Binding::with_security(port, cert, key),
-            static_dir: None,
-            mods: Vec::new()
+            ..Host::new(port)
         }
     }
 
@@ -128,31 +210,248 @@ impl Host {
 
     /// Obtains the current serving directory, if any.
     pub fn serving_dir(&self) -> Option<&Path> {
-        if let Some(ref path) = self.static_dir { Some(path.as_path()) }
-        else { None }
+        self.static_dir.as_ref().map(|p| p.path())
+    }
+    /// Obtains the current serving directory along with its origin, if any.
+    pub fn serving_dir_path(&self) -> Option<&ConfigPath> {
+        self.static_dir.as_ref()
     }
     /// Sets the serving directory for the host.
     pub fn set_serving_dir<P>(&mut self, path: P)
         where
             P: AsRef<Path>
     {
-        self.static_dir = Some(path.as_ref().to_path_buf());
+        self.static_dir = Some(ConfigPath::explicit(path));
     }
     /// Removes the current serving directory from the host.
     pub fn clear_serving_dir(&mut self) {
         self.static_dir = None;
     }
+    /// Returns `true` if directory listings are rendered for the serving directory and `false`
+    /// otherwise.
+    pub fn autoindex(&self) -> bool {
+        self.autoindex
+    }
+    /// Enables or disables the directory listings of the serving directory.
+    pub fn set_autoindex(&mut self, enabled: bool) {
+        self.autoindex = enabled;
+    }
+    /// Obtains the names of the index files, in order of preference.
+    pub fn index(&self) -> Vec<&str> {
+        self.index.iter().map(|i| i.as_str()).collect()
+    }
+    /// Sets the names of the index files, in order of preference.
+    pub fn set_index(&mut self, index: &[&str]) {
+        self.index = index.iter().map(|i| (*i).to_owned()).collect();
+    }
+    /// Obtains the path of the first index file that exists in the serving directory, if any.
+    pub fn index_file(&self) -> Option<PathBuf> {
+        let serving_dir = self.serving_dir()?;
+
+        self.index.iter()
+            .map(|i| serving_dir.join(i))
+            .find(|p| p.is_file())
+    }
+
+    /// Obtains the maximum size of the body of a request, if any.
+    pub fn max_body_size(&self) -> Option<ByteSize> {
+        self.max_body_size
+    }
+    /// Sets the maximum size of the body of a request.
+    pub fn set_max_body_size<S>(&mut self, size: S)
+        where
+            S: Into<ByteSize>
+    {
+        self.max_body_size = Some(size.into());
+    }
+    /// Removes the limit on the size of the body of a request.
+    pub fn clear_max_body_size(&mut self) {
+        self.max_body_size = None;
+    }
+
+    /// Obtains the compression settings of the static files, if any.
+    pub fn compression(&self) -> Option<&Compression> {
+        self.compression.as_ref()
+    }
+    /// Sets the compression settings of the static files.
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = Some(compression);
+    }
+    /// Disables compression of the static files.
+    pub fn clear_compression(&mut self) {
+        self.compression = None;
+    }
+
+    /// Obtains the path of the error page for the status `code`, if any.
+    pub fn error_page(&self, code: u16) -> Option<&Path> {
+        self.error_pages.get(&code).map(|p| p.as_path())
+    }
+    /// Obtains a vector of the status codes with an error page along with the paths of the pages,
+    /// ordered by status code.
+    pub fn error_pages(&self) -> Vec<(u16, &Path)> {
+        self.error_pages.iter().map(|(c, p)| (*c, p.as_path())).collect()
+    }
+    /// Sets the error page for the status `code`.
+    pub fn set_error_page<P>(&mut self, code: u16, path: P)
+        where
+            P: AsRef<Path>
+    {
+        self.error_pages.insert(code, path.as_ref().to_path_buf());
+    }
+    /// Removes the error page for the status `code`.
+    pub fn remove_error_page(&mut self, code: u16) {
+        self.error_pages.remove(&code);
+    }
+
+    /// Obtains a vector of references to the cache rules of the static files.
+    pub fn cache_rules(&self) -> Vec<&CacheRule> {
+        self.cache.iter().collect()
+    }
+    /// Adds a cache rule; rules are matched in the order in which they are added.
+    pub fn add_cache_rule(&mut self, rule: CacheRule) {
+        self.cache.push(rule);
+    }
+    /// Removes all the cache rules.
+    pub fn clear_cache_rules(&mut self) {
+        self.cache.clear();
+    }
+    /// Obtains the first cache rule that applies to `path`, if any.
+    pub fn cache_rule_for(&self, path: &str) -> Option<&CacheRule> {
+        self.cache.iter().find(|r| r.matches(path))
+    }
+
+    /// Obtains a vector of references to the redirect rules.
+    pub fn redirects(&self) -> Vec<&Redirect> {
+        self.redirects.iter().collect()
+    }
+    /// Adds a redirect rule; rules are matched in the order in which they are added.
+    pub fn add_redirect(&mut self, redirect: Redirect) {
+        self.redirects.push(redirect);
+    }
+    /// Removes all the redirect rules.
+    pub fn clear_redirects(&mut self) {
+        self.redirects.clear();
+    }
+    /// Obtains the first redirect rule that applies to `path` along with its target, if any.
+    pub fn redirect_for(&self, path: &str) -> Option<(&Redirect, String)> {
+        self.redirects.iter()
+            .filter_map(|r| r.target(path).map(|t| (r, t)))
+            .next()
+    }
+
+    /// Obtains a vector of references to the proxy rules.
+    pub fn proxy_rules(&self) -> Vec<&ProxyRule> {
+        self.proxies.iter().collect()
+    }
+    /// Adds a proxy rule; rules are matched in the order in which they are added.
+    pub fn add_proxy_rule(&mut self, rule: ProxyRule) {
+        self.proxies.push(rule);
+    }
+    /// Removes all the proxy rules.
+    pub fn clear_proxy_rules(&mut self) {
+        self.proxies.clear();
+    }
+    /// Obtains the first proxy rule that applies to `path`, if any.
+    pub fn proxy_rule_for(&self, path: &str) -> Option<&ProxyRule> {
+        self.proxies.iter().find(|r| r.matches(path))
+    }
+
+    /// Obtains a vector of references to the authentication rules.
+    pub fn auth_rules(&self) -> Vec<&Auth> {
+        self.auth.iter().collect()
+    }
+    /// Adds an authentication rule.
+    pub fn add_auth_rule(&mut self, rule: Auth) {
+        self.auth.push(rule);
+    }
+    /// Removes all the authentication rules.
+    pub fn clear_auth_rules(&mut self) {
+        self.auth.clear();
+    }
+    /// Obtains the authentication rule protecting `path`, if any; if more rules apply, the one
+    /// with the longest path prefix is returned.
+    pub fn auth_for(&self, path: &str) -> Option<&Auth> {
+        self.auth.iter()
+            .filter(|a| a.matches(path))
+            .max_by_key(|a| a.path().trim_end_matches('/').len())
+    }
+
+    /// Obtains the request tracing settings, if any.
+    pub fn observability(&self) -> Option<&Observability> {
+        self.observability.as_ref()
+    }
+    /// Sets the request tracing settings.
+    pub fn set_observability(&mut self, observability: Observability) {
+        self.observability = Some(observability);
+    }
+    /// Removes the request tracing settings.
+    pub fn clear_observability(&mut self) {
+        self.observability = None;
+    }
+
+    /// Obtains the rate limiting policy, if any.
+    pub fn rate_limit(&self) -> Option<&RateLimit> {
+        self.rate_limit.as_ref()
+    }
+    /// Sets the rate limiting policy.
+    pub fn set_rate_limit(&mut self, rate_limit: RateLimit) {
+        self.rate_limit = Some(rate_limit);
+    }
+    /// Disables rate limiting.
+    pub fn clear_rate_limit(&mut self) {
+        self.rate_limit = None;
+    }
+
+    /// Obtains the status page settings, if any.
+    pub fn status_page(&self) -> Option<&StatusPage> {
+        self.status_page.as_ref()
+    }
+    /// Sets the status page settings.
+    pub fn set_status_page(&mut self, status_page: StatusPage) {
+        self.status_page = Some(status_page);
+    }
+    /// Disables the status page.
+    pub fn clear_status_page(&mut self) {
+        self.status_page = None;
+    }
 
     /// Obtains a vector of references to the underlying `Module` structures defining module
-    /// configuration for this host.
+    /// configuration for this host, sorted by priority (see `Module::priority`).
     pub fn mods(&self) -> Vec<&Module> {
-        self.mods.iter().collect()
+        let mut mods: Vec<&Module> = self.mods.iter().collect();
+        mods.sort_by_key(|m| m.priority());
+        mods
     }
     /// Obtains a vector of mutable references to the underlying `Module` structures defining module
     /// configuration for this host.
     pub fn mods_mut(&mut self) -> Vec<&mut Module> {
         self.mods.iter_mut().collect()
     }
+    /// Obtains the names of the module groups enabled on this host (see
+    /// `ConfigurationFile::module_group`).
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+    /// Enables the module group `name` on this host.
+    pub fn add_group(&mut self, name: &str) {
+        if !self.groups.iter().any(|g| g == name) {
+            self.groups.push(name.to_owned());
+        }
+    }
+    /// Disables the module group `name` on this host.
+    pub fn remove_group(&mut self, name: &str) {
+        self.groups.retain(|g| g != name);
+    }
+    /// Obtains the `[host.environment]` table of this host, if any; it overrides the global
+    /// `[environment]` table for the modules of this host (see
+    /// `ConfigurationFile::environment_for`).
+    pub fn environment(&self) -> Option<&Value> {
+        self.environment.as_ref()
+    }
+    /// Sets the `[host.environment]` table of this host.
+    pub fn set_environment(&mut self, environment: Option<Value>) {
+        self.environment = environment;
+    }
     /// Adds a new module to the module list for this host.
     pub fn add_mod(&mut self, module: Module) {
         self.mods.push(module);
@@ -181,7 +480,12 @@ impl Id for Host {
     }
 }
 
-impl Validator<Host> for PathBuf {
+/// Validates a host on its own.
+///
+/// The libraries of the modules of the host are not checked here, since whether a host module is
+/// enabled depends on the global modules: they are validated by the `ConfigurationFile` validator
+/// against the effective module set of the host (see `ConfigurationFile::modules_for`).
+impl Validator<Host> for () {
     fn validate(&self, logger: &mut Logger, item: &Host) -> Result<(), Error> {
         lazy_static! {
             static ref RE_IP: Regex = Regex::new(REGEX_IP_ADDRESS_STRING).unwrap();
@@ -198,25 +502,104 @@ impl Validator<Host> for PathBuf {
             }
         }
 
-        if let Some(serving_dir) = item.serving_dir() {
-            PathValidator(Severity::Error, PathValidatorKind::ExistingDirectory)
-                .validate(logger, &serving_dir)?;
+        if let Some(serving_dir) = item.serving_dir_path() {
+            ConfigPathValidator(Severity::Error, PathValidatorKind::ExistingDirectory, "[[host]].static_dir")
+                .validate(logger, serving_dir)?;
+
+            if !item.autoindex() && !item.index.is_empty() && item.index_file().is_none() {
+                let desc = format!("None of the index files of host '{}' ({}) exists in '{}'.", item.identifier(), item.index.join(", "), serving_dir);
+                logger.log(Severity::Warning, &desc);
+            }
+        }
+        if item.autoindex() && item.serving_dir().is_none() {
+            let desc = format!("Directory listing of host '{}' enabled without a static directory; ignoring.", item.identifier());
+            logger.log(Severity::Warning, &desc);
         }
 
-        let validator = IdValidator(Severity::Critical, self.clone(), PhantomData);
-        validator.validate(logger, &item.mods())?;
+        if let Some(size) = item.max_body_size() {
+            if size.bytes() == 0 {
+                let desc = format!("Maximum body size of host '{}' must be greater than zero.", item.identifier());
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("max_body_size".to_owned()))?;
+            }
+        }
+
+        if let Some(compression) = item.compression() {
+            if item.serving_dir().is_none() {
+                let desc = format!("Compression settings of host '{}' specified without a static directory; ignoring.", item.identifier());
+                logger.log(Severity::Warning, &desc);
+            }
+            ().validate(logger, compression)?;
+        }
+
+        for (code, path) in item.error_pages.iter() {
+            if *code < 400 || *code > 599 {
+                let desc = format!("Error page of host '{}' defined for status {}, which is not an error status.", item.identifier(), code);
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("error_pages".to_owned()))?;
+            }
+            PathValidator(Severity::Error, PathValidatorKind::ExistingFile)
+                .validate(logger, path)?;
+        }
+
+        for rule in item.cache_rules() {
+            ().validate(logger, rule)?;
+        }
+
+        for redirect in item.redirects() {
+            ().validate(logger, redirect)?;
+        }
+        for rule in item.auth_rules() {
+            ().validate(logger, rule)?;
+        }
+
+        OptionValidator(()).validate(logger, &item.observability)?;
+        OptionValidator(()).validate(logger, &item.rate_limit)?;
+
+        if let Some(status_page) = item.status_page() {
+            match item.serving_dir() {
+                Some(serving_dir) => serving_dir.to_path_buf().validate(logger, status_page)?,
+                None => {
+                    let desc = format!("Status page of host '{}' specified without a static directory.", item.identifier());
+                    logger.log(Severity::Error, &desc);
+                    Err(Error::InvalidValue("status_page".to_owned()))?;
+                }
+            }
+        }
+
+        IdValidator(Severity::Critical, (), PhantomData)
+            .validate(logger, &item.mods())?;
 
         Ok(())
     }
 }
 
+/// Validates a host, where the validator is the modules directory.
+///
+/// Kept for compatibility: the directory is no longer used, since the libraries of the modules of
+/// the host are checked against its effective module set; this is the same as validating through
+/// `()`.
+impl Validator<Host> for PathBuf {
+    fn validate(&self, logger: &mut Logger, item: &Host) -> Result<(), Error> {
+        ().validate(logger, item)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::path::{Path, PathBuf};
 
+    use crate::config::compression::Compression;
     use crate::config::host::Host;
     use crate::config::module::Module;
+    use crate::config::observability::Observability;
+    use crate::config::auth::Auth;
     use crate::config::port::Binding;
+    use crate::config::rate_limit::RateLimit;
+    use crate::config::redirect::Redirect;
+    use crate::config::size::ByteSize;
+    use crate::config::status::StatusPage;
+    use crate::diagnostics::Validator;
     use crate::error::event::Event;
 
     #[test]
@@ -257,11 +640,278 @@ mod test {
         assert!(host.serving_dir().is_none());
     }
 
+    #[test]
+    /// Tests directory listings.
+    fn test_autoindex() {
+        let mut host = Host::new(80);
+        let mut events: Vec<Event> = Vec::new();
+        assert!(!host.autoindex());
+
+        host.set_autoindex(true);
+        assert!(host.autoindex());
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+        assert_eq!(events.len(), 1);
+
+        let host: Host = toml::from_str(r#"
+        listen = 80
+        static_dir = "./tests/www"
+        autoindex = true
+        "#).unwrap();
+        events.clear();
+        assert!(host.autoindex());
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    /// Tests the maximum body size.
+    fn test_max_body_size() {
+        let mut host = Host::new(80);
+        let mut events: Vec<Event> = Vec::new();
+        assert!(host.max_body_size().is_none());
+
+        host.set_max_body_size(1024);
+        assert_eq!(host.max_body_size().unwrap(), ByteSize::new(1024));
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+
+        host.set_max_body_size(0);
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_err());
+
+        host.clear_max_body_size();
+        assert!(host.max_body_size().is_none());
+
+        let host: Host = toml::from_str(r#"
+        listen = 80
+        max_body_size = "8MB"
+        "#).unwrap();
+        assert_eq!(host.max_body_size().unwrap(), ByteSize::new(8 * 1024 * 1024));
+    }
+
+    #[test]
+    /// Tests the compression settings.
+    fn test_compression() {
+        let host: Host = toml::from_str(r#"
+        listen = 80
+        static_dir = "./tests/www"
+        compression = { brotli = true, min_size = 256 }
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+        let compression = host.compression().unwrap();
+
+        assert!(compression.gzip());
+        assert!(compression.brotli());
+        assert_eq!(compression.min_size(), ByteSize::new(256));
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+
+        let mut host = Host::new(80);
+        assert!(host.compression().is_none());
+        host.set_compression(Compression::new());
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+        assert_eq!(events.len(), 1);
+        host.clear_compression();
+        assert!(host.compression().is_none());
+    }
+
+    #[test]
+    /// Tests the error pages.
+    fn test_error_pages() {
+        let mut host: Host = toml::from_str(r#"
+        listen = 80
+        error_pages = { 404 = "./tests/www/index.html" }
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(host.error_page(404).unwrap(), Path::new("./tests/www/index.html"));
+        assert!(host.error_page(500).is_none());
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+
+        host.set_error_page(500, "./tests/www/500.html");
+        assert_eq!(host.error_pages().len(), 2);
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_err());
+
+        host.remove_error_page(500);
+        host.set_error_page(200, "./tests/www/index.html");
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_err());
+
+        assert!(toml::from_str::<Host>("listen = 80\nerror_pages = { abc = \"./404.html\" }").is_err());
+    }
+
+    #[test]
+    /// Tests the cache rules.
+    fn test_cache_rules() {
+        let mut host: Host = toml::from_str(r#"
+        listen = 80
+
+        [[cache]]
+        path = "/static/**/*.{js,css}"
+        max_age = 31536000
+        immutable = true
+
+        [[cache]]
+        path = "/**"
+        max_age = 60
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(host.cache_rules().len(), 2);
+        assert!(host.cache_rule_for("/static/js/app.js").unwrap().immutable());
+        assert_eq!(host.cache_rule_for("/index.html").unwrap().max_age(), Some(60));
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+
+        host.clear_cache_rules();
+        assert!(host.cache_rule_for("/index.html").is_none());
+    }
+
+    #[test]
+    /// Tests the redirect rules.
+    fn test_redirects() {
+        let mut host: Host = toml::from_str(r#"
+        listen = 80
+
+        [[redirect]]
+        from = "^/legacy/(.*)$"
+        to = "/$1"
+
+        [[redirect]]
+        from = "^/.*$"
+        to = "https://localhost$0"
+        status = 308
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(host.redirects().len(), 2);
+        assert_eq!(host.redirect_for("/legacy/index.html").unwrap().1, "/index.html");
+        let (redirect, target) = host.redirect_for("/about").unwrap();
+        assert_eq!(redirect.status(), 308);
+        assert_eq!(target, "https://localhost/about");
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+
+        host.add_redirect(Redirect::new("^/(", "/"));
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_err());
+        host.clear_redirects();
+        assert!(host.redirect_for("/about").is_none());
+    }
+
+    #[test]
+    /// Tests the request tracing settings.
+    fn test_observability() {
+        let host: Host = toml::from_str(r#"
+        listen = 80
+
+        [observability]
+        generate_request_id = false
+        trace_sampling = 0.1
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+        let observability = host.observability().unwrap();
+
+        assert_eq!(observability.request_id_header(), "X-Request-Id");
+        assert!(!observability.generate_request_id());
+        assert_eq!(observability.trace_sampling(), 0.1);
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+
+        let mut host = Host::new(80);
+        let mut observability = Observability::new();
+        observability.set_trace_sampling(-1.0);
+        host.set_observability(observability);
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_err());
+        host.clear_observability();
+        assert!(host.observability().is_none());
+    }
+
+    #[test]
+    /// Tests the authentication rules.
+    fn test_auth_rules() {
+        let mut host: Host = toml::from_str(r#"
+        listen = 80
+
+        [[auth]]
+        path = "/"
+        users = { admin = "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=" }
+
+        [[auth]]
+        path = "/admin/"
+        users = { root = "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=" }
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(host.auth_for("/index.html").unwrap().path(), "/");
+        assert_eq!(host.auth_for("/admin/users").unwrap().path(), "/admin/");
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+
+        host.add_auth_rule(Auth::new("/private"));
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_err());
+        host.clear_auth_rules();
+        assert!(host.auth_for("/admin").is_none());
+    }
+
+    #[test]
+    /// Tests the rate limiting policy.
+    fn test_rate_limit() {
+        let mut host: Host = toml::from_str(r#"
+        listen = 80
+        rate_limit = { requests_per_second = 5.0, burst = 10 }
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(host.rate_limit().unwrap().burst(), 10);
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+
+        host.set_rate_limit(RateLimit::by_header(5.0, ""));
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_err());
+        host.clear_rate_limit();
+        assert!(host.rate_limit().is_none());
+    }
+
+    #[test]
+    /// Tests the status page settings.
+    fn test_status_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut host = Host::new(80);
+        let mut events: Vec<Event> = Vec::new();
+        assert!(host.status_page().is_none());
+
+        host.set_status_page(StatusPage::new());
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_err());
+
+        host.set_serving_dir(dir.path());
+        host.set_autoindex(true);
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+
+        host.clear_status_page();
+        assert!(host.status_page().is_none());
+    }
+
+    #[test]
+    /// Tests the index files.
+    fn test_index() {
+        let mut host: Host = toml::from_str(r#"
+        listen = 80
+        static_dir = "./tests/www"
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(host.index(), vec!["index.html", "index.htm"]);
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+        assert!(events.is_empty());
+        assert!(host.index_file().is_some());
+
+        host.set_index(&["default.html"]);
+        assert_eq!(host.index(), vec!["default.html"]);
+        assert!(host.index_file().is_none());
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+        assert_eq!(events.len(), 1);
+
+        host.set_autoindex(true);
+        assert!(PathBuf::from("./mods/").validate(&mut events, &host).is_ok());
+        assert_eq!(events.len(), 1);
+    }
+
     #[test]
     /// Tests the `has_module` function.
     fn test_has_module() {
         let mut host = Host::new(80);
-        let module = Module::new("mod_test");
+        let module = Module::new("mod_test").unwrap();
         assert_eq!(host.has_module("mod_test"), false);
 
         host.add_mod(module);
@@ -273,8 +923,8 @@ mod test {
     fn test_remove_mod() {
         let mut host = Host::new(80);
 
-        host.add_mod(Module::new("mod_dummy"));
-        host.add_mod(Module::new("mod_test"));
+        host.add_mod(Module::new("mod_dummy").unwrap());
+        host.add_mod(Module::new("mod_test").unwrap());
 
         assert_eq!(host.has_module("mod_dummy"), true);
         assert_eq!(host.has_module("mod_test"), true);
@@ -288,7 +938,6 @@ mod test {
     #[test]
     /// Tests the `validate` function.
     fn test_validate() {
-        use crate::diagnostics::Validator;
         use std::str::FromStr;
         let host = Host::new(80);
         let host_ssl = Host::with_security(443, "./tests/test_cert.pem", "./tests/test_key.pem");
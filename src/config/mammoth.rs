@@ -1,17 +1,442 @@
 //! The `Mammoth` structure contains the general configuration for Mammoth, such as the location of
 //! the modules and the log settings.
+use std::io;
+use std::net::ToSocketAddrs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use crate::diagnostics::{Logger, PathValidator, PathValidatorKind, Validator};
+use serde::{Deserialize, Deserializer};
+
+#[cfg(feature = "acme")]
+use crate::config::acme::Acme;
+use crate::config::module::{LibraryNames, LoadPolicy, ModuleConflict};
+use crate::config::path::{ConfigPath, ConfigPathValidator};
+use crate::config::rlimits::ResourceLimits;
+use crate::config::size::ByteSize;
+use crate::loaded::watchdog;
+use crate::version::CompatibilityPolicy;
+use crate::diagnostics::{CompositeLogger, LogEntity, LogFileMode, Logger, OptionValidator, PathValidator, PathValidatorKind, Validator};
+use crate::diagnostics::console::ConsoleLogger;
+#[cfg(all(windows, feature = "windows"))]
+use crate::diagnostics::eventlog::EventLogLogger;
+#[cfg(all(unix, feature = "journald"))]
+use crate::diagnostics::journald::JournaldLogger;
+use crate::diagnostics::origin::OriginFilter;
+use crate::diagnostics::syslog::{Facility, SyslogLogger};
 use crate::error::Error;
 use crate::error::severity::Severity;
 
 /// Structure that defines the general configuration for the Mammoth application.
 #[derive(Clone, Debug, Deserialize)]
 pub struct Mammoth {
-    mods_dir: Option<PathBuf>,
+    mods_dir: Option<ConfigPath>,
     log_file: Option<PathBuf>,
-    log_severity: Option<Severity>
+    log_severity: Option<Severity>,
+    log_rotate: Option<LogRotation>,
+    #[serde(default = "default_log")]
+    log: Vec<LogSink>,
+    rlimits: Option<ResourceLimits>,
+    #[serde(default = "default_compatibility")]
+    compatibility: CompatibilityPolicy,
+    #[serde(default = "default_library_names")]
+    library_names: LibraryNames,
+    #[serde(default)]
+    module_policy: LoadPolicy,
+    #[serde(default)]
+    module_conflicts: ModuleConflict,
+    module_timeout: Option<u64>,
+    #[cfg(feature = "acme")]
+    acme: Option<Acme>
+}
+
+#[doc(hidden)]
+fn default_log() -> Vec<LogSink> { Vec::new() }
+#[doc(hidden)]
+fn default_compatibility() -> CompatibilityPolicy { CompatibilityPolicy::default() }
+#[doc(hidden)]
+fn default_library_names() -> LibraryNames { LibraryNames::default() }
+
+/// Defines where a log sink writes its information.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogTarget {
+    /// The sink writes into a file.
+    File,
+    /// The sink writes into the standard output.
+    Stdout,
+    /// The sink writes into the console, i.e. the standard output and the standard error, with a
+    /// color for each severity.
+    Console,
+    /// The sink writes into the system log.
+    Syslog,
+    /// The sink writes structured entries into the systemd journal (requires the `journald`
+    /// feature).
+    #[cfg(feature = "journald")]
+    Journald,
+    /// The sink writes into the Windows Event Log (requires the `windows` feature).
+    #[cfg(feature = "windows")]
+    EventLog
+}
+
+/// Structure that defines a single log sink, i.e. a target along with its own severity.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct LogSink {
+    target: LogTarget,
+    #[serde(default = "default_sink_severity")]
+    severity: Severity,
+    path: Option<PathBuf>,
+    rotate: Option<LogRotation>,
+    mode: Option<LogFileMode>,
+    buffer: Option<LogBuffer>,
+    facility: Option<Facility>,
+    ident: Option<String>,
+    address: Option<String>,
+    color: Option<bool>,
+    #[serde(default)]
+    compact: bool,
+    #[serde(default)]
+    origin: OriginFilter
+}
+
+#[doc(hidden)]
+fn default_sink_severity() -> Severity { Severity::Warning }
+
+impl LogSink {
+    /// Creates a new `LogSink` structure writing into the file at `path`.
+    pub fn file<P>(severity: Severity, path: P) -> LogSink
+        where
+            P: AsRef<Path>
+    {
+        LogSink {
+            target: LogTarget::File,
+            severity,
+            path: Some(path.as_ref().to_path_buf()),
+            rotate: None,
+            mode: None,
+            buffer: None,
+            facility: None,
+            ident: None,
+            address: None,
+            color: None,
+            compact: false,
+            origin: OriginFilter::new()
+        }
+    }
+    /// Creates a new `LogSink` structure writing into the standard output.
+    pub fn stdout(severity: Severity) -> LogSink {
+        LogSink {
+            target: LogTarget::Stdout,
+            severity,
+            path: None,
+            rotate: None,
+            mode: None,
+            buffer: None,
+            facility: None,
+            ident: None,
+            address: None,
+            color: None,
+            compact: false,
+            origin: OriginFilter::new()
+        }
+    }
+    /// Creates a new `LogSink` structure writing into the console.
+    pub fn console(severity: Severity) -> LogSink {
+        LogSink {
+            target: LogTarget::Console,
+            severity,
+            path: None,
+            rotate: None,
+            mode: None,
+            buffer: None,
+            facility: None,
+            ident: None,
+            address: None,
+            color: None,
+            compact: false,
+            origin: OriginFilter::new()
+        }
+    }
+    /// Creates a new `LogSink` structure writing into the system log with the given `facility` and
+    /// `ident`.
+    pub fn syslog(severity: Severity, facility: Facility, ident: &str) -> LogSink {
+        LogSink {
+            target: LogTarget::Syslog,
+            severity,
+            path: None,
+            rotate: None,
+            mode: None,
+            buffer: None,
+            facility: Some(facility),
+            ident: Some(ident.to_owned()),
+            address: None,
+            color: None,
+            compact: false,
+            origin: OriginFilter::new()
+        }
+    }
+    /// Obtains the target of the sink.
+    pub fn target(&self) -> LogTarget {
+        self.target
+    }
+    /// Obtains the minimum severity of the information written by the sink.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+    /// Obtains the path of the log file, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+    /// Obtains the log rotation settings, if any.
+    pub fn rotation(&self) -> Option<&LogRotation> {
+        self.rotate.as_ref()
+    }
+    /// Sets the log rotation settings.
+    pub fn set_rotation(&mut self, rotation: LogRotation) {
+        self.rotate = Some(rotation);
+    }
+    /// Obtains the mode in which the log file is opened, defaulting to `append`.
+    pub fn mode(&self) -> LogFileMode {
+        self.mode.unwrap_or_default()
+    }
+    /// Sets the mode in which the log file is opened.
+    pub fn set_mode(&mut self, mode: LogFileMode) {
+        self.mode = Some(mode);
+    }
+    /// Obtains the buffering settings, if any.
+    pub fn buffer(&self) -> Option<&LogBuffer> {
+        self.buffer.as_ref()
+    }
+    /// Sets the buffering settings.
+    pub fn set_buffer(&mut self, buffer: LogBuffer) {
+        self.buffer = Some(buffer);
+    }
+    /// Obtains the syslog facility, defaulting to `daemon`.
+    pub fn facility(&self) -> Facility {
+        self.facility.unwrap_or_default()
+    }
+    /// Obtains the syslog (or journal) identifier, defaulting to `mammoth`.
+    pub fn ident(&self) -> &str {
+        self.ident.as_deref().unwrap_or("mammoth")
+    }
+    /// Obtains the address of the remote syslog collector, if any.
+    ///
+    /// If no address is given, the sink writes into the local syslog daemon.
+    pub fn address(&self) -> Option<&str> {
+        self.address.as_deref()
+    }
+    /// Sets the address of the remote syslog collector.
+    pub fn set_address(&mut self, address: &str) {
+        self.address = Some(address.to_owned());
+    }
+    /// Obtains whether the console uses colors, if forced.
+    ///
+    /// If not forced, the console uses colors only if the standard output is a terminal.
+    pub fn color(&self) -> Option<bool> {
+        self.color
+    }
+    /// Forces the console to use colors or not.
+    pub fn set_color(&mut self, color: bool) {
+        self.color = Some(color);
+    }
+    /// Returns `true` if the console uses the compact format and `false` otherwise.
+    pub fn is_compact(&self) -> bool {
+        self.compact
+    }
+    /// Sets whether the console uses the compact format.
+    pub fn set_compact(&mut self, compact: bool) {
+        self.compact = compact;
+    }
+    /// Obtains the filter selecting the information written by the sink by its origin.
+    pub fn origin(&self) -> &OriginFilter {
+        &self.origin
+    }
+    /// Sets the filter selecting the information written by the sink by its origin.
+    pub fn set_origin(&mut self, origin: OriginFilter) {
+        self.origin = origin;
+    }
+    /// Constructs the `Logger` described by the sink.
+    pub fn build(&self) -> Result<Box<dyn Logger>, Error> {
+        match self.target {
+            LogTarget::File => {
+                let path = self.path().ok_or_else(|| Error::InvalidValue("mammoth.log.path".to_owned()))?;
+                let entity = if let Some(rotation) = self.rotation() {
+                    LogEntity::with_rotation(self.severity, path, *rotation)?
+                } else {
+                    LogEntity::with_mode(self.severity, path, self.mode())?
+                };
+                Ok(Box::new(self.buffered(entity)))
+            },
+            LogTarget::Stdout => Ok(Box::new(self.buffered(LogEntity::new(self.severity, Arc::new(RwLock::new(io::stdout())))))),
+            LogTarget::Console => {
+                let mut console = ConsoleLogger::new(self.severity).with_compact(self.compact);
+                if let Some(color) = self.color {
+                    console = console.with_color(color);
+                }
+                Ok(Box::new(console))
+            },
+            LogTarget::Syslog => if let Some(address) = self.address() {
+                Ok(Box::new(SyslogLogger::udp(self.severity, self.facility(), self.ident(), address)?))
+            } else {
+                self.build_local_syslog()
+            },
+            #[cfg(feature = "journald")]
+            LogTarget::Journald => self.build_journald(),
+            #[cfg(feature = "windows")]
+            LogTarget::EventLog => self.build_eventlog()
+        }
+    }
+
+    #[doc(hidden)]
+    fn buffered(&self, entity: LogEntity) -> LogEntity {
+        match self.buffer {
+            Some(buffer) => entity.with_buffer(buffer),
+            None => entity
+        }
+    }
+
+    #[cfg(unix)]
+    #[doc(hidden)]
+    fn build_local_syslog(&self) -> Result<Box<dyn Logger>, Error> {
+        Ok(Box::new(SyslogLogger::new(self.severity, self.facility(), self.ident())?))
+    }
+
+    #[cfg(not(unix))]
+    #[doc(hidden)]
+    fn build_local_syslog(&self) -> Result<Box<dyn Logger>, Error> {
+        Err(Error::InvalidValue("mammoth.log.address".to_owned()))
+    }
+
+    #[cfg(all(unix, feature = "journald"))]
+    #[doc(hidden)]
+    fn build_journald(&self) -> Result<Box<dyn Logger>, Error> {
+        Ok(Box::new(JournaldLogger::new(self.severity, self.ident())?))
+    }
+
+    #[cfg(all(not(unix), feature = "journald"))]
+    #[doc(hidden)]
+    fn build_journald(&self) -> Result<Box<dyn Logger>, Error> {
+        Err(Error::InvalidValue("mammoth.log.target".to_owned()))
+    }
+
+    #[cfg(all(windows, feature = "windows"))]
+    #[doc(hidden)]
+    fn build_eventlog(&self) -> Result<Box<dyn Logger>, Error> {
+        Ok(Box::new(EventLogLogger::new(self.severity, self.ident())?))
+    }
+
+    #[cfg(all(not(windows), feature = "windows"))]
+    #[doc(hidden)]
+    fn build_eventlog(&self) -> Result<Box<dyn Logger>, Error> {
+        Err(Error::InvalidValue("mammoth.log.target".to_owned()))
+    }
+}
+
+/// Structure that defines when the log file should be rotated (i.e. when it exceeds a given size,
+/// when it gets older than a given age or both) and how many old log files should be kept.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct LogRotation {
+    size: Option<ByteSize>,
+    max_age: Option<u64>,
+    #[serde(default = "default_keep")]
+    keep: usize
+}
+
+#[doc(hidden)]
+fn default_keep() -> usize { 5 }
+
+impl LogRotation {
+    /// Creates a new `LogRotation` structure that rotates the log file whenever it exceeds `size`,
+    /// keeping at most `keep` old log files.
+    pub fn new<S>(size: S, keep: usize) -> LogRotation
+        where
+            S: Into<ByteSize>
+    {
+        LogRotation {
+            size: Some(size.into()),
+            max_age: None,
+            keep
+        }
+    }
+    /// Creates a new `LogRotation` structure that rotates the log file whenever it gets older than
+    /// `seconds`, keeping at most `keep` old log files.
+    pub fn by_age(seconds: u64, keep: usize) -> LogRotation {
+        LogRotation {
+            size: None,
+            max_age: Some(seconds),
+            keep
+        }
+    }
+    /// Also rotates the log file whenever it gets older than `seconds`.
+    pub fn with_max_age(mut self, seconds: u64) -> LogRotation {
+        self.max_age = Some(seconds);
+        self
+    }
+    /// Obtains the size after which the log file is rotated, if any.
+    pub fn size(&self) -> Option<ByteSize> {
+        self.size
+    }
+    /// Obtains the age after which the log file is rotated, if any.
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age.map(Duration::from_secs)
+    }
+    /// Obtains the number of old log files to be kept.
+    pub fn keep(&self) -> usize {
+        self.keep
+    }
+}
+
+/// Structure that defines how the information written by a log sink is buffered, i.e. the size of
+/// the buffer and the interval after which the buffered information is written anyway.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct LogBuffer {
+    #[serde(default = "default_buffer_size")]
+    size: ByteSize,
+    #[serde(default, deserialize_with = "deserialize_interval")]
+    interval: Option<Duration>
+}
+
+#[doc(hidden)]
+fn default_buffer_size() -> ByteSize { ByteSize::new(64 * 1024) }
+
+/// Deserializes the interval of a log buffer, which is written in seconds in TOML.
+fn deserialize_interval<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>
+{
+    Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+}
+
+impl LogBuffer {
+    /// Creates a new `LogBuffer` structure buffering at most `size` bytes.
+    pub fn new<S>(size: S) -> LogBuffer
+        where
+            S: Into<ByteSize>
+    {
+        LogBuffer {
+            size: size.into(),
+            interval: None
+        }
+    }
+    /// Also writes the buffered information whenever `interval` has elapsed since the last write.
+    pub fn with_interval(mut self, interval: Duration) -> LogBuffer {
+        self.interval = Some(interval);
+        self
+    }
+    /// Obtains the size of the buffer.
+    pub fn size(&self) -> ByteSize {
+        self.size
+    }
+    /// Obtains the interval after which the buffered information is written, if any.
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+}
+
+impl Default for LogBuffer {
+    fn default() -> LogBuffer {
+        LogBuffer::new(default_buffer_size())
+    }
 }
 
 impl Mammoth {
@@ -20,14 +445,27 @@ impl Mammoth {
         Mammoth {
             mods_dir: None,
             log_file: None,
-            log_severity: None
+            log_severity: None,
+            log_rotate: None,
+            log: Vec::new(),
+            rlimits: None,
+            compatibility: default_compatibility(),
+            library_names: default_library_names(),
+            module_policy: LoadPolicy::new(),
+            module_conflicts: ModuleConflict::default(),
+            module_timeout: None,
+            #[cfg(feature = "acme")]
+            acme: None
         }
     }
 
     /// Obtains the modules directory.
     pub fn mods_dir(&self) -> Option<&Path> {
-        if let Some(ref path) = self.mods_dir { Some(path.as_path()) }
-        else { None }
+        self.mods_dir.as_ref().map(|p| p.path())
+    }
+    /// Obtains the modules directory along with its origin.
+    pub fn mods_dir_path(&self) -> Option<&ConfigPath> {
+        self.mods_dir.as_ref()
     }
     /// Obtains the log file path.
     pub fn log_file(&self) -> Option<&Path> {
@@ -38,12 +476,113 @@ impl Mammoth {
     pub fn log_severity(&self) -> Option<Severity> {
         self.log_severity
     }
+    /// Obtains the log rotation settings, if any.
+    pub fn log_rotation(&self) -> Option<&LogRotation> {
+        self.log_rotate.as_ref()
+    }
+    /// Obtains the log sinks.
+    ///
+    /// If no `[[mammoth.log]]` sink is specified, the `log_file`, `log_severity` and `log_rotate`
+    /// keys define a single file sink.
+    pub fn log_sinks(&self) -> Vec<LogSink> {
+        if !self.log.is_empty() {
+            self.log.clone()
+        } else if let Some(log_file) = self.log_file() {
+            let mut sink = LogSink::file(self.log_severity.unwrap_or_else(default_sink_severity), log_file);
+            sink.rotate = self.log_rotate;
+            vec![sink]
+        } else {
+            Vec::new()
+        }
+    }
+    /// Constructs a logger that forwards the information to every log sink, each receiving only
+    /// the information with at least its own severity and selected by its origin filter.
+    pub fn logger(&self) -> Result<CompositeLogger, Error> {
+        #[cfg(feature = "chaos")]
+        crate::testing::chaos::check(crate::testing::chaos::FailurePoint::LoggerInit)?;
+
+        let mut logger = CompositeLogger::new();
+        for sink in self.log_sinks() {
+            logger.push_boxed(sink.severity(), sink.origin().clone(), sink.build()?);
+        }
+        Ok(logger)
+    }
+    /// Obtains the module compatibility policy.
+    pub fn compatibility(&self) -> &CompatibilityPolicy {
+        &self.compatibility
+    }
+    /// Sets the module compatibility policy.
+    pub fn set_compatibility(&mut self, compatibility: CompatibilityPolicy) {
+        self.compatibility = compatibility;
+    }
+    /// Obtains the file names of the module libraries searched in the modules directory.
+    pub fn library_names(&self) -> &LibraryNames {
+        &self.library_names
+    }
+    /// Sets the file names of the module libraries searched in the modules directory.
+    pub fn set_library_names(&mut self, names: LibraryNames) {
+        self.library_names = names;
+    }
+    /// Obtains the policy restricting which modules can be loaded.
+    pub fn module_policy(&self) -> &LoadPolicy {
+        &self.module_policy
+    }
+    /// Sets the policy restricting which modules can be loaded.
+    pub fn set_module_policy(&mut self, policy: LoadPolicy) {
+        self.module_policy = policy;
+    }
+    /// Obtains how a module defined both globally and in a host is resolved (see
+    /// `EffectiveModule::resolve`).
+    pub fn module_conflicts(&self) -> ModuleConflict {
+        self.module_conflicts
+    }
+    /// Sets how a module defined both globally and in a host is resolved.
+    pub fn set_module_conflicts(&mut self, conflict: ModuleConflict) {
+        self.module_conflicts = conflict;
+    }
+    /// Obtains how long the server waits for a module to return from its lifecycle calls (see
+    /// `loaded::watchdog`), defaulting to `watchdog::DEFAULT_TIMEOUT`; `None` if it waits
+    /// indefinitely.
+    pub fn module_timeout(&self) -> Option<Duration> {
+        match self.module_timeout {
+            Some(0) => None,
+            Some(seconds) => Some(Duration::from_secs(seconds)),
+            None => Some(watchdog::DEFAULT_TIMEOUT)
+        }
+    }
+    /// Sets how many seconds the server waits for a module to return from its lifecycle calls;
+    /// zero waits indefinitely.
+    pub fn set_module_timeout(&mut self, seconds: u64) {
+        self.module_timeout = Some(seconds);
+    }
+    /// Obtains the resource limits of the process, if any.
+    pub fn rlimits(&self) -> Option<&ResourceLimits> {
+        self.rlimits.as_ref()
+    }
+    /// Sets the resource limits of the process.
+    pub fn set_rlimits(&mut self, rlimits: ResourceLimits) {
+        self.rlimits = Some(rlimits);
+    }
+    /// Leaves the resource limits of the process unchanged.
+    pub fn clear_rlimits(&mut self) {
+        self.rlimits = None;
+    }
+    /// Obtains the ACME settings, if any.
+    #[cfg(feature = "acme")]
+    pub fn acme(&self) -> Option<&Acme> {
+        self.acme.as_ref()
+    }
+    /// Sets the ACME settings.
+    #[cfg(feature = "acme")]
+    pub fn set_acme(&mut self, acme: Acme) {
+        self.acme = Some(acme);
+    }
     /// Sets the modules directory.
     pub fn set_mods_dir<P>(&mut self, path: P)
         where
             P: AsRef<Path>
     {
-        self.mods_dir = Some(path.as_ref().to_path_buf());
+        self.mods_dir = Some(ConfigPath::explicit(path));
     }
     /// Sets the log file path.
     pub fn set_log_file<P>(&mut self, path: P)
@@ -56,17 +595,109 @@ impl Mammoth {
     pub fn set_log_severity(&mut self, severity: Severity) {
         self.log_severity = Some(severity);
     }
+    /// Sets the log rotation settings.
+    pub fn set_log_rotation(&mut self, rotation: LogRotation) {
+        self.log_rotate = Some(rotation);
+    }
+    /// Disables log rotation.
+    pub fn clear_log_rotation(&mut self) {
+        self.log_rotate = None;
+    }
+    /// Adds a log sink.
+    pub fn add_log_sink(&mut self, sink: LogSink) {
+        self.log.push(sink);
+    }
+    /// Removes all the log sinks.
+    pub fn clear_log_sinks(&mut self) {
+        self.log.clear();
+    }
 }
 
 impl Validator<Mammoth> for () {
     fn validate(&self, logger: &mut Logger, item: &Mammoth) -> Result<(), Error> {
-        if let Some(mods_dir) = item.mods_dir() {
-            PathValidator(Severity::Error, PathValidatorKind::ExistingDirectory)
-                .validate(logger, &mods_dir)?;
+        if let Some(mods_dir) = item.mods_dir_path() {
+            ConfigPathValidator(Severity::Error, PathValidatorKind::ExistingDirectory, "[mammoth].mods_dir")
+                .validate(logger, mods_dir)?;
+        }
+        OptionValidator(PathValidator(Severity::Error, PathValidatorKind::FilePath))
+            .validate(logger, &item.log_file)?;
+        if item.log_rotation().is_some() && item.log_file().is_none() {
+            logger.log(Severity::Warning, "Log rotation specified without a log file; ignoring.");
+        }
+        if !item.log.is_empty() && (item.log_file.is_some() || item.log_severity.is_some() || item.log_rotate.is_some()) {
+            logger.log(Severity::Warning, "Both [[mammoth.log]] sinks and 'log_file' specified; ignoring 'log_file', 'log_severity' and 'log_rotate'.");
         }
-        if let Some(log_file) = item.log_file() {
-            PathValidator(Severity::Error, PathValidatorKind::FilePath)
-                .validate(logger, &log_file)?;
+        for sink in item.log_sinks() {
+            ().validate(logger, &sink)?;
+        }
+        OptionValidator(()).validate(logger, &item.rlimits)?;
+        ().validate(logger, item.compatibility())?;
+        ().validate(logger, item.library_names())?;
+        #[cfg(feature = "acme")]
+        OptionValidator(()).validate(logger, &item.acme)?;
+        Ok(())
+    }
+}
+
+impl Validator<LogSink> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &LogSink) -> Result<(), Error> {
+        match item.target() {
+            LogTarget::File => if let Some(path) = item.path() {
+                PathValidator(Severity::Error, PathValidatorKind::FilePath)
+                    .validate(logger, &path)?;
+            } else {
+                logger.log(Severity::Error, "Log sink of type 'file' without a 'path'.");
+                Err(Error::InvalidValue("mammoth.log.path".to_owned()))?;
+            },
+            _ => if item.path().is_some() || item.rotation().is_some() || item.mode.is_some() {
+                logger.log(Severity::Warning, "Only log sinks of type 'file' use 'path', 'rotate' and 'mode'; ignoring.");
+            }
+        }
+        if item.target() != LogTarget::File && item.target() != LogTarget::Stdout && item.buffer().is_some() {
+            logger.log(Severity::Warning, "Only log sinks of type 'file' and 'stdout' use 'buffer'; ignoring.");
+        }
+        if let Some(buffer) = item.buffer() {
+            if buffer.size().bytes() == 0 {
+                logger.log(Severity::Error, "Log buffer size must be greater than zero.");
+                Err(Error::InvalidValue("mammoth.log.buffer.size".to_owned()))?;
+            }
+            if buffer.interval() == Some(Duration::from_secs(0)) {
+                logger.log(Severity::Error, "Log buffer interval must be greater than zero.");
+                Err(Error::InvalidValue("mammoth.log.buffer.interval".to_owned()))?;
+            }
+        }
+        if item.rotation().is_some() && item.mode() != LogFileMode::Append {
+            logger.log(Severity::Warning, "Rotated log files are always appended to; ignoring 'mode'.");
+        }
+        if item.target() != LogTarget::Syslog && (item.facility.is_some() || item.address.is_some()) {
+            logger.log(Severity::Warning, "Only log sinks of type 'syslog' use 'facility' and 'address'; ignoring.");
+        }
+        if (item.target() == LogTarget::File || item.target() == LogTarget::Stdout || item.target() == LogTarget::Console) && item.ident.is_some() {
+            logger.log(Severity::Warning, "Log sinks of type 'file', 'stdout' and 'console' do not use 'ident'; ignoring.");
+        }
+        if item.target() != LogTarget::Console && (item.color.is_some() || item.compact) {
+            logger.log(Severity::Warning, "Only log sinks of type 'console' use 'color' and 'compact'; ignoring.");
+        }
+        if let Some(address) = item.address() {
+            if address.to_socket_addrs().is_err() {
+                let desc = format!("Invalid syslog address: '{}'.", address);
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("mammoth.log.address".to_owned()))?;
+            }
+        }
+        if let Some(rotation) = item.rotation() {
+            if rotation.size().is_none() && rotation.max_age().is_none() {
+                logger.log(Severity::Error, "Log rotation requires a 'size', a 'max_age' or both.");
+                Err(Error::InvalidValue("log_rotate".to_owned()))?;
+            }
+            if rotation.size().map(|s| s.bytes() == 0).unwrap_or(false) {
+                logger.log(Severity::Error, "Log rotation size must be greater than zero.");
+                Err(Error::InvalidValue("log_rotate.size".to_owned()))?;
+            }
+            if rotation.max_age() == Some(Duration::from_secs(0)) {
+                logger.log(Severity::Error, "Log rotation age must be greater than zero.");
+                Err(Error::InvalidValue("log_rotate.max_age".to_owned()))?;
+            }
         }
         Ok(())
     }
@@ -75,8 +706,16 @@ impl Validator<Mammoth> for () {
 #[cfg(test)]
 mod test {
     use std::path::Path;
+    use std::time::Duration;
 
     use crate::config::Mammoth;
+    use crate::config::module::LibraryNames;
+    use crate::config::mammoth::{LogBuffer, LogRotation, LogSink, LogTarget};
+    use crate::config::size::ByteSize;
+    use crate::diagnostics::{LogFileMode, Validator};
+    use crate::diagnostics::origin::OriginFilter;
+    use crate::diagnostics::syslog::Facility;
+    use crate::error::event::Event;
     use crate::error::severity::Severity;
 
     #[test]
@@ -105,5 +744,265 @@ mod test {
         assert_eq!(mammoth.mods_dir().unwrap(), Path::new("./mods/"));
         assert_eq!(mammoth.log_file().unwrap(), Path::new("mammoth.log"));
         assert_eq!(mammoth.log_severity().unwrap(), Severity::Warning);
+
+        assert_eq!(mammoth.module_timeout(), Some(Duration::from_secs(30)));
+        mammoth.set_module_timeout(5);
+        assert_eq!(mammoth.module_timeout(), Some(Duration::from_secs(5)));
+        mammoth.set_module_timeout(0);
+        assert!(mammoth.module_timeout().is_none());
+    }
+
+    #[test]
+    /// Tests deserialization and validation of the log rotation settings.
+    fn test_log_rotation() {
+        let toml = r#"
+        log_file = "mammoth.log"
+        log_rotate = { size = "10MB", keep = 3 }
+        "#;
+        let mammoth: Mammoth = toml::from_str(toml).unwrap();
+        let rotation = mammoth.log_rotation().unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(rotation.size(), Some(ByteSize::new(10 * 1024 * 1024)));
+        assert!(rotation.max_age().is_none());
+        assert_eq!(rotation.keep(), 3);
+        assert_eq!(mammoth.log_sinks()[0].rotation(), Some(rotation));
+        assert!(().validate(&mut events, &mammoth).is_ok());
+
+        let mammoth: Mammoth = toml::from_str(r#"log_rotate = { size = 1024 }"#).unwrap();
+        assert_eq!(mammoth.log_rotation().unwrap().keep(), 5);
+
+        let mut mammoth = Mammoth::new();
+        mammoth.set_log_file("mammoth.log");
+        mammoth.set_log_rotation(LogRotation::new(0, 5));
+        assert!(().validate(&mut events, &mammoth).is_err());
+
+        let mammoth: Mammoth = toml::from_str(r#"
+        log_file = "mammoth.log"
+        log_rotate = { max_age = 86400 }
+        "#).unwrap();
+        assert!(mammoth.log_rotation().unwrap().size().is_none());
+        assert_eq!(mammoth.log_rotation().unwrap().max_age(), Some(Duration::from_secs(86400)));
+        assert!(().validate(&mut events, &mammoth).is_ok());
+
+        let mut mammoth = Mammoth::new();
+        mammoth.set_log_file("mammoth.log");
+        mammoth.set_log_rotation(LogRotation::by_age(0, 5));
+        assert!(().validate(&mut events, &mammoth).is_err());
+        mammoth.set_log_rotation(LogRotation::new(1024, 5).with_max_age(3600));
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        let mut mammoth: Mammoth = toml::from_str(r#"
+        log_file = "mammoth.log"
+        log_rotate = { keep = 3 }
+        "#).unwrap();
+        assert!(().validate(&mut events, &mammoth).is_err());
+
+        mammoth.clear_log_rotation();
+        assert!(mammoth.log_rotation().is_none());
+    }
+
+    #[test]
+    /// Tests deserialization and validation of the library file names.
+    fn test_library_names() {
+        let mammoth: Mammoth = toml::from_str(r#"library_names = ["lib{name}.so", "{name}.so", "{name}.dll"]"#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(mammoth.library_names().patterns(), &["lib{name}.so", "{name}.so", "{name}.dll"]);
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert_eq!(Mammoth::new().library_names(), &LibraryNames::default());
+
+        let mammoth: Mammoth = toml::from_str(r#"library_names = ["mod_test.so"]"#).unwrap();
+        assert!(().validate(&mut events, &mammoth).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    /// Tests the `[[mammoth.log]]` sinks and their fallback to the `log_file` key.
+    fn test_log_sinks() {
+        let toml = r#"
+        [[log]]
+        target = "stdout"
+        severity = "information"
+
+        [[log]]
+        target = "file"
+        path = "mammoth.log"
+        rotate = { size = "1MB" }
+        "#;
+        let mammoth: Mammoth = toml::from_str(toml).unwrap();
+        let sinks = mammoth.log_sinks();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(sinks.len(), 2);
+        assert_eq!(sinks[0], LogSink::stdout(Severity::Information));
+        assert_eq!(sinks[1].target(), LogTarget::File);
+        assert_eq!(sinks[1].severity(), Severity::Warning);
+        assert_eq!(sinks[1].path().unwrap(), Path::new("mammoth.log"));
+        assert_eq!(sinks[1].rotation().unwrap().size(), Some(ByteSize::new(1024 * 1024)));
+        assert!(().validate(&mut events, &mammoth).is_ok());
+
+        let mammoth: Mammoth = toml::from_str(r#"log_file = "mammoth.log""#).unwrap();
+        assert_eq!(mammoth.log_sinks(), vec![LogSink::file(Severity::Warning, "mammoth.log")]);
+
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "file" }]"#).unwrap();
+        assert!(().validate(&mut events, &mammoth).is_err());
+
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "console", color = false, compact = true }]"#).unwrap();
+        let sink = &mammoth.log_sinks()[0];
+        assert_eq!(sink.target(), LogTarget::Console);
+        assert_eq!(sink.color(), Some(false));
+        assert!(sink.is_compact());
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert!(sink.build().is_ok());
+        let mut console = LogSink::console(Severity::Information);
+        console.set_compact(true);
+        assert_ne!(console, LogSink::console(Severity::Information));
+
+        assert!(toml::from_str::<Mammoth>(r#"log = [{ target = "printer" }]"#).is_err());
+    }
+
+    #[test]
+    /// Tests the syslog log sink.
+    fn test_log_sink_syslog() {
+        let toml = r#"
+        [[log]]
+        target = "syslog"
+        facility = "local3"
+        ident = "my_server"
+        address = "127.0.0.1:514"
+        "#;
+        let mammoth: Mammoth = toml::from_str(toml).unwrap();
+        let sink = &mammoth.log_sinks()[0];
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(sink.target(), LogTarget::Syslog);
+        assert_eq!(sink.facility(), Facility::Local3);
+        assert_eq!(sink.ident(), "my_server");
+        assert_eq!(sink.address(), Some("127.0.0.1:514"));
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert!(sink.build().is_ok());
+
+        let default = LogSink::syslog(Severity::Error, Facility::Daemon, "mammoth");
+        assert_eq!(toml::from_str::<Mammoth>(r#"log = [{ target = "syslog", severity = "error" }]"#).unwrap().log_sinks()[0].facility(), default.facility());
+
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "syslog", address = "not an address" }]"#).unwrap();
+        assert!(().validate(&mut events, &mammoth).is_err());
+    }
+
+    #[test]
+    /// Tests the modes of the file log sinks.
+    fn test_log_sink_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("mammoth.log");
+        let mammoth: Mammoth = toml::from_str(&format!(r#"log = [{{ target = "file", path = "{}", mode = "truncate" }}]"#, path.display())).unwrap();
+        let sink = &mammoth.log_sinks()[0];
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(sink.mode(), LogFileMode::Truncate);
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert!(events.is_empty());
+        assert!(sink.build().is_ok());
+        assert!(path.exists());
+        assert_eq!(LogSink::file(Severity::Warning, &path).mode(), LogFileMode::Append);
+
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "stdout", mode = "create" }]"#).unwrap();
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert_eq!(events.len(), 1);
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "file", path = "mammoth.log", mode = "create", rotate = { size = "1MB" } }]"#).unwrap();
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    /// Tests the buffering of the log sinks.
+    fn test_log_sink_buffer() {
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "stdout", buffer = { size = "4KB", interval = 2 } }, { target = "file", path = "mammoth.log", buffer = {} }]"#).unwrap();
+        let sinks = mammoth.log_sinks();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(sinks[0].buffer(), Some(&LogBuffer::new(4096).with_interval(Duration::from_secs(2))));
+        assert_eq!(sinks[0].buffer().unwrap().interval(), Some(Duration::from_secs(2)));
+        assert_eq!(sinks[1].buffer(), Some(&LogBuffer::default()));
+        assert_eq!(sinks[1].buffer().unwrap().interval(), None);
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert!(events.is_empty());
+
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "syslog", buffer = { size = "4KB" } }]"#).unwrap();
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert_eq!(events.len(), 1);
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "stdout", buffer = { size = 0 } }]"#).unwrap();
+        assert!(().validate(&mut events, &mammoth).is_err());
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "stdout", buffer = { interval = 0 } }]"#).unwrap();
+        assert!(().validate(&mut events, &mammoth).is_err());
+    }
+
+    #[test]
+    /// Tests the origin filters of the log sinks.
+    fn test_log_sink_origin() {
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "stdout" }, { target = "stdout", origin = { modules = ["mod_auth"], sections = ["mod"] } }]"#).unwrap();
+        let sinks = mammoth.log_sinks();
+
+        assert!(sinks[0].origin().is_empty());
+        assert_eq!(sinks[1].origin(), &OriginFilter::new().with_module("mod_auth").with_section("mod"));
+        assert!(sinks[1].origin().matches(&[("module", "mod_auth"), ("section", "mod")]));
+        assert!(!sinks[1].origin().matches(&[("module", "mod_auth"), ("section", "host.mod")]));
+    }
+
+    #[test]
+    #[cfg(feature = "journald")]
+    /// Tests the journald log sink.
+    fn test_log_sink_journald() {
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "journald", ident = "my_server" }]"#).unwrap();
+        let sink = &mammoth.log_sinks()[0];
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(sink.target(), LogTarget::Journald);
+        assert_eq!(sink.ident(), "my_server");
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert!(events.is_empty());
+
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "journald", facility = "local0" }]"#).unwrap();
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "windows")]
+    /// Tests the Windows Event Log sink.
+    fn test_log_sink_eventlog() {
+        let mammoth: Mammoth = toml::from_str(r#"log = [{ target = "eventlog", ident = "my_service" }]"#).unwrap();
+        let sink = &mammoth.log_sinks()[0];
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(sink.target(), LogTarget::EventLog);
+        assert_eq!(sink.ident(), "my_service");
+        assert!(().validate(&mut events, &mammoth).is_ok());
+        assert!(events.is_empty());
+        #[cfg(not(windows))]
+        assert!(sink.build().is_err());
+    }
+
+    #[test]
+    /// Tests the construction of the composite logger from the log sinks.
+    fn test_logger() {
+        use crate::diagnostics::Logger;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut mammoth = Mammoth::new();
+        mammoth.add_log_sink(LogSink::file(Severity::Information, dir.path().join("info.log")));
+        mammoth.add_log_sink(LogSink::file(Severity::Error, dir.path().join("error.log")));
+
+        let mut logger = mammoth.logger().unwrap();
+        assert_eq!(logger.severity(), Some(Severity::Information));
+        logger.log(Severity::Debug, "Debug.");
+        logger.log(Severity::Information, "Information.");
+        logger.log(Severity::Error, "Error.");
+
+        let info = std::fs::read_to_string(dir.path().join("info.log")).unwrap();
+        let error = std::fs::read_to_string(dir.path().join("error.log")).unwrap();
+        assert_eq!(info.lines().count(), 2);
+        assert_eq!(error.lines().count(), 1);
+
+        mammoth.clear_log_sinks();
+        assert!(mammoth.logger().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,346 @@
+//! Registry of the deprecated configuration keys and automatic migration of old configurations.
+//!
+//! Every `Deprecation` describes a key (and, optionally, a specific value of the key) that is
+//! still accepted but will be removed in a future release, along with a hint about its
+//! replacement.
+//! Deprecated keys are detected when a `ConfigurationFile` is parsed and reported as warnings
+//! during its validation; `migrate` rewrites a configuration replacing them whenever possible.
+//!
+//! Keys are written as in `ConfigurationSet`, where `[*]` matches any element of an array of
+//! tables (e.g. `host[*].mod[*].name`).
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use toml::Value;
+use toml::value::Table;
+
+use crate::error::Error;
+
+/// Structure that describes a deprecated key.
+#[derive(Debug)]
+pub struct Deprecation {
+    key: &'static str,
+    value: Option<&'static str>,
+    replacement: &'static str,
+    removal: &'static str,
+    fix: Option<fn(&mut Value)>
+}
+
+/// Structure that describes a deprecated key found in a configuration.
+#[derive(Clone, Debug)]
+pub struct DeprecatedKey {
+    key: String,
+    deprecation: &'static Deprecation
+}
+
+/// List of all the deprecated keys.
+///
+/// Keys are added here when they are retired, along with the function migrating them (if
+/// possible), and removed from here and from the configuration at the release given as their
+/// `removal`.
+pub static DEPRECATIONS: &[Deprecation] = &[
+    Deprecation {
+        key: "mammoth.log_file",
+        value: None,
+        replacement: "a [[mammoth.log]] sink with target = \"file\" and the same `path`",
+        removal: "0.1.0",
+        fix: Some(migrate_log_file)
+    },
+    Deprecation {
+        key: "mammoth.log_severity",
+        value: None,
+        replacement: "the `severity` key of a [[mammoth.log]] sink",
+        removal: "0.1.0",
+        fix: Some(migrate_log_file)
+    }
+];
+
+impl Deprecation {
+    /// Obtains the deprecated key.
+    pub fn key(&self) -> &str {
+        self.key
+    }
+    /// Obtains the deprecated value of the key, if only a specific value is deprecated.
+    pub fn value(&self) -> Option<&str> {
+        self.value
+    }
+    /// Obtains a description of the replacement of the deprecated key.
+    pub fn replacement(&self) -> &str {
+        self.replacement
+    }
+    /// Obtains the version in which the deprecated key will be removed.
+    pub fn removal(&self) -> &str {
+        self.removal
+    }
+    /// Returns `true` if the deprecated key can be migrated automatically and `false` otherwise.
+    pub fn can_fix(&self) -> bool {
+        self.fix.is_some()
+    }
+}
+
+impl DeprecatedKey {
+    /// Obtains the deprecated key, as found in the configuration.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+    /// Obtains the description of the deprecation.
+    pub fn deprecation(&self) -> &Deprecation {
+        self.deprecation
+    }
+}
+
+impl Display for DeprecatedKey {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.deprecation.value {
+            Some(value) => write!(f, "Value '{}' of key '{}'", value, self.key)?,
+            None => write!(f, "Key '{}'", self.key)?
+        }
+        write!(f, " is deprecated and will be removed in version {}; use {} instead.", self.deprecation.removal, self.deprecation.replacement)
+    }
+}
+
+/// Collects the values matching the `pattern` under `value`, along with their concrete keys.
+fn find<'a>(value: &'a Value, pattern: &[&str], key: String, found: &mut Vec<(String, &'a Value)>) {
+    let (segment, rest) = match pattern.split_first() {
+        Some(split) => split,
+        None => {
+            found.push((key, value));
+            return;
+        }
+    };
+    let (name, array) = if let Some(name) = segment.strip_suffix("[*]") { (name, true) } else { (*segment, false) };
+    let child_key = if key.is_empty() { name.to_owned() } else { format!("{}.{}", key, name) };
+
+    match value.get(name) {
+        Some(Value::Array(elements)) if array => for (i, element) in elements.iter().enumerate() {
+            find(element, rest, format!("{}[{}]", child_key, i), found);
+        },
+        Some(child) if !array => find(child, rest, child_key, found),
+        _ => {}
+    }
+}
+
+/// Finds all the deprecated keys used in the configuration `value`.
+pub fn check(value: &Value) -> Vec<DeprecatedKey> {
+    check_in(value, DEPRECATIONS)
+}
+
+/// Finds all the keys of `deprecations` used in the configuration `value`.
+fn check_in(value: &Value, deprecations: &'static [Deprecation]) -> Vec<DeprecatedKey> {
+    let mut deprecated = Vec::new();
+
+    for deprecation in deprecations.iter() {
+        let pattern: Vec<&str> = deprecation.key.split('.').collect();
+        let mut found = Vec::new();
+        find(value, &pattern, String::new(), &mut found);
+
+        for (key, found) in found {
+            let matches = match deprecation.value {
+                Some(deprecated) => found.as_str() == Some(deprecated),
+                None => true
+            };
+            if matches {
+                deprecated.push(DeprecatedKey { key, deprecation });
+            }
+        }
+    }
+
+    deprecated
+}
+
+/// Migrates the configuration `value`, replacing the deprecated keys whenever possible.
+///
+/// Returns the deprecated keys that could not be migrated automatically.
+pub fn migrate(value: &mut Value) -> Vec<DeprecatedKey> {
+    migrate_in(value, DEPRECATIONS)
+}
+
+/// Migrates the configuration `value`, replacing the keys of `deprecations` whenever possible.
+fn migrate_in(value: &mut Value, deprecations: &'static [Deprecation]) -> Vec<DeprecatedKey> {
+    for deprecated in check_in(value, deprecations) {
+        if let Some(fix) = deprecated.deprecation.fix {
+            fix(value);
+        }
+    }
+
+    check_in(value, deprecations)
+}
+
+/// Migrates the configuration contained in the TOML string `contents` (see `migrate`).
+///
+/// Returns the migrated TOML string and the deprecated keys that could not be migrated.
+pub fn migrate_str(contents: &str) -> Result<(String, Vec<DeprecatedKey>), Error> {
+    let mut value: Value = toml::from_str(contents)?;
+    let remaining = migrate(&mut value);
+    let contents = toml::to_string(&value)
+        .map_err(|err| Error::Generic(Box::new(err)))?;
+
+    Ok((contents, remaining))
+}
+
+/// Moves `mammoth.log_file`, `mammoth.log_severity` and `mammoth.log_rotate` into a new file
+/// sink in `[[mammoth.log]]`.
+fn migrate_log_file(value: &mut Value) {
+    let mammoth = match value.get_mut("mammoth").and_then(|m| m.as_table_mut()) {
+        Some(mammoth) => mammoth,
+        None => return
+    };
+    // If sinks are already specified, the old keys are ignored anyway.
+    let has_sinks = mammoth.get("log")
+        .and_then(|l| l.as_array())
+        .map(|l| !l.is_empty())
+        .unwrap_or(false);
+    let path = mammoth.remove("log_file");
+    let severity = mammoth.remove("log_severity");
+    let rotate = mammoth.remove("log_rotate");
+
+    if let (Some(path), false) = (path, has_sinks) {
+        let mut sink = Table::new();
+        sink.insert("target".to_owned(), Value::from("file"));
+        sink.insert("path".to_owned(), path);
+        if let Some(severity) = severity { sink.insert("severity".to_owned(), severity); }
+        if let Some(rotate) = rotate { sink.insert("rotate".to_owned(), rotate); }
+
+        mammoth.insert("log".to_owned(), Value::Array(vec![Value::Table(sink)]));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use toml::Value;
+
+    use crate::config::ConfigurationFile;
+    use crate::config::migrations::{check, check_in, migrate, migrate_in, migrate_str, Deprecation};
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+    use crate::error::severity::Severity;
+
+    static TEST_DEPRECATIONS: &[Deprecation] = &[
+        Deprecation {
+            key: "host[*].root",
+            value: None,
+            replacement: "`static_dir`",
+            removal: "0.1.0",
+            fix: Some(migrate_root)
+        },
+        Deprecation {
+            key: "host[*].autoindex",
+            value: Some("auto"),
+            replacement: "`true` or `false`",
+            removal: "0.1.0",
+            fix: None
+        }
+    ];
+
+    /// Renames `host[*].root` into `host[*].static_dir`.
+    fn migrate_root(value: &mut Value) {
+        let hosts = match value.get_mut("host").and_then(|h| h.as_array_mut()) {
+            Some(hosts) => hosts,
+            None => return
+        };
+
+        for host in hosts.iter_mut().filter_map(|h| h.as_table_mut()) {
+            if let Some(root) = host.remove("root") {
+                host.insert("static_dir".to_owned(), root);
+            }
+        }
+    }
+
+    const OLD: &str = r#"
+    [mammoth]
+
+    [[host]]
+    listen = 8080
+
+    [[host]]
+    listen = 8088
+    root = "./"
+    autoindex = "auto"
+    "#;
+
+    const LOG_FILE: &str = r#"
+    [mammoth]
+    log_file = "mammoth.log"
+    log_severity = "information"
+    log_rotate = { size = "1MB", keep = 2 }
+
+    [[host]]
+    listen = 8080
+    "#;
+
+    #[test]
+    /// Tests detection of deprecated keys.
+    fn test_check() {
+        let value: Value = toml::from_str(OLD).unwrap();
+        let deprecated = check_in(&value, TEST_DEPRECATIONS);
+
+        assert_eq!(deprecated.len(), 2);
+        assert_eq!(deprecated[0].key(), "host[1].root");
+        assert_eq!(deprecated[0].deprecation().removal(), "0.1.0");
+        assert!(deprecated[0].deprecation().can_fix());
+        assert!(deprecated[0].to_string().contains("removed in version 0.1.0"));
+        assert_eq!(deprecated[1].key(), "host[1].autoindex");
+        assert!(deprecated[1].to_string().starts_with("Value 'auto' of key 'host[1].autoindex'"));
+
+        let value: Value = toml::from_str("[mammoth]\n[[host]]\nlisten = 80\nautoindex = true").unwrap();
+        assert!(check_in(&value, TEST_DEPRECATIONS).is_empty());
+
+        let value: Value = toml::from_str(LOG_FILE).unwrap();
+        let deprecated = check(&value);
+        assert_eq!(deprecated.len(), 2);
+        assert_eq!(deprecated[0].key(), "mammoth.log_file");
+        assert_eq!(deprecated[1].key(), "mammoth.log_severity");
+        assert!(check(&toml::from_str(OLD).unwrap()).is_empty());
+    }
+
+    #[test]
+    /// Tests that deprecated keys are reported as warnings during validation.
+    fn test_validation_warnings() {
+        let configuration = ConfigurationFile::from_str(LOG_FILE).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(configuration.deprecations().len(), 2);
+        ().validate(&mut events, &configuration).unwrap();
+        let warnings: Vec<&Event> = events.iter().filter(|e| e.to_string().contains("deprecated")).collect();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings.iter().all(|e| e.severity() == Severity::Warning));
+        assert!(warnings[0].to_string().contains("Key 'mammoth.log_file' is deprecated and will be removed in version 0.1.0"));
+
+        let configuration = ConfigurationFile::from_str("[mammoth]\n[[host]]\nlisten = 8080").unwrap();
+        assert!(configuration.deprecations().is_empty());
+    }
+
+    #[test]
+    /// Tests the automatic migration of deprecated keys.
+    fn test_migrate() {
+        let mut value: Value = toml::from_str(OLD).unwrap();
+        let remaining = migrate_in(&mut value, TEST_DEPRECATIONS);
+
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].key(), "host[1].autoindex");
+        assert_eq!(value["host"][1]["static_dir"].as_str(), Some("./"));
+        assert!(value["host"][1].get("root").is_none());
+
+        let (migrated, remaining) = migrate_str(LOG_FILE).unwrap();
+        let migrated = ConfigurationFile::from_str(&migrated).unwrap();
+        let sinks = migrated.mammoth().log_sinks();
+        assert!(remaining.is_empty());
+        assert!(migrated.deprecations().is_empty());
+        assert_eq!(sinks.len(), 1);
+        assert_eq!(sinks[0].path(), Some(Path::new("mammoth.log")));
+        assert_eq!(sinks[0].severity(), Severity::Information);
+        assert_eq!(sinks[0].rotation().unwrap().keep(), 2);
+
+        // The old keys are dropped if sinks are already specified, since they are ignored.
+        let mut value: Value = toml::from_str(r#"
+        [mammoth]
+        log_file = "mammoth.log"
+        log = [{ target = "stdout" }]
+        "#).unwrap();
+        assert!(migrate(&mut value).is_empty());
+        assert!(value["mammoth"].get("log_file").is_none());
+        assert_eq!(value["mammoth"]["log"].as_array().unwrap().len(), 1);
+    }
+}
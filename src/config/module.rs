@@ -1,113 +1,443 @@
 //! The `Module` structure contains the configuration for a Mammoth module.
 //!
-//! A 'module' is a dynamic library (`.dll` in Windows and `.so` in Linux) containing additional
-//! functionality to the server.
-//! The main entry point is a `__construct` function that loads all the needed configuration.
-//! The simplest module is as follows.
-//! ```rust
+//! A 'module' is a dynamic library (`.dll` in Windows, `.dylib` in macOS and `.so` in Linux)
+//! containing additional functionality to the server.
+//! Unless a `location` is given, the library of a module is searched in `mods_dir` both with and
+//! without the `lib` prefix (e.g. `libmod_test.so` and `mod_test.so`), as Cargo names libraries
+//! differently on different platforms.
+//! The only entry point is a `__mammoth_module` function returning the C-compatible function table
+//! of the module (see `abi::ModuleVTable`), so that modules built with a different compiler can be
+//! loaded safely. The table is generated by the `mammoth_module` attribute of the `mammoth-macro`
+//! crate; the simplest module is as follows.
+//! ```rust,ignore
+//! use mammoth_macro::mammoth_module;
 //! use mammoth_setup::MammothInterface;
 //! use mammoth_setup::diagnostics::{Log, Logger};
 //! use mammoth_setup::error::Error;
 //! use toml::Value;
 //!
+//! #[mammoth_module(construct)]
 //! struct LibraryModule {
 //!     /* fields omitted */
 //! }
 //!
 //! impl Log for LibraryModule {
 //!     /* implementation omitted */
-//! #    fn register_logger(&mut self,logger: std::sync::Arc<std::sync::RwLock<Logger>>) {
-//! #        unimplemented!()
-//! #    }
-//! #    fn retrieve_logger(&self) -> Option<std::sync::Arc<std::sync::RwLock<Logger>>> {
-//! #        unimplemented!()
-//! #    }
 //! }
 //!
 //! impl MammothInterface for LibraryModule {
-//! #    fn on_validation(&self,_: &mut Logger) -> Result<(), Error> {
-//! #        unimplemented!()
-//! #    }
 //!     /* implementation omitted */
 //! }
 //!
-//! #[no_mangle]
-//! fn __construct() -> *mut MammothInterface {
-//!     let interface = LibraryModule { /* ... */ };
-//!     /* initialization omitted */
-//!     let interface = Box::new(interface);
-//!     let interface = Box::into_raw(interface);
-//!     interface
+//! fn construct(config: Option<Value>) -> LibraryModule {
+//!     LibraryModule { /* ... */ }
 //! }
 //! ```
 //!
-//! There may be other available entry points in the future (probably, at least a `__version`
-//! function and a `__validate` function).
+//! The function table also exposes the version of `mammoth-setup` the module is built with, used to
+//! check compatibility, and the metadata of the module (see `Module::inspect`).
+//!
+//! Since module names are used to build the paths of the libraries, they are restricted to ASCII
+//! letters, digits, `_` and `-` (see `ModuleName`).
 
+use std::borrow::Borrow;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
 use std::path::{PathBuf, Path};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use libloading::{Library, Symbol};
-use semver::{Version, VersionReq};
+use libloading::Library;
+use openssl::sha::sha256;
+use semver::Version;
+use serde::{Deserialize, Deserializer};
+use serde::de;
 use toml::Value;
 
 use crate::MammothInterface;
-use crate::loaded::library::LoadedModuleSet;
-use crate::diagnostics::{Id, Logger, Validator};
+use crate::abi;
+use crate::abi::ForeignModule;
+use crate::config::HostIdentifier;
+use crate::config::glob::Glob;
+use crate::config::path::{ConfigPath, ConfigPathValidator};
+use crate::config::schema::SchemaValidator;
+use crate::loaded::library::{LoadedLibrary, LoadedModuleSet};
+use crate::loaded::registry::StaticModuleRegistry;
+use crate::loaded::watchdog;
+use crate::diagnostics::{Id, Logger, PathValidatorKind, Validator};
+use crate::diagnostics::origin::{Origin, OriginLogger};
 use crate::error::Error;
 use crate::error::severity::Severity;
+use crate::metadata::{ModuleKind, ModuleMetadata};
+use crate::sandbox::SandboxedFs;
 use crate::version;
+#[cfg(feature = "wasm")]
+use crate::wasm::WasmModule;
+use crate::version::{CompatibilityPolicy, CompatVerdict};
+
+/// Extension of the dynamic libraries on the current platform.
+#[cfg(target_os="windows")]
+pub const DYLIB_EXT: &str = ".dll";
+/// Extension of the dynamic libraries on the current platform.
+#[cfg(target_os="macos")]
+pub const DYLIB_EXT: &str = ".dylib";
+/// Extension of the dynamic libraries on the current platform.
+#[cfg(all(unix, not(target_os="macos")))]
+pub const DYLIB_EXT: &str = ".so";
 
+/// Prefixes of the library file names, in the order in which they are tried.
 #[cfg(target_os="windows")]
-pub(crate) const DYLIB_EXT: &str = ".dll";
-#[cfg(target_os="linux")]
-pub(crate) const DYLIB_EXT: &str = ".so";
+pub const DYLIB_PREFIXES: &[&str] = &["", "lib"];
+/// Prefixes of the library file names, in the order in which they are tried.
+#[cfg(not(target_os="windows"))]
+pub const DYLIB_PREFIXES: &[&str] = &["lib", ""];
+
+/// Placeholder for the name of the module in the patterns of `LibraryNames`.
+pub const NAME_PLACEHOLDER: &str = "{name}";
+
+/// Structure that contains the patterns of the file names of the module libraries, in the order
+/// in which they are tried, where `{name}` stands for the name of the module, e.g.
+/// `["lib{name}.so", "{name}.so", "{name}.dll"]`.
+///
+/// The default patterns are the ones of the current platform (see `DYLIB_PREFIXES` and
+/// `DYLIB_EXT`). Both loading and validation resolve libraries through this structure.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct LibraryNames(Vec<String>);
+
+impl LibraryNames {
+    /// Creates a new `LibraryNames` structure from the given patterns.
+    pub fn new(patterns: Vec<String>) -> LibraryNames {
+        LibraryNames(patterns)
+    }
+
+    /// Obtains the patterns of the file names.
+    pub fn patterns(&self) -> &[String] {
+        &self.0
+    }
+    /// Obtains the file names of the library of the module `name`, in the order in which they are
+    /// tried.
+    pub fn file_names(&self, name: &ModuleName) -> Vec<String> {
+        self.0.iter()
+            .map(|pattern| pattern.replace(NAME_PLACEHOLDER, name.as_str()))
+            .collect()
+    }
+    /// Obtains the paths in which the library of the module `name` is searched within `dir`, in
+    /// the order in which they are tried.
+    pub fn candidates<P>(&self, dir: P, name: &ModuleName) -> Vec<PathBuf>
+        where
+            P: AsRef<Path>
+    {
+        self.file_names(name).into_iter()
+            .map(|file_name| dir.as_ref().join(file_name))
+            .collect()
+    }
+    /// Obtains the path of the library of the module `name` within `dir`, i.e. the first existing
+    /// candidate, or the first candidate if none exists.
+    pub fn resolve<P>(&self, dir: P, name: &ModuleName) -> PathBuf
+        where
+            P: AsRef<Path>
+    {
+        let mut candidates = self.candidates(dir, name);
+        let index = candidates.iter().position(|p| p.is_file()).unwrap_or(0);
+        candidates.swap_remove(index)
+    }
+}
+
+impl Default for LibraryNames {
+    fn default() -> LibraryNames {
+        LibraryNames(DYLIB_PREFIXES.iter()
+            .map(|prefix| format!("{}{}{}", prefix, NAME_PLACEHOLDER, DYLIB_EXT))
+            .collect())
+    }
+}
+
+impl Validator<LibraryNames> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &LibraryNames) -> Result<(), Error> {
+        if item.patterns().is_empty() {
+            logger.log(Severity::Error, "No library file names specified.");
+            Err(Error::InvalidValue("mammoth.library_names".to_owned()))?;
+        }
+        for pattern in item.patterns() {
+            if !pattern.contains(NAME_PLACEHOLDER) || pattern.contains('/') || pattern.contains('\\') {
+                let desc = format!("Invalid library file name '{}': must contain '{}' and no path separators.", pattern, NAME_PLACEHOLDER);
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("mammoth.library_names".to_owned()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Structure that restricts which modules may ever be loaded, regardless of the configuration of
+/// the hosts, e.g. `{ allow = ["mod_*", "/opt/mammoth/mods/**"], deny = ["mod_debug*"] }`.
+///
+/// Patterns containing a `/` are matched against the path of the library of the module, the other
+/// ones against its name; built-in modules have no path. A module is denied if it matches any
+/// `deny` pattern or if `allow` is not empty and the module matches none of its patterns.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct LoadPolicy {
+    #[serde(default)]
+    allow: Vec<Glob>,
+    #[serde(default)]
+    deny: Vec<Glob>
+}
+
+impl LoadPolicy {
+    /// Creates a new `LoadPolicy` structure allowing every module.
+    pub fn new() -> LoadPolicy {
+        LoadPolicy::default()
+    }
+
+    /// Obtains the patterns of the allowed modules; if empty, every module not denied is allowed.
+    pub fn allowed(&self) -> &[Glob] {
+        &self.allow
+    }
+    /// Adds a pattern to the ones of the allowed modules.
+    pub fn allow(&mut self, pattern: Glob) {
+        self.allow.push(pattern);
+    }
+    /// Obtains the patterns of the denied modules.
+    pub fn denied(&self) -> &[Glob] {
+        &self.deny
+    }
+    /// Adds a pattern to the ones of the denied modules.
+    pub fn deny(&mut self, pattern: Glob) {
+        self.deny.push(pattern);
+    }
+    /// Returns `true` if the module `name`, whose library is at `path` (if any), may be loaded and
+    /// `false` otherwise.
+    pub fn permits(&self, name: &str, path: Option<&Path>) -> bool {
+        let path = path.map(|p| p.to_string_lossy().replace('\\', "/"));
+        let matches = |glob: &Glob| if glob.pattern().contains('/') {
+            path.as_ref().is_some_and(|p| glob.matches(p))
+        } else {
+            glob.matches(name)
+        };
+
+        !self.deny.iter().any(matches) && (self.allow.is_empty() || self.allow.iter().any(matches))
+    }
+    /// Checks that the module `name`, whose library is at `path` (if any), may be loaded.
+    ///
+    /// Fails with `Error::ModuleDenied` otherwise.
+    pub fn check(&self, name: &str, path: Option<&Path>) -> Result<(), Error> {
+        if !self.permits(name, path) {
+            Err(Error::ModuleDenied(name.to_owned()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Obtains the file names of the library of the module `name` on the current platform, in the
+/// order in which they are tried.
+pub fn library_file_names(name: &ModuleName) -> Vec<String> {
+    LibraryNames::default().file_names(name)
+}
+
+/// Obtains the paths in which the library of the module `name` is searched within `dir` on the
+/// current platform, in the order in which they are tried.
+pub fn library_candidates<P>(dir: P, name: &ModuleName) -> Vec<PathBuf>
+    where
+        P: AsRef<Path>
+{
+    LibraryNames::default().candidates(dir, name)
+}
+
+/// Obtains the path of the library of the module `name` within `dir` on the current platform (see
+/// `LibraryNames::resolve`).
+pub fn resolve_library<P>(dir: P, name: &ModuleName) -> PathBuf
+    where
+        P: AsRef<Path>
+{
+    LibraryNames::default().resolve(dir, name)
+}
+
+/// Computes the SHA-256 checksum of the file at `path`, as a lowercase hexadecimal string.
+pub fn sha256_file<P>(path: P) -> Result<String, Error>
+    where
+        P: AsRef<Path>
+{
+    let digest = sha256(&fs::read(path)?);
+
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Structure that contains a valid module name, i.e. a non-empty string containing only ASCII
+/// letters, digits, `_` and `-`.
+///
+/// Module names cannot contain path separators, whitespace or dots, so they can be safely used to
+/// build the paths of the libraries.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModuleName(String);
+
+impl ModuleName {
+    /// Creates a new `ModuleName` structure, failing with `Error::InvalidModuleName` if `name` is
+    /// not a valid module name.
+    pub fn new(name: &str) -> Result<ModuleName, Error> {
+        match ModuleName::problem(name) {
+            Some(_) => Err(Error::InvalidModuleName(name.to_owned())),
+            None => Ok(ModuleName(name.to_owned()))
+        }
+    }
+    /// Obtains a description of the reason why `name` is not a valid module name, if any.
+    pub fn problem(name: &str) -> Option<&'static str> {
+        if name.trim().is_empty() {
+            Some("module names cannot be empty")
+        } else if name.contains(['/', '\\']) {
+            Some("module names cannot contain path separators")
+        } else if name.contains('.') {
+            Some("module names cannot contain dots")
+        } else if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            Some("module names can only contain ASCII letters, digits, '_' and '-'")
+        } else {
+            None
+        }
+    }
+    /// Obtains the name as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ModuleName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for ModuleName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for ModuleName {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for ModuleName {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<ModuleName, Error> {
+        ModuleName::new(name)
+    }
+}
+
+impl PartialEq<str> for ModuleName {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for ModuleName {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl<'de> Deserialize<'de> for ModuleName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+        D: Deserializer<'de> {
+        let name = String::deserialize(deserializer)?;
+        match ModuleName::problem(&name) {
+            Some(problem) => Err(de::Error::custom(format!("invalid module name '{}': {}", name, problem))),
+            None => Ok(ModuleName(name))
+        }
+    }
+}
+
+/// Describes how many instances of a module are constructed when it is enabled on several hosts.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Instancing {
+    /// A single instance serves every host, constructed with the configuration of the first host
+    /// that loads it.
+    #[default]
+    Shared,
+    /// Every host gets its own instance, constructed with the configuration effective for that
+    /// host; the constructor can obtain the host through `abi::current_host`.
+    PerHost
+}
 
 /// Structure that defines configuration for a module library.
 #[derive(Clone, Debug, Deserialize)]
 pub struct Module {
-    name: String,
-    location: Option<PathBuf>,
+    name: ModuleName,
+    location: Option<ConfigPath>,
     #[serde(default = "default_enabled")]
     enabled: bool,
-    config: Option<Value>
+    config: Option<Value>,
+    debug_symbols: Option<PathBuf>,
+    requires: Option<Vec<ModuleName>>,
+    priority: Option<i32>,
+    sha256: Option<String>,
+    instancing: Option<Instancing>,
+    kind: Option<ModuleKind>,
+    before: Option<Vec<ModuleName>>,
+    after: Option<Vec<ModuleName>>,
+    allowed_paths: Option<Vec<PathBuf>>,
+    required: Option<bool>,
+    #[serde(skip)]
+    inherited_config: Option<Value>
 }
 
 #[doc(hidden)]
 fn default_enabled() -> bool { true }
 
 impl Module {
-    /// Creates a new `Module` structure given its name.
-    pub fn new(name: &str) -> Module {
+    /// Creates a new `Module` structure given its name, failing with `Error::InvalidModuleName` if
+    /// `name` is not a valid module name (see `ModuleName`).
+    pub fn new(name: &str) -> Result<Module, Error> {
+        Ok(Module::with_name(ModuleName::new(name)?))
+    }
+    /// Creates a new `Module` structure given its validated name.
+    pub fn with_name(name: ModuleName) -> Module {
         Module {
-            name: name.to_owned(),
+            name,
             location: None,
             enabled: true,
-            config: None
+            config: None,
+            debug_symbols: None,
+            requires: None,
+            priority: None,
+            sha256: None,
+            instancing: None,
+            kind: None,
+            before: None,
+            after: None,
+            allowed_paths: None,
+            required: None,
+            inherited_config: None
         }
     }
-    /// Creates a new, disabled `Module` structure given its name.
-    pub fn new_disabled(name: &str) -> Module {
-        Module {
-            name: name.to_owned(),
-            location: None,
+    /// Creates a new, disabled `Module` structure given its name, failing as `Module::new` does.
+    pub fn new_disabled(name: &str) -> Result<Module, Error> {
+        Ok(Module {
             enabled: false,
-            config: None
-        }
+            ..Module::new(name)?
+        })
     }
-    /// Creates a new `Module` structure given its name and configuration.
-    pub fn with_config(name: &str, enabled: bool, config: Value) -> Module
+    /// Creates a new `Module` structure given its name and configuration, failing as `Module::new`
+    /// does.
+    pub fn with_config(name: &str, enabled: bool, config: Value) -> Result<Module, Error>
     {
-        Module {
-            name: name.to_owned(),
-            location: None,
+        Ok(Module {
             enabled,
-            config: Some(config)
-        }
+            config: Some(config),
+            ..Module::new(name)?
+        })
     }
     /// Obtains the name of the module.
     pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+    /// Obtains the validated name of the module.
+    pub fn module_name(&self) -> &ModuleName {
         &self.name
     }
     /// Enables the module.
@@ -122,11 +452,41 @@ impl Module {
     pub fn enabled(&self) -> bool {
         self.enabled
     }
+    /// Returns `true` if the server cannot start without this module and `false` if the module is
+    /// optional, i.e. it is skipped with a warning if its validation or its loading fails.
+    ///
+    /// If not specified, modules are required.
+    pub fn is_required(&self) -> bool {
+        self.required.unwrap_or(true)
+    }
+    /// Sets whether the server can start without this module.
+    pub fn set_required(&mut self, required: bool) {
+        self.required = Some(required);
+    }
 
     /// Returns a reference to the `TOML` module configuration, if any.
+    ///
+    /// This is the configuration declared by this entry; see `effective_config` for the
+    /// configuration passed to the module.
     pub fn config(&self) -> Option<&Value> {
         self.config.as_ref()
     }
+    /// Obtains the configuration passed to the module, i.e. the configuration of this entry
+    /// deep-merged over the configuration inherited from the global entry with the same name, if
+    /// any (see `EffectiveModule::combine`).
+    ///
+    /// Tables are merged key by key, with the values of this entry taking precedence; any other
+    /// value, arrays included, replaces the inherited one.
+    pub fn effective_config(&self) -> Option<Value> {
+        match (&self.inherited_config, &self.config) {
+            (Some(inherited), Some(config)) => {
+                let mut merged = inherited.clone();
+                merge_config(&mut merged, config);
+                Some(merged)
+            },
+            (inherited, config) => config.as_ref().or(inherited.as_ref()).cloned()
+        }
+    }
     /// Returns a mutable reference to the `TOML` module configuration, if any.
     pub fn config_mut(&mut self) -> Option<&mut Value> {
         self.config.as_mut()
@@ -141,116 +501,928 @@ impl Module {
     /// If no location is given, this function returns `None` and Mammoth uses the default module
     /// directory.
     pub fn location(&self) -> Option<&Path> {
-        self.location.as_ref().and_then(|p| Some(p.as_path()))
+        self.location.as_ref().map(|p| p.path())
     }
     /// Sets the path of the library containing this module.
     pub fn set_location<P>(&mut self, path: P)
         where
             P: AsRef<Path>
     {
-        self.location = Some(path.as_ref().to_path_buf());
+        self.location = Some(ConfigPath::explicit(path));
     }
     /// Removes the given path of the library containing this module.
     pub fn clear_location(&mut self) {
         self.location = None;
     }
-    /// Tries to load the library.
+    /// Returns the path of the file containing the debug symbols of the library, if any.
+    ///
+    /// Debug symbols are used to resolve the frames of the backtraces generated within the module
+    /// (see `diagnostics::symbolicate`).
+    pub fn debug_symbols(&self) -> Option<&Path> {
+        self.debug_symbols.as_deref()
+    }
+    /// Sets the path of the file containing the debug symbols of the library.
+    pub fn set_debug_symbols<P>(&mut self, path: P)
+        where
+            P: AsRef<Path>
+    {
+        self.debug_symbols = Some(path.as_ref().to_path_buf());
+    }
+    /// Removes the path of the file containing the debug symbols of the library.
+    pub fn clear_debug_symbols(&mut self) {
+        self.debug_symbols = None;
+    }
+    /// Obtains the priority of the module; modules with lower priorities are loaded first, and
+    /// modules with the same priority are loaded in the order in which they are declared.
+    ///
+    /// If not specified, the priority is zero.
+    pub fn priority(&self) -> i32 {
+        self.priority.unwrap_or(0)
+    }
+    /// Sets the priority of the module.
+    pub fn set_priority(&mut self, priority: i32) {
+        self.priority = Some(priority);
+    }
+    /// Obtains the expected SHA-256 checksum of the library of the module, as a hexadecimal
+    /// string, if any.
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+    /// Sets the expected SHA-256 checksum of the library of the module.
+    pub fn set_sha256(&mut self, checksum: &str) {
+        self.sha256 = Some(checksum.to_owned());
+    }
+    /// Removes the expected checksum of the library of the module.
+    pub fn clear_sha256(&mut self) {
+        self.sha256 = None;
+    }
+    /// Obtains how the module is instantiated when enabled on several hosts.
+    ///
+    /// If not specified, a single instance is shared by every host.
+    pub fn instancing(&self) -> Instancing {
+        self.instancing.unwrap_or_default()
+    }
+    /// Sets how the module is instantiated when enabled on several hosts.
+    pub fn set_instancing(&mut self, instancing: Instancing) {
+        self.instancing = Some(instancing);
+    }
+    /// Obtains the kind of the module specified in the configuration, if any.
+    pub fn kind(&self) -> Option<ModuleKind> {
+        self.kind
+    }
+    /// Sets the kind of the module.
+    pub fn set_kind(&mut self, kind: ModuleKind) {
+        self.kind = Some(kind);
+    }
+    /// Obtains the kind of the module: the one specified in the configuration, if any, or else the
+    /// one declared in `metadata`, if any, or else `ModuleKind::Service`.
+    pub fn effective_kind(&self, metadata: Option<&ModuleMetadata>) -> ModuleKind {
+        self.kind
+            .or_else(|| metadata.and_then(|m| m.kind()))
+            .unwrap_or(ModuleKind::Service)
+    }
+    /// Obtains the kind of the module as `effective_kind` does, inspecting its library within
+    /// `mods_dir` through `names` (see `Module::inspect_in`).
+    ///
+    /// Libraries that cannot be inspected declare no kind.
+    pub fn kind_in<P>(&self, mods_dir: P, names: &LibraryNames) -> ModuleKind
+        where
+            P: AsRef<Path>
+    {
+        self.effective_kind(self.inspect_in(mods_dir, names).ok().as_ref())
+    }
+    /// Obtains the names of the middleware modules this module must precede in the middleware
+    /// chain of a host.
+    pub fn before(&self) -> &[ModuleName] {
+        self.before.as_deref().unwrap_or(&[])
+    }
+    /// Adds a middleware module to the ones this module must precede.
+    pub fn add_before(&mut self, name: ModuleName) {
+        self.before.get_or_insert_with(Vec::new).push(name);
+    }
+    /// Obtains the names of the middleware modules this module must follow in the middleware chain
+    /// of a host.
+    pub fn after(&self) -> &[ModuleName] {
+        self.after.as_deref().unwrap_or(&[])
+    }
+    /// Adds a middleware module to the ones this module must follow.
+    pub fn add_after(&mut self, name: ModuleName) {
+        self.after.get_or_insert_with(Vec::new).push(name);
+    }
+    /// Returns `true` if the module declares the paths it is allowed to access and `false`
+    /// otherwise.
+    pub fn is_sandboxed(&self) -> bool {
+        self.allowed_paths.is_some()
+    }
+    /// Obtains the paths the module is allowed to access.
+    pub fn allowed_paths(&self) -> &[PathBuf] {
+        self.allowed_paths.as_deref().unwrap_or(&[])
+    }
+    /// Adds a path to the ones the module is allowed to access.
+    pub fn add_allowed_path<P>(&mut self, path: P)
+        where
+            P: AsRef<Path>
+    {
+        self.allowed_paths.get_or_insert_with(Vec::new).push(path.as_ref().to_path_buf());
+    }
+    /// Obtains the handle through which the module accesses its allowed paths (see
+    /// `MammothInterface::on_sandbox`).
+    pub fn sandbox(&self) -> SandboxedFs {
+        SandboxedFs::new(self.allowed_paths())
+    }
+    /// Checks the library at `path` against the expected checksum, if any.
+    ///
+    /// Fails with `Error::ChecksumMismatch` if the library has been replaced by a different file.
+    pub fn verify_checksum<P>(&self, path: P) -> Result<(), Error>
+        where
+            P: AsRef<Path>
+    {
+        if let Some(ref expected) = self.sha256 {
+            let actual = sha256_file(&path)?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                return Err(Error::ChecksumMismatch(path.as_ref().to_path_buf(), expected.clone(), actual));
+            }
+        }
+
+        Ok(())
+    }
+    /// Obtains the names of the modules required by this module in the configuration.
+    pub fn requires(&self) -> &[ModuleName] {
+        self.requires.as_deref().unwrap_or(&[])
+    }
+    /// Adds a module to the ones required by this module.
+    pub fn add_requirement(&mut self, name: ModuleName) {
+        self.requires.get_or_insert_with(Vec::new).push(name);
+    }
+    /// Obtains the names of the modules required by this module, both in the configuration and in
+    /// the metadata exported by its library (see `Module::inspect`), using `mods_dir` as the
+    /// default directory if no location is given.
+    ///
+    /// Libraries that cannot be inspected contribute no requirement.
+    pub fn requirements<P>(&self, mods_dir: P) -> Vec<ModuleName>
+        where
+            P: AsRef<Path>
+    {
+        self.requirements_in(mods_dir, &LibraryNames::default())
+    }
+    /// Obtains the names of the modules required by this module as `requirements` does, searching
+    /// the library within `mods_dir` through `names`.
+    pub fn requirements_in<P>(&self, mods_dir: P, names: &LibraryNames) -> Vec<ModuleName>
+        where
+            P: AsRef<Path>
+    {
+        let mut requirements = self.requires().to_vec();
+        if let Ok(metadata) = self.inspect_in(mods_dir, names) {
+            for name in metadata.requires().into_iter().filter_map(|r| ModuleName::new(r).ok()) {
+                if !requirements.contains(&name) {
+                    requirements.push(name);
+                }
+            }
+        }
+        requirements
+    }
+    /// Returns `true` if this module is compiled into the server, i.e. its name is registered in
+    /// the global `StaticModuleRegistry`.
+    pub fn is_static(&self) -> bool {
+        StaticModuleRegistry::global().read().unwrap().contains(self.name())
+    }
+    /// Returns `true` if the location of this module points to a WebAssembly module, i.e. a file
+    /// with the `.wasm` extension, which requires the `wasm` feature (see `wasm`).
+    pub fn is_wasm(&self) -> bool {
+        self.location().is_some_and(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+    }
+    /// Obtains the path of the library containing this module, using `mods_dir` as the default
+    /// directory if no location is given.
+    pub fn library_path<P>(&self, mods_dir: P) -> PathBuf
+        where
+            P: AsRef<Path>
+    {
+        self.library(mods_dir).path().to_path_buf()
+    }
+    /// Obtains the path of the library containing this module along with its origin, i.e.
+    /// explicit if a location is given and defaulted into `mods_dir` otherwise.
+    ///
+    /// A defaulted path is the first existing candidate (see `library_candidates`), or the first
+    /// candidate if none exists.
+    pub fn library<P>(&self, mods_dir: P) -> ConfigPath
+        where
+            P: AsRef<Path>
+    {
+        self.library_in(mods_dir, &LibraryNames::default())
+    }
+    /// Obtains the path of the library containing this module along with its origin as `library`
+    /// does, using the file names given by `names`.
+    pub fn library_in<P>(&self, mods_dir: P, names: &LibraryNames) -> ConfigPath
+        where
+            P: AsRef<Path>
+    {
+        if let Some(ref path) = self.location {
+            path.clone()
+        } else {
+            ConfigPath::defaulted(names.resolve(mods_dir, &self.name))
+        }
+    }
+    /// Obtains the paths in which the library containing this module is searched, in the order in
+    /// which they are tried: the location, if given, or the platform-dependent file names within
+    /// `mods_dir` otherwise.
+    pub fn library_candidates<P>(&self, mods_dir: P) -> Vec<PathBuf>
+        where
+            P: AsRef<Path>
+    {
+        self.library_candidates_in(mods_dir, &LibraryNames::default())
+    }
+    /// Obtains the paths in which the library containing this module is searched as
+    /// `library_candidates` does, using the file names given by `names`.
+    pub fn library_candidates_in<P>(&self, mods_dir: P, names: &LibraryNames) -> Vec<PathBuf>
+        where
+            P: AsRef<Path>
+    {
+        if let Some(ref path) = self.location {
+            vec![path.path().to_path_buf()]
+        } else {
+            names.candidates(mods_dir, &self.name)
+        }
+    }
+    /// Obtains the version of the module by loading its library, using `mods_dir` as the default
+    /// directory if no location is given.
+    ///
+    /// Built-in and WebAssembly modules are not built against `mammoth-setup`: their version is the
+    /// one declared in their metadata (see `inspect`), and obtaining it fails if they declare none.
+    pub fn version<P>(&self, mods_dir: P) -> Result<Version, Error>
+        where
+            P: AsRef<Path>
+    {
+        self.version_in(mods_dir, &LibraryNames::default())
+    }
+    /// Obtains the version of the module as `version` does, searching the library within
+    /// `mods_dir` through `names`.
+    pub fn version_in<P>(&self, mods_dir: P, names: &LibraryNames) -> Result<Version, Error>
+        where
+            P: AsRef<Path>
+    {
+        if self.is_static() || self.is_wasm() {
+            let metadata = self.inspect_in(mods_dir, names)?;
+            let version = metadata.version()
+                .ok_or_else(|| Error::Module(format!("module '{}' does not declare its version", self.name())))?;
+            return Version::parse(version).map_err(|_| Error::InvalidValue("version".to_owned()));
+        }
+        // Not a `LoadedLibrary`: inspecting the library must not run its shutdown hook.
+        let library = Library::new(self.library_in(mods_dir, names).path())?;
+
+        abi::module_version(abi::vtable(&library)?)
+    }
+    /// Obtains the metadata of the module by loading its library, using `mods_dir` as the default
+    /// directory if no location is given.
+    ///
+    /// The module is not constructed, so this can be used to inspect a library before enabling it.
+    /// Built-in modules (see `is_static`) are described by the metadata they are registered with,
+    /// if any.
+    pub fn inspect<P>(&self, mods_dir: P) -> Result<ModuleMetadata, Error>
+        where
+            P: AsRef<Path>
+    {
+        self.inspect_in(mods_dir, &LibraryNames::default())
+    }
+    /// Obtains the metadata of the module as `inspect` does, searching the library within
+    /// `mods_dir` through `names`.
+    pub fn inspect_in<P>(&self, mods_dir: P, names: &LibraryNames) -> Result<ModuleMetadata, Error>
+        where
+            P: AsRef<Path>
+    {
+        if let Some(metadata) = StaticModuleRegistry::global().read().unwrap().metadata(self.name()) {
+            return Ok(metadata.clone());
+        }
+        if self.is_wasm() {
+            return self.wasm_metadata();
+        }
+        let library = Library::new(self.library_in(mods_dir, names).path())?;
+
+        abi::module_metadata(abi::vtable(&library)?)
+    }
+    /// Tries to load the library, checking its name and path against the module policy of `mod_set`
+    /// and its version against the compatibility policy of `mod_set`.
+    ///
+    /// Built-in modules (see `is_static`) are constructed through the global
+    /// `StaticModuleRegistry` instead, without touching the filesystem.
     pub fn load_into(&self, mod_set: &mut LoadedModuleSet) -> Result<(), Error>
     {
+        self.load_into_for(mod_set, None)
+    }
+    /// Tries to load the library as by `load_into`, constructing an instance owned by `host` if
+    /// any (see `Instancing::PerHost`).
+    pub fn load_into_for(&self, mod_set: &mut LoadedModuleSet, host: Option<&HostIdentifier>) -> Result<(), Error>
+    {
+        #[cfg(feature = "chaos")]
+        crate::testing::chaos::check(crate::testing::chaos::FailurePoint::ModuleLoad(self.name.to_string()))?;
+
         let lib_path = if let Some(ref path) = self.location {
-            path.clone()
+            path.path().to_path_buf()
         } else {
-            mod_set.lib_path(self.name())
+            mod_set.lib_path(&self.name)
         };
 
-        let library = &mod_set.load(lib_path)?.library;
+        let owner = host.cloned();
+        if self.is_static() {
+            mod_set.module_policy().check(self.name(), None)?;
+        } else {
+            mod_set.module_policy().check(self.name(), Some(&lib_path))?;
+        }
+        if let Some((interface, metadata)) = self.instantiate_static(host, mod_set) {
+            self.notify_load(&interface, None, mod_set.timeout())?;
+            let kind = self.effective_kind(metadata.as_ref());
+            mod_set.insert_for(&self.name, interface, metadata, None, owner, kind);
+            return Ok(());
+        }
+        self.verify_checksum(&lib_path)?;
+        if self.is_wasm() {
+            let (interface, metadata) = self.instantiate_wasm(host, mod_set)?;
+            self.notify_load(&interface, None, mod_set.timeout())?;
+            let kind = self.effective_kind(metadata.as_ref());
+            mod_set.insert_for(&self.name, interface, metadata, None, owner, kind);
+            return Ok(());
+        }
 
-        let version = unsafe {
-            let controller: Symbol<extern fn() -> Version> = library.get(b"__version")?;
-            controller()
-        };
+        let loaded = mod_set.load(lib_path)?;
+        let (interface, metadata) = self.instantiate(&loaded.library, host, mod_set)?;
+        self.notify_load(&interface, Some(loaded.clone()), mod_set.timeout())?;
+
+        let kind = self.effective_kind(metadata.as_ref());
+        mod_set.insert_for(&self.name, interface, metadata, Some(loaded.clone()), owner, kind);
+
+        Ok(())
+    }
+    /// Constructs the module from `library` for `host`, if any, checking its version against the
+    /// compatibility policy of `mod_set`, and prepares it as by `prepare`; the caller notifies it
+    /// through `on_load` (see `notify_load`).
+    pub(crate) fn instantiate(&self, library: &Library, host: Option<&HostIdentifier>, mod_set: &LoadedModuleSet) -> Result<Instance, Error> {
+        let policy = mod_set.policy();
+        let vtable = abi::vtable(library)?;
+        let version = abi::module_version(vtable)?;
 
-        if !version::compatible(&version) {
-            Err(Error::InvalidModuleVersion(version.clone(), VersionReq::from_str(version::COMPATIBILITY_STRING).unwrap()))?;
+        if !version::check(policy, self.name(), &version).is_compatible() {
+            Err(Error::InvalidModuleVersion(version.clone(), policy.requirement_for(self.name()).clone()))?;
         }
+        abi::check_host_version(vtable)?;
 
-        let configuration = self.config.clone();
+        let metadata = abi::module_metadata(vtable).ok();
 
         let interface = unsafe {
-            let constructor: Symbol<extern fn(Option<Value>) -> *mut MammothInterface> = library.get(b"__construct")?;
-            Arc::new(Box::from_raw(constructor(configuration)))
+            ForeignModule::with_context(vtable, self.effective_config().as_ref(), host, mod_set.environment())?
+        };
+
+        Ok((self.prepare(Box::new(interface), host, mod_set), metadata))
+    }
+    /// Constructs the module for `host`, if any, through the global `StaticModuleRegistry` and
+    /// prepares it as by `prepare`; returns `None` if the module is not registered.
+    pub(crate) fn instantiate_static(&self, host: Option<&HostIdentifier>, mod_set: &LoadedModuleSet) -> Option<Instance> {
+        let (factory, metadata) = {
+            let registry = StaticModuleRegistry::global().read().unwrap();
+            (registry.factory(self.name())?, registry.metadata(self.name()).cloned())
         };
+        let config = self.effective_config();
+        let interface = abi::with_host(host.cloned(), || {
+            abi::with_environment(mod_set.environment().cloned(), || factory(config.as_ref()))
+        });
+
+        Some((self.prepare(interface, host, mod_set), metadata))
+    }
+    /// Constructs the WebAssembly module at the location of this module for `host`, if any, and
+    /// prepares it as by `prepare`.
+    #[cfg_attr(not(feature = "wasm"), allow(unused_variables))]
+    pub(crate) fn instantiate_wasm(&self, host: Option<&HostIdentifier>, mod_set: &LoadedModuleSet) -> Result<Instance, Error> {
+        let path = self.location().map(Path::to_path_buf).unwrap_or_default();
+
+        #[cfg(feature = "wasm")]
+        {
+            let metadata = WasmModule::metadata(&path).ok();
+            let interface = WasmModule::with_context(&path, self.effective_config().as_ref(), host, mod_set.environment())?;
+
+            Ok((self.prepare(Box::new(interface), host, mod_set), metadata))
+        }
+        #[cfg(not(feature = "wasm"))]
+        { Err(Error::WasmUnsupported(path)) }
+    }
+    /// Registers the logger of `mod_set`, if any, on the constructed module, tagging its events
+    /// with their origin (see `origin`), then notifies it through `on_bus` (given a handle to the
+    /// bus of `mod_set`) and `on_sandbox`.
+    fn prepare(&self, mut interface: Box<dyn MammothInterface>, host: Option<&HostIdentifier>, mod_set: &LoadedModuleSet) -> Arc<Box<dyn MammothInterface>> {
+        if let Some(logger) = mod_set.logger() {
+            interface.register_logger(Arc::new(RwLock::new(OriginLogger::new(self.origin(host), logger.clone()))));
+        }
+        let interface = Arc::new(interface);
+
+        interface.on_bus(mod_set.bus().handle());
+        interface.on_sandbox(self.sandbox());
+
+        interface
+    }
+    /// Obtains the origin of the events of the module constructed for `host`, if any, i.e. its
+    /// name, the host and the section declaring it (`host.mod` for the modules owned by a host and
+    /// `mod` otherwise).
+    pub fn origin(&self, host: Option<&HostIdentifier>) -> Origin {
+        let origin = Origin::new().with_module(self.name());
+        match host {
+            Some(host) => origin.with_host(&host.to_string()).with_section("host.mod"),
+            None => origin.with_section("mod")
+        }
+    }
+    /// Notifies the constructed module through `on_load`, waiting for at most `timeout` (see
+    /// `loaded::watchdog`) and keeping `source` loaded until the call returns.
+    pub(crate) fn notify_load(&self, interface: &Arc<Box<dyn MammothInterface>>, source: Option<Arc<LoadedLibrary>>, timeout: Option<Duration>) -> Result<(), Error> {
+        let interface = interface.clone();
+        watchdog::with_deadline(self.name(), "on_load", timeout, move || {
+            interface.on_load();
+            drop(interface);
+            drop(source);
+        })
+    }
+    fn wasm_metadata(&self) -> Result<ModuleMetadata, Error> {
+        let path = self.location().map(Path::to_path_buf).unwrap_or_default();
+
+        #[cfg(feature = "wasm")]
+        { WasmModule::metadata(&path) }
+        #[cfg(not(feature = "wasm"))]
+        { Err(Error::WasmUnsupported(path)) }
+    }
+}
 
-        interface.on_load();
+/// Orders `modules` so that every module comes after the modules it requires, as obtained by
+/// `requirements`; modules that do not depend on each other keep their relative order (e.g. the
+/// order given by their priorities, see `Module::priority`).
+///
+/// Fails if a required module is not in `modules` or if the requirements form a cycle.
+pub fn load_order<'a, F>(modules: &[&'a Module], requirements: F) -> Result<Vec<&'a Module>, Error>
+    where
+        F: Fn(&Module) -> Vec<ModuleName>
+{
+    let requirements: Vec<Vec<ModuleName>> = modules.iter().map(|&m| requirements(m)).collect();
+    let mut ordered: Vec<usize> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
 
-        mod_set.insert(self.name(), interface);
+    fn visit(index: usize, modules: &[&Module], requirements: &[Vec<ModuleName>], ordered: &mut Vec<usize>, stack: &mut Vec<usize>) -> Result<(), Error> {
+        if ordered.contains(&index) {
+            return Ok(());
+        }
+        if let Some(start) = stack.iter().position(|&i| i == index) {
+            let mut cycle: Vec<ModuleName> = stack[start..].iter().map(|&i| modules[i].name.clone()).collect();
+            cycle.push(modules[index].name.clone());
+            return Err(Error::DependencyCycle(cycle));
+        }
+
+        stack.push(index);
+        for name in requirements[index].iter() {
+            match modules.iter().position(|m| &m.name == name) {
+                Some(dependency) => visit(dependency, modules, requirements, ordered, stack)?,
+                None => return Err(Error::MissingDependency(modules[index].name.clone(), name.clone()))
+            }
+        }
+        stack.pop();
+        ordered.push(index);
 
         Ok(())
     }
+
+    for index in 0..modules.len() {
+        visit(index, modules, &requirements, &mut ordered, &mut stack)?;
+    }
+
+    Ok(ordered.into_iter().map(|i| modules[i]).collect())
+}
+
+/// Orders the middleware `modules` of a host into the chain wrapping its requests, so that every
+/// module comes after the modules listed in its `after` constraints and before the modules listed
+/// in its `before` constraints; modules that are not constrained keep their relative order (e.g.
+/// the order given by their priorities, see `Module::priority`), so that the order is
+/// deterministic.
+///
+/// Constraints naming modules that are not in `modules` are ignored. Fails with
+/// `Error::MiddlewareCycle` if the constraints form a cycle.
+pub fn chain_order<'a>(modules: &[&'a Module]) -> Result<Vec<&'a Module>, Error> {
+    let predecessors = |module: &Module| -> Vec<ModuleName> {
+        modules.iter()
+            .filter(|m| module.after().contains(&m.name) || m.before().contains(&module.name))
+            .map(|m| m.name.clone())
+            .collect()
+    };
+
+    load_order(modules, predecessors).map_err(|err| match err {
+        Error::DependencyCycle(mut cycle) => {
+            // Cycles are found following the predecessors, i.e. against the order of the chain.
+            cycle.reverse();
+            Error::MiddlewareCycle(cycle)
+        },
+        err => err
+    })
+}
+
+/// Interface of a constructed module, along with the metadata exported by its library, if any.
+pub(crate) type Instance = (Arc<Box<dyn MammothInterface>>, Option<ModuleMetadata>);
+
+/// Structure that defines a named group of modules, enabled together on the hosts referencing it
+/// through `groups = ["<name>"]`, e.g.
+/// ```toml
+/// [module_group.observability]
+/// modules = ["mod_metrics", "mod_tracing", "mod_access_log"]
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ModuleGroup {
+    #[serde(default)]
+    modules: Vec<ModuleName>
+}
+
+impl ModuleGroup {
+    /// Creates a new, empty `ModuleGroup` structure.
+    pub fn new() -> ModuleGroup {
+        ModuleGroup::default()
+    }
+
+    /// Obtains the names of the modules of the group.
+    pub fn modules(&self) -> &[ModuleName] {
+        &self.modules
+    }
+    /// Adds the module `name` to the group.
+    pub fn add_module(&mut self, name: ModuleName) {
+        if !self.modules.contains(&name) {
+            self.modules.push(name);
+        }
+    }
+}
+
+/// Describes where the configuration of an `EffectiveModule` comes from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ModuleSource {
+    /// The module is defined only in the global `[[mod]]` section.
+    Global,
+    /// The module is defined only in the `[[host.mod]]` section of the host.
+    Host,
+    /// The module is defined in the global `[[mod]]` section and overridden in the `[[host.mod]]`
+    /// section of the host.
+    Overridden
+}
+
+/// Describes how a host-level module entry is combined with the global entry with the same name.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleConflict {
+    /// Fields that are not specified in the host-level entry are inherited from the global one.
+    #[default]
+    Merge,
+    /// The host-level entry replaces the global one as a whole.
+    Override,
+    /// Defining the same module both globally and in a host is an error.
+    Error
+}
+
+impl Display for ModuleConflict {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ModuleConflict::Merge => write!(f, "merge"),
+            ModuleConflict::Override => write!(f, "override"),
+            ModuleConflict::Error => write!(f, "error")
+        }
+    }
+}
+
+/// Structure that defines a module as it is effectively used by a specific host, i.e. after
+/// combining the global and the host-level module configurations.
+#[derive(Clone, Debug)]
+pub struct EffectiveModule {
+    module: Module,
+    source: ModuleSource
+}
+
+impl EffectiveModule {
+    /// Combines the `global` and the `host` module lists into the list of the enabled modules of
+    /// the host, sorted by priority (see `Module::priority`).
+    ///
+    /// Host-level entries override the global entries with the same name: fields that are not
+    /// specified in the host-level entry are inherited from the global one, the configurations are
+    /// deep-merged (see `Module::effective_config`), and a disabled host-level entry removes the
+    /// global module from the host.
+    pub fn combine(global: &[&Module], host: &[&Module]) -> Vec<EffectiveModule> {
+        EffectiveModule::combine_with(global, host, ModuleConflict::Merge)
+    }
+    /// Combines the `global` and the `host` module lists as by `combine`, resolving the host-level
+    /// entries that share their name with a global entry as specified by `conflict`.
+    ///
+    /// With `ModuleConflict::Error`, such host-level entries are ignored; use `resolve` to detect
+    /// them.
+    pub fn combine_with(global: &[&Module], host: &[&Module], conflict: ModuleConflict) -> Vec<EffectiveModule> {
+        let mut modules: Vec<EffectiveModule> = global.iter()
+            .map(|&m| EffectiveModule { module: m.clone(), source: ModuleSource::Global })
+            .collect();
+
+        for &over in host {
+            match (modules.iter_mut().find(|e| e.module.name() == over.name()), conflict) {
+                (Some(_), ModuleConflict::Error) => {},
+                (Some(effective), ModuleConflict::Override) => {
+                    effective.module = over.clone();
+                    effective.source = ModuleSource::Overridden;
+                },
+                (Some(effective), ModuleConflict::Merge) => {
+                    effective.module = Module {
+                        name: over.name.clone(),
+                        location: over.location.clone().or_else(|| effective.module.location.take()),
+                        enabled: over.enabled,
+                        config: over.config.clone(),
+                        debug_symbols: over.debug_symbols.clone().or_else(|| effective.module.debug_symbols.take()),
+                        requires: over.requires.clone().or_else(|| effective.module.requires.take()),
+                        priority: over.priority.or(effective.module.priority),
+                        sha256: over.sha256.clone().or_else(|| effective.module.sha256.take()),
+                        instancing: over.instancing.or(effective.module.instancing),
+                        kind: over.kind.or(effective.module.kind),
+                        before: over.before.clone().or_else(|| effective.module.before.take()),
+                        after: over.after.clone().or_else(|| effective.module.after.take()),
+                        allowed_paths: over.allowed_paths.clone().or_else(|| effective.module.allowed_paths.take()),
+                        required: over.required.or(effective.module.required),
+                        inherited_config: effective.module.effective_config()
+                    };
+                    effective.source = ModuleSource::Overridden;
+                },
+                (None, _) => modules.push(EffectiveModule { module: over.clone(), source: ModuleSource::Host })
+            }
+        }
+
+        modules.retain(|e| e.module.enabled());
+        modules.sort_by_key(|e| e.module.priority());
+        modules
+    }
+    /// Combines the `global` and the `host` module lists as by `combine_with`.
+    ///
+    /// Fails with `Error::ModuleConflict` if `conflict` is `ModuleConflict::Error` and a module is
+    /// defined in both lists, even if the host-level entry only disables it.
+    pub fn resolve(global: &[&Module], host: &[&Module], conflict: ModuleConflict) -> Result<Vec<EffectiveModule>, Error> {
+        if conflict == ModuleConflict::Error {
+            if let Some(&over) = host.iter().find(|&&m| global.iter().any(|g| g.name() == m.name())) {
+                Err(Error::ModuleConflict(over.name.clone()))?;
+            }
+        }
+        Ok(EffectiveModule::combine_with(global, host, conflict))
+    }
+
+    /// Obtains the resulting module configuration.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+    /// Transforms the current structure into the resulting module configuration.
+    pub fn into_module(self) -> Module {
+        self.module
+    }
+    /// Obtains where the module configuration comes from.
+    pub fn source(&self) -> ModuleSource {
+        self.source
+    }
 }
 
 impl Id for Module {
-    type Identifier = String;
+    type Identifier = ModuleName;
 
     fn id(&self) -> Self::Identifier {
-        self.name.to_owned()
+        self.name.clone()
+    }
+}
+
+impl Validator<Module> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &Module) -> Result<(), Error> {
+        // Module names are validated when the module is created.
+        if let Some(checksum) = item.sha256() {
+            if checksum.len() != 64 || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+                let desc = format!("Invalid SHA-256 checksum of module '{}': expected 64 hexadecimal digits.", item.name());
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("[[mod]].sha256".to_owned()))?;
+            }
+        }
+        if let (true, Some(config)) = (item.is_sandboxed(), item.effective_config()) {
+            let violations = item.sandbox().violations(&config);
+            for path in violations.iter() {
+                let desc = format!("Configuration of module '{}' references '{}', outside of its allowed paths.", item.name(), path.display());
+                logger.log(Severity::Error, &desc);
+            }
+            if let Some(path) = violations.into_iter().next() {
+                Err(Error::SandboxViolation(path))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the kind of `item` specified in the configuration, if any, is the one declared in
+/// `metadata`, if any.
+fn validate_kind(logger: &mut dyn Logger, item: &Module, metadata: Option<&ModuleMetadata>) -> Result<(), Error> {
+    if let (Some(configured), Some(declared)) = (item.kind(), metadata.and_then(|m| m.kind())) {
+        if configured != declared {
+            let desc = format!("Module '{}' is configured as a {} module, but declares itself as a {} module.", item.name(), configured, declared);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("[[mod]].kind".to_owned()))?;
+        }
     }
+
+    Ok(())
 }
 
+/// Validator that checks that the library of a module exists in the modules directory (searched
+/// through the given file names), that its version is accepted by the compatibility policy and
+/// that it accepts its configuration, waiting for at most the given timeout for `on_validation`
+/// (see `loaded::watchdog`); the module is constructed with the given environment (see
+/// `abi::current_environment`).
+pub struct ModuleValidator<'a>(pub &'a Path, pub &'a CompatibilityPolicy, pub &'a LibraryNames, pub Option<Duration>, pub Option<&'a Value>);
+
 impl Validator<Module> for PathBuf {
     fn validate(&self, logger: &mut Logger, item: &Module) -> Result<(), Error> {
-        let filename = if let Some(filename) = item.location() {
-            filename.to_path_buf()
-        } else {
-            self.join(item.name().to_owned() + DYLIB_EXT)
-        };
-        let lib = Library::new(&filename)?;
-        let ver: Version = unsafe {
-            let ver_fn: Symbol<extern fn() -> Version> = lib.get(b"__version")?;
-            ver_fn()
-        };
+        ModuleValidator(self, &CompatibilityPolicy::default(), &LibraryNames::default(), Some(watchdog::DEFAULT_TIMEOUT), None).validate(logger, item)
+    }
+}
 
-        if !version::compatible(&ver) {
-            let desc = format!("Incompatible module version for '{}': {}. Must respect requisite {}.", item.name(), &ver, version::COMPATIBILITY_STRING);
+impl<'a> Validator<Module> for ModuleValidator<'a> {
+    fn validate(&self, logger: &mut dyn Logger, item: &Module) -> Result<(), Error> {
+        let ModuleValidator(mods_dir, policy, names, timeout, environment) = *self;
+        let config = item.effective_config();
+        let (factory, metadata) = {
+            let registry = StaticModuleRegistry::global().read().unwrap();
+            (registry.factory(item.name()), registry.metadata(item.name()).cloned())
+        };
+        if let Some(factory) = factory {
+            if item.sha256().is_some() {
+                let desc = format!("Module '{}' is built into the server; ignoring its checksum.", item.name());
+                logger.log(Severity::Warning, &desc);
+            }
+            validate_kind(logger, item, metadata.as_ref())?;
+            let interface = abi::with_environment(environment.cloned(), || factory(config.as_ref()));
+            return watchdog::validate(item.name(), Arc::new(interface), (), timeout, logger);
+        }
+        let filename = item.library_in(mods_dir, names);
+        if filename.is_explicit() {
+            ConfigPathValidator(Severity::Critical, PathValidatorKind::ExistingFile, "[[mod]].location")
+                .validate(logger, &filename)?;
+        } else if !filename.path().is_file() {
+            let candidates = item.library_candidates_in(mods_dir, names);
+            let tried: Vec<String> = candidates.iter().map(|p| format!("'{}'", p.display())).collect();
+            let desc = format!("Library of module '{}' not found in mods_dir; tried: {} (set [[mod]].location to override).", item.name(), tried.join(", "));
+            logger.log(Severity::Critical, &desc);
+            Err(Error::LibraryNotFound(item.name.clone(), candidates))?;
+        }
+        if let Err(err) = item.verify_checksum(filename.path()) {
+            let desc = format!("Library of module '{}' does not match its checksum: {}.", item.name(), err);
             logger.log(Severity::Critical, &desc);
-            Err(Error::InvalidModuleVersion(ver.clone(), VersionReq::from_str(version::COMPATIBILITY_STRING).unwrap()))?;
+            Err(err)?;
         }
+        if item.is_wasm() {
+            #[cfg(feature = "wasm")]
+            {
+                validate_kind(logger, item, WasmModule::metadata(filename.path()).ok().as_ref())?;
+                let interface: Arc<Box<dyn MammothInterface>> = Arc::new(Box::new(WasmModule::with_context(filename.path(), config.as_ref(), None, environment)?));
+                return watchdog::validate(item.name(), interface, (), timeout, logger);
+            }
+            #[cfg(not(feature = "wasm"))]
+            {
+                let desc = format!("Module '{}' is a WebAssembly module, but the server is built without the `wasm` feature.", item.name());
+                logger.log(Severity::Critical, &desc);
+                Err(Error::WasmUnsupported(filename.path().to_path_buf()))?;
+            }
+        }
+        // The library is kept alive by the validated instance; it is not a `LoadedLibrary`, so that
+        // validating a module does not run the shutdown hook of a library that is still in use.
+        let lib = Arc::new(Library::new(filename.path())?);
+        let vtable = abi::vtable(&lib)?;
+        let ver: Version = abi::module_version(vtable)?;
 
-        let configuration = if let Some(config) = item.config() {
-            Some(config.to_owned())
-        } else {
-            None
-        };
+        match version::check(policy, item.name(), &ver) {
+            CompatVerdict::Compatible(_) => {},
+            CompatVerdict::Allowed(reason) => {
+                let desc = format!("Module '{}' loaded by compatibility policy: {}.", item.name(), reason);
+                logger.log(Severity::Warning, &desc);
+            },
+            CompatVerdict::Denied(reason) | CompatVerdict::Incompatible(reason) => {
+                let desc = format!("Incompatible module version for '{}': {}.", item.name(), reason);
+                logger.log(Severity::Critical, &desc);
+                Err(Error::InvalidModuleVersion(ver.clone(), policy.requirement_for(item.name()).clone()))?;
+            }
+        }
+        match abi::check_host_version(vtable) {
+            Err(Error::InvalidModuleVersion(version, requirement)) => {
+                let desc = format!("Module '{}' requires a server version respecting {}, found: {}.", item.name(), requirement, version);
+                logger.log(Severity::Critical, &desc);
+                Err(Error::InvalidModuleVersion(version, requirement))?;
+            },
+            other => other?
+        }
+        validate_kind(logger, item, abi::module_metadata(vtable).ok().as_ref())?;
+        #[cfg(feature = "actix")]
+        {
+            let fingerprint = abi::module_factory_fingerprint(vtable);
+            if !fingerprint.is_empty() && fingerprint != abi::FACTORY_FINGERPRINT {
+                let desc = format!("Module '{}' is built with '{}' instead of '{}': its routes and services are not registered.", item.name(), fingerprint, abi::FACTORY_FINGERPRINT);
+                logger.log(Severity::Warning, &desc);
+            }
+        }
+        if let Some(schema) = abi::module_config_schema(vtable)? {
+            SchemaValidator(item.name(), &schema).validate(logger, &config.as_ref())?;
+        }
 
-        let interface: Box<MammothInterface> = unsafe {
-            let constructor: Symbol<extern fn(Option<Value>) -> *mut MammothInterface> = lib.get(b"__construct")?;
-            Box::from_raw(constructor(configuration))
+        let interface: Arc<Box<dyn MammothInterface>> = match unsafe { ForeignModule::with_context(vtable, config.as_ref(), None, environment) } {
+            Ok(interface) => Arc::new(Box::new(interface)),
+            Err(err) => {
+                let desc = format!("Module '{}' cannot be constructed from its configuration: {}.", item.name(), err);
+                logger.log(Severity::Critical, &desc);
+                Err(err)?
+            }
         };
 
-        interface.on_validation(logger)?;
+        watchdog::validate(item.name(), interface, lib.clone(), timeout, logger)
+    }
+}
 
-        Ok(())
+/// Deep-merges `over` into `base`: tables are merged key by key, any other value of `over`
+/// replaces the corresponding value of `base`.
+pub(crate) fn merge_config(base: &mut Value, over: &Value) {
+    match (base, over) {
+        (Value::Table(base), Value::Table(over)) => for (key, value) in over.iter() {
+            match base.get_mut(key) {
+                Some(existing) => merge_config(existing, value),
+                None => { base.insert(key.clone(), value.clone()); }
+            }
+        },
+        (base, over) => *base = over.clone()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::path::PathBuf;
+    use std::fs;
+    use std::path::{Path, PathBuf};
     use std::str::FromStr;
+    use std::sync::{Arc, Mutex, RwLock};
 
+    use libloading::Library;
     use toml::Value;
 
+    use crate::MammothInterface;
     use crate::config::Module;
+    use crate::config::glob::Glob;
+    use crate::config::module::{chain_order, library_file_names, load_order, resolve_library, sha256_file, EffectiveModule, LibraryNames, LoadPolicy, ModuleConflict, ModuleName, ModuleSource};
+    use crate::error::Error;
     use crate::error::event::Event;
+    use crate::error::severity::Severity;
     use crate::loaded::library::LoadedModuleSet;
-    use crate::diagnostics::Validator;
+    use crate::loaded::registry::StaticModuleRegistry;
+    use crate::diagnostics::{AsyncLoggerReference, Log, Logger, Validator};
+    use crate::diagnostics::origin::Origin;
+    use crate::metadata::Capability;
+    use crate::sandbox::SandboxedFs;
+
+    static SANDBOX: Mutex<Option<SandboxedFs>> = Mutex::new(None);
+
+    struct SandboxedModule;
+
+    impl Log for SandboxedModule {
+        fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            None
+        }
+    }
+
+    impl MammothInterface for SandboxedModule {
+        fn on_sandbox(&self, sandbox: SandboxedFs) {
+            *SANDBOX.lock().unwrap() = Some(sandbox);
+        }
+
+        fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct LoggingModule {
+        logger: Option<AsyncLoggerReference>
+    }
+
+    impl Log for LoggingModule {
+        fn register_logger(&mut self, logger: AsyncLoggerReference) {
+            self.logger = Some(logger);
+        }
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            self.logger.clone()
+        }
+    }
+
+    impl MammothInterface for LoggingModule {
+        fn on_load(&self) {
+            self.log(Severity::Information, "Module loaded.");
+        }
+
+        fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+            Ok(())
+        }
+    }
 
     #[test]
     /// Tests `Module` properties.
     fn test_generic_properties() {
-        let mut module = Module::new("mod_test");
-        let module_disabled = Module::new_disabled("mod_disabled");
-        let module_with_config = Module::with_config("mod_configured", true, Value::from(42));
+        let mut module = Module::new("mod_test").unwrap();
+        let module_disabled = Module::new_disabled("mod_disabled").unwrap();
+        let module_with_config = Module::with_config("mod_configured", true, Value::from(42)).unwrap();
 
         assert_eq!(module.name(), "mod_test");
         assert_eq!(module.location(), None);
@@ -277,33 +1449,519 @@ mod test {
         assert_eq!(module.enabled(), false);
         module.enable();
         assert_eq!(module.enabled(), true);
+
+        assert_eq!(module.debug_symbols(), None);
+        module.set_debug_symbols("./symbols/mod_test.debug");
+        assert_eq!(module.debug_symbols().unwrap().to_str().unwrap(), "./symbols/mod_test.debug");
+        module.clear_debug_symbols();
+        assert_eq!(module.debug_symbols(), None);
+    }
+
+    #[test]
+    /// Tests the platform-dependent file names of the libraries.
+    fn test_library_file_names() {
+        let name = ModuleName::new("mod_test").unwrap();
+        let lms = LoadedModuleSet::new("./target/debug");
+
+        #[cfg(target_os = "windows")]
+        assert_eq!(library_file_names(&name), vec!["mod_test.dll", "libmod_test.dll"]);
+        #[cfg(target_os = "macos")]
+        assert_eq!(library_file_names(&name), vec!["libmod_test.dylib", "mod_test.dylib"]);
+        #[cfg(all(unix, not(target_os = "macos")))]
+        assert_eq!(library_file_names(&name), vec!["libmod_test.so", "mod_test.so"]);
+
+        assert_eq!(lms.lib_path(&name), resolve_library("./target/debug", &name));
+        assert_eq!(lms.lib_path(&name), Module::new("mod_test").unwrap().library_path("./target/debug"));
+        assert!(lms.lib_path(&name).is_file());
+    }
+
+    #[test]
+    /// Tests the configurable file names of the libraries.
+    fn test_library_names() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("mod_custom.plugin"), b"").unwrap();
+        let name = ModuleName::new("mod_custom").unwrap();
+        let names = LibraryNames::new(vec!["lib{name}.so".to_owned(), "{name}.plugin".to_owned()]);
+
+        assert_eq!(names.file_names(&name), vec!["libmod_custom.so", "mod_custom.plugin"]);
+        assert_eq!(names.resolve(dir.path(), &name), dir.path().join("mod_custom.plugin"));
+
+        let mut lms = LoadedModuleSet::new(dir.path());
+        assert_eq!(lms.lib_path(&name), dir.path().join(library_file_names(&name).remove(0)));
+        lms.set_library_names(names.clone());
+        assert_eq!(lms.lib_path(&name), dir.path().join("mod_custom.plugin"));
+        assert_eq!(Module::new("mod_custom").unwrap().library_in(dir.path(), &names).path(), dir.path().join("mod_custom.plugin"));
+
+        let mut events: Vec<Event> = Vec::new();
+        assert!(().validate(&mut events, &names).is_ok());
+        assert!(().validate(&mut events, &LibraryNames::new(vec!["mod.so".to_owned()])).is_err());
+        assert!(().validate(&mut events, &LibraryNames::new(vec!["../{name}.so".to_owned()])).is_err());
+        assert!(().validate(&mut events, &LibraryNames::new(Vec::new())).is_err());
+    }
+
+    #[test]
+    /// Tests the validation of module names.
+    fn test_module_name() {
+        assert!(ModuleName::new("mod_test-2").is_ok());
+        for name in ["", "   ", "../mod_test", "mods/mod_test", "mod.test", "mod test", "mòd"].iter() {
+            match ModuleName::new(name) {
+                Err(Error::InvalidModuleName(_)) => {},
+                _ => panic!("Should be 'InvalidModuleName' error for '{}'.", name)
+            }
+        }
+
+        let err = toml::from_str::<Module>(r#"name = "../mod_test""#).unwrap_err();
+        assert!(err.to_string().contains("invalid module name '../mod_test': module names cannot contain path separators"));
+        let module = toml::from_str::<Module>(r#"name = "mod_test""#).unwrap();
+        assert_eq!(module.module_name(), &ModuleName::new("mod_test").unwrap());
+    }
+
+    #[test]
+    /// Tests that the constructors of `Module` reject invalid names.
+    fn test_module_new_invalid() {
+        match Module::new("..") {
+            Err(Error::InvalidModuleName(name)) => assert_eq!(name, ".."),
+            _ => panic!("Should be 'InvalidModuleName' error.")
+        }
+        assert!(Module::new_disabled("mod test").is_err());
+        assert!(Module::with_config("", true, Value::from("config")).is_err());
+    }
+
+    #[test]
+    /// Tests the combination of global and host-level modules.
+    fn test_effective_modules() {
+        let global_test = Module::with_config("mod_test", true, Value::from(42)).unwrap();
+        let global_dummy = Module::new("mod_dummy").unwrap();
+        let global_disabled = Module::new_disabled("mod_disabled").unwrap();
+        let mut host_test = Module::new("mod_test").unwrap();
+        host_test.set_location("./target/debug/mod_test.so");
+        let host_dummy = Module::new_disabled("mod_dummy").unwrap();
+        let host_only = Module::new("mod_host").unwrap();
+
+        let modules = EffectiveModule::combine(
+            &[&global_test, &global_dummy, &global_disabled],
+            &[&host_test, &host_dummy, &host_only]
+        );
+
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[0].module().name(), "mod_test");
+        assert_eq!(modules[0].source(), ModuleSource::Overridden);
+        assert_eq!(modules[0].module().config(), None);
+        assert_eq!(modules[0].module().effective_config(), Some(Value::from(42)));
+        assert!(modules[0].module().location().is_some());
+        assert_eq!(modules[1].module().name(), "mod_host");
+        assert_eq!(modules[1].source(), ModuleSource::Host);
+
+        let modules = EffectiveModule::combine(&[&global_test, &global_dummy], &[]);
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[1].source(), ModuleSource::Global);
+    }
+
+    #[test]
+    /// Tests the deep merge of global and host-level module configurations.
+    fn test_effective_config() {
+        let global: Value = toml::from_str(r#"
+        root = "/var/www"
+        methods = ["GET", "POST"]
+        [cache]
+        enabled = true
+        ttl = 60
+        "#).unwrap();
+        let host: Value = toml::from_str(r#"
+        methods = ["GET"]
+        [cache]
+        ttl = 5
+        "#).unwrap();
+        let global_test = Module::with_config("mod_test", true, global).unwrap();
+        let host_test = Module::with_config("mod_test", true, host.clone()).unwrap();
+
+        let modules = EffectiveModule::combine(&[&global_test], &[&host_test]);
+        let config = modules[0].module().effective_config().unwrap();
+        assert_eq!(modules[0].module().config(), Some(&host));
+        assert_eq!(config["root"].as_str(), Some("/var/www"));
+        assert_eq!(config["methods"].as_array().unwrap().len(), 1);
+        assert_eq!(config["cache"]["enabled"].as_bool(), Some(true));
+        assert_eq!(config["cache"]["ttl"].as_integer(), Some(5));
+
+        let modules = EffectiveModule::combine_with(&[&global_test], &[&host_test], ModuleConflict::Override);
+        assert_eq!(modules[0].module().effective_config(), Some(host));
+        assert_eq!(global_test.effective_config(), global_test.config().cloned());
+    }
+
+    #[test]
+    /// Tests the conflict policies between global and host-level modules.
+    fn test_module_conflicts() {
+        let global_test = Module::with_config("mod_test", true, Value::from(42)).unwrap();
+        let global_dummy = Module::new("mod_dummy").unwrap();
+        let mut host_test = Module::new("mod_test").unwrap();
+        host_test.set_location("./target/debug/mod_test.so");
+
+        let modules = EffectiveModule::combine_with(&[&global_test, &global_dummy], &[&host_test], ModuleConflict::Override);
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[0].source(), ModuleSource::Overridden);
+        assert_eq!(modules[0].module().config(), None);
+        assert!(modules[0].module().location().is_some());
+
+        let modules = EffectiveModule::combine_with(&[&global_test, &global_dummy], &[&host_test], ModuleConflict::Merge);
+        assert_eq!(modules[0].module().effective_config(), Some(Value::from(42)));
+
+        match EffectiveModule::resolve(&[&global_test, &global_dummy], &[&host_test], ModuleConflict::Error) {
+            Err(Error::ModuleConflict(name)) => assert_eq!(name, "mod_test"),
+            _ => panic!("Should be 'ModuleConflict' error.")
+        }
+        let modules = EffectiveModule::resolve(&[&global_test], &[&global_dummy], ModuleConflict::Error).unwrap();
+        assert_eq!(modules.len(), 2);
     }
 
     #[test]
     /// Tests module loading.
     fn test_module_load_into() {
-        let module = Module::new("mod_test");
+        let module = Module::new("mod_test").unwrap();
+        let mut lms = LoadedModuleSet::new("./target/debug/");
+
+        module.load_into(&mut lms).unwrap();
+    }
+
+    #[test]
+    /// Tests the restriction of the modules that can be loaded.
+    fn test_load_policy() {
+        let mut policy = LoadPolicy::new();
+        assert!(policy.permits("mod_debug", None));
+        policy.allow(Glob::new("mod_*").unwrap());
+        policy.allow(Glob::new("/opt/mammoth/**").unwrap());
+        policy.deny(Glob::new("mod_debug*").unwrap());
+
+        assert!(policy.permits("mod_auth", None));
+        assert!(policy.permits("auth", Some(Path::new("/opt/mammoth/mods/libauth.so"))));
+        assert!(!policy.permits("auth", None));
+        assert!(!policy.permits("mod_debug_tools", Some(Path::new("/opt/mammoth/mods/libmod_debug_tools.so"))));
+        assert!(policy.check("mod_debug", None).is_err());
+
+        let mut lms = LoadedModuleSet::new("./target/debug/");
+        lms.set_module_policy(policy);
+        assert!(Module::new("mod_test").unwrap().load_into(&mut lms).is_ok());
+        let mut policy = LoadPolicy::new();
+        policy.deny(Glob::new("mod_test").unwrap());
+        lms.set_module_policy(policy);
+        match lms.reload("mod_test", &Module::new("mod_test").unwrap()) {
+            Err(Error::ModuleDenied(name)) => assert_eq!(name, "mod_test"),
+            _ => panic!("Should be 'ModuleDenied' error.")
+        }
+    }
+
+    #[test]
+    /// Tests the verification of the checksums of the libraries.
+    fn test_module_checksum() {
+        let mods_dir = PathBuf::from("./target/debug/");
+        let mut module = Module::new("mod_test").unwrap();
+        let checksum = sha256_file(module.library_path(&mods_dir)).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        module.set_sha256(&checksum.to_uppercase());
+        assert!(().validate(&mut events, &module).is_ok());
+        assert!(mods_dir.validate(&mut events, &module).is_ok());
+        module.load_into(&mut LoadedModuleSet::new(&mods_dir)).unwrap();
+
+        module.set_sha256(&"0".repeat(64));
+        match module.load_into(&mut LoadedModuleSet::new(&mods_dir)) {
+            Err(Error::ChecksumMismatch(_, expected, actual)) => {
+                assert_eq!(expected, "0".repeat(64));
+                assert_eq!(actual, checksum);
+            },
+            _ => panic!("Should be 'ChecksumMismatch' error.")
+        }
+        assert!(mods_dir.validate(&mut events, &module).is_err());
+
+        let mut lms = LoadedModuleSet::new(&mods_dir);
+        Module::new("mod_test").unwrap().load_into(&mut lms).unwrap();
+        match lms.reload("mod_test", &module) {
+            Err(Error::ChecksumMismatch(path, _, actual)) => {
+                assert_eq!(path, module.library_path(&mods_dir));
+                assert_eq!(actual, checksum);
+            },
+            _ => panic!("Should be 'ChecksumMismatch' error.")
+        }
+        assert_eq!(lms.module_names(), vec!["mod_test"]);
+
+        module.set_sha256("not a checksum");
+        assert!(().validate(&mut events, &module).is_err());
+    }
+
+    #[test]
+    /// Tests the order given by the priorities of the modules.
+    fn test_priority() {
+        let mut late = Module::new("mod_late").unwrap();
+        late.set_priority(10);
+        let mut early = Module::new("mod_early").unwrap();
+        early.set_priority(-5);
+        let first = Module::new("mod_first").unwrap();
+        let second = Module::new("mod_second").unwrap();
+        let mut host_late = Module::new("mod_first").unwrap();
+        host_late.set_priority(20);
+
+        let modules = EffectiveModule::combine(&[&late, &first, &early, &second], &[]);
+        let names: Vec<&str> = modules.iter().map(|e| e.module().name()).collect();
+        assert_eq!(names, vec!["mod_early", "mod_first", "mod_second", "mod_late"]);
+
+        let modules = EffectiveModule::combine(&[&late, &first, &early, &second], &[&host_late]);
+        let names: Vec<&str> = modules.iter().map(|e| e.module().name()).collect();
+        assert_eq!(names, vec!["mod_early", "mod_second", "mod_late", "mod_first"]);
+
+        let mut second = Module::new("mod_second").unwrap();
+        second.add_requirement(ModuleName::new("mod_late").unwrap());
+        let modules = EffectiveModule::combine(&[&late, &first, &early, &second], &[]);
+        let modules: Vec<&Module> = modules.iter().map(|e| e.module()).collect();
+        let order = load_order(&modules, |m| m.requires().to_vec()).unwrap();
+        let names: Vec<&str> = order.iter().map(|m| m.name()).collect();
+        assert_eq!(names, vec!["mod_early", "mod_first", "mod_late", "mod_second"]);
+    }
+
+    #[test]
+    /// Tests the load order of modules depending on each other.
+    fn test_load_order() {
+        let mut web = Module::new("mod_web").unwrap();
+        web.add_requirement(ModuleName::new("mod_session").unwrap());
+        let mut session = Module::new("mod_session").unwrap();
+        session.add_requirement(ModuleName::new("mod_store").unwrap());
+        let store = Module::new("mod_store").unwrap();
+        let other = Module::new("mod_other").unwrap();
+
+        let order = load_order(&[&web, &other, &session, &store], |m| m.requires().to_vec()).unwrap();
+        let names: Vec<&str> = order.iter().map(|m| m.name()).collect();
+        assert_eq!(names, vec!["mod_store", "mod_session", "mod_web", "mod_other"]);
+
+        match load_order(&[&web, &session], |m| m.requires().to_vec()) {
+            Err(Error::MissingDependency(name, dependency)) => {
+                assert_eq!(name.as_str(), "mod_session");
+                assert_eq!(dependency.as_str(), "mod_store");
+            },
+            _ => panic!("Should be 'MissingDependency' error.")
+        }
+
+        let mut store = Module::new("mod_store").unwrap();
+        store.add_requirement(ModuleName::new("mod_web").unwrap());
+        match load_order(&[&web, &session, &store], |m| m.requires().to_vec()) {
+            Err(err @ Error::DependencyCycle(_)) => {
+                assert_eq!(err.to_string(), "Cyclic module dependency: mod_web -> mod_session -> mod_store -> mod_web");
+            },
+            _ => panic!("Should be 'DependencyCycle' error.")
+        }
+    }
+
+    #[test]
+    /// Tests the order of a middleware chain.
+    fn test_chain_order() {
+        let mut compress = Module::new("mod_compress").unwrap();
+        compress.add_after(ModuleName::new("mod_auth").unwrap());
+        let mut log = Module::new("mod_log").unwrap();
+        log.add_before(ModuleName::new("mod_auth").unwrap());
+        log.add_before(ModuleName::new("mod_missing").unwrap());
+        let auth = Module::new("mod_auth").unwrap();
+        let cors = Module::new("mod_cors").unwrap();
+
+        let order = chain_order(&[&compress, &cors, &auth, &log]).unwrap();
+        let names: Vec<&str> = order.iter().map(|m| m.name()).collect();
+        assert_eq!(names, vec!["mod_log", "mod_auth", "mod_compress", "mod_cors"]);
+        let order = chain_order(&[&cors, &compress, &auth, &log]).unwrap();
+        let names: Vec<&str> = order.iter().map(|m| m.name()).collect();
+        assert_eq!(names, vec!["mod_cors", "mod_log", "mod_auth", "mod_compress"]);
+
+        let mut auth = Module::new("mod_auth").unwrap();
+        auth.add_after(ModuleName::new("mod_compress").unwrap());
+        match chain_order(&[&compress, &auth, &log]) {
+            Err(err @ Error::MiddlewareCycle(_)) => {
+                assert_eq!(err.to_string(), "Cyclic middleware ordering: mod_compress -> mod_auth -> mod_compress");
+            },
+            _ => panic!("Should be 'MiddlewareCycle' error.")
+        }
+
+        let mut host_auth = Module::new("mod_auth").unwrap();
+        host_auth.add_before(ModuleName::new("mod_log").unwrap());
+        let modules = EffectiveModule::combine(&[&auth, &log], &[&host_auth]);
+        let modules: Vec<&Module> = modules.iter().map(|e| e.module()).collect();
+        assert_eq!(modules[0].after(), &[ModuleName::new("mod_compress").unwrap()]);
+        assert!(chain_order(&modules).is_err());
+    }
+
+    #[test]
+    /// Tests the filesystem sandbox of a module.
+    fn test_module_sandbox() {
+        let mut module: Module = toml::from_str(r#"
+        name = "mod_sandboxed"
+        allowed_paths = ["/var/lib/mammoth"]
+        config = { target = "/var/lib/mammoth/uploads", backup = ["/etc/shadow"] }
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(module.is_sandboxed());
+        assert_eq!(module.allowed_paths(), &[PathBuf::from("/var/lib/mammoth")]);
+        match ().validate(&mut events, &module) {
+            Err(Error::SandboxViolation(path)) => assert_eq!(path, PathBuf::from("/etc/shadow")),
+            _ => panic!("Should be 'SandboxViolation' error.")
+        }
+        module.add_allowed_path("/etc");
+        assert!(().validate(&mut events, &module).is_ok());
+        assert!(().validate(&mut events, &Module::with_config("mod_unsandboxed", true, Value::from("/etc/shadow")).unwrap()).is_ok());
+
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_sandboxed", |_| Box::new(SandboxedModule)).unwrap();
+        let mut lms = LoadedModuleSet::new("./nonexistent");
+        module.load_into(&mut lms).unwrap();
+        assert_eq!(SANDBOX.lock().unwrap().as_ref().unwrap().roots(), module.sandbox().roots());
+        assert!(module.sandbox().allows("/etc/hosts"));
+
+        lms.unload("mod_sandboxed").unwrap();
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_sandboxed");
+    }
+
+    #[test]
+    /// Tests the registration of the logger of a `LoadedModuleSet` on the loaded modules.
+    fn test_module_logger() {
+        let events = Arc::new(RwLock::new(Vec::<Event>::new()));
+        let mut lms = LoadedModuleSet::new("./nonexistent");
+
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_logging", |_| Box::new(LoggingModule::default())).unwrap();
+        Module::new("mod_logging").unwrap().load_into(&mut lms).unwrap();
+        assert!(events.read().unwrap().is_empty());
+        lms.unload("mod_logging").unwrap();
+
+        lms.set_logger(events.clone());
+        Module::new("mod_logging").unwrap().load_into(&mut lms).unwrap();
+        assert_eq!(events.read().unwrap().len(), 1);
+        assert_eq!(events.read().unwrap()[0].severity(), Severity::Information);
+        assert_eq!(events.read().unwrap()[0].description(), "Module loaded.");
+        assert_eq!(events.read().unwrap()[0].origin(), Origin::new().with_module("mod_logging").with_section("mod"));
+
+        lms.unload("mod_logging").unwrap();
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_logging");
+    }
+
+    #[test]
+    /// Tests the metadata exported by the library of a module.
+    fn test_module_inspect() {
+        let module = Module::new("mod_test").unwrap();
+        let metadata = module.inspect("./target/debug/").unwrap();
+        let mut lms = LoadedModuleSet::new("./target/debug/");
+
+        assert_eq!(metadata.name(), "mod_test");
+        assert!(metadata.has_capability(Capability::Validation));
+        module.load_into(&mut lms).unwrap();
+        assert_eq!(lms.metadata("mod_test"), Some(&metadata));
+        assert!(lms.metadata("mod_missing").is_none());
+        assert!(Module::new("mod_missing").unwrap().inspect("./target/debug/").is_err());
+    }
+
+    #[test]
+    /// Tests that inspecting or validating a library does not call its shutdown hook, unlike
+    /// dropping the set owning its modules.
+    fn test_module_library_shutdown() {
+        // The library is copied, so that its state is not shared with the other tests.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("libmod_test.so");
+        fs::copy("./target/debug/libmod_test.so", &path).unwrap();
+        let module = Module::new("mod_test").unwrap();
+        let mut lms = LoadedModuleSet::new(dir.path());
+        let mut events: Vec<Event> = Vec::new();
+
+        module.load_into(&mut lms).unwrap();
+        let library = Library::new(&path).unwrap();
+        let shutdowns = unsafe { library.get::<extern "C" fn() -> usize>(b"__mod_test_shutdowns").unwrap() };
+
+        module.inspect(dir.path()).unwrap();
+        module.version(dir.path()).unwrap();
+        dir.path().to_path_buf().validate(&mut events, &module).unwrap();
+        assert_eq!(shutdowns(), 0);
+        drop(lms);
+        assert_eq!(shutdowns(), 1);
+    }
+
+    #[test]
+    /// Tests module unloading.
+    fn test_module_unload() {
+        let module = Module::new("mod_test").unwrap();
+        let mut lms = LoadedModuleSet::new("./target/debug/");
+
+        module.load_into(&mut lms).unwrap();
+        module.load_into(&mut lms).unwrap();
+        assert_eq!(lms.module_names(), vec!["mod_test", "mod_test"]);
+        assert_eq!(lms.library_paths().len(), 1);
+
+        lms.unload("mod_test").unwrap();
+        assert!(lms.module_names().is_empty());
+        assert!(lms.library_paths().is_empty());
+        match lms.unload("mod_test") {
+            Err(Error::ModuleNotLoaded(name)) => assert_eq!(name, "mod_test"),
+            _ => panic!("Should be 'ModuleNotLoaded' error.")
+        }
+    }
+
+    #[test]
+    /// Tests module hot-reloading.
+    fn test_module_reload() {
+        let module = Module::new("mod_test").unwrap();
         let mut lms = LoadedModuleSet::new("./target/debug/");
 
         module.load_into(&mut lms).unwrap();
+        let original = lms.library_paths()[0].to_path_buf();
+
+        lms.reload("mod_test", &Module::with_config("mod_test", true, Value::from("reloaded")).unwrap()).unwrap();
+        assert_eq!(lms.module_names(), vec!["mod_test"]);
+        assert_eq!(lms.library_paths().len(), 1);
+        assert_eq!(lms.library_paths()[0], original.as_path());
+        assert!(lms.metadata("mod_test").is_some());
+
+        let failing = Module::with_config("mod_test", true, Value::from("test_error")).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+        assert!(lms.reload_with("mod_test", &failing, &mut events).is_err());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].description(), "Error tested successfully!");
+        assert_eq!(lms.module_names(), vec!["mod_test"]);
+        assert_eq!(lms.library_paths().len(), 1);
+        match lms.reload("mod_missing", &module) {
+            Err(Error::ModuleNotLoaded(_)) => {},
+            _ => panic!("Should be 'ModuleNotLoaded' error.")
+        }
     }
 
     #[test]
     /// Tests module validation.
     fn test_module_validation() {
         let validator = PathBuf::from_str("./target/debug/").unwrap();
-        let module = Module::new("mod_test");
+        let module = Module::new("mod_test").unwrap();
         let mut events: Vec<Event> = Vec::new();
 
         validator.validate(&mut events, &module).unwrap();
     }
 
+    #[test]
+    /// Tests that a missing library is reported along with the origin of its path.
+    fn test_missing_library_validation() {
+        let validator = PathBuf::from_str("./i_do_not_exist/").unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        match validator.validate(&mut events, &Module::new("mod_missing").unwrap()) {
+            Err(Error::LibraryNotFound(name, paths)) => {
+                assert_eq!(name.as_str(), "mod_missing");
+                assert_eq!(paths.len(), 2);
+            },
+            _ => panic!("Should be 'LibraryNotFound' error.")
+        }
+        assert!(events[0].to_string().contains("tried: './i_do_not_exist/"));
+        assert!(events[0].to_string().contains("(set [[mod]].location to override)"));
+
+        let mut module = Module::new("mod_missing").unwrap();
+        module.set_location("./i_do_not_exist/mod_missing.so");
+        assert!(validator.validate(&mut events, &module).is_err());
+        assert!(!events[1].to_string().contains("override"));
+    }
+
     #[test]
     /// Tests module validation resulting in error.
     fn test_err_module_validation() {
         let validator = PathBuf::from_str("./target/debug/").unwrap();
         let configuration = Value::from("test_error");
-        let module = Module::with_config("mod_test", true, configuration);
+        let module = Module::with_config("mod_test", true, configuration).unwrap();
         let mut events: Vec<Event> = Vec::new();
 
         assert!(validator.validate(&mut events, &module).is_err());
@@ -0,0 +1,127 @@
+//! The `Observability` structure contains the request tracing settings of a host.
+use crate::diagnostics::{Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Structure that defines how the requests of a host are identified and traced.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Observability {
+    #[serde(default = "default_request_id_header")]
+    request_id_header: String,
+    #[serde(default = "default_generate_request_id")]
+    generate_request_id: bool,
+    #[serde(default = "default_trace_sampling")]
+    trace_sampling: f64
+}
+
+#[doc(hidden)]
+fn default_request_id_header() -> String { "X-Request-Id".to_owned() }
+#[doc(hidden)]
+fn default_generate_request_id() -> bool { true }
+#[doc(hidden)]
+fn default_trace_sampling() -> f64 { 0.0 }
+
+impl Observability {
+    /// Creates a new `Observability` structure with the default settings, i.e. request
+    /// identifiers generated into the `X-Request-Id` header and no tracing.
+    pub fn new() -> Observability {
+        Observability {
+            request_id_header: default_request_id_header(),
+            generate_request_id: default_generate_request_id(),
+            trace_sampling: default_trace_sampling()
+        }
+    }
+
+    /// Obtains the name of the header containing the request identifier.
+    pub fn request_id_header(&self) -> &str {
+        &self.request_id_header
+    }
+    /// Sets the name of the header containing the request identifier.
+    pub fn set_request_id_header(&mut self, header: &str) {
+        self.request_id_header = header.to_owned();
+    }
+    /// Returns `true` if a request identifier should be generated for the requests without one and
+    /// `false` otherwise.
+    pub fn generate_request_id(&self) -> bool {
+        self.generate_request_id
+    }
+    /// Sets whether a request identifier should be generated for the requests without one.
+    pub fn set_generate_request_id(&mut self, generate: bool) {
+        self.generate_request_id = generate;
+    }
+    /// Obtains the ratio of the requests to be traced, between `0.0` (none) and `1.0` (all).
+    pub fn trace_sampling(&self) -> f64 {
+        self.trace_sampling
+    }
+    /// Sets the ratio of the requests to be traced, between `0.0` (none) and `1.0` (all).
+    pub fn set_trace_sampling(&mut self, ratio: f64) {
+        self.trace_sampling = ratio;
+    }
+}
+
+impl Default for Observability {
+    fn default() -> Self {
+        Observability::new()
+    }
+}
+
+/// Returns `true` if `name` is a valid HTTP header name and `false` otherwise.
+pub(crate) fn is_valid_header(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+impl Validator<Observability> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &Observability) -> Result<(), Error> {
+        if !is_valid_header(item.request_id_header()) {
+            let desc = format!("Invalid request identifier header: '{}'.", item.request_id_header());
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("observability.request_id_header".to_owned()))?;
+        }
+        if !(0.0..=1.0).contains(&item.trace_sampling()) {
+            let desc = format!("Trace sampling ratio must be between 0.0 and 1.0, found {}.", item.trace_sampling());
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("observability.trace_sampling".to_owned()))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::observability::Observability;
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests deserialization and defaults.
+    fn test_deserialize() {
+        let observability: Observability = toml::from_str(r#"
+        request_id_header = "X-Correlation-Id"
+        trace_sampling = 0.25
+        "#).unwrap();
+
+        assert_eq!(observability.request_id_header(), "X-Correlation-Id");
+        assert!(observability.generate_request_id());
+        assert_eq!(observability.trace_sampling(), 0.25);
+
+        let observability: Observability = toml::from_str("").unwrap();
+        assert_eq!(observability, Observability::new());
+    }
+
+    #[test]
+    /// Tests validation.
+    fn test_validate() {
+        let mut observability = Observability::new();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(().validate(&mut events, &observability).is_ok());
+
+        observability.set_trace_sampling(1.5);
+        assert!(().validate(&mut events, &observability).is_err());
+
+        observability.set_trace_sampling(1.0);
+        observability.set_request_id_header("Request Id");
+        assert!(().validate(&mut events, &observability).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! The `ConfigPath` structure contains a path of the configuration along with its origin.
+//!
+//! Paths can be written explicitly in the configuration file, derived from a default (e.g. the
+//! library of a module without `location` is searched in `mods_dir`), or resolved against a base
+//! directory (e.g. the directory of the configuration file); remembering the origin allows
+//! validation messages to explain where a path comes from and how to override it.
+use std::env;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::diagnostics::{is_writable_dir, Logger, PathValidatorKind, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Describes where a `ConfigPath` comes from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PathOrigin {
+    /// The path is written in the configuration.
+    Explicit,
+    /// The path is derived from a default value.
+    Defaulted,
+    /// The path is written in the configuration as a relative path and resolved against a base
+    /// directory.
+    Resolved
+}
+
+/// Structure that defines a path of the configuration along with its origin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigPath {
+    path: PathBuf,
+    origin: PathOrigin,
+    original: Option<PathBuf>
+}
+
+impl ConfigPath {
+    /// Creates a new `ConfigPath` structure for a path written in the configuration.
+    pub fn explicit<P>(path: P) -> ConfigPath
+        where
+            P: AsRef<Path>
+    {
+        ConfigPath {
+            path: path.as_ref().to_path_buf(),
+            origin: PathOrigin::Explicit,
+            original: None
+        }
+    }
+    /// Creates a new `ConfigPath` structure for a path derived from a default value.
+    pub fn defaulted<P>(path: P) -> ConfigPath
+        where
+            P: AsRef<Path>
+    {
+        ConfigPath {
+            path: path.as_ref().to_path_buf(),
+            origin: PathOrigin::Defaulted,
+            original: None
+        }
+    }
+    /// Resolves the current path against the `base` directory, if relative.
+    ///
+    /// Absolute paths are left untouched.
+    pub fn resolve<P>(&mut self, base: P)
+        where
+            P: AsRef<Path>
+    {
+        if self.path.is_relative() {
+            let resolved = base.as_ref().join(&self.path);
+            self.original = Some(std::mem::replace(&mut self.path, resolved));
+            self.origin = PathOrigin::Resolved;
+        }
+    }
+
+    /// Obtains the path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Obtains the origin of the path.
+    pub fn origin(&self) -> PathOrigin {
+        self.origin
+    }
+    /// Returns `true` if the path is written in the configuration and `false` otherwise.
+    pub fn is_explicit(&self) -> bool {
+        self.origin != PathOrigin::Defaulted
+    }
+    /// Obtains the path as written in the configuration, before being resolved.
+    pub fn original(&self) -> &Path {
+        self.original.as_deref().unwrap_or(&self.path)
+    }
+    /// Obtains the absolute form of the path, using the current directory as the base directory
+    /// of relative paths.
+    pub fn absolute(&self) -> PathBuf {
+        if self.path.is_absolute() {
+            self.path.clone()
+        } else {
+            env::current_dir()
+                .map(|dir| dir.join(&self.path))
+                .unwrap_or_else(|_| self.path.clone())
+        }
+    }
+    /// Describes the path for the messages of the configuration `key` (e.g. `[mammoth].mods_dir`).
+    pub fn describe(&self, key: &str) -> String {
+        let name = key.rsplit('.').next().unwrap_or(key);
+
+        match self.origin {
+            PathOrigin::Explicit => format!("{} '{}'", name, self.path.display()),
+            PathOrigin::Defaulted => format!("defaulted {} '{}'", name, self.path.display()),
+            PathOrigin::Resolved => format!("{} '{}' (resolved to '{}')", name, self.original().display(), self.path.display())
+        }
+    }
+}
+
+impl AsRef<Path> for ConfigPath {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Display for ConfigPath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.path.display())
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigPath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+        D: Deserializer<'de> {
+        PathBuf::deserialize(deserializer).map(ConfigPath::explicit)
+    }
+}
+
+/// Validates a `ConfigPath` using the specified severity and validator kind; the last field is the
+/// configuration key of the path (e.g. `[mammoth].mods_dir`), used in the messages.
+#[derive(Copy, Clone)]
+pub struct ConfigPathValidator<'a>(pub Severity, pub PathValidatorKind, pub &'a str);
+
+impl<'a> Validator<ConfigPath> for ConfigPathValidator<'a> {
+    fn validate(&self, logger: &mut dyn Logger, item: &ConfigPath) -> Result<(), Error> {
+        let ConfigPathValidator(severity, kind, key) = *self;
+        let path = item.path();
+
+        let (problem, error) = match kind {
+            PathValidatorKind::FilePath if path.to_string_lossy().ends_with('/') =>
+                ("is not a valid file name", Error::InvalidFilePath(path.to_path_buf())),
+            PathValidatorKind::ExistingDirectory if !path.is_dir() =>
+                ("does not exist or is not a directory", Error::FileNotFound(path.to_path_buf())),
+            PathValidatorKind::ExistingFile if !path.is_file() =>
+                ("does not exist or is not a file", Error::FileNotFound(path.to_path_buf())),
+            PathValidatorKind::WritableDirectory if !is_writable_dir(path) =>
+                ("does not exist or is not writable", Error::InvalidDirectory(path.to_path_buf())),
+            _ => return Ok(())
+        };
+
+        let desc = if item.is_explicit() {
+            format!("{} {}.", item.describe(key), problem)
+        } else {
+            format!("{} {} (set {} to override).", item.describe(key), problem, key)
+        };
+        logger.log(severity, &desc);
+        if severity >= Severity::Error { Err(error)?; }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::config::path::{ConfigPath, ConfigPathValidator, PathOrigin};
+    use crate::diagnostics::{PathValidatorKind, Validator};
+    use crate::error::event::Event;
+    use crate::error::severity::Severity;
+
+    #[test]
+    /// Tests origins and resolution.
+    fn test_origin() {
+        let explicit: ConfigPath = toml::from_str::<std::collections::BTreeMap<String, ConfigPath>>(r#"a = "./mods/""#)
+            .unwrap()
+            .remove("a")
+            .unwrap();
+        assert_eq!(explicit.origin(), PathOrigin::Explicit);
+        assert_eq!(explicit.path(), Path::new("./mods/"));
+        assert!(explicit.absolute().is_absolute());
+
+        let mut resolved = ConfigPath::explicit("www");
+        resolved.resolve("/srv/");
+        assert_eq!(resolved.origin(), PathOrigin::Resolved);
+        assert_eq!(resolved.path(), Path::new("/srv/www"));
+        assert_eq!(resolved.original(), Path::new("www"));
+
+        let mut absolute = ConfigPath::explicit("/srv/www");
+        absolute.resolve("/tmp/");
+        assert_eq!(absolute.origin(), PathOrigin::Explicit);
+        assert_eq!(absolute.path(), Path::new("/srv/www"));
+    }
+
+    #[test]
+    /// Tests the validation messages.
+    fn test_validate() {
+        let mut events: Vec<Event> = Vec::new();
+        let validator = ConfigPathValidator(Severity::Error, PathValidatorKind::ExistingDirectory, "[mammoth].mods_dir");
+
+        assert!(validator.validate(&mut events, &ConfigPath::explicit("./tests/")).is_ok());
+        assert!(validator.validate(&mut events, &ConfigPath::defaulted("./i_do_not_exist/")).is_err());
+        assert_eq!(events.len(), 1);
+
+        let message = events[0].to_string();
+        assert!(message.contains("defaulted mods_dir './i_do_not_exist/' does not exist"));
+        assert!(message.contains("(set [mammoth].mods_dir to override)"));
+
+        let validator = ConfigPathValidator(Severity::Warning, PathValidatorKind::ExistingFile, "[[host]].cert");
+        assert!(validator.validate(&mut events, &ConfigPath::explicit("./cert.pem")).is_ok());
+        assert!(!events[1].to_string().contains("override"));
+    }
+}
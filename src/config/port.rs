@@ -1,12 +1,31 @@
 //! The `Binding` structure contains the configuration for a binding port.
+//!
+//! Along with the port and the security settings, a binding can specify the address it listens on
+//! (any IPv4 address by default), how its listener is sharded (see `ShardPlan`) and the options of
+//! its sockets (see `SocketOptions`).
 
+use std::convert::TryFrom;
 use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
+use std::option;
 use std::path::{Path, PathBuf};
 
-use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
-use serde::{Deserialize, Deserializer};
+use openssl::ocsp::{OcspResponse, OcspResponseStatus};
+use openssl::pkey::PKey;
+use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslOptions, SslSessionCacheMode, SslVersion};
+use openssl::x509::{X509, X509VerifyResult};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::{MapAccess, Visitor};
-
+use serde::ser::SerializeMap;
+
+#[cfg(feature = "acme")]
+use crate::config::acme::Acme;
+use crate::config::path::{ConfigPath, ConfigPathValidator};
+use crate::config::shard::ShardPlan;
+use crate::config::socket::SocketOptions;
+use crate::config::tls::TlsProfile;
 use crate::diagnostics::{Logger, PathValidator, PathValidatorKind, Validator};
 use crate::error::Error;
 use crate::error::severity::Severity;
@@ -15,9 +34,25 @@ use crate::error::severity::Severity;
 #[derive(Clone, Debug, PartialEq)]
 pub struct Binding {
     port: u16,
+    address: Option<String>,
     secure: bool,
-    cert: Option<PathBuf>,
-    key: Option<PathBuf>
+    cert: Option<ConfigPath>,
+    key: Option<ConfigPath>,
+    chain: Option<ConfigPath>,
+    tls: Option<String>,
+    #[cfg(feature = "acme")]
+    acme: bool,
+    #[cfg(feature = "self_signed")]
+    self_signed: bool,
+    tls_min_version: Option<TlsVersion>,
+    tls_max_version: Option<TlsVersion>,
+    session_tickets: Option<bool>,
+    session_cache: Option<u32>,
+    ocsp_response: Option<ConfigPath>,
+    ocsp_url: Option<String>,
+    shards: Option<usize>,
+    pin_to_cores: Vec<usize>,
+    socket: SocketOptions
 }
 
 #[doc(hidden)]
@@ -25,22 +60,103 @@ pub struct Binding {
 #[serde(field_identifier, rename_all = "lowercase")]
 pub(super) enum PortFields {
     Port,
+    Address,
     Secure,
     Cert,
-    Key
+    Key,
+    #[serde(alias = "intermediates")]
+    Chain,
+    Tls,
+    #[cfg(feature = "acme")]
+    Acme,
+    #[cfg(feature = "self_signed")]
+    #[serde(rename = "self_signed")]
+    SelfSigned,
+    #[serde(rename = "tls_min_version")]
+    TlsMinVersion,
+    #[serde(rename = "tls_max_version")]
+    TlsMaxVersion,
+    #[serde(rename = "session_tickets")]
+    SessionTickets,
+    #[serde(rename = "session_cache")]
+    SessionCache,
+    #[serde(rename = "ocsp_response")]
+    OcspResponse,
+    #[serde(rename = "ocsp_url")]
+    OcspUrl,
+    Shards,
+    #[serde(rename = "pin_to_cores")]
+    PinToCores,
+    Socket
 }
 
 #[doc(hidden)]
 struct PortVisitor;
 
+/// Versions of the TLS protocol.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsVersion {
+    /// TLS 1.0.
+    #[serde(rename = "1.0")]
+    Tls10,
+    /// TLS 1.1.
+    #[serde(rename = "1.1")]
+    Tls11,
+    /// TLS 1.2.
+    #[serde(rename = "1.2")]
+    Tls12,
+    /// TLS 1.3.
+    #[serde(rename = "1.3")]
+    Tls13
+}
+
+impl TlsVersion {
+    /// Obtains the corresponding OpenSSL protocol version.
+    pub fn ssl_version(self) -> SslVersion {
+        match self {
+            TlsVersion::Tls10 => SslVersion::TLS1,
+            TlsVersion::Tls11 => SslVersion::TLS1_1,
+            TlsVersion::Tls12 => SslVersion::TLS1_2,
+            TlsVersion::Tls13 => SslVersion::TLS1_3
+        }
+    }
+}
+
+impl std::fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            TlsVersion::Tls10 => write!(f, "TLS 1.0"),
+            TlsVersion::Tls11 => write!(f, "TLS 1.1"),
+            TlsVersion::Tls12 => write!(f, "TLS 1.2"),
+            TlsVersion::Tls13 => write!(f, "TLS 1.3")
+        }
+    }
+}
+
 impl Binding {
     /// Creates a new `Binding` structure for a port, given the port number.
     pub fn new(port: u16) -> Binding {
         Binding {
             port,
+            address: None,
             secure: false,
             cert: None,
-            key: None
+            key: None,
+            chain: None,
+            tls: None,
+            #[cfg(feature = "acme")]
+            acme: false,
+            #[cfg(feature = "self_signed")]
+            self_signed: false,
+            tls_min_version: None,
+            tls_max_version: None,
+            session_tickets: None,
+            session_cache: None,
+            ocsp_response: None,
+            ocsp_url: None,
+            shards: None,
+            pin_to_cores: Vec::new(),
+            socket: SocketOptions::new()
         }
     }
     /// Creates a new `Binding` structure for a secure port,
@@ -50,39 +166,277 @@ impl Binding {
             P: AsRef<Path>,
             Q: AsRef<Path> {
         Binding {
-            port,
             secure: true,
-            cert: Some(cert.as_ref().to_path_buf()),
-            key: Some(key.as_ref().to_path_buf())
+            cert: Some(ConfigPath::explicit(cert)),
+            key: Some(ConfigPath::explicit(key)),
+            ..Binding::new(port)
         }
     }
     /// Obtains the port number.
     pub fn port(&self) -> u16 {
         self.port
     }
+    /// Obtains the address literal the binding listens on, if specified.
+    pub fn address(&self) -> Option<&str> {
+        self.address.as_deref()
+    }
+    /// Obtains the IP address the binding listens on; if not specified, this is `0.0.0.0`.
+    ///
+    /// Fails if the specified address is not a valid IPv4 or IPv6 literal.
+    pub fn ip_addr(&self) -> Result<IpAddr, Error> {
+        match self.address {
+            Some(ref address) => address.parse()
+                .map_err(|_| Error::InvalidValue("listen.address".to_owned())),
+            None => Ok(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+        }
+    }
     /// Returns a value that indicates if the binding is secure or not.
     pub fn secure(&self) -> bool {
         self.secure
     }
     /// Obtains the path to the certificate file, if any.
     pub fn cert(&self) -> Option<&Path> {
-        if let Some(ref path) = self.cert { Some(path) }
-        else { None }
+        self.cert.as_ref().map(|p| p.path())
+    }
+    /// Obtains the path to the certificate file along with its origin, if any.
+    pub fn cert_path(&self) -> Option<&ConfigPath> {
+        self.cert.as_ref()
     }
     /// Obtains the path to the key file, if any.
     pub fn key(&self) -> Option<&Path> {
-        if let Some(ref path) = self.key { Some(path) }
-        else { None }
+        self.key.as_ref().map(|p| p.path())
+    }
+    /// Obtains the path to the key file along with its origin, if any.
+    pub fn key_path(&self) -> Option<&ConfigPath> {
+        self.key.as_ref()
+    }
+    /// Obtains the path to the file of the intermediate certificates, if any.
+    ///
+    /// If not specified, the intermediate certificates (if any) are read from the certificate file,
+    /// after the leaf certificate.
+    pub fn chain(&self) -> Option<&Path> {
+        self.chain.as_ref().map(|p| p.path())
+    }
+    /// Obtains the path to the file of the intermediate certificates along with its origin, if any.
+    pub fn chain_path(&self) -> Option<&ConfigPath> {
+        self.chain.as_ref()
+    }
+    /// Sets the path to the file of the intermediate certificates.
+    pub fn set_chain<P>(&mut self, path: P)
+        where
+            P: AsRef<Path>
+    {
+        self.chain = Some(ConfigPath::explicit(path));
+    }
+    /// Removes the file of the intermediate certificates.
+    pub fn clear_chain(&mut self) {
+        self.chain = None;
+    }
+    /// Obtains the name of the TLS profile referenced by the binding, if any.
+    pub fn tls_profile(&self) -> Option<&str> {
+        self.tls.as_deref()
+    }
+    /// Makes the binding secure, taking its TLS settings from the profile named `name`.
+    ///
+    /// The certificate and the key are set by `apply_tls_profile`.
+    pub fn set_tls_profile(&mut self, name: &str) {
+        self.clear_security();
+        self.secure = true;
+        self.tls = Some(name.to_owned());
+    }
+    /// Applies the settings of `profile` to the binding.
+    ///
+    /// The certificate, the key and the chain are taken from the profile, while the protocol
+    /// settings of the profile are used only where the binding does not specify its own.
+    pub fn apply_tls_profile(&mut self, profile: &TlsProfile) {
+        self.cert = Some(profile.cert().clone());
+        self.key = Some(profile.key().clone());
+        self.chain = profile.chain().cloned();
+        self.tls_min_version = self.tls_min_version.or_else(|| profile.tls_min_version());
+        self.tls_max_version = self.tls_max_version.or_else(|| profile.tls_max_version());
+        self.session_tickets = self.session_tickets.or_else(|| profile.session_tickets());
+        self.session_cache = self.session_cache.or_else(|| profile.session_cache());
+    }
+    /// Returns `true` if the intermediate certificates of `chain` form a chain starting from the
+    /// leaf certificate of `cert`, i.e. each certificate is issued and signed by the following one.
+    ///
+    /// Fails if the files cannot be read or do not contain PEM-encoded certificates.
+    pub fn chain_matches(&self) -> Result<bool, Error> {
+        let (cert, chain) = match (self.cert(), self.chain()) {
+            (Some(cert), Some(chain)) => (cert, chain),
+            _ => return Ok(true)
+        };
+        let leaf = X509::from_pem(&fs::read(cert)?)?;
+        let intermediates = X509::stack_from_pem(&fs::read(chain)?)?;
+        if intermediates.is_empty() {
+            return Ok(false);
+        }
+
+        let mut child = &leaf;
+        for parent in intermediates.iter() {
+            let key = parent.public_key()?;
+            if parent.issued(child) != X509VerifyResult::OK || !child.verify(&key)? {
+                return Ok(false);
+            }
+            child = parent;
+        }
+        Ok(true)
+    }
+    /// Returns `true` if the certificate of the binding is obtained through ACME.
+    #[cfg(feature = "acme")]
+    pub fn acme(&self) -> bool {
+        self.acme
+    }
+    /// Makes the binding secure, obtaining its certificate through ACME.
+    ///
+    /// The certificate and the key are set by `set_acme_paths`.
+    #[cfg(feature = "acme")]
+    pub fn set_acme(&mut self) {
+        self.clear_security();
+        self.secure = true;
+        self.acme = true;
+    }
+    /// Points the certificate and the key of an ACME binding to the ACME cache of `domain`.
+    #[cfg(feature = "acme")]
+    pub fn set_acme_paths(&mut self, acme: &Acme, domain: &str) {
+        self.cert = Some(ConfigPath::defaulted(acme.cert_path(domain)));
+        self.key = Some(ConfigPath::defaulted(acme.key_path(domain)));
+    }
+    /// Returns `true` if the binding uses a generated self-signed certificate.
+    #[cfg(feature = "self_signed")]
+    pub fn self_signed(&self) -> bool {
+        self.self_signed
+    }
+    /// Makes the binding secure with a newly generated self-signed certificate for `hostname`,
+    /// stored in a new temporary directory (see `config::self_signed`).
+    ///
+    /// The certificate is not trusted by clients and is meant for local development only.
+    #[cfg(feature = "self_signed")]
+    pub fn generate_self_signed(&mut self, hostname: &str) -> Result<(), Error> {
+        let (cert, key) = crate::config::self_signed::write(crate::config::self_signed::directory()?, hostname)?;
+
+        self.clear_security();
+        self.secure = true;
+        self.self_signed = true;
+        self.cert = Some(ConfigPath::defaulted(cert));
+        self.key = Some(ConfigPath::defaulted(key));
+
+        Ok(())
+    }
+    /// Returns `true` if the certificate of the binding is obtained through ACME or generated.
+    fn acme_or_self_signed(&self) -> bool {
+        #[cfg(feature = "acme")]
+        { if self.acme { return true; } }
+        #[cfg(feature = "self_signed")]
+        { if self.self_signed { return true; } }
+        false
+    }
+    /// Returns `true` if the certificate of the binding is still to be obtained through ACME.
+    fn certificate_pending(&self) -> bool {
+        #[cfg(feature = "acme")]
+        { self.acme && !(self.cert().is_some_and(Path::is_file) && self.key().is_some_and(Path::is_file)) }
+        #[cfg(not(feature = "acme"))]
+        { false }
+    }
+    /// Returns `true` if the private key corresponds to the public key of the certificate.
+    ///
+    /// Fails if the files cannot be read or do not contain a PEM-encoded certificate and key.
+    pub fn key_matches(&self) -> Result<bool, Error> {
+        let (cert, key) = match (self.cert(), self.key()) {
+            (Some(cert), Some(key)) => (cert, key),
+            _ => return Err(Error::SecureBindOnInsecure)
+        };
+        let cert = X509::from_pem(&fs::read(cert)?)?;
+        let key = PKey::private_key_from_pem(&fs::read(key)?)?;
+
+        Ok(cert.public_key()?.public_eq(&key))
+    }
+    /// Obtains the minimum TLS version accepted by the binding, if specified.
+    pub fn tls_min_version(&self) -> Option<TlsVersion> {
+        self.tls_min_version
+    }
+    /// Obtains the maximum TLS version accepted by the binding, if specified.
+    pub fn tls_max_version(&self) -> Option<TlsVersion> {
+        self.tls_max_version
+    }
+    /// Sets the minimum TLS version accepted by the binding.
+    pub fn set_tls_min_version(&mut self, version: Option<TlsVersion>) {
+        self.tls_min_version = version;
+    }
+    /// Sets the maximum TLS version accepted by the binding.
+    pub fn set_tls_max_version(&mut self, version: Option<TlsVersion>) {
+        self.tls_max_version = version;
+    }
+    /// Returns `true` if TLS sessions can be resumed through session tickets (the default).
+    pub fn session_tickets(&self) -> bool {
+        self.session_tickets.unwrap_or(true)
+    }
+    /// Enables or disables the resumption of TLS sessions through session tickets.
+    pub fn set_session_tickets(&mut self, enabled: bool) {
+        self.session_tickets = Some(enabled);
+    }
+    /// Obtains the maximum number of TLS sessions kept in the server-side cache, if specified;
+    /// zero means that the cache is disabled.
+    ///
+    /// If not specified, the default size of OpenSSL is used.
+    pub fn session_cache(&self) -> Option<u32> {
+        self.session_cache
+    }
+    /// Sets the maximum number of TLS sessions kept in the server-side cache; zero disables the
+    /// cache.
+    pub fn set_session_cache(&mut self, size: Option<u32>) {
+        self.session_cache = size;
+    }
+    /// Obtains the path to the DER-encoded OCSP response stapled to the handshakes, if any.
+    pub fn ocsp_response(&self) -> Option<&Path> {
+        self.ocsp_response.as_ref().map(|p| p.path())
+    }
+    /// Sets the path to the DER-encoded OCSP response stapled to the handshakes.
+    pub fn set_ocsp_response<P>(&mut self, path: P)
+        where
+            P: AsRef<Path>
+    {
+        self.ocsp_response = Some(ConfigPath::explicit(path));
+    }
+    /// Obtains the URL of the OCSP responder the stapled response is refreshed from, if any.
+    ///
+    /// Responses are not fetched by this crate: the runtime is expected to periodically store
+    /// them into `ocsp_response`.
+    pub fn ocsp_url(&self) -> Option<&str> {
+        self.ocsp_url.as_deref()
+    }
+    /// Sets the URL of the OCSP responder the stapled response is refreshed from.
+    pub fn set_ocsp_url(&mut self, url: &str) {
+        self.ocsp_url = Some(url.to_owned());
+    }
+    /// Disables OCSP stapling.
+    pub fn clear_ocsp(&mut self) {
+        self.ocsp_response = None;
+        self.ocsp_url = None;
     }
     /// Sets the port number.
     pub fn set_port(&mut self, port: u16) {
         self.port = port;
     }
+    /// Sets the IP address the binding listens on.
+    pub fn set_address(&mut self, address: IpAddr) {
+        self.address = Some(address.to_string());
+    }
+    /// Makes the binding listen on any IPv4 address.
+    pub fn clear_address(&mut self) {
+        self.address = None;
+    }
     /// Removes security from this binding.
     pub fn clear_security(&mut self) {
         self.secure = false;
         self.cert = None;
         self.key = None;
+        self.chain = None;
+        self.tls = None;
+        #[cfg(feature = "acme")]
+        { self.acme = false; }
+        #[cfg(feature = "self_signed")]
+        { self.self_signed = false; }
     }
     /// Sets security for this binding, given a path to a certificate and a path to the relative key.
     pub fn set_security<P, Q>(&mut self, cert: P, key: Q)
@@ -91,34 +445,270 @@ impl Binding {
             Q: AsRef<Path>
     {
         self.secure = true;
-        self.cert = Some(cert.as_ref().to_path_buf());
-        self.key = Some(key.as_ref().to_path_buf());
+        self.cert = Some(ConfigPath::explicit(cert));
+        self.key = Some(ConfigPath::explicit(key));
+        self.tls = None;
+        #[cfg(feature = "acme")]
+        { self.acme = false; }
+        #[cfg(feature = "self_signed")]
+        { self.self_signed = false; }
+    }
+    /// Obtains the number of listener sockets of the binding.
+    ///
+    /// If not specified, this is the number of cores in `pin_to_cores`, or one if no core is given.
+    pub fn shards(&self) -> usize {
+        self.shards.unwrap_or_else(|| self.pin_to_cores.len().max(1))
+    }
+    /// Sets the number of listener sockets of the binding.
+    pub fn set_shards(&mut self, shards: usize) {
+        self.shards = Some(shards);
+    }
+    /// Resets the number of listener sockets of the binding to its default.
+    pub fn clear_shards(&mut self) {
+        self.shards = None;
+    }
+    /// Obtains the CPU cores the workers of the listeners are pinned to.
+    pub fn pin_to_cores(&self) -> &[usize] {
+        &self.pin_to_cores
+    }
+    /// Sets the CPU cores the workers of the listeners are pinned to.
+    pub fn set_pin_to_cores(&mut self, cores: &[usize]) {
+        self.pin_to_cores = cores.to_vec();
+    }
+    /// Removes the pinning of the workers of the listeners.
+    pub fn clear_pin_to_cores(&mut self) {
+        self.pin_to_cores.clear();
+    }
+    /// Obtains the options of the sockets of the binding.
+    pub fn socket(&self) -> &SocketOptions {
+        &self.socket
+    }
+    /// Obtains a mutable reference to the options of the sockets of the binding.
+    pub fn socket_mut(&mut self) -> &mut SocketOptions {
+        &mut self.socket
+    }
+    /// Sets the options of the sockets of the binding.
+    pub fn set_socket(&mut self, socket: SocketOptions) {
+        self.socket = socket;
+    }
+    /// Returns `true` if the listener sockets are bound with `SO_REUSEPORT`.
+    ///
+    /// If not specified in the socket options, this is `true` only if the binding is sharded.
+    pub fn reuse_port(&self) -> bool {
+        self.socket.reuse_port().unwrap_or_else(|| self.shards() > 1)
+    }
+    /// Obtains the plan of the listener sockets of the binding.
+    pub fn shard_plan(&self) -> ShardPlan {
+        ShardPlan::new(self.port, self.shards(), &self.pin_to_cores)
     }
     /// Tries to construct a `SslAcceptor` structure from the given certificate and key files.
     pub fn ssl_acceptor(&self) -> Result<SslAcceptor, Error> {
+        #[cfg(feature = "chaos")]
+        crate::testing::chaos::check(crate::testing::chaos::FailurePoint::TlsSetup)?;
+
         if self.secure {
             let mut ssl_builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
             ssl_builder.set_private_key_file(self.key.as_ref().unwrap(), SslFiletype::PEM)?;
-            ssl_builder.set_certificate_chain_file(self.cert.as_ref().unwrap())?;
+            if let Some(ref chain) = self.chain {
+                ssl_builder.set_certificate_file(self.cert.as_ref().unwrap(), SslFiletype::PEM)?;
+                for intermediate in X509::stack_from_pem(&fs::read(chain)?)? {
+                    ssl_builder.add_extra_chain_cert(intermediate)?;
+                }
+            } else {
+                ssl_builder.set_certificate_chain_file(self.cert.as_ref().unwrap())?;
+            }
+            ssl_builder.check_private_key()?;
+            if let Some(version) = self.tls_min_version {
+                ssl_builder.set_min_proto_version(Some(version.ssl_version()))?;
+            }
+            if let Some(version) = self.tls_max_version {
+                ssl_builder.set_max_proto_version(Some(version.ssl_version()))?;
+            }
+            if !self.session_tickets() {
+                ssl_builder.set_options(SslOptions::NO_TICKET);
+            }
+            match self.session_cache {
+                Some(0) => { ssl_builder.set_session_cache_mode(SslSessionCacheMode::OFF); },
+                Some(size) => {
+                    ssl_builder.set_session_cache_mode(SslSessionCacheMode::SERVER);
+                    ssl_builder.set_session_cache_size(i32::try_from(size).unwrap_or(i32::MAX));
+                },
+                None => {}
+            }
+            if let Some(ref path) = self.ocsp_response {
+                let response = fs::read(path)?;
+                OcspResponse::from_der(&response)?;
+                ssl_builder.set_status_callback(move |ssl| {
+                    ssl.set_ocsp_status(&response)?;
+                    Ok(true)
+                })?;
+            }
 
             Ok(ssl_builder.build())
         } else {
             Err(Error::SecureBindOnInsecure)
         }
     }
-    /// Obtains an address string from the given port.
+    /// Obtains the files the `SslAcceptor` of the binding is built from, i.e. the certificate, the
+    /// key, the chain and the OCSP response, if any.
+    pub fn tls_files(&self) -> Vec<&Path> {
+        [&self.cert, &self.key, &self.chain, &self.ocsp_response].iter()
+            .filter_map(|p| p.as_ref().map(|p| p.path()))
+            .collect()
+    }
+    /// Rebuilds the `SslAcceptor` structure from the current content of the TLS files, logging the
+    /// outcome through `logger`.
+    ///
+    /// Since the acceptor is built from scratch, a failed reload does not affect the acceptors
+    /// already in use.
+    pub fn reload_tls(&self, logger: &mut dyn Logger) -> Result<SslAcceptor, Error> {
+        match self.ssl_acceptor() {
+            Ok(acceptor) => {
+                let desc = format!("TLS certificate of port {} reloaded.", self.port);
+                logger.log(Severity::Information, &desc);
+                Ok(acceptor)
+            },
+            Err(err) => {
+                let desc = format!("Could not reload the TLS certificate of port {}: {}; keeping the previous one.", self.port, err);
+                logger.log(Severity::Error, &desc);
+                Err(err)
+            }
+        }
+    }
+    /// Obtains an address string from the given address and port; IPv6 addresses are enclosed in
+    /// square brackets.
     pub fn to_addr_string(&self) -> String {
-        format!("0.0.0.0:{}", self.port)
+        match self.ip_addr() {
+            Ok(ip) => SocketAddr::new(ip, self.port).to_string(),
+            Err(_) => format!("{}:{}", self.address.as_deref().unwrap_or_default(), self.port)
+        }
+    }
+}
+
+impl ToSocketAddrs for Binding {
+    type Iter = option::IntoIter<SocketAddr>;
+
+    fn to_socket_addrs(&self) -> io::Result<Self::Iter> {
+        match self.ip_addr() {
+            Ok(ip) => Ok(Some(SocketAddr::new(ip, self.port)).into_iter()),
+            Err(_) => Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid IP address"))
+        }
     }
 }
 
 impl Validator<Binding> for () {
-    fn validate(&self, logger: &mut Logger, item: &Binding) -> Result<(), Error> {
+    fn validate(&self, logger: &mut dyn Logger, item: &Binding) -> Result<(), Error> {
+        match (item.port(), item.secure()) {
+            (0, _) => {
+                logger.log(Severity::Warning, "Port 0 binds to a random port chosen by the system.");
+            },
+            (443, false) => {
+                logger.log(Severity::Warning, "Port 443 is insecure; clients expect HTTPS on this port.");
+            },
+            (80, true) => {
+                logger.log(Severity::Warning, "Port 80 is secure; clients expect plain HTTP on this port.");
+            },
+            _ => {}
+        }
+        if let (Some(min), Some(max)) = (item.tls_min_version(), item.tls_max_version()) {
+            if min > max {
+                let desc = format!("Minimum TLS version of port {} ({}) is greater than the maximum one ({}).", item.port(), min, max);
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("tls_min_version".to_owned()))?;
+            }
+        }
+        if !item.secure() && (item.tls_min_version().is_some() || item.tls_max_version().is_some()) {
+            let desc = format!("TLS versions specified for the insecure port {}; ignoring.", item.port());
+            logger.log(Severity::Warning, &desc);
+        }
         if item.secure() {
-            let validator = PathValidator(Severity::Critical, PathValidatorKind::ExistingFile);
-
-            validator.validate(logger, &item.cert().unwrap())?;
-            validator.validate(logger, &item.key().unwrap())?;
+            if item.session_cache().is_some_and(|size| i32::try_from(size).is_err()) {
+                let desc = format!("Session cache of port {} cannot hold more than {} sessions.", item.port(), i32::MAX);
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("listen.session_cache".to_owned()))?;
+            }
+            if !item.session_tickets() && item.session_cache() == Some(0) {
+                let desc = format!("TLS session resumption disabled for port {}; every connection performs a full handshake.", item.port());
+                logger.log(Severity::Information, &desc);
+            }
+        } else if !item.session_tickets() || item.session_cache().is_some() {
+            let desc = format!("TLS session options specified for the insecure port {}; ignoring.", item.port());
+            logger.log(Severity::Warning, &desc);
+        }
+        if item.secure() {
+            if let Some(path) = item.ocsp_response() {
+                PathValidator(Severity::Error, PathValidatorKind::ExistingFile)
+                    .validate(logger, &path.to_path_buf())?;
+                match fs::read(path).ok().and_then(|der| OcspResponse::from_der(&der).ok()) {
+                    Some(response) => if response.status() != OcspResponseStatus::SUCCESSFUL {
+                        let desc = format!("OCSP response of port {} is not successful; clients may reject it.", item.port());
+                        logger.log(Severity::Warning, &desc);
+                    },
+                    None => {
+                        let desc = format!("Invalid OCSP response of port {}: '{}'.", item.port(), path.display());
+                        logger.log(Severity::Error, &desc);
+                        Err(Error::InvalidValue("listen.ocsp_response".to_owned()))?;
+                    }
+                }
+            }
+            if let Some(url) = item.ocsp_url() {
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    let desc = format!("Invalid OCSP responder URL of port {}: '{}'.", item.port(), url);
+                    logger.log(Severity::Error, &desc);
+                    Err(Error::InvalidValue("listen.ocsp_url".to_owned()))?;
+                }
+                if item.ocsp_response().is_none() {
+                    let desc = format!("OCSP responder specified for port {} without 'ocsp_response'; nothing is stapled until a response is stored.", item.port());
+                    logger.log(Severity::Warning, &desc);
+                }
+            }
+        } else if item.ocsp_response().is_some() || item.ocsp_url().is_some() {
+            let desc = format!("OCSP stapling configured for port {} without a certificate; ignoring.", item.port());
+            logger.log(Severity::Warning, &desc);
+        }
+        if item.ip_addr().is_err() {
+            let desc = format!("Invalid listen address of port {}: '{}'; expected an IPv4 or IPv6 literal.", item.port(), item.address().unwrap_or_default());
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("listen.address".to_owned()))?;
+        }
+        if item.secure() && (item.cert_path().is_none() || item.key_path().is_none()) {
+            let desc = match item.tls_profile() {
+                Some(name) => format!("Port {} references the unknown TLS profile '{}'.", item.port(), name),
+                None => format!("Secure port {} has no certificate or key.", item.port())
+            };
+            logger.log(Severity::Critical, &desc);
+            Err(Error::InvalidValue("listen.cert".to_owned()))?;
+        } else if item.certificate_pending() {
+            let desc = format!("Certificate of port {} not obtained yet; it will be requested through ACME.", item.port());
+            logger.log(Severity::Information, &desc);
+        } else if item.secure() {
+            ConfigPathValidator(Severity::Critical, PathValidatorKind::ExistingFile, "[[host]].listen.cert")
+                .validate(logger, item.cert_path().unwrap())?;
+            ConfigPathValidator(Severity::Critical, PathValidatorKind::ExistingFile, "[[host]].listen.key")
+                .validate(logger, item.key_path().unwrap())?;
+            match item.key_matches() {
+                Ok(true) => {},
+                Ok(false) => {
+                    let (cert, key) = (item.cert().unwrap(), item.key().unwrap());
+                    let desc = format!("Private key '{}' of port {} does not match the certificate '{}'.", key.display(), item.port(), cert.display());
+                    logger.log(Severity::Critical, &desc);
+                    Err(Error::KeyMismatch(cert.to_path_buf(), key.to_path_buf()))?;
+                },
+                Err(err) => {
+                    let desc = format!("Could not read the certificate or the private key of port {}: {}.", item.port(), err);
+                    logger.log(Severity::Critical, &desc);
+                    Err(err)?;
+                }
+            }
+            if let Some(chain) = item.chain_path() {
+                ConfigPathValidator(Severity::Critical, PathValidatorKind::ExistingFile, "[[host]].listen.chain")
+                    .validate(logger, chain)?;
+                if !item.chain_matches().unwrap_or(false) {
+                    let desc = format!("Certificate chain of port {} does not match the leaf certificate.", item.port());
+                    logger.log(Severity::Critical, &desc);
+                    Err(Error::InvalidValue("listen.chain".to_owned()))?;
+                }
+            }
 
             if let Err(err) = item.ssl_acceptor() {
                 logger.log(Severity::Critical, "Could not construct an SSL acceptor.");
@@ -126,18 +716,40 @@ impl Validator<Binding> for () {
             }
         }
 
+        if item.shards() == 0 {
+            let desc = format!("Number of shards of port {} must be greater than zero.", item.port());
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("shards".to_owned()))?;
+        }
+        let available = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        for (i, core) in item.pin_to_cores().iter().enumerate() {
+            if item.pin_to_cores()[..i].contains(core) {
+                let desc = format!("Core {} of port {} is listed more than once in pin_to_cores.", core, item.port());
+                logger.log(Severity::Warning, &desc);
+            }
+            if *core >= available {
+                let desc = format!("Core {} of port {} is not available; only {} cores found.", core, item.port(), available);
+                logger.log(Severity::Warning, &desc);
+            }
+        }
+        if item.socket().reuse_port() == Some(false) && item.shards() > 1 {
+            let desc = format!("Port {} has {} shards but disables reuse_port.", item.port(), item.shards());
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("socket.reuse_port".to_owned()))?;
+        }
+        ().validate(logger, item.socket())?;
+        if item.pin_to_cores().len() > item.shards() {
+            let desc = format!("Port {} pins {} shards to {} cores; some cores are unused.", item.port(), item.shards(), item.pin_to_cores().len());
+            logger.log(Severity::Warning, &desc);
+        }
+
         Ok(())
     }
 }
 
 impl From<u16> for Binding {
     fn from(value: u16) -> Self {
-        Binding {
-            port: value,
-            secure: false,
-            cert: None,
-            key: None
-        }
+        Binding::new(value)
     }
 }
 
@@ -145,7 +757,7 @@ impl <'de> Visitor<'de> for PortVisitor {
     type Value = Binding;
 
     fn expecting(&self, f: &mut Formatter) -> ::std::fmt::Result {
-        write!(f, "a positive number less than 65536 or an object containing the binding parameters.")
+        write!(f, "a positive number less than 65536, an address such as \"127.0.0.1:8080\" or \"[::1]:8443\", or an object containing the binding parameters.")
     }
 
     fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E> where
@@ -188,12 +800,38 @@ impl <'de> Visitor<'de> for PortVisitor {
         Ok(Binding::from(v as u16))
     }
 
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where
+        E: serde::de::Error, {
+        let addr: SocketAddr = v.parse()
+            .map_err(|_| E::custom(format!("invalid binding address '{}'; expected an IPv4 or IPv6 address with a port, e.g. \"127.0.0.1:8080\" or \"[::1]:8443\"", v)))?;
+        let mut binding = Binding::new(addr.port());
+        binding.set_address(addr.ip());
+
+        Ok(binding)
+    }
+
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where
         A: MapAccess<'de>, {
         let mut port: Option<u16> = None;
+        let mut address: Option<String> = None;
         let mut secure: Option<bool> = None;
         let mut cert: Option<PathBuf> = None;
         let mut key: Option<PathBuf> = None;
+        let mut chain: Option<PathBuf> = None;
+        let mut tls: Option<String> = None;
+        #[cfg(feature = "acme")]
+        let mut acme: Option<bool> = None;
+        #[cfg(feature = "self_signed")]
+        let mut self_signed: Option<bool> = None;
+        let mut tls_min_version: Option<TlsVersion> = None;
+        let mut tls_max_version: Option<TlsVersion> = None;
+        let mut session_tickets: Option<bool> = None;
+        let mut session_cache: Option<u32> = None;
+        let mut ocsp_response: Option<PathBuf> = None;
+        let mut ocsp_url: Option<String> = None;
+        let mut shards: Option<usize> = None;
+        let mut pin_to_cores: Option<Vec<usize>> = None;
+        let mut socket: Option<SocketOptions> = None;
 
         while let Some(k) = map.next_key()? {
             match k {
@@ -201,6 +839,10 @@ impl <'de> Visitor<'de> for PortVisitor {
                     if port.is_some() { return Err(serde::de::Error::duplicate_field("port")); }
                     port = Some(map.next_value()?);
                 }
+                PortFields::Address => {
+                    if address.is_some() { return Err(serde::de::Error::duplicate_field("address")); }
+                    address = Some(map.next_value()?);
+                }
                 PortFields::Secure => {
                     if secure.is_some() { return Err(serde::de::Error::duplicate_field("secure")); }
                     secure = Some(map.next_value()?);
@@ -213,20 +855,116 @@ impl <'de> Visitor<'de> for PortVisitor {
                     if key.is_some() { return Err(serde::de::Error::duplicate_field("key")); }
                     key = Some(map.next_value()?);
                 }
+                PortFields::Chain => {
+                    if chain.is_some() { return Err(serde::de::Error::duplicate_field("chain")); }
+                    chain = Some(map.next_value()?);
+                }
+                PortFields::Tls => {
+                    if tls.is_some() { return Err(serde::de::Error::duplicate_field("tls")); }
+                    tls = Some(map.next_value()?);
+                }
+                #[cfg(feature = "acme")]
+                PortFields::Acme => {
+                    if acme.is_some() { return Err(serde::de::Error::duplicate_field("acme")); }
+                    acme = Some(map.next_value()?);
+                }
+                #[cfg(feature = "self_signed")]
+                PortFields::SelfSigned => {
+                    if self_signed.is_some() { return Err(serde::de::Error::duplicate_field("self_signed")); }
+                    self_signed = Some(map.next_value()?);
+                }
+                PortFields::TlsMinVersion => {
+                    if tls_min_version.is_some() { return Err(serde::de::Error::duplicate_field("tls_min_version")); }
+                    tls_min_version = Some(map.next_value()?);
+                }
+                PortFields::TlsMaxVersion => {
+                    if tls_max_version.is_some() { return Err(serde::de::Error::duplicate_field("tls_max_version")); }
+                    tls_max_version = Some(map.next_value()?);
+                }
+                PortFields::SessionTickets => {
+                    if session_tickets.is_some() { return Err(serde::de::Error::duplicate_field("session_tickets")); }
+                    session_tickets = Some(map.next_value()?);
+                }
+                PortFields::SessionCache => {
+                    if session_cache.is_some() { return Err(serde::de::Error::duplicate_field("session_cache")); }
+                    session_cache = Some(map.next_value()?);
+                }
+                PortFields::OcspResponse => {
+                    if ocsp_response.is_some() { return Err(serde::de::Error::duplicate_field("ocsp_response")); }
+                    ocsp_response = Some(map.next_value()?);
+                }
+                PortFields::OcspUrl => {
+                    if ocsp_url.is_some() { return Err(serde::de::Error::duplicate_field("ocsp_url")); }
+                    ocsp_url = Some(map.next_value()?);
+                }
+                PortFields::Shards => {
+                    if shards.is_some() { return Err(serde::de::Error::duplicate_field("shards")); }
+                    shards = Some(map.next_value()?);
+                }
+                PortFields::PinToCores => {
+                    if pin_to_cores.is_some() { return Err(serde::de::Error::duplicate_field("pin_to_cores")); }
+                    pin_to_cores = Some(map.next_value()?);
+                }
+                PortFields::Socket => {
+                    if socket.is_some() { return Err(serde::de::Error::duplicate_field("socket")); }
+                    socket = Some(map.next_value()?);
+                }
             }
         }
 
         let port = port.ok_or_else(|| serde::de::Error::missing_field("port"))?;
-        if let Some(false) = secure {
-            Ok(Binding::new(port))
+        #[cfg(feature = "acme")]
+        let acme = acme.unwrap_or(false);
+        #[cfg(not(feature = "acme"))]
+        let acme = false;
+        #[cfg(feature = "self_signed")]
+        let self_signed = self_signed.unwrap_or(false);
+        #[cfg(not(feature = "self_signed"))]
+        let self_signed = false;
+        if [acme, self_signed, tls.is_some()].iter().filter(|&&b| b).count() > 1 {
+            return Err(serde::de::Error::custom("only one of 'tls', 'acme' and 'self_signed' can be specified"));
+        }
+        let mut binding = if acme || self_signed || tls.is_some() {
+            if tls.is_some() && chain.is_some() {
+                return Err(serde::de::Error::custom("'tls' cannot be combined with 'chain'; specify the chain in the TLS profile"));
+            }
+            if cert.is_some() || key.is_some() || secure == Some(false) {
+                let field = if acme { "acme" } else if self_signed { "self_signed" } else { "tls" };
+                return Err(serde::de::Error::custom(format!("'{}' cannot be combined with 'cert', 'key' or 'secure = false'", field)));
+            }
+            let mut binding = Binding::new(port);
+            binding.secure = true;
+            binding
+        } else if let Some(false) = secure {
+            Binding::new(port)
         } else if secure.unwrap_or(false) || cert.is_some() || key.is_some() {
             if cert.is_none() { return Err(serde::de::Error::missing_field("cert")); }
             if key.is_none() { return Err(serde::de::Error::missing_field("key")); }
 
-            Ok(Binding::with_security(port, cert.unwrap(), key.unwrap()))
+            Binding::with_security(port, cert.unwrap(), key.unwrap())
         } else {
-            Ok(Binding::new(port))
+            Binding::new(port)
+        };
+        if binding.secure && !acme && !self_signed {
+            binding.chain = chain.map(ConfigPath::explicit);
         }
+        binding.tls = tls;
+        #[cfg(feature = "acme")]
+        { binding.acme = acme; }
+        #[cfg(feature = "self_signed")]
+        { binding.self_signed = self_signed; }
+        binding.address = address;
+        binding.tls_min_version = tls_min_version;
+        binding.tls_max_version = tls_max_version;
+        binding.session_tickets = session_tickets;
+        binding.session_cache = session_cache;
+        binding.ocsp_response = ocsp_response.map(ConfigPath::explicit);
+        binding.ocsp_url = ocsp_url;
+        binding.shards = shards;
+        binding.pin_to_cores = pin_to_cores.unwrap_or_default();
+        binding.socket = socket.unwrap_or_default();
+
+        Ok(binding)
     }
 }
 
@@ -237,12 +975,82 @@ impl<'de> Deserialize<'de> for Binding {
     }
 }
 
+/// Serializes the binding to its minimal representation: the bare port number for a plain
+/// insecure binding, and a map containing only the specified keys otherwise.
+///
+/// Paths are written as in the configuration, before being resolved; the certificate and the key
+/// of the bindings using a TLS profile, ACME or a self-signed certificate are not written.
+impl Serialize for Binding {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+        S: Serializer {
+        if *self == Binding::new(self.port) {
+            return serializer.serialize_u16(self.port);
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("port", &self.port)?;
+        if let Some(ref address) = self.address {
+            map.serialize_entry("address", address)?;
+        }
+        if let Some(ref tls) = self.tls {
+            map.serialize_entry("tls", tls)?;
+        } else if self.acme_or_self_signed() {
+            #[cfg(feature = "acme")]
+            { if self.acme { map.serialize_entry("acme", &true)?; } }
+            #[cfg(feature = "self_signed")]
+            { if self.self_signed { map.serialize_entry("self_signed", &true)?; } }
+        } else if let (true, Some(cert), Some(key)) = (self.secure, self.cert.as_ref(), self.key.as_ref()) {
+            map.serialize_entry("cert", cert.original())?;
+            map.serialize_entry("key", key.original())?;
+            if let Some(ref chain) = self.chain {
+                map.serialize_entry("chain", chain.original())?;
+            }
+        } else if self.secure {
+            map.serialize_entry("secure", &true)?;
+        }
+        if let Some(version) = self.tls_min_version {
+            map.serialize_entry("tls_min_version", &version)?;
+        }
+        if let Some(version) = self.tls_max_version {
+            map.serialize_entry("tls_max_version", &version)?;
+        }
+        if let Some(enabled) = self.session_tickets {
+            map.serialize_entry("session_tickets", &enabled)?;
+        }
+        if let Some(size) = self.session_cache {
+            map.serialize_entry("session_cache", &size)?;
+        }
+        if let Some(ref path) = self.ocsp_response {
+            map.serialize_entry("ocsp_response", path.original())?;
+        }
+        if let Some(ref url) = self.ocsp_url {
+            map.serialize_entry("ocsp_url", url)?;
+        }
+        if let Some(shards) = self.shards {
+            map.serialize_entry("shards", &shards)?;
+        }
+        if !self.pin_to_cores.is_empty() {
+            map.serialize_entry("pin_to_cores", &self.pin_to_cores)?;
+        }
+        if self.socket != SocketOptions::default() {
+            map.serialize_entry("socket", &self.socket)?;
+        }
+        map.end()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::BTreeMap;
+    use std::io::Write;
+    use std::net::ToSocketAddrs;
     use std::path::Path;
 
-    use super::Binding;
+    use openssl::ocsp::{OcspResponse, OcspResponseStatus};
+
+    use super::{Binding, TlsVersion};
+    use crate::error::Error;
+    use crate::diagnostics::Validator;
     use crate::error::event::Event;
 
     #[test]
@@ -316,6 +1124,56 @@ mod test {
         assert_eq!(param, test);
     }
 
+    #[test]
+    /// Tests deserialization from address string.
+    fn test_deserialize_str() {
+        let toml = r#"
+        v4 = "127.0.0.1:8080"
+        v6 = "[::1]:8443"
+        host = "localhost:8080"
+        "#;
+
+        let param = toml::from_str::<BTreeMap<String, toml::Value>>(toml).unwrap();
+        let v4 = param["v4"].clone().try_into::<Binding>().unwrap();
+        let v6 = param["v6"].clone().try_into::<Binding>().unwrap();
+
+        assert_eq!(v4.port(), 8080);
+        assert_eq!(v4.address(), Some("127.0.0.1"));
+        assert!(!v4.secure());
+        assert_eq!(v6.port(), 8443);
+        assert_eq!(v6.to_addr_string(), "[::1]:8443");
+        assert!(param["host"].clone().try_into::<Binding>().is_err());
+    }
+
+    #[test]
+    /// Tests serialization to the minimal representation.
+    fn test_serialize() {
+        let param = Binding::new(80);
+        assert_eq!(toml::Value::try_from(&param).unwrap(), toml::Value::Integer(80));
+
+        let toml = r#"
+        port = 443
+        address = "::1"
+        cert = "./tests/test_cert.pem"
+        key = "./tests/test_key.pem"
+        tls_min_version = "1.2"
+        socket = { nodelay = true }
+        "#;
+        let param = toml::from_str::<Binding>(toml).unwrap();
+        let value = toml::Value::try_from(&param).unwrap();
+        let table = value.as_table().unwrap();
+
+        assert_eq!(table.keys().collect::<Vec<_>>(), vec!["address", "cert", "key", "port", "socket", "tls_min_version"]);
+        assert_eq!(table["tls_min_version"].as_str(), Some("1.2"));
+        assert_eq!(value.try_into::<Binding>().unwrap(), param);
+
+        let mut param = Binding::new(8080);
+        param.set_address("127.0.0.1".parse().unwrap());
+        let value = toml::Value::try_from(&param).unwrap();
+        assert_eq!(value.as_table().unwrap().len(), 2);
+        assert_eq!(value.try_into::<Binding>().unwrap(), param);
+    }
+
     #[test]
     /// Tests deserialization from map.
     fn test_deserialize_map() {
@@ -398,6 +1256,56 @@ mod test {
         assert!(toml::from_str::<Binding>(toml).is_err());
     }
 
+    #[test]
+    /// Tests deserialization and validation of the sharding settings.
+    fn test_shards() {
+        let toml = r#"
+        port = 8080
+        shards = 4
+        pin_to_cores = [0, 0]
+        "#;
+        let mut param = toml::from_str::<Binding>(toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(param.shards(), 4);
+        assert_eq!(param.pin_to_cores(), &[0, 0]);
+        assert!(param.shard_plan().reuse_port());
+        assert!(().validate(&mut events, &param).is_ok());
+        assert_eq!(events.len(), 1);
+
+        param.clear_shards();
+        assert_eq!(param.shards(), 2);
+        param.clear_pin_to_cores();
+        assert_eq!(param.shards(), 1);
+        assert!(!param.shard_plan().reuse_port());
+
+        param.set_shards(0);
+        assert!(().validate(&mut events, &param).is_err());
+    }
+
+    #[test]
+    /// Tests the socket options of a binding.
+    fn test_socket() {
+        let toml = r#"
+        port = 8080
+        shards = 2
+        socket = { nodelay = true, keepalive = { idle = 30 } }
+        "#;
+        let mut param = toml::from_str::<Binding>(toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(param.socket().nodelay());
+        assert_eq!(param.socket().keepalive().unwrap().idle(), 30);
+        assert!(param.reuse_port());
+        assert!(().validate(&mut events, &param).is_ok());
+
+        param.socket_mut().set_reuse_port(false);
+        assert!(!param.reuse_port());
+        assert!(().validate(&mut events, &param).is_err());
+        param.clear_shards();
+        assert!(().validate(&mut events, &param).is_ok());
+    }
+
     #[test]
     /// Tests binding string creation.
     fn test_to_addr_string() {
@@ -408,6 +1316,26 @@ mod test {
         assert_eq!(param_sec.to_addr_string(), "0.0.0.0:443");
     }
 
+    #[test]
+    /// Tests the IPv4 and IPv6 listen addresses.
+    fn test_address() {
+        let mut param = toml::from_str::<Binding>("port = 8080\naddress = \"::\"").unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(param.address(), Some("::"));
+        assert_eq!(param.to_addr_string(), "[::]:8080");
+        assert_eq!(param.to_socket_addrs().unwrap().next(), Some("[::]:8080".parse().unwrap()));
+        assert!(().validate(&mut events, &param).is_ok());
+
+        param.set_address("127.0.0.1".parse().unwrap());
+        assert_eq!(param.to_addr_string(), "127.0.0.1:8080");
+
+        let param = toml::from_str::<Binding>("port = 8080\naddress = \"localhost\"").unwrap();
+        assert!(param.ip_addr().is_err());
+        assert!(param.to_socket_addrs().is_err());
+        assert!(().validate(&mut events, &param).is_err());
+    }
+
     #[test]
     /// Tests Ssl acceptor from `Binding`.
     fn test_ssl_acceptor() {
@@ -417,6 +1345,195 @@ mod test {
         let _ = param_ssl.ssl_acceptor().unwrap();
     }
 
+    #[test]
+    /// Tests the TLS protocol versions of a secure binding.
+    fn test_tls_versions() {
+        let toml = r#"
+        port = 8443
+        cert = "./tests/test_cert.pem"
+        key = "./tests/test_key.pem"
+        tls_min_version = "1.2"
+        tls_max_version = "1.3"
+        "#;
+        let mut param = toml::from_str::<Binding>(toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(param.tls_min_version(), Some(TlsVersion::Tls12));
+        assert_eq!(param.tls_max_version(), Some(TlsVersion::Tls13));
+        assert!(param.ssl_acceptor().is_ok());
+        assert!(().validate(&mut events, &param).is_ok());
+
+        param.set_tls_max_version(Some(TlsVersion::Tls11));
+        assert!(().validate(&mut events, &param).is_err());
+
+        assert!(toml::from_str::<Binding>("port = 8443\ntls_min_version = \"1.4\"").is_err());
+    }
+
+    #[test]
+    /// Tests that the private key must match the certificate.
+    fn test_key_matches() {
+        let mut param = Binding::with_security(8443, "./tests/test_cert.pem", "./tests/test_key.pem");
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(param.key_matches().unwrap());
+
+        param.set_security("./tests/test_cert.pem", "./tests/test_leaf_key.pem");
+        assert!(!param.key_matches().unwrap());
+        assert!(param.ssl_acceptor().is_err());
+        match ().validate(&mut events, &param) {
+            Err(Error::KeyMismatch(_, _)) => {},
+            _ => panic!("Should be 'KeyMismatch' error.")
+        }
+
+        param.set_security("./tests/test_cert.pem", "./tests/test_cert.pem");
+        assert!(param.key_matches().is_err());
+        assert!(().validate(&mut events, &param).is_err());
+    }
+
+    #[test]
+    /// Tests the certificate chain given in a separate file.
+    fn test_chain() {
+        let toml = r#"
+        port = 8443
+        cert = "./tests/test_leaf_cert.pem"
+        key = "./tests/test_leaf_key.pem"
+        intermediates = "./tests/test_chain.pem"
+        "#;
+        let mut param = toml::from_str::<Binding>(toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(param.chain(), Some(Path::new("./tests/test_chain.pem")));
+        assert!(param.chain_matches().unwrap());
+        assert!(param.ssl_acceptor().is_ok());
+        assert!(().validate(&mut events, &param).is_ok());
+
+        param.set_chain("./tests/test_cert.pem");
+        assert!(!param.chain_matches().unwrap());
+        assert!(().validate(&mut events, &param).is_err());
+
+        param.clear_security();
+        assert!(param.chain().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "acme")]
+    /// Tests the bindings whose certificate is obtained through ACME.
+    fn test_acme() {
+        use crate::config::acme::Acme;
+
+        let mut param = toml::from_str::<Binding>("port = 443\nacme = true").unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(param.secure());
+        assert!(param.acme());
+        assert!(().validate(&mut events, &param).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        param.set_acme_paths(&Acme::new("admin@example.com", dir.path()), "example.com");
+        assert_eq!(param.cert(), Some(dir.path().join("example.com/cert.pem").as_path()));
+        assert!(().validate(&mut events, &param).is_ok());
+
+        assert!(toml::from_str::<Binding>("port = 443\nacme = true\ncert = \"cert.pem\"").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "self_signed")]
+    /// Tests the bindings using a generated self-signed certificate.
+    fn test_self_signed() {
+        let mut param = toml::from_str::<Binding>("port = 18443\nself_signed = true").unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(param.self_signed());
+        assert!(().validate(&mut events, &param).is_err());
+
+        param.generate_self_signed("localhost").unwrap();
+        assert!(param.cert().unwrap().is_file());
+        assert!(param.key_matches().unwrap());
+        assert!(().validate(&mut events, &param).is_ok());
+        std::fs::remove_dir_all(param.cert().unwrap().parent().unwrap()).unwrap();
+
+        assert!(toml::from_str::<Binding>("port = 18443\nself_signed = true\nkey = \"key.pem\"").is_err());
+    }
+
+    #[test]
+    /// Tests the TLS session resumption settings.
+    fn test_session_resumption() {
+        let toml = r#"
+        port = 8443
+        cert = "./tests/test_cert.pem"
+        key = "./tests/test_key.pem"
+        session_tickets = false
+        session_cache = 1024
+        "#;
+        let mut param = toml::from_str::<Binding>(toml).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(!param.session_tickets());
+        assert_eq!(param.session_cache(), Some(1024));
+        assert!(param.ssl_acceptor().is_ok());
+        assert!(().validate(&mut events, &param).is_ok());
+        assert!(events.is_empty());
+
+        param.set_session_cache(Some(0));
+        assert!(param.ssl_acceptor().is_ok());
+        assert!(().validate(&mut events, &param).is_ok());
+        assert_eq!(events.len(), 1);
+
+        param.set_session_cache(Some(u32::MAX));
+        assert!(().validate(&mut events, &param).is_err());
+
+        let param = Binding::new(8080);
+        assert!(param.session_tickets());
+        assert!(param.session_cache().is_none());
+    }
+
+    #[test]
+    /// Tests the OCSP stapling settings.
+    fn test_ocsp() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let response = OcspResponse::create(OcspResponseStatus::TRY_LATER, None).unwrap();
+        file.write_all(&response.to_der().unwrap()).unwrap();
+
+        let mut param = Binding::with_security(8443, "./tests/test_cert.pem", "./tests/test_key.pem");
+        let mut events: Vec<Event> = Vec::new();
+
+        param.set_ocsp_response(file.path());
+        param.set_ocsp_url("http://ocsp.example.com");
+        assert_eq!(param.ocsp_response(), Some(file.path()));
+        assert!(param.ssl_acceptor().is_ok());
+        assert!(().validate(&mut events, &param).is_ok());
+        assert_eq!(events.len(), 1);
+
+        param.set_ocsp_url("ocsp.example.com");
+        assert!(().validate(&mut events, &param).is_err());
+
+        param.clear_ocsp();
+        param.set_ocsp_response("./tests/test_cert.pem");
+        assert!(param.ssl_acceptor().is_err());
+        assert!(().validate(&mut events, &param).is_err());
+
+        let mut param = Binding::new(8080);
+        param.set_ocsp_url("http://ocsp.example.com");
+        events.clear();
+        assert!(().validate(&mut events, &param).is_ok());
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    /// Tests the warnings about the conventional ports.
+    fn test_validate_well_known_ports() {
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(().validate(&mut events, &Binding::new(8080)).is_ok());
+        assert!(events.is_empty());
+        assert!(().validate(&mut events, &Binding::new(443)).is_ok());
+        assert_eq!(events.len(), 1);
+        assert!(().validate(&mut events, &Binding::with_security(80, "./tests/test_cert.pem", "./tests/test_key.pem")).is_ok());
+        assert_eq!(events.len(), 2);
+        assert!(().validate(&mut events, &Binding::new(0)).is_ok());
+        assert_eq!(events.len(), 3);
+    }
+
     #[test]
     /// Tests the `Validate` trait implementation.
     fn test_validate() {
@@ -0,0 +1,42 @@
+//! The `ProxyRule` structure contains a proxy rule of a host.
+//!
+//! Requests whose path matches the rule are forwarded to the backends of the referenced upstream
+//! group (see `Upstream`).
+//!
+//! # Example
+//! ```toml
+//! [[host.proxy]]
+//! path = "/api/**"
+//! upstream = "api"
+//! ```
+use crate::config::glob::Glob;
+
+/// Structure that defines a proxy rule.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ProxyRule {
+    path: Glob,
+    upstream: String
+}
+
+impl ProxyRule {
+    /// Creates a new `ProxyRule` structure forwarding the paths matching `path` to the upstream
+    /// group named `upstream`.
+    pub fn new(path: Glob, upstream: &str) -> ProxyRule {
+        ProxyRule {
+            path,
+            upstream: upstream.to_owned()
+        }
+    }
+    /// Obtains the path pattern of the rule.
+    pub fn path(&self) -> &Glob {
+        &self.path
+    }
+    /// Obtains the name of the upstream group.
+    pub fn upstream(&self) -> &str {
+        &self.upstream
+    }
+    /// Returns `true` if the rule applies to `path` and `false` otherwise.
+    pub fn matches(&self, path: &str) -> bool {
+        self.path.matches(path)
+    }
+}
@@ -0,0 +1,171 @@
+//! The `RateLimit` structure contains the rate limiting policy of a host.
+//!
+//! Requests are throttled with a token bucket per client: each bucket holds at most `burst`
+//! tokens and is refilled at `requests_per_second` tokens per second; clients are identified by
+//! their IP address or by the value of a request header (e.g. an API key).
+//! The policy is only declared here and enforced by the runtime.
+//!
+//! # Example
+//! ```toml
+//! [host.rate_limit]
+//! requests_per_second = 10.0
+//! burst = 20
+//! key = "header"
+//! header = "X-Api-Key"
+//! ```
+use crate::config::observability::is_valid_header;
+use crate::diagnostics::{Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Describes how the clients of a rate limit are identified.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitKey {
+    /// Clients are identified by their IP address.
+    Ip,
+    /// Clients are identified by the value of the `header` of the rate limit.
+    Header
+}
+
+/// Structure that defines the rate limiting policy of a host.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct RateLimit {
+    requests_per_second: f64,
+    burst: Option<u32>,
+    #[serde(default = "default_key")]
+    key: RateLimitKey,
+    header: Option<String>
+}
+
+#[doc(hidden)]
+fn default_key() -> RateLimitKey { RateLimitKey::Ip }
+
+impl RateLimit {
+    /// Creates a new `RateLimit` structure allowing `requests_per_second` requests per second to
+    /// every IP address.
+    pub fn new(requests_per_second: f64) -> RateLimit {
+        RateLimit {
+            requests_per_second,
+            burst: None,
+            key: default_key(),
+            header: None
+        }
+    }
+    /// Creates a new `RateLimit` structure allowing `requests_per_second` requests per second to
+    /// every value of the request `header`.
+    pub fn by_header(requests_per_second: f64, header: &str) -> RateLimit {
+        RateLimit {
+            key: RateLimitKey::Header,
+            header: Some(header.to_owned()),
+            ..RateLimit::new(requests_per_second)
+        }
+    }
+
+    /// Obtains the number of requests per second allowed to each client.
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+    /// Sets the number of requests per second allowed to each client.
+    pub fn set_requests_per_second(&mut self, requests_per_second: f64) {
+        self.requests_per_second = requests_per_second;
+    }
+    /// Obtains the maximum number of requests a client can perform at once.
+    ///
+    /// If not specified, this is the number of requests per second (rounded up).
+    pub fn burst(&self) -> u32 {
+        self.burst.unwrap_or_else(|| self.requests_per_second.ceil().max(1.0) as u32)
+    }
+    /// Sets the maximum number of requests a client can perform at once.
+    pub fn set_burst(&mut self, burst: u32) {
+        self.burst = Some(burst);
+    }
+    /// Obtains how the clients are identified.
+    pub fn key(&self) -> RateLimitKey {
+        self.key
+    }
+    /// Obtains the header identifying the clients, if the clients are identified by a header.
+    pub fn header(&self) -> Option<&str> {
+        match self.key {
+            RateLimitKey::Header => self.header.as_deref(),
+            RateLimitKey::Ip => None
+        }
+    }
+}
+
+impl Validator<RateLimit> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &RateLimit) -> Result<(), Error> {
+        if item.requests_per_second.is_nan() || item.requests_per_second <= 0.0 || item.requests_per_second.is_infinite() {
+            let desc = format!("Rate limit must allow a positive number of requests per second, found {}.", item.requests_per_second);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("rate_limit.requests_per_second".to_owned()))?;
+        }
+        if item.burst == Some(0) {
+            logger.log(Severity::Error, "Rate limit burst must be greater than zero.");
+            Err(Error::InvalidValue("rate_limit.burst".to_owned()))?;
+        }
+        match (item.key, item.header.as_deref()) {
+            (RateLimitKey::Header, None) => {
+                logger.log(Severity::Error, "Rate limit keyed by header without a 'header'.");
+                Err(Error::InvalidValue("rate_limit.header".to_owned()))?;
+            },
+            (RateLimitKey::Header, Some(header)) => if !is_valid_header(header) {
+                let desc = format!("Invalid rate limit header: '{}'.", header);
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("rate_limit.header".to_owned()))?;
+            },
+            (RateLimitKey::Ip, Some(_)) => {
+                logger.log(Severity::Warning, "Rate limit keyed by IP address specifies a 'header'; ignoring.");
+            },
+            (RateLimitKey::Ip, None) => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::rate_limit::{RateLimit, RateLimitKey};
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests deserialization and defaults.
+    fn test_deserialize() {
+        let limit: RateLimit = toml::from_str(r#"
+        requests_per_second = 10.0
+        burst = 20
+        key = "header"
+        header = "X-Api-Key"
+        "#).unwrap();
+
+        assert_eq!(limit.burst(), 20);
+        assert_eq!(limit.key(), RateLimitKey::Header);
+        assert_eq!(limit.header(), Some("X-Api-Key"));
+
+        let limit: RateLimit = toml::from_str("requests_per_second = 2.5").unwrap();
+        assert_eq!(limit.burst(), 3);
+        assert_eq!(limit.key(), RateLimitKey::Ip);
+        assert!(limit.header().is_none());
+    }
+
+    #[test]
+    /// Tests validation.
+    fn test_validate() {
+        let mut events: Vec<Event> = Vec::new();
+        let mut limit = RateLimit::new(10.0);
+
+        assert!(().validate(&mut events, &limit).is_ok());
+        limit.set_burst(0);
+        assert!(().validate(&mut events, &limit).is_err());
+
+        limit.set_burst(5);
+        limit.set_requests_per_second(0.0);
+        assert!(().validate(&mut events, &limit).is_err());
+
+        assert!(().validate(&mut events, &RateLimit::by_header(1.0, "X-Api-Key")).is_ok());
+        assert!(().validate(&mut events, &RateLimit::by_header(1.0, "Api Key")).is_err());
+        assert!(toml::from_str::<RateLimit>("requests_per_second = 1.0\nkey = \"cookie\"").is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! The `Redirect` structure contains a redirect rule of a host.
+//!
+//! The `from` pattern is a regular expression matched against the requested path; the `to`
+//! target can reference its capture groups (e.g. `$1` or `${name}`), while `$0` is the whole
+//! matched path.
+//!
+//! # Example
+//! ```toml
+//! [[host.redirect]]
+//! from = "^/blog/(.*)$"
+//! to = "https://blog.example.com/$1"
+//! status = 308
+//! ```
+use regex::Regex;
+
+use crate::diagnostics::{Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Status codes allowed for the redirects.
+pub const REDIRECT_STATUS_CODES: &[u16] = &[301, 302, 303, 307, 308];
+
+/// Structure that defines a redirect rule.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Redirect {
+    from: String,
+    to: String,
+    #[serde(default = "default_status")]
+    status: u16
+}
+
+#[doc(hidden)]
+fn default_status() -> u16 { 301 }
+
+impl Redirect {
+    /// Creates a new `Redirect` structure that permanently redirects the paths matching `from` to
+    /// `to`.
+    pub fn new(from: &str, to: &str) -> Redirect {
+        Redirect {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            status: default_status()
+        }
+    }
+    /// Obtains the pattern of the redirected paths.
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+    /// Obtains the target of the redirect.
+    pub fn to(&self) -> &str {
+        &self.to
+    }
+    /// Obtains the status code of the redirect.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+    /// Sets the status code of the redirect.
+    pub fn set_status(&mut self, status: u16) {
+        self.status = status;
+    }
+    /// Returns `true` if the redirect is permanent (i.e. with status 301 or 308) and `false`
+    /// otherwise.
+    pub fn is_permanent(&self) -> bool {
+        self.status == 301 || self.status == 308
+    }
+    /// Compiles the pattern of the redirected paths.
+    pub fn regex(&self) -> Result<Regex, Error> {
+        Regex::new(&self.from)
+            .map_err(|_| Error::InvalidValue("redirect.from".to_owned()))
+    }
+    /// Obtains the target of the redirect for `path`, if the rule applies to it.
+    pub fn target(&self, path: &str) -> Option<String> {
+        let regex = self.regex().ok()?;
+        let captures = regex.captures(path)?;
+        let mut target = String::new();
+        captures.expand(&self.to, &mut target);
+
+        Some(target)
+    }
+}
+
+impl Validator<Redirect> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &Redirect) -> Result<(), Error> {
+        if let Err(err) = item.regex() {
+            let desc = format!("Invalid pattern of redirect '{}'.", item.from);
+            logger.log(Severity::Error, &desc);
+            Err(err)?;
+        }
+        if item.to.is_empty() {
+            let desc = format!("Redirect '{}' has an empty target.", item.from);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("redirect.to".to_owned()))?;
+        }
+        if !REDIRECT_STATUS_CODES.contains(&item.status) {
+            let desc = format!("Redirect '{}' has status {}, which is not a redirect status.", item.from, item.status);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("redirect.status".to_owned()))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::redirect::Redirect;
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests deserialization and the targets of the redirects.
+    fn test_redirect() {
+        let redirect: Redirect = toml::from_str(r#"
+        from = "^/blog/(?P<post>.*)$"
+        to = "https://blog.example.com/${post}"
+        status = 308
+        "#).unwrap();
+
+        assert!(redirect.is_permanent());
+        assert_eq!(redirect.target("/blog/hello").unwrap(), "https://blog.example.com/hello");
+        assert!(redirect.target("/about").is_none());
+
+        let redirect = Redirect::new("^/old$", "/new");
+        assert_eq!(redirect.status(), 301);
+        assert_eq!(redirect.target("/old").unwrap(), "/new");
+    }
+
+    #[test]
+    /// Tests validation.
+    fn test_validate() {
+        let mut events: Vec<Event> = Vec::new();
+        let mut redirect = Redirect::new("^/(.*)$", "https://example.com/$1");
+
+        assert!(().validate(&mut events, &redirect).is_ok());
+        redirect.set_status(200);
+        assert!(().validate(&mut events, &redirect).is_err());
+
+        assert!(().validate(&mut events, &Redirect::new("^/(", "/")).is_err());
+        assert!(().validate(&mut events, &Redirect::new("^/", "")).is_err());
+        assert_eq!(events.len(), 3);
+    }
+}
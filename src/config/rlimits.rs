@@ -0,0 +1,251 @@
+//! The `ResourceLimits` structure contains the resource limits of the server process.
+//!
+//! Limits are applied by the embedding server at startup through `ResourceLimits::apply`, which
+//! raises the soft limit of every configured resource (and its hard limit, if allowed); limits that
+//! the operating system refuses are reported as warnings and do not prevent the startup.
+//! Resource limits are only supported on Unix systems.
+//!
+//! # Example
+//! ```toml
+//! [mammoth.rlimits]
+//! max_open_files = 65536
+//! core_size = 0
+//! address_space = "16GB"
+//! ```
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::config::size::ByteSize;
+use crate::diagnostics::{Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Describes a resource of the process that can be limited.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Resource {
+    /// Maximum number of open file descriptors (`RLIMIT_NOFILE`).
+    OpenFiles,
+    /// Maximum size of the core dumps (`RLIMIT_CORE`).
+    CoreSize,
+    /// Maximum size of the virtual memory of the process (`RLIMIT_AS`).
+    AddressSpace
+}
+
+/// Structure that defines the resource limits of the server process.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct ResourceLimits {
+    max_open_files: Option<u64>,
+    core_size: Option<ByteSize>,
+    address_space: Option<ByteSize>
+}
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Resource::OpenFiles => write!(f, "max_open_files"),
+            Resource::CoreSize => write!(f, "core_size"),
+            Resource::AddressSpace => write!(f, "address_space")
+        }
+    }
+}
+
+impl Resource {
+    /// Obtains the current soft and hard limits of the resource, where `None` means unlimited.
+    ///
+    /// Returns `None` if the limits cannot be obtained (e.g. on non-Unix systems).
+    pub fn current(self) -> Option<(Option<u64>, Option<u64>)> {
+        sys::get(self).ok()
+    }
+}
+
+impl ResourceLimits {
+    /// Creates a new `ResourceLimits` structure that leaves every limit unchanged.
+    pub fn new() -> ResourceLimits {
+        ResourceLimits::default()
+    }
+
+    /// Obtains the maximum number of open files, if any.
+    pub fn max_open_files(&self) -> Option<u64> {
+        self.max_open_files
+    }
+    /// Sets the maximum number of open files.
+    pub fn set_max_open_files(&mut self, files: u64) {
+        self.max_open_files = Some(files);
+    }
+    /// Obtains the maximum size of the core dumps, if any.
+    pub fn core_size(&self) -> Option<ByteSize> {
+        self.core_size
+    }
+    /// Sets the maximum size of the core dumps; a size of zero disables core dumps.
+    pub fn set_core_size<S>(&mut self, size: S)
+        where
+            S: Into<ByteSize>
+    {
+        self.core_size = Some(size.into());
+    }
+    /// Obtains the maximum size of the address space, if any.
+    pub fn address_space(&self) -> Option<ByteSize> {
+        self.address_space
+    }
+    /// Sets the maximum size of the address space.
+    pub fn set_address_space<S>(&mut self, size: S)
+        where
+            S: Into<ByteSize>
+    {
+        self.address_space = Some(size.into());
+    }
+    /// Obtains the configured limits.
+    pub fn limits(&self) -> Vec<(Resource, u64)> {
+        let mut limits = Vec::new();
+        if let Some(files) = self.max_open_files { limits.push((Resource::OpenFiles, files)); }
+        if let Some(size) = self.core_size { limits.push((Resource::CoreSize, size.bytes())); }
+        if let Some(size) = self.address_space { limits.push((Resource::AddressSpace, size.bytes())); }
+        limits
+    }
+    /// Applies the configured limits to the current process.
+    ///
+    /// Limits refused by the operating system are logged as warnings.
+    pub fn apply(&self, logger: &mut dyn Logger) {
+        for (resource, value) in self.limits() {
+            if let Err(err) = sys::set(resource, value) {
+                let desc = format!("Could not set resource limit '{}' to {}: {}.", resource, value, err);
+                logger.log(Severity::Warning, &desc);
+            }
+        }
+    }
+}
+
+impl Validator<ResourceLimits> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &ResourceLimits) -> Result<(), Error> {
+        if item.max_open_files == Some(0) {
+            logger.log(Severity::Error, "Resource limit 'max_open_files' must be greater than zero.");
+            Err(Error::InvalidValue("rlimits.max_open_files".to_owned()))?;
+        }
+
+        for (resource, value) in item.limits() {
+            match resource.current() {
+                Some((_, Some(hard))) if value > hard => {
+                    let desc = format!("Resource limit '{}' ({}) exceeds the hard limit ({}); it can be applied only with elevated privileges.", resource, value, hard);
+                    logger.log(Severity::Warning, &desc);
+                },
+                None => {
+                    let desc = format!("Resource limit '{}' is not supported on this system; ignoring.", resource);
+                    logger.log(Severity::Warning, &desc);
+                },
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+#[doc(hidden)]
+mod sys {
+    use std::io;
+
+    use super::Resource;
+
+    fn code(resource: Resource) -> libc::c_int {
+        (match resource {
+            Resource::OpenFiles => libc::RLIMIT_NOFILE,
+            Resource::CoreSize => libc::RLIMIT_CORE,
+            Resource::AddressSpace => libc::RLIMIT_AS
+        }) as libc::c_int
+    }
+
+    // `rlim_t` is not 64 bits wide on every platform.
+    #[allow(clippy::useless_conversion)]
+    fn from_rlim(value: libc::rlim_t) -> Option<u64> {
+        if value == libc::RLIM_INFINITY { None } else { Some(u64::from(value)) }
+    }
+
+    fn get_raw(resource: Resource) -> io::Result<libc::rlimit> {
+        let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        // SAFETY: `limit` is a valid, writable `rlimit` structure.
+        if unsafe { libc::getrlimit(code(resource) as _, &mut limit) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(limit)
+    }
+
+    pub fn get(resource: Resource) -> io::Result<(Option<u64>, Option<u64>)> {
+        let limit = get_raw(resource)?;
+        Ok((from_rlim(limit.rlim_cur), from_rlim(limit.rlim_max)))
+    }
+
+    pub fn set(resource: Resource, value: u64) -> io::Result<()> {
+        let mut limit = get_raw(resource)?;
+        let value = value as libc::rlim_t;
+        limit.rlim_cur = value;
+        if limit.rlim_max != libc::RLIM_INFINITY && limit.rlim_max < value {
+            limit.rlim_max = value;
+        }
+        // SAFETY: `limit` is a valid `rlimit` structure.
+        if unsafe { libc::setrlimit(code(resource) as _, &limit) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+#[doc(hidden)]
+mod sys {
+    use std::io;
+
+    use super::Resource;
+
+    pub fn get(_: Resource) -> io::Result<(Option<u64>, Option<u64>)> {
+        Err(io::Error::new(io::ErrorKind::Other, "resource limits are not supported"))
+    }
+
+    pub fn set(_: Resource, _: u64) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "resource limits are not supported"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::rlimits::{Resource, ResourceLimits};
+    use crate::config::size::ByteSize;
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests deserialization and validation.
+    fn test_rlimits() {
+        let limits: ResourceLimits = toml::from_str(r#"
+        max_open_files = 1024
+        core_size = 0
+        address_space = "16GB"
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(limits.max_open_files(), Some(1024));
+        assert_eq!(limits.core_size(), Some(ByteSize::new(0)));
+        assert_eq!(limits.limits().len(), 3);
+        assert!(().validate(&mut events, &limits).is_ok());
+
+        let mut limits = ResourceLimits::new();
+        limits.set_max_open_files(0);
+        assert!(().validate(&mut events, &limits).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    /// Tests that limits above the hard limit produce a warning.
+    fn test_hard_limit() {
+        let mut events: Vec<Event> = Vec::new();
+        let hard = match Resource::OpenFiles.current().unwrap() {
+            (_, Some(hard)) => hard,
+            (_, None) => return
+        };
+
+        let mut limits = ResourceLimits::new();
+        limits.set_max_open_files(hard + 1);
+        assert!(().validate(&mut events, &limits).is_ok());
+        assert_eq!(events.len(), 1);
+    }
+}
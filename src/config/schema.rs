@@ -0,0 +1,321 @@
+//! The `ConfigSchema` structure describes the `config` table accepted by a module.
+//!
+//! A module may provide its schema (see `abi::ModuleVTable::config_schema`), so that the
+//! configuration written by the user is checked during the validation, before the module is
+//! constructed: unknown keys, missing required keys and values of the wrong type are reported
+//! together, rather than as the first deserialization error of the constructor.
+//!
+//! Schemas are serialized as TOML, e.g.
+//! ```toml
+//! [fields.realm]
+//! type = "string"
+//! required = true
+//! description = "Name of the protected area."
+//!
+//! [fields.users]
+//! type = "table"
+//!
+//! [fields.users.schema]
+//! allow_unknown = true
+//! ```
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use toml::Value;
+
+use crate::diagnostics::{Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Type of the value of a configuration key.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldKind {
+    /// Any value.
+    Any,
+    /// An array of values.
+    Array,
+    /// A boolean.
+    Boolean,
+    /// A date and time.
+    Datetime,
+    /// A floating-point number; integers are accepted as well.
+    Float,
+    /// An integer.
+    Integer,
+    /// A string.
+    String,
+    /// A table, optionally described by its own schema.
+    Table
+}
+
+impl FieldKind {
+    /// Returns `true` if `value` is of this type.
+    pub fn matches(self, value: &Value) -> bool {
+        matches!((self, value),
+            (FieldKind::Any, _)
+            | (FieldKind::Array, Value::Array(_))
+            | (FieldKind::Boolean, Value::Boolean(_))
+            | (FieldKind::Datetime, Value::Datetime(_))
+            | (FieldKind::Float, Value::Float(_))
+            | (FieldKind::Float, Value::Integer(_))
+            | (FieldKind::Integer, Value::Integer(_))
+            | (FieldKind::String, Value::String(_))
+            | (FieldKind::Table, Value::Table(_)))
+    }
+}
+
+impl Display for FieldKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match self {
+            FieldKind::Any => "any",
+            FieldKind::Array => "array",
+            FieldKind::Boolean => "boolean",
+            FieldKind::Datetime => "datetime",
+            FieldKind::Float => "float",
+            FieldKind::Integer => "integer",
+            FieldKind::String => "string",
+            FieldKind::Table => "table"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Structure that describes a key of the configuration.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct FieldSchema {
+    #[serde(rename = "type")]
+    kind: FieldKind,
+    #[serde(default)]
+    required: bool,
+    description: Option<String>,
+    schema: Option<ConfigSchema>
+}
+
+impl FieldSchema {
+    /// Creates a new, optional `FieldSchema` structure given the type of the value.
+    pub fn new(kind: FieldKind) -> FieldSchema {
+        FieldSchema {
+            kind,
+            required: false,
+            description: None,
+            schema: None
+        }
+    }
+    /// Creates a new, required `FieldSchema` structure given the type of the value.
+    pub fn required(kind: FieldKind) -> FieldSchema {
+        FieldSchema {
+            required: true,
+            ..FieldSchema::new(kind)
+        }
+    }
+    /// Creates a new, optional `FieldSchema` structure describing a table through `schema`.
+    pub fn table(schema: ConfigSchema) -> FieldSchema {
+        FieldSchema {
+            schema: Some(schema),
+            ..FieldSchema::new(FieldKind::Table)
+        }
+    }
+    /// Sets the description of the key.
+    pub fn with_description(mut self, description: &str) -> FieldSchema {
+        self.description = Some(description.to_owned());
+        self
+    }
+    /// Makes the key required.
+    pub fn with_required(mut self) -> FieldSchema {
+        self.required = true;
+        self
+    }
+
+    /// Obtains the type of the value.
+    pub fn kind(&self) -> FieldKind {
+        self.kind
+    }
+    /// Returns `true` if the key must be specified.
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+    /// Obtains the description of the key, if any.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+    /// Obtains the schema of the table, if any.
+    pub fn schema(&self) -> Option<&ConfigSchema> {
+        self.schema.as_ref()
+    }
+}
+
+/// Structure that describes the keys of a configuration table.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct ConfigSchema {
+    #[serde(default)]
+    allow_unknown: bool,
+    #[serde(default)]
+    fields: BTreeMap<String, FieldSchema>
+}
+
+impl ConfigSchema {
+    /// Creates a new `ConfigSchema` structure without keys, refusing unknown keys.
+    pub fn new() -> ConfigSchema {
+        ConfigSchema {
+            allow_unknown: false,
+            fields: BTreeMap::new()
+        }
+    }
+    /// Adds the key `name`, described by `field`.
+    pub fn field(mut self, name: &str, field: FieldSchema) -> ConfigSchema {
+        self.fields.insert(name.to_owned(), field);
+        self
+    }
+    /// Accepts keys that are not described by the schema.
+    pub fn with_unknown_keys(mut self) -> ConfigSchema {
+        self.allow_unknown = true;
+        self
+    }
+
+    /// Obtains the description of the key `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&FieldSchema> {
+        self.fields.get(name)
+    }
+    /// Obtains the described keys, in alphabetical order.
+    pub fn keys(&self) -> Vec<&str> {
+        self.fields.keys().map(String::as_str).collect()
+    }
+    /// Returns `true` if keys that are not described by the schema are accepted.
+    pub fn allows_unknown_keys(&self) -> bool {
+        self.allow_unknown
+    }
+
+    /// Checks `config` against the schema, returning every violation found; a missing
+    /// configuration is checked as an empty table.
+    pub fn check(&self, config: Option<&Value>) -> Vec<SchemaViolation> {
+        let empty = Value::Table(Default::default());
+        let mut violations = Vec::new();
+        self.check_value("config", config.unwrap_or(&empty), &mut violations);
+        violations
+    }
+
+    fn check_value(&self, path: &str, value: &Value, violations: &mut Vec<SchemaViolation>) {
+        let table = match value.as_table() {
+            Some(table) => table,
+            None => {
+                violations.push(SchemaViolation::WrongType(path.to_owned(), FieldKind::Table));
+                return;
+            }
+        };
+
+        for (name, field) in self.fields.iter() {
+            let key = format!("{}.{}", path, name);
+            match table.get(name) {
+                Some(value) if !field.kind.matches(value) => violations.push(SchemaViolation::WrongType(key, field.kind)),
+                Some(value) => if let Some(ref schema) = field.schema {
+                    schema.check_value(&key, value, violations);
+                },
+                None if field.required => violations.push(SchemaViolation::MissingKey(key)),
+                None => {}
+            }
+        }
+        if !self.allow_unknown {
+            for name in table.keys().filter(|name| !self.fields.contains_key(name.as_str())) {
+                violations.push(SchemaViolation::UnknownKey(format!("{}.{}", path, name)));
+            }
+        }
+    }
+}
+
+/// Describes a mismatch between a configuration and its schema, given the path of the key (e.g.
+/// `config.users.admin`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaViolation {
+    /// A required key is missing.
+    MissingKey(String),
+    /// A key is not described by the schema.
+    UnknownKey(String),
+    /// The value of a key is not of the expected type.
+    WrongType(String, FieldKind)
+}
+
+impl Display for SchemaViolation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            SchemaViolation::MissingKey(key) => write!(f, "missing required key '{}'", key),
+            SchemaViolation::UnknownKey(key) => write!(f, "unknown key '{}'", key),
+            SchemaViolation::WrongType(key, kind) => write!(f, "key '{}' must be of type {}", key, kind)
+        }
+    }
+}
+
+/// Validator that checks the configuration of the module `0` against its schema.
+pub struct SchemaValidator<'a>(pub &'a str, pub &'a ConfigSchema);
+
+impl<'a> Validator<Option<&'a Value>> for SchemaValidator<'a> {
+    fn validate(&self, logger: &mut dyn Logger, item: &Option<&'a Value>) -> Result<(), Error> {
+        let SchemaValidator(name, schema) = *self;
+        let violations = schema.check(*item);
+        for violation in violations.iter() {
+            let desc = format!("Invalid configuration of module '{}': {}.", name, violation);
+            logger.log(Severity::Error, &desc);
+        }
+        if !violations.is_empty() {
+            Err(Error::InvalidValue("[[mod]].config".to_owned()))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use toml::Value;
+
+    use crate::config::schema::{ConfigSchema, FieldKind, FieldSchema, SchemaValidator, SchemaViolation};
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+
+    fn schema() -> ConfigSchema {
+        ConfigSchema::new()
+            .field("realm", FieldSchema::required(FieldKind::String).with_description("Name of the protected area."))
+            .field("timeout", FieldSchema::new(FieldKind::Float))
+            .field("users", FieldSchema::table(ConfigSchema::new()
+                .field("admin", FieldSchema::required(FieldKind::Boolean))))
+    }
+
+    #[test]
+    /// Tests checking configurations against a schema.
+    fn test_check() {
+        let schema = schema();
+        let valid: Value = toml::from_str("realm = \"private\"\ntimeout = 30\nusers = { admin = true }").unwrap();
+        assert!(schema.check(Some(&valid)).is_empty());
+
+        let invalid: Value = toml::from_str("timeout = \"30s\"\nport = 8080\nusers = { guest = false }").unwrap();
+        assert_eq!(schema.check(Some(&invalid)), vec![
+            SchemaViolation::MissingKey("config.realm".to_owned()),
+            SchemaViolation::WrongType("config.timeout".to_owned(), FieldKind::Float),
+            SchemaViolation::MissingKey("config.users.admin".to_owned()),
+            SchemaViolation::UnknownKey("config.users.guest".to_owned()),
+            SchemaViolation::UnknownKey("config.port".to_owned())
+        ]);
+        assert_eq!(schema.check(None), vec![SchemaViolation::MissingKey("config.realm".to_owned())]);
+        assert_eq!(schema.check(Some(&Value::from(42))), vec![SchemaViolation::WrongType("config".to_owned(), FieldKind::Table)]);
+        assert!(ConfigSchema::new().with_unknown_keys().check(Some(&invalid)).is_empty());
+
+        let mut events: Vec<Event> = Vec::new();
+        assert!(SchemaValidator("mod_auth", &schema).validate(&mut events, &Some(&valid)).is_ok());
+        assert!(SchemaValidator("mod_auth", &schema).validate(&mut events, &Some(&invalid)).is_err());
+        assert_eq!(events.len(), 5);
+    }
+
+    #[test]
+    /// Tests the serialization of a schema.
+    fn test_serialization() {
+        let schema = schema();
+        let serialized = toml::to_string(&schema).unwrap();
+
+        assert_eq!(toml::from_str::<ConfigSchema>(&serialized).unwrap(), schema);
+        let parsed: ConfigSchema = toml::from_str("[fields.realm]\ntype = \"string\"\nrequired = true").unwrap();
+        assert_eq!(parsed.keys(), vec!["realm"]);
+        assert!(parsed.get("realm").unwrap().is_required());
+        assert!(!parsed.allows_unknown_keys());
+    }
+}
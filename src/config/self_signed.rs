@@ -0,0 +1,167 @@
+//! Generation of throwaway self-signed certificates for local development; only available with the
+//! `self_signed` feature.
+//!
+//! Bindings declared with `self_signed = true` do not specify `cert` and `key`: parsing the
+//! configuration does not touch the filesystem, and a new certificate for the hostname of the host
+//! (or `localhost`) is generated when the runtime calls `Binding::generate_self_signed` (see also
+//! `ConfigurationFile::generate_self_signed`), stored in a newly created temporary directory
+//! accessible only by its owner.
+//! Browsers do not trust these certificates: they must never be used in production.
+//!
+//! # Example
+//! ```toml
+//! [[host]]
+//! hostname = "localhost"
+//! listen = { port = 8443, self_signed = true }
+//! ```
+use std::fs;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use openssl::asn1::{Asn1Integer, Asn1Time};
+use openssl::bn::{BigNum, MsbOption};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::x509::{X509, X509NameBuilder};
+use openssl::x509::extension::{BasicConstraints, ExtendedKeyUsage, SubjectAlternativeName};
+
+use crate::error::Error;
+
+/// Number of days the generated certificates are valid for.
+pub const SELF_SIGNED_DAYS: u32 = 30;
+
+/// Generates a self-signed certificate for `hostname` along with its private key.
+///
+/// If `hostname` is an IP address, the certificate is issued for that address.
+pub fn generate(hostname: &str) -> Result<(X509, PKey<Private>), Error> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "Mammoth development certificate")?;
+    name.append_entry_by_nid(Nid::COMMONNAME, hostname)?;
+    let name = name.build();
+
+    let mut serial = BigNum::new()?;
+    serial.rand(127, MsbOption::MAYBE_ZERO, false)?;
+    let serial = Asn1Integer::from_bn(&serial)?;
+    let not_before = Asn1Time::days_from_now(0)?;
+    let not_after = Asn1Time::days_from_now(SELF_SIGNED_DAYS)?;
+
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(&serial)?;
+    builder.set_subject_name(&name)?;
+    builder.set_issuer_name(&name)?;
+    builder.set_pubkey(&key)?;
+    builder.set_not_before(&not_before)?;
+    builder.set_not_after(&not_after)?;
+    builder.append_extension(BasicConstraints::new().critical().build()?)?;
+    builder.append_extension(ExtendedKeyUsage::new().server_auth().build()?)?;
+    let mut alt_name = SubjectAlternativeName::new();
+    if hostname.parse::<IpAddr>().is_ok() {
+        alt_name.ip(hostname);
+    } else {
+        alt_name.dns(hostname);
+    }
+    let alt_name = alt_name.build(&builder.x509v3_context(None, None))?;
+    builder.append_extension(alt_name)?;
+    builder.sign(&key, MessageDigest::sha256())?;
+
+    Ok((builder.build(), key))
+}
+
+/// Creates a new directory, accessible only by its owner, in the temporary directory of the system.
+///
+/// The directory is not removed automatically, since the certificate stored into it must outlive
+/// the configuration.
+pub fn directory() -> Result<PathBuf, Error> {
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("mammoth-self-signed-");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o700));
+    }
+
+    Ok(builder.tempdir()?.keep())
+}
+
+/// Generates a self-signed certificate for `hostname` and stores it along with its key into `dir`,
+/// which must not already contain them; returns the paths of the certificate and of the key.
+pub fn write<P>(dir: P, hostname: &str) -> Result<(PathBuf, PathBuf), Error>
+    where
+        P: AsRef<Path>
+{
+    let dir = dir.as_ref();
+    let (cert, key) = generate(hostname)?;
+    let cert_path = dir.join("cert.pem");
+    let key_path = dir.join("key.pem");
+
+    private_file(&key_path)?.write_all(&key.private_key_to_pem_pkcs8()?)?;
+    private_file(&cert_path)?.write_all(&cert.to_pem()?)?;
+
+    Ok((cert_path, key_path))
+}
+
+/// Creates a new file readable only by its owner, failing if `path` already exists (even as a
+/// symbolic link).
+fn private_file(path: &Path) -> Result<fs::File, Error> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    Ok(options.open(path)?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::self_signed::{directory, generate, write};
+
+    #[test]
+    /// Tests the generation of a certificate.
+    fn test_generate() {
+        let (cert, key) = generate("localhost").unwrap();
+
+        assert!(cert.public_key().unwrap().public_eq(&key));
+        assert!(cert.verify(&key).unwrap());
+        assert!(format!("{:?}", cert.subject_alt_names().unwrap()[0].dnsname()).contains("localhost"));
+
+        let (cert, _) = generate("127.0.0.1").unwrap();
+        assert_eq!(cert.subject_alt_names().unwrap()[0].ipaddress(), Some(&[127, 0, 0, 1][..]));
+    }
+
+    #[test]
+    /// Tests that the certificate and the key are written, without overwriting existing files.
+    fn test_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, key) = write(dir.path(), "localhost").unwrap();
+
+        assert!(cert.is_file());
+        assert!(key.is_file());
+        assert!(write(dir.path(), "localhost").is_err());
+    }
+
+    #[test]
+    /// Tests that every certificate is stored in a new private directory.
+    fn test_directory() {
+        let first = directory().unwrap();
+        let second = directory().unwrap();
+
+        assert_ne!(first, second);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(first.metadata().unwrap().permissions().mode() & 0o777, 0o700);
+        }
+        std::fs::remove_dir(first).unwrap();
+        std::fs::remove_dir(second).unwrap();
+    }
+}
@@ -0,0 +1,234 @@
+//! The `ConfigurationSet` structure contains a configuration built from a base file and a list of
+//! overlays (e.g. profiles), applied in order.
+//!
+//! Every overlay takes precedence over the base file and over the previous overlays; the structure
+//! remembers, for every key, which file provided its final value, so that complex layouts can be
+//! debugged with `ConfigurationSet::why`.
+//!
+//! Tables are merged key by key, arrays of tables (such as `[[host]]`) are merged element by
+//! element, and any other value is replaced as a whole.
+//!
+//! Keys are written as in `host[0].static_dir` or `mammoth.log_file`.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use toml::Value;
+
+use crate::config::ConfigurationFile;
+use crate::error::Error;
+
+/// Structure that contains a layered configuration and the provenance of each of its keys.
+#[derive(Clone, Debug)]
+pub struct ConfigurationSet {
+    configuration: ConfigurationFile,
+    files: Vec<PathBuf>,
+    provenance: BTreeMap<String, usize>
+}
+
+impl ConfigurationSet {
+    /// Creates a `ConfigurationSet` structure given the `base` TOML file and the list of
+    /// `overlays`, in increasing order of precedence.
+    pub fn load<P, Q>(base: P, overlays: &[Q]) -> Result<ConfigurationSet, Error>
+        where
+            P: AsRef<Path>,
+            Q: AsRef<Path>
+    {
+        let mut files = vec![base.as_ref().to_path_buf()];
+        files.extend(overlays.iter().map(|p| p.as_ref().to_path_buf()));
+
+        let mut merged = Value::Table(Default::default());
+        let mut provenance = BTreeMap::new();
+
+        for (index, path) in files.iter().enumerate() {
+            let mut file = File::open(path)?;
+            let mut contents = String::new();
+            file.read_to_string(&mut contents)?;
+
+            let layer: Value = toml::from_str(&contents)?;
+            merge(&mut merged, layer, "", index, &mut provenance);
+        }
+
+        let configuration = ConfigurationFile::from_value(merged)?;
+
+        Ok(ConfigurationSet {
+            configuration,
+            files,
+            provenance
+        })
+    }
+    /// Obtains the resulting configuration.
+    pub fn configuration(&self) -> &ConfigurationFile {
+        &self.configuration
+    }
+    /// Transforms the current structure into the resulting configuration.
+    pub fn into_configuration(self) -> ConfigurationFile {
+        self.configuration
+    }
+    /// Obtains the list of the loaded files, in increasing order of precedence.
+    pub fn files(&self) -> Vec<&Path> {
+        self.files.iter().map(|p| p.as_path()).collect()
+    }
+    /// Obtains the file that provided the final value of `key`, if the key exists.
+    ///
+    /// For tables and arrays, the file is the one with the highest precedence among those that
+    /// contributed to their content.
+    pub fn why(&self, key: &str) -> Option<&Path> {
+        self.provenance.get(key).map(|&index| self.files[index].as_path())
+    }
+    /// Obtains the list of all the keys along with the file that provided their final value.
+    pub fn explain(&self) -> Vec<(&str, &Path)> {
+        self.provenance.iter()
+            .map(|(key, &index)| (key.as_str(), self.files[index].as_path()))
+            .collect()
+    }
+}
+
+/// Obtains the key of the child `name` of the `parent` key.
+fn child_key(parent: &str, name: &str) -> String {
+    if parent.is_empty() { name.to_owned() } else { format!("{}.{}", parent, name) }
+}
+
+/// Records `index` as the provenance of `key` and of all its children.
+fn record(value: &Value, key: &str, index: usize, provenance: &mut BTreeMap<String, usize>) {
+    if !key.is_empty() {
+        provenance.insert(key.to_owned(), index);
+    }
+    match value {
+        Value::Table(table) => for (name, child) in table.iter() {
+            record(child, &child_key(key, name), index, provenance);
+        },
+        Value::Array(array) if is_table_array(array) => for (i, child) in array.iter().enumerate() {
+            record(child, &format!("{}[{}]", key, i), index, provenance);
+        },
+        _ => {}
+    }
+}
+
+/// Returns `true` if the array is an array of tables and `false` otherwise.
+fn is_table_array(array: &[Value]) -> bool {
+    !array.is_empty() && array.iter().all(|v| v.is_table())
+}
+
+/// Merges `layer` into `base`, recording the provenance of the merged keys.
+fn merge(base: &mut Value, layer: Value, key: &str, index: usize, provenance: &mut BTreeMap<String, usize>) {
+    match (base, layer) {
+        (Value::Table(base), Value::Table(layer)) => {
+            if !key.is_empty() {
+                provenance.insert(key.to_owned(), index);
+            }
+            for (name, value) in layer {
+                let child = child_key(key, &name);
+                match base.get_mut(&name) {
+                    Some(existing) => merge(existing, value, &child, index, provenance),
+                    None => {
+                        record(&value, &child, index, provenance);
+                        base.insert(name, value);
+                    }
+                }
+            }
+        },
+        (Value::Array(base), Value::Array(layer)) if is_table_array(base) && is_table_array(&layer) => {
+            provenance.insert(key.to_owned(), index);
+            for (i, value) in layer.into_iter().enumerate() {
+                let child = format!("{}[{}]", key, i);
+                if i < base.len() {
+                    merge(&mut base[i], value, &child, index, provenance);
+                } else {
+                    record(&value, &child, index, provenance);
+                    base.push(value);
+                }
+            }
+        },
+        (base, layer) => {
+            provenance.retain(|k, _| !(k.starts_with(key) && k[key.len()..].starts_with(['.', '['])));
+            record(&layer, key, index, provenance);
+            *base = layer;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::config::set::ConfigurationSet;
+
+    #[test]
+    /// Tests layering of a base file with two overlays.
+    fn test_layering() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.toml");
+        let production = dir.path().join("production.toml");
+        let local = dir.path().join("local.toml");
+
+        fs::write(&base, r#"
+        [mammoth]
+        log_file = "mammoth.log"
+
+        [[host]]
+        listen = 8080
+        static_dir = "./www/"
+
+        [[host]]
+        listen = 8088
+        "#).unwrap();
+        fs::write(&production, r#"
+        [mammoth]
+        log_severity = "error"
+
+        [[host]]
+        static_dir = "/srv/www/"
+        "#).unwrap();
+        fs::write(&local, r#"
+        [mammoth]
+        log_file = "local.log"
+        "#).unwrap();
+
+        let set = ConfigurationSet::load(&base, &[&production, &local]).unwrap();
+        let configuration = set.configuration();
+
+        assert_eq!(set.files().len(), 3);
+        assert_eq!(configuration.hosts().len(), 2);
+        assert_eq!(configuration.hosts()[0].serving_dir().unwrap(), Path::new("/srv/www/"));
+        assert_eq!(configuration.hosts()[0].binding().port(), 8080);
+        assert_eq!(configuration.mammoth().log_file().unwrap(), Path::new("local.log"));
+
+        assert_eq!(set.why("host[0].static_dir").unwrap(), production.as_path());
+        assert_eq!(set.why("host[0].listen").unwrap(), base.as_path());
+        assert_eq!(set.why("host[1].listen").unwrap(), base.as_path());
+        assert_eq!(set.why("mammoth.log_file").unwrap(), local.as_path());
+        assert_eq!(set.why("mammoth.log_severity").unwrap(), production.as_path());
+        assert_eq!(set.why("mammoth").unwrap(), local.as_path());
+        assert!(set.why("host[2]").is_none());
+        assert!(set.why("mammoth.mods_dir").is_none());
+    }
+
+    #[test]
+    /// Tests that an overlay that replaces a table with a value also replaces the provenance of
+    /// the children of the table.
+    fn test_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.toml");
+        let overlay = dir.path().join("overlay.toml");
+
+        fs::write(&base, r#"
+        [mammoth]
+
+        [[host]]
+        listen = { port = 8080 }
+        "#).unwrap();
+        fs::write(&overlay, r#"
+        [[host]]
+        listen = 8088
+        "#).unwrap();
+
+        let set = ConfigurationSet::load(&base, &[&overlay]).unwrap();
+
+        assert_eq!(set.configuration().hosts()[0].binding().port(), 8088);
+        assert_eq!(set.why("host[0].listen").unwrap(), overlay.as_path());
+        assert!(set.why("host[0].listen.port").is_none());
+    }
+}
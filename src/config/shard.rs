@@ -0,0 +1,101 @@
+//! The `ShardPlan` structure describes how the listener of a port is sharded.
+//!
+//! A binding can be served by several listener sockets bound to the same port with
+//! `SO_REUSEPORT`, each one accepting connections on its own worker; workers can optionally be
+//! pinned to specific CPU cores.
+//! This crate does not create the sockets nor spawn the workers: the plan only collects the
+//! tuning knobs of the configuration so that embedders can apply them.
+//!
+//! # Example
+//! ```toml
+//! [[host]]
+//! listen = { port = 8080, shards = 4, pin_to_cores = [0, 1] }
+//! ```
+//! produces four listeners on port 8080, whose workers are pinned to cores 0, 1, 0 and 1.
+
+/// Structure that describes a single listener socket of a `ShardPlan`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ListenerShard {
+    index: usize,
+    core: Option<usize>
+}
+
+/// Structure that describes the listener sockets of a port.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShardPlan {
+    port: u16,
+    listeners: Vec<ListenerShard>
+}
+
+impl ListenerShard {
+    /// Obtains the index of the listener within its plan.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// Obtains the CPU core the worker of the listener should be pinned to, if any.
+    pub fn core(&self) -> Option<usize> {
+        self.core
+    }
+}
+
+impl ShardPlan {
+    /// Creates a new `ShardPlan` structure for `shards` listeners on `port`, assigning the `cores`
+    /// to the listeners in a round-robin fashion.
+    pub fn new(port: u16, shards: usize, cores: &[usize]) -> ShardPlan {
+        let listeners = (0..shards)
+            .map(|index| ListenerShard {
+                index,
+                core: if cores.is_empty() { None } else { Some(cores[index % cores.len()]) }
+            })
+            .collect();
+
+        ShardPlan {
+            port,
+            listeners
+        }
+    }
+
+    /// Obtains the port of the listeners.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+    /// Obtains a vector of references to the listeners.
+    pub fn listeners(&self) -> Vec<&ListenerShard> {
+        self.listeners.iter().collect()
+    }
+    /// Returns `true` if the listeners must be bound with `SO_REUSEPORT` (i.e. if there is more
+    /// than one listener) and `false` otherwise.
+    pub fn reuse_port(&self) -> bool {
+        self.listeners.len() > 1
+    }
+    /// Obtains the distinct cores used by the plan, in order of first use.
+    pub fn cores(&self) -> Vec<usize> {
+        let mut cores = Vec::new();
+        for core in self.listeners.iter().filter_map(|l| l.core) {
+            if !cores.contains(&core) { cores.push(core); }
+        }
+        cores
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::shard::ShardPlan;
+
+    #[test]
+    /// Tests the assignment of the cores to the listeners.
+    fn test_plan() {
+        let plan = ShardPlan::new(8080, 4, &[0, 1]);
+        let cores: Vec<Option<usize>> = plan.listeners().iter().map(|l| l.core()).collect();
+
+        assert_eq!(plan.port(), 8080);
+        assert!(plan.reuse_port());
+        assert_eq!(cores, vec![Some(0), Some(1), Some(0), Some(1)]);
+        assert_eq!(plan.cores(), vec![0, 1]);
+
+        let plan = ShardPlan::new(80, 1, &[]);
+        assert!(!plan.reuse_port());
+        assert_eq!(plan.listeners()[0].core(), None);
+        assert!(plan.cores().is_empty());
+    }
+}
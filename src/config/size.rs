@@ -0,0 +1,157 @@
+//! The `ByteSize` structure contains a human-readable amount of bytes, such as `"10MB"`.
+//!
+//! Sizes can be written either as a plain integer (number of bytes) or as a string composed by a
+//! number followed by an optional unit; units are case-insensitive and use powers of 1024, i.e.
+//! `"1KB"`, `"1K"` and `"1KiB"` all describe 1024 bytes.
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::de::{Deserialize, Deserializer, Error, Unexpected, Visitor};
+
+const UNITS: [(&str, u64); 13] = [
+    ("b", 1),
+    ("k", 1 << 10),
+    ("kb", 1 << 10),
+    ("kib", 1 << 10),
+    ("m", 1 << 20),
+    ("mb", 1 << 20),
+    ("mib", 1 << 20),
+    ("g", 1 << 30),
+    ("gb", 1 << 30),
+    ("gib", 1 << 30),
+    ("t", 1 << 40),
+    ("tb", 1 << 40),
+    ("tib", 1 << 40)
+];
+
+/// Structure that defines an amount of bytes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ByteSize(u64);
+
+impl ByteSize {
+    /// Creates a new `ByteSize` structure given the number of bytes.
+    pub fn new(bytes: u64) -> ByteSize {
+        ByteSize(bytes)
+    }
+    /// Obtains the number of bytes.
+    pub fn bytes(self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ByteSize, String> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let unit = unit.trim().to_lowercase();
+
+        let number: u64 = number.parse().map_err(|_| format!("invalid size: '{}'", s))?;
+        let multiplier = if unit.is_empty() {
+            1
+        } else {
+            UNITS.iter()
+                .find(|(name, _)| *name == unit)
+                .map(|(_, multiplier)| *multiplier)
+                .ok_or_else(|| format!("invalid size unit: '{}'", unit))?
+        };
+
+        number.checked_mul(multiplier)
+            .map(ByteSize)
+            .ok_or_else(|| format!("size too big: '{}'", s))
+    }
+}
+
+impl Display for ByteSize {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}B", self.0)
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        ByteSize(bytes)
+    }
+}
+
+/// Visitor for `ByteSize` deserialization, from either an integer or a string.
+struct ByteSizeVisitor;
+
+impl<'de> Visitor<'de> for ByteSizeVisitor {
+    type Value = ByteSize;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, r#"a non-negative number of bytes or a size string such as "10MB""#)
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<ByteSize, E> where
+        E: Error {
+        if v < 0 {
+            Err(Error::invalid_value(Unexpected::Signed(v), &self))
+        } else {
+            Ok(ByteSize(v as u64))
+        }
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<ByteSize, E> where
+        E: Error {
+        Ok(ByteSize(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<ByteSize, E> where
+        E: Error {
+        ByteSize::from_str(v).map_err(|_| Error::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+        D: Deserializer<'de> {
+        deserializer.deserialize_any(ByteSizeVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use super::ByteSize;
+
+    #[test]
+    /// Tests parsing of size strings.
+    fn test_from_str() {
+        assert_eq!(ByteSize::from_str("512").unwrap().bytes(), 512);
+        assert_eq!(ByteSize::from_str("512B").unwrap().bytes(), 512);
+        assert_eq!(ByteSize::from_str("8k").unwrap().bytes(), 8 * 1024);
+        assert_eq!(ByteSize::from_str("8KiB").unwrap().bytes(), 8 * 1024);
+        assert_eq!(ByteSize::from_str("10MB").unwrap().bytes(), 10 * 1024 * 1024);
+        assert_eq!(ByteSize::from_str("10 mb").unwrap().bytes(), 10 * 1024 * 1024);
+        assert_eq!(ByteSize::from_str("2G").unwrap().bytes(), 2 * 1024 * 1024 * 1024);
+
+        assert!(ByteSize::from_str("").is_err());
+        assert!(ByteSize::from_str("MB").is_err());
+        assert!(ByteSize::from_str("10XB").is_err());
+        assert!(ByteSize::from_str("-10MB").is_err());
+        assert!(ByteSize::from_str("99999999999TB").is_err());
+    }
+
+    #[test]
+    /// Tests deserialization from both integers and strings.
+    fn test_deserialize() {
+        let toml = r#"
+        a = 1024
+        b = "1KB"
+        "#;
+        let map = toml::from_str::<BTreeMap<String, ByteSize>>(toml).unwrap();
+
+        assert_eq!(map.get("a").unwrap(), &ByteSize::new(1024));
+        assert_eq!(map.get("b").unwrap(), &ByteSize::new(1024));
+
+        assert!(toml::from_str::<BTreeMap<String, ByteSize>>(r#"a = -1"#).is_err());
+        assert!(toml::from_str::<BTreeMap<String, ByteSize>>(r#"a = "lots""#).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! The `SocketOptions` structure contains the options of the listener sockets of a binding.
+//!
+//! Like the `ShardPlan`, the options are only collected here so that embedders can apply them
+//! to the sockets they create.
+//!
+//! # Example
+//! ```toml
+//! [[host]]
+//! listen = { port = 8080, socket = { reuse_port = true, nodelay = true, keepalive = { idle = 60, interval = 10, retries = 5 } } }
+//! ```
+use crate::diagnostics::{Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Structure that defines the options of the listener sockets of a binding.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SocketOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reuse_port: Option<bool>,
+    #[serde(default = "default_nodelay")]
+    nodelay: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keepalive: Option<Keepalive>
+}
+
+/// Structure that defines the TCP keepalive settings of the accepted connections.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Keepalive {
+    idle: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interval: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retries: Option<u32>
+}
+
+#[doc(hidden)]
+fn default_nodelay() -> bool { false }
+
+impl SocketOptions {
+    /// Creates a new `SocketOptions` structure with the default options of the system.
+    pub fn new() -> SocketOptions {
+        SocketOptions::default()
+    }
+
+    /// Obtains whether the sockets are bound with `SO_REUSEPORT`, if specified.
+    ///
+    /// If not specified, `SO_REUSEPORT` is used only by sharded bindings (see
+    /// `Binding::reuse_port`).
+    pub fn reuse_port(&self) -> Option<bool> {
+        self.reuse_port
+    }
+    /// Sets whether the sockets are bound with `SO_REUSEPORT`.
+    pub fn set_reuse_port(&mut self, reuse_port: bool) {
+        self.reuse_port = Some(reuse_port);
+    }
+    /// Returns `true` if the accepted connections use `TCP_NODELAY`, i.e. disable Nagle's algorithm.
+    pub fn nodelay(&self) -> bool {
+        self.nodelay
+    }
+    /// Sets whether the accepted connections use `TCP_NODELAY`.
+    pub fn set_nodelay(&mut self, nodelay: bool) {
+        self.nodelay = nodelay;
+    }
+    /// Obtains the TCP keepalive settings, if any.
+    pub fn keepalive(&self) -> Option<&Keepalive> {
+        self.keepalive.as_ref()
+    }
+    /// Enables TCP keepalive with the given settings.
+    pub fn set_keepalive(&mut self, keepalive: Keepalive) {
+        self.keepalive = Some(keepalive);
+    }
+    /// Disables TCP keepalive.
+    pub fn clear_keepalive(&mut self) {
+        self.keepalive = None;
+    }
+}
+
+impl Keepalive {
+    /// Creates a new `Keepalive` structure sending the first probe after `idle` seconds of
+    /// inactivity, leaving the interval and the number of probes to the system.
+    pub fn new(idle: u64) -> Keepalive {
+        Keepalive {
+            idle,
+            interval: None,
+            retries: None
+        }
+    }
+
+    /// Obtains the inactivity time after which the first probe is sent, in seconds
+    /// (`TCP_KEEPIDLE`).
+    pub fn idle(&self) -> u64 {
+        self.idle
+    }
+    /// Obtains the interval between two probes, in seconds, if specified (`TCP_KEEPINTVL`).
+    pub fn interval(&self) -> Option<u64> {
+        self.interval
+    }
+    /// Sets the interval between two probes, in seconds.
+    pub fn set_interval(&mut self, seconds: u64) {
+        self.interval = Some(seconds);
+    }
+    /// Obtains the number of unanswered probes after which the connection is dropped, if specified
+    /// (`TCP_KEEPCNT`).
+    pub fn retries(&self) -> Option<u32> {
+        self.retries
+    }
+    /// Sets the number of unanswered probes after which the connection is dropped.
+    pub fn set_retries(&mut self, retries: u32) {
+        self.retries = Some(retries);
+    }
+}
+
+impl Validator<SocketOptions> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &SocketOptions) -> Result<(), Error> {
+        if cfg!(not(unix)) && item.reuse_port == Some(true) {
+            logger.log(Severity::Warning, "SO_REUSEPORT is not supported on this system; ignoring 'reuse_port'.");
+        }
+        if let Some(keepalive) = item.keepalive() {
+            if keepalive.idle == 0 {
+                logger.log(Severity::Error, "Keepalive idle time must be greater than zero.");
+                Err(Error::InvalidValue("socket.keepalive.idle".to_owned()))?;
+            }
+            if keepalive.interval == Some(0) {
+                logger.log(Severity::Error, "Keepalive interval must be greater than zero.");
+                Err(Error::InvalidValue("socket.keepalive.interval".to_owned()))?;
+            }
+            if keepalive.retries == Some(0) {
+                logger.log(Severity::Error, "Keepalive retries must be greater than zero.");
+                Err(Error::InvalidValue("socket.keepalive.retries".to_owned()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::socket::{Keepalive, SocketOptions};
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests deserialization and validation of the socket options.
+    fn test_socket_options() {
+        let mut options: SocketOptions = toml::from_str(r#"
+        reuse_port = true
+        nodelay = true
+        keepalive = { idle = 60, interval = 10, retries = 5 }
+        "#).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert_eq!(options.reuse_port(), Some(true));
+        assert!(options.nodelay());
+        assert_eq!(options.keepalive().unwrap().idle(), 60);
+        assert_eq!(options.keepalive().unwrap().interval(), Some(10));
+        assert_eq!(options.keepalive().unwrap().retries(), Some(5));
+        assert!(().validate(&mut events, &options).is_ok());
+
+        options.set_keepalive(Keepalive::new(0));
+        assert!(().validate(&mut events, &options).is_err());
+
+        let options: SocketOptions = toml::from_str("").unwrap();
+        assert_eq!(options, SocketOptions::new());
+        assert!(options.reuse_port().is_none());
+        assert!(!options.nodelay());
+    }
+}
@@ -0,0 +1,235 @@
+//! The `StatusPage` structure contains the settings of the status page of a host.
+//!
+//! The status page is a static HTML file, rendered by the runtime from a `StatusReport` into the
+//! serving directory of the host every `refresh` seconds, so that a human-readable status is
+//! available without any module.
+//!
+//! Templates are HTML files containing placeholders: `{{refresh}}` is replaced by the refresh
+//! interval, `{{entries}}` by a table row for each entry of the report and `{{<name>}}` by the value
+//! of the entry `<name>`; values are HTML-escaped.
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::diagnostics::{Logger, PathValidator, PathValidatorKind, Validator};
+use crate::diagnostics::report::StatusReport;
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Template used when no template file is given.
+pub const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta http-equiv="refresh" content="{{refresh}}">
+<title>Status</title>
+</head>
+<body>
+<table>
+{{entries}}
+</table>
+</body>
+</html>
+"#;
+
+/// Structure that defines the status page of a host.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct StatusPage {
+    #[serde(default = "default_path")]
+    path: PathBuf,
+    template: Option<PathBuf>,
+    #[serde(default = "default_refresh")]
+    refresh: u64
+}
+
+#[doc(hidden)]
+fn default_path() -> PathBuf { PathBuf::from("status.html") }
+#[doc(hidden)]
+fn default_refresh() -> u64 { 30 }
+
+impl StatusPage {
+    /// Creates a new `StatusPage` structure rendered into `status.html` every 30 seconds using the
+    /// default template.
+    pub fn new() -> StatusPage {
+        StatusPage {
+            path: default_path(),
+            template: None,
+            refresh: default_refresh()
+        }
+    }
+
+    /// Obtains the path of the page, relative to the serving directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Sets the path of the page, relative to the serving directory.
+    pub fn set_path<P>(&mut self, path: P)
+        where
+            P: AsRef<Path>
+    {
+        self.path = path.as_ref().to_path_buf();
+    }
+    /// Obtains the path of the template file, if any.
+    pub fn template(&self) -> Option<&Path> {
+        self.template.as_deref()
+    }
+    /// Sets the path of the template file.
+    pub fn set_template<P>(&mut self, path: P)
+        where
+            P: AsRef<Path>
+    {
+        self.template = Some(path.as_ref().to_path_buf());
+    }
+    /// Uses the default template.
+    pub fn clear_template(&mut self) {
+        self.template = None;
+    }
+    /// Obtains the refresh interval, in seconds.
+    pub fn refresh(&self) -> u64 {
+        self.refresh
+    }
+    /// Sets the refresh interval, in seconds.
+    pub fn set_refresh(&mut self, seconds: u64) {
+        self.refresh = seconds;
+    }
+    /// Obtains the path of the rendered file given the serving directory `static_dir`.
+    pub fn output<P>(&self, static_dir: P) -> PathBuf
+        where
+            P: AsRef<Path>
+    {
+        static_dir.as_ref().join(&self.path)
+    }
+    /// Renders the page from the `report`.
+    pub fn render(&self, report: &StatusReport) -> Result<String, Error> {
+        let mut page = match self.template {
+            Some(ref template) => fs::read_to_string(template)?,
+            None => DEFAULT_TEMPLATE.to_owned()
+        };
+
+        let rows: Vec<String> = report.entries().iter()
+            .map(|(name, value)| format!("<tr><th>{}</th><td>{}</td></tr>", escape(name), escape(value)))
+            .collect();
+        page = page.replace("{{refresh}}", &self.refresh.to_string())
+            .replace("{{entries}}", &rows.join("\n"));
+        for (name, value) in report.entries() {
+            page = page.replace(&format!("{{{{{}}}}}", name), &escape(value));
+        }
+
+        Ok(page)
+    }
+    /// Renders the page from the `report` into the serving directory `static_dir`.
+    ///
+    /// The page is written into a temporary file which is then renamed, so that clients never
+    /// read a partially written page.
+    pub fn write<P>(&self, static_dir: P, report: &StatusReport) -> Result<(), Error>
+        where
+            P: AsRef<Path>
+    {
+        let output = self.output(static_dir);
+        let mut temporary = output.clone().into_os_string();
+        temporary.push(".tmp");
+
+        fs::write(&temporary, self.render(report)?)?;
+        fs::rename(&temporary, &output)?;
+
+        Ok(())
+    }
+}
+
+impl Default for StatusPage {
+    fn default() -> Self {
+        StatusPage::new()
+    }
+}
+
+/// Escapes the HTML special characters of `text`.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Validates a `StatusPage` structure, where the validator is the serving directory of the host.
+impl Validator<StatusPage> for PathBuf {
+    fn validate(&self, logger: &mut dyn Logger, item: &StatusPage) -> Result<(), Error> {
+        let escapes = item.path.components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+        if escapes || item.path.file_name().is_none() {
+            let desc = format!("Status page '{}' must be a file name relative to the static directory.", item.path.display());
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidFilePath(item.path.clone()))?;
+        }
+        if let Some(template) = item.template() {
+            PathValidator(Severity::Error, PathValidatorKind::ExistingFile)
+                .validate(logger, &template)?;
+        }
+        if item.refresh == 0 {
+            logger.log(Severity::Error, "Refresh interval of the status page must be greater than zero.");
+            Err(Error::InvalidValue("status_page.refresh".to_owned()))?;
+        }
+
+        let output = item.output(self);
+        let directory = output.parent().unwrap_or(self);
+        PathValidator(Severity::Error, PathValidatorKind::WritableDirectory)
+            .validate(logger, &directory)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use crate::config::status::StatusPage;
+    use crate::diagnostics::Validator;
+    use crate::diagnostics::report::StatusReport;
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests rendering with the default template and with a template file.
+    fn test_render() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut page: StatusPage = toml::from_str("refresh = 10").unwrap();
+        let mut report = StatusReport::new();
+        report.insert("healthy", true);
+        report.insert("modules", "<none>");
+
+        assert_eq!(page.path(), Path::new("status.html"));
+        let html = page.render(&report).unwrap();
+        assert!(html.contains(r#"content="10""#));
+        assert!(html.contains("<tr><th>modules</th><td>&lt;none&gt;</td></tr>"));
+
+        let template = dir.path().join("template.html");
+        fs::write(&template, "healthy: {{healthy}}").unwrap();
+        page.set_template(&template);
+        assert_eq!(page.render(&report).unwrap(), "healthy: true");
+
+        page.write(dir.path(), &report).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("status.html")).unwrap(), "healthy: true");
+    }
+
+    #[test]
+    /// Tests validation.
+    fn test_validate() {
+        let dir = tempfile::tempdir().unwrap();
+        let static_dir = dir.path().to_path_buf();
+        let mut events: Vec<Event> = Vec::new();
+        let mut page = StatusPage::new();
+
+        assert!(static_dir.validate(&mut events, &page).is_ok());
+        assert!(PathBuf::from("./i_do_not_exist/").validate(&mut events, &page).is_err());
+
+        page.set_path("../status.html");
+        assert!(static_dir.validate(&mut events, &page).is_err());
+
+        let mut page = StatusPage::new();
+        page.set_refresh(0);
+        assert!(static_dir.validate(&mut events, &page).is_err());
+
+        let mut page = StatusPage::new();
+        page.set_template("./i_do_not_exist.html");
+        assert!(static_dir.validate(&mut events, &page).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! The `TlsProfile` structure contains TLS settings shared by several bindings.
+//!
+//! Profiles are defined once in the `[tls.<name>]` sections and referenced by the bindings through
+//! `tls = "<name>"`, so that many hosts can share, e.g., a wildcard certificate. Protocol settings
+//! written in the binding take precedence over the ones of the profile.
+//!
+//! # Example
+//! ```toml
+//! [tls.wildcard]
+//! cert = "/etc/ssl/certs/wildcard.pem"
+//! key = "/etc/ssl/private/wildcard.key"
+//! tls_min_version = "1.2"
+//!
+//! [[host]]
+//! hostname = "www.example.com"
+//! listen = { port = 443, tls = "wildcard" }
+//! ```
+use std::convert::TryFrom;
+use std::path::Path;
+
+use crate::config::path::{ConfigPath, ConfigPathValidator};
+use crate::config::port::TlsVersion;
+use crate::diagnostics::{Logger, PathValidatorKind, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Structure that defines a named set of TLS settings.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TlsProfile {
+    cert: ConfigPath,
+    key: ConfigPath,
+    #[serde(alias = "intermediates")]
+    chain: Option<ConfigPath>,
+    tls_min_version: Option<TlsVersion>,
+    tls_max_version: Option<TlsVersion>,
+    session_tickets: Option<bool>,
+    session_cache: Option<u32>
+}
+
+impl TlsProfile {
+    /// Creates a new `TlsProfile` structure given a path to a certificate and a path to the
+    /// relative key.
+    pub fn new<P, Q>(cert: P, key: Q) -> TlsProfile
+        where
+            P: AsRef<Path>,
+            Q: AsRef<Path>
+    {
+        TlsProfile {
+            cert: ConfigPath::explicit(cert),
+            key: ConfigPath::explicit(key),
+            chain: None,
+            tls_min_version: None,
+            tls_max_version: None,
+            session_tickets: None,
+            session_cache: None
+        }
+    }
+
+    /// Obtains the path of the certificate.
+    pub fn cert(&self) -> &ConfigPath {
+        &self.cert
+    }
+    /// Obtains the path of the private key.
+    pub fn key(&self) -> &ConfigPath {
+        &self.key
+    }
+    /// Obtains the path of the intermediate certificates, if any.
+    pub fn chain(&self) -> Option<&ConfigPath> {
+        self.chain.as_ref()
+    }
+    /// Sets the path of the intermediate certificates.
+    pub fn set_chain<P>(&mut self, chain: P)
+        where
+            P: AsRef<Path>
+    {
+        self.chain = Some(ConfigPath::explicit(chain));
+    }
+    /// Obtains the minimum TLS version, if specified.
+    pub fn tls_min_version(&self) -> Option<TlsVersion> {
+        self.tls_min_version
+    }
+    /// Sets the minimum TLS version.
+    pub fn set_tls_min_version(&mut self, version: Option<TlsVersion>) {
+        self.tls_min_version = version;
+    }
+    /// Obtains the maximum TLS version, if specified.
+    pub fn tls_max_version(&self) -> Option<TlsVersion> {
+        self.tls_max_version
+    }
+    /// Sets the maximum TLS version.
+    pub fn set_tls_max_version(&mut self, version: Option<TlsVersion>) {
+        self.tls_max_version = version;
+    }
+    /// Obtains whether session tickets are enabled, if specified.
+    pub fn session_tickets(&self) -> Option<bool> {
+        self.session_tickets
+    }
+    /// Sets whether session tickets are enabled.
+    pub fn set_session_tickets(&mut self, enabled: Option<bool>) {
+        self.session_tickets = enabled;
+    }
+    /// Obtains the size of the server-side session cache, if specified.
+    pub fn session_cache(&self) -> Option<u32> {
+        self.session_cache
+    }
+    /// Sets the size of the server-side session cache; zero disables the cache.
+    pub fn set_session_cache(&mut self, size: Option<u32>) {
+        self.session_cache = size;
+    }
+}
+
+impl Validator<TlsProfile> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &TlsProfile) -> Result<(), Error> {
+        ConfigPathValidator(Severity::Critical, PathValidatorKind::ExistingFile, "[tls].cert")
+            .validate(logger, item.cert())?;
+        ConfigPathValidator(Severity::Critical, PathValidatorKind::ExistingFile, "[tls].key")
+            .validate(logger, item.key())?;
+        if let Some(chain) = item.chain() {
+            ConfigPathValidator(Severity::Critical, PathValidatorKind::ExistingFile, "[tls].chain")
+                .validate(logger, chain)?;
+        }
+        if let (Some(min), Some(max)) = (item.tls_min_version(), item.tls_max_version()) {
+            if min > max {
+                let desc = format!("Minimum TLS version of the profile ({}) is greater than the maximum one ({}).", min, max);
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("tls.tls_min_version".to_owned()))?;
+            }
+        }
+        if item.session_cache().is_some_and(|size| i32::try_from(size).is_err()) {
+            let desc = format!("Session cache of the profile cannot hold more than {} sessions.", i32::MAX);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("tls.session_cache".to_owned()))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+//! The `Upstream` structure contains the configuration of a group of backends.
+//!
+//! Upstream groups are defined in the top-level `[[upstream]]` tables and referenced by name from
+//! the proxy rules of the hosts (see `ProxyRule`); the runtime balances the proxied requests among
+//! the backends of the group according to its `Strategy`.
+//!
+//! # Example
+//! ```toml
+//! [[upstream]]
+//! name = "api"
+//! backends = ["10.0.0.1:8000", "api-2.internal:8000"]
+//! strategy = "least_conn"
+//! health_check = 10
+//! ```
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+
+use regex::Regex;
+
+use crate::config::host::{REGEX_IP_ADDRESS_STRING, REGEX_NAME_ADDRESS_STRING};
+use crate::diagnostics::{Id, Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Describes how requests are distributed among the backends of an upstream group.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// Backends are used in turn.
+    RoundRobin,
+    /// Requests are sent to the backend with the fewest active connections.
+    LeastConn
+}
+
+/// Structure that defines a group of backends.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Upstream {
+    name: String,
+    backends: Vec<String>,
+    #[serde(default = "default_strategy")]
+    strategy: Strategy,
+    health_check: Option<u64>
+}
+
+#[doc(hidden)]
+fn default_strategy() -> Strategy { Strategy::RoundRobin }
+
+impl Display for Strategy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Strategy::RoundRobin => write!(f, "round_robin"),
+            Strategy::LeastConn => write!(f, "least_conn")
+        }
+    }
+}
+
+impl Upstream {
+    /// Creates a new `Upstream` structure given its `name` and `backends`, balanced in turn and
+    /// without health checks.
+    pub fn new(name: &str, backends: &[&str]) -> Upstream {
+        Upstream {
+            name: name.to_owned(),
+            backends: backends.iter().map(|b| (*b).to_owned()).collect(),
+            strategy: default_strategy(),
+            health_check: None
+        }
+    }
+
+    /// Obtains the name of the group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Obtains the addresses of the backends.
+    pub fn backends(&self) -> Vec<&str> {
+        self.backends.iter().map(|b| b.as_str()).collect()
+    }
+    /// Adds a backend given its address.
+    pub fn add_backend(&mut self, address: &str) {
+        self.backends.push(address.to_owned());
+    }
+    /// Removes a backend given its address.
+    pub fn remove_backend(&mut self, address: &str) {
+        self.backends.retain(|b| b != address);
+    }
+    /// Obtains the load balancing strategy.
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+    /// Sets the load balancing strategy.
+    pub fn set_strategy(&mut self, strategy: Strategy) {
+        self.strategy = strategy;
+    }
+    /// Obtains the interval between two health checks of the backends, in seconds, if any.
+    pub fn health_check(&self) -> Option<u64> {
+        self.health_check
+    }
+    /// Sets the interval between two health checks of the backends, in seconds.
+    pub fn set_health_check(&mut self, seconds: u64) {
+        self.health_check = Some(seconds);
+    }
+    /// Disables the health checks of the backends.
+    pub fn clear_health_check(&mut self) {
+        self.health_check = None;
+    }
+}
+
+impl Id for Upstream {
+    type Identifier = String;
+
+    fn id(&self) -> Self::Identifier {
+        self.name.to_owned()
+    }
+}
+
+/// Returns `true` if `address` is a valid backend address (i.e. `host:port`) and `false`
+/// otherwise.
+fn is_backend_address(address: &str) -> bool {
+    lazy_static! {
+        static ref RE_IP: Regex = Regex::new(REGEX_IP_ADDRESS_STRING).unwrap();
+        static ref RE_ADDR: Regex = Regex::new(REGEX_NAME_ADDRESS_STRING).unwrap();
+    }
+
+    if address.parse::<SocketAddr>().is_ok() { return true; }
+
+    match address.rsplit_once(':') {
+        Some((host, port)) => (RE_IP.is_match(host) || RE_ADDR.is_match(host))
+            && port.parse::<u16>().map(|p| p != 0).unwrap_or(false),
+        None => false
+    }
+}
+
+impl Validator<Upstream> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &Upstream) -> Result<(), Error> {
+        if item.backends.is_empty() {
+            let desc = format!("Upstream '{}' has no backends.", item.name);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("upstream.backends".to_owned()))?;
+        }
+        for backend in item.backends.iter() {
+            if !is_backend_address(backend) {
+                let desc = format!("Invalid address of backend '{}' in upstream '{}'.", backend, item.name);
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue("upstream.backends".to_owned()))?;
+            }
+        }
+        if item.health_check == Some(0) {
+            let desc = format!("Health check interval of upstream '{}' must be greater than zero.", item.name);
+            logger.log(Severity::Error, &desc);
+            Err(Error::InvalidValue("upstream.health_check".to_owned()))?;
+        }
+        if item.health_check.is_none() && item.backends.len() > 1 {
+            let desc = format!("Upstream '{}' has no health checks; failed backends will keep receiving requests.", item.name);
+            logger.log(Severity::Information, &desc);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::upstream::{Strategy, Upstream};
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests deserialization.
+    fn test_upstream() {
+        let upstream: Upstream = toml::from_str(r#"
+        name = "api"
+        backends = ["10.0.0.1:8000", "api-2.internal:8000"]
+        strategy = "least_conn"
+        health_check = 10
+        "#).unwrap();
+
+        assert_eq!(upstream.name(), "api");
+        assert_eq!(upstream.backends().len(), 2);
+        assert_eq!(upstream.strategy(), Strategy::LeastConn);
+        assert_eq!(upstream.health_check(), Some(10));
+
+        let upstream: Upstream = toml::from_str(r#"name = "api"
+        backends = ["[::1]:8000"]"#).unwrap();
+        assert_eq!(upstream.strategy(), Strategy::RoundRobin);
+        assert!(toml::from_str::<Upstream>(r#"name = "api"
+        backends = []
+        strategy = "random""#).is_err());
+    }
+
+    #[test]
+    /// Tests validation of the backends.
+    fn test_validate() {
+        let mut events: Vec<Event> = Vec::new();
+        let mut upstream = Upstream::new("api", &["127.0.0.1:8000", "[::1]:8000", "localhost:8000"]);
+        upstream.set_health_check(5);
+
+        assert!(().validate(&mut events, &upstream).is_ok());
+        assert!(events.is_empty());
+
+        upstream.add_backend("localhost");
+        assert!(().validate(&mut events, &upstream).is_err());
+        upstream.remove_backend("localhost");
+        upstream.add_backend("local_host:80");
+        assert!(().validate(&mut events, &upstream).is_err());
+
+        assert!(().validate(&mut events, &Upstream::new("api", &[])).is_err());
+    }
+}
@@ -2,15 +2,35 @@
 //!
 //! This module provides the main traits and structures for both validation and log file writing.
 
+pub mod console;
+pub mod counting;
+#[cfg(all(windows, feature = "windows"))]
+pub mod eventlog;
+#[cfg(all(unix, feature = "journald"))]
+pub mod journald;
+pub mod origin;
+pub mod report;
+pub mod symbolicate;
+pub mod syslog;
+
 use std::any::Any;
-use std::fs::File;
-use std::io::Write;
+use std::ffi::OsString;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufWriter, Write};
 use std::marker::PhantomData;
-use std::path::Path;
-use std::sync::{Arc, RwLock};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use regex::Regex;
 
+use crate::config::mammoth::{LogBuffer, LogRotation};
+use crate::diagnostics::origin::OriginFilter;
 use crate::error::Error;
-use crate::error::event::Event;
+use crate::error::event::{Event, CODE_FIELD};
 use crate::error::severity::Severity;
 
 /// Same to `Arc<RwLock<Logger>>`.
@@ -76,12 +96,250 @@ pub trait Logger: Any + Send + Sync {
     /// track of the events that have `Severity` greater than or equal to `Warning`, every
     /// information of kind `Debug` or `Information` may be omitted.
     fn log(&mut self, _: Severity, _: &str);
+    /// Stores a particular information about the execution, along with its severity and some
+    /// structured fields as key-value pairs.
+    ///
+    /// The default behavior is to append the fields to the description (see `format_fields`);
+    /// loggers able to store structured data should override it.
+    fn log_kv(&mut self, sev: Severity, desc: &str, fields: &[(&str, &str)]) {
+        self.log(sev, &format_fields(desc, fields));
+    }
+    /// Stores a particular information caused by `error`, along with its severity and the code of
+    /// the error (see `Error::code`) as the structured field `code`.
+    fn log_error(&mut self, sev: Severity, desc: &str, error: &Error) {
+        self.log_kv(sev, desc, &[(CODE_FIELD, error.code())]);
+    }
+    /// Writes the information buffered by the logger, if any, into its final destination.
+    ///
+    /// The default behavior is to do nothing; loggers buffering information should override it.
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+    /// Flushes the logger and releases its destination; information stored afterwards may be
+    /// discarded.
+    ///
+    /// The default behavior is to flush the logger (see `flush`).
+    fn close(&mut self) -> Result<(), Error> {
+        self.flush()
+    }
+}
+
+/// Appends `fields` to `desc` as space-separated `key=value` pairs, quoting the values that are
+/// empty or contain whitespace or quotes.
+pub fn format_fields(desc: &str, fields: &[(&str, &str)]) -> String {
+    let mut result = desc.to_owned();
+    for (key, value) in fields {
+        if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"') {
+            result.push_str(&format!(" {}={:?}", key, value));
+        } else {
+            result.push_str(&format!(" {}={}", key, value));
+        }
+    }
+
+    result
 }
 
 impl Logger for Vec<Event> {
     fn log(&mut self, sev: Severity, desc: &str) {
         self.push(Event::new(sev, desc));
     }
+
+    fn log_kv(&mut self, sev: Severity, desc: &str, fields: &[(&str, &str)]) {
+        self.push(Event::with_fields(sev, desc, fields));
+    }
+}
+
+/// Logger that forwards every event to each of its children, e.g. to write the same information
+/// into a file, the standard output and an in-memory buffer at once.
+///
+/// Each child has its own minimum severity and its own `OriginFilter`: events with a lower severity
+/// or not selected by the filter are not forwarded to it.
+pub struct CompositeLogger {
+    children: Vec<CompositeChild>
+}
+
+/// Child of a `CompositeLogger`, along with the events it receives.
+struct CompositeChild {
+    severity: Severity,
+    origin: OriginFilter,
+    logger: Box<dyn Logger>
+}
+
+impl CompositeChild {
+    /// Returns `true` if the child receives the event and `false` otherwise.
+    fn accepts(&self, severity: Severity, fields: &[(&str, &str)]) -> bool {
+        severity >= self.severity && self.origin.matches(fields)
+    }
+}
+
+impl CompositeLogger {
+    /// Creates a new, empty `CompositeLogger` structure.
+    pub fn new() -> CompositeLogger {
+        CompositeLogger {
+            children: Vec::new()
+        }
+    }
+    /// Adds a child logger receiving every event.
+    pub fn push<L>(&mut self, logger: L)
+        where
+            L: Logger
+    {
+        self.push_filtered(Severity::Debug, logger);
+    }
+    /// Adds a child logger receiving only the events with at least the given `severity`.
+    pub fn push_filtered<L>(&mut self, severity: Severity, logger: L)
+        where
+            L: Logger
+    {
+        self.push_matching(severity, OriginFilter::new(), logger);
+    }
+    /// Adds a child logger receiving only the events with at least the given `severity` and
+    /// selected by `origin`.
+    pub fn push_matching<L>(&mut self, severity: Severity, origin: OriginFilter, logger: L)
+        where
+            L: Logger
+    {
+        self.push_boxed(severity, origin, Box::new(logger));
+    }
+    /// Adds a boxed child logger receiving only the events with at least the given `severity` and
+    /// selected by `origin`.
+    pub fn push_boxed(&mut self, severity: Severity, origin: OriginFilter, logger: Box<dyn Logger>) {
+        self.children.push(CompositeChild {
+            severity,
+            origin,
+            logger
+        });
+    }
+    /// Adds a child logger receiving every event, returning the composite logger.
+    pub fn with<L>(mut self, logger: L) -> CompositeLogger
+        where
+            L: Logger
+    {
+        self.push(logger);
+        self
+    }
+    /// Adds a child logger receiving only the events with at least the given `severity`, returning
+    /// the composite logger.
+    pub fn with_filtered<L>(mut self, severity: Severity, logger: L) -> CompositeLogger
+        where
+            L: Logger
+    {
+        self.push_filtered(severity, logger);
+        self
+    }
+    /// Adds a child logger receiving only the events with at least the given `severity` and
+    /// selected by `origin`, returning the composite logger.
+    pub fn with_matching<L>(mut self, severity: Severity, origin: OriginFilter, logger: L) -> CompositeLogger
+        where
+            L: Logger
+    {
+        self.push_matching(severity, origin, logger);
+        self
+    }
+    /// Obtains the number of child loggers.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+    /// Returns `true` if the logger has no children and `false` otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+    /// Obtains the lowest severity forwarded to at least one child, if any.
+    pub fn severity(&self) -> Option<Severity> {
+        self.children.iter().map(|child| child.severity).min()
+    }
+}
+
+impl Default for CompositeLogger {
+    fn default() -> CompositeLogger {
+        CompositeLogger::new()
+    }
+}
+
+impl From<Vec<Box<dyn Logger>>> for CompositeLogger {
+    fn from(loggers: Vec<Box<dyn Logger>>) -> CompositeLogger {
+        let mut composite = CompositeLogger::new();
+        for logger in loggers {
+            composite.push_boxed(Severity::Debug, OriginFilter::new(), logger);
+        }
+
+        composite
+    }
+}
+
+impl From<Vec<(Severity, Box<dyn Logger>)>> for CompositeLogger {
+    fn from(loggers: Vec<(Severity, Box<dyn Logger>)>) -> CompositeLogger {
+        let mut composite = CompositeLogger::new();
+        for (severity, logger) in loggers {
+            composite.push_boxed(severity, OriginFilter::new(), logger);
+        }
+
+        composite
+    }
+}
+
+impl Logger for CompositeLogger {
+    fn log(&mut self, sev: Severity, desc: &str) {
+        for child in self.children.iter_mut() {
+            if child.accepts(sev, &[]) {
+                child.logger.log(sev, desc);
+            }
+        }
+    }
+
+    fn log_kv(&mut self, sev: Severity, desc: &str, fields: &[(&str, &str)]) {
+        for child in self.children.iter_mut() {
+            if child.accepts(sev, fields) {
+                child.logger.log_kv(sev, desc, fields);
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+        for child in self.children.iter_mut() {
+            result = result.and(child.logger.flush());
+        }
+        result
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+        for child in self.children.iter_mut() {
+            result = result.and(child.logger.close());
+        }
+        result
+    }
+}
+
+impl Logger for Vec<Box<dyn Logger>> {
+    fn log(&mut self, sev: Severity, desc: &str) {
+        for logger in self.iter_mut() {
+            logger.log(sev, desc);
+        }
+    }
+
+    fn log_kv(&mut self, sev: Severity, desc: &str, fields: &[(&str, &str)]) {
+        for logger in self.iter_mut() {
+            logger.log_kv(sev, desc, fields);
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+        for logger in self.iter_mut() {
+            result = result.and(logger.flush());
+        }
+        result
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+        for logger in self.iter_mut() {
+            result = result.and(logger.close());
+        }
+        result
+    }
 }
 
 /// Can produce information about the execution.
@@ -107,6 +365,24 @@ pub trait Log
             alr.log(sev, desc);
         }
     }
+    /// Stores some information, along with some structured fields as key-value pairs, in the
+    /// previously stored logger (see `Logger::log_kv`).
+    fn log_kv(&self, sev: Severity, desc: &str, fields: &[(&str, &str)]) {
+        if let Some(logger) = self.retrieve_logger() {
+            let mut alr = logger.write().unwrap();
+
+            alr.log_kv(sev, desc, fields);
+        }
+    }
+    /// Flushes the previously stored logger, if any (see `Logger::flush`).
+    fn flush_logger(&self) -> Result<(), Error> {
+        if let Some(logger) = self.retrieve_logger() {
+            let mut alr = logger.write().unwrap();
+
+            alr.flush()?;
+        }
+        Ok(())
+    }
 }
 
 /// Validates a structure.
@@ -126,6 +402,36 @@ impl<T> Validator<T> for Fn(&mut Logger, &T) -> Result<(), Error> {
     }
 }
 
+/// Validates the inner value of an `Option` through the wrapped validator, if any; `None` is
+/// always valid.
+///
+/// # Example
+/// ```
+/// # use std::path::PathBuf;
+/// # use mammoth_setup::diagnostics::{OptionValidator, PathValidator, PathValidatorKind, Validator};
+/// # use mammoth_setup::error::event::Event;
+/// # use mammoth_setup::error::severity::Severity;
+/// let validator = OptionValidator(PathValidator(Severity::Error, PathValidatorKind::ExistingFile));
+/// let mut events: Vec<Event> = Vec::new();
+///
+/// assert!(validator.validate(&mut events, &None::<PathBuf>).is_ok());
+/// assert!(validator.validate(&mut events, &Some(PathBuf::from("i_do_not_exist"))).is_err());
+/// ```
+#[derive(Copy, Clone)]
+pub struct OptionValidator<V>(pub V);
+
+impl<T, V> Validator<Option<T>> for OptionValidator<V>
+    where
+        V: Validator<T>
+{
+    fn validate(&self, logger: &mut dyn Logger, item: &Option<T>) -> ValidationResult {
+        match item {
+            Some(item) => self.0.validate(logger, item),
+            None => Ok(())
+        }
+    }
+}
+
 /// Kind of validation for paths.
 #[derive(Copy, Clone)]
 pub enum PathValidatorKind {
@@ -135,6 +441,27 @@ pub enum PathValidatorKind {
     ExistingFile,
     /// Validates if the path is correct for a file name.
     FilePath,
+    /// Validates if the path is an existing directory in which files can be created.
+    WritableDirectory,
+}
+
+/// Returns `true` if `path` is an existing directory in which files can be created and `false`
+/// otherwise.
+///
+/// The check creates and removes a probe file, since permissions alone do not account for
+/// read-only mounts and access control lists.
+pub fn is_writable_dir<P>(path: P) -> bool
+    where
+        P: AsRef<Path>
+{
+    let path = path.as_ref();
+    if !path.is_dir() { return false; }
+
+    let probe = path.join(format!(".mammoth-probe-{}", std::process::id()));
+    match OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => fs::remove_file(&probe).is_ok(),
+        Err(_) => false
+    }
 }
 /// Validates a path using the specified severity and validator kind.
 #[derive(Copy, Clone)]
@@ -164,19 +491,113 @@ impl<P> Validator<P> for PathValidator
                 let desc = format!("File does not exist: '{:?}'.", item);
                 logger.log(severity, &desc);
                 if severity >= Severity::Error { Err(Error::FileNotFound(item.to_path_buf()))?; }
+            },
+            PathValidatorKind::WritableDirectory => if !is_writable_dir(item) {
+                let desc = format!("Directory does not exist or is not writable: '{:?}'.", item);
+                logger.log(severity, &desc);
+                if severity >= Severity::Error { Err(Error::InvalidDirectory(item.to_path_buf()))?; }
             }
         }
 
         Ok(())
     }
 }
+
+/// Validates a string against a regular expression using the specified severity, reporting
+/// mismatches as invalid values of the specified key.
+///
+/// The pattern must match the whole string; an invalid pattern is always reported as an error.
+#[derive(Copy, Clone)]
+pub struct PatternValidator<'a>(pub Severity, pub &'a str, pub &'a str);
+
+impl<'a, S> Validator<S> for PatternValidator<'a>
+    where
+        S: AsRef<str>
+{
+    fn validate(&self, logger: &mut dyn Logger, item: &S) -> Result<(), Error> {
+        let PatternValidator(severity, key, pattern) = *self;
+        let item = item.as_ref();
+
+        let regex = match Regex::new(&format!("^(?:{})$", pattern)) {
+            Ok(regex) => regex,
+            Err(_) => {
+                let desc = format!("Invalid pattern for '{}': '{}'.", key, pattern);
+                logger.log(Severity::Error, &desc);
+                Err(Error::InvalidValue(key.to_owned()))?
+            }
+        };
+        if !regex.is_match(item) {
+            let desc = format!("Value of '{}' does not match '{}': '{}'.", key, pattern, item);
+            logger.log(severity, &desc);
+            if severity >= Severity::Error { Err(Error::InvalidValue(key.to_owned()))?; }
+        }
+
+        Ok(())
+    }
+}
+
+/// Defines how a log file is opened by `LogEntity::with_mode`.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFileMode {
+    /// Creates a new log file, failing if it already exists.
+    Create,
+    /// Appends to the log file, creating it if it does not exist.
+    #[default]
+    Append,
+    /// Empties the log file, creating it if it does not exist.
+    Truncate
+}
+
+impl LogFileMode {
+    /// Opens the file at `filename` according to the mode, creating its parent directories if
+    /// they do not exist.
+    pub fn open<P>(self, filename: P) -> Result<File, Error>
+        where
+            P: AsRef<Path>
+    {
+        let filename = filename.as_ref();
+        if let Some(parent) = filename.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let mut options = OpenOptions::new();
+        match self {
+            LogFileMode::Create => options.write(true).create_new(true),
+            LogFileMode::Append => options.append(true).create(true),
+            LogFileMode::Truncate => options.write(true).truncate(true).create(true)
+        };
+        Ok(options.open(filename)?)
+    }
+}
+
 /// Defines an entity (usually, a file) able to collect log information.
 ///
 /// In particular, contains an (asynchronous reference to an) item that implements the `Write` trait
 /// in order to write log information.
+///
+/// When constructed with `LogEntity::with_rotation`, the entity keeps track of the size and of the
+/// age of the log file and, whenever it exceeds the configured size or gets older than the
+/// configured age, renames it to `<filename>.1` (shifting the older files to `<filename>.2`,
+/// `<filename>.3` and so on, and discarding the ones exceeding the configured number) and reopens
+/// a new, empty log file.
+///
+/// When buffered with `LogEntity::with_buffer`, the information is written into the entity
+/// whenever the buffer is full, whenever the configured interval has elapsed since the last flush
+/// (checked by a background thread, so that an idle server writes its buffered information too),
+/// whenever an event with severity `Error` or higher is logged and whenever the entity is flushed
+/// (see `Logger::flush`).
 pub struct LogEntity {
     severity: Severity,
-    entity: Arc<RwLock<Write + Send + Sync>>
+    entity: Arc<RwLock<Write + Send + Sync>>,
+    rotation: Option<(PathBuf, LogRotation)>,
+    buffer: Option<LogBuffer>,
+    written: u64,
+    opened: SystemTime,
+    flushed: Instant,
+    closed: bool
 }
 
 impl LogEntity {
@@ -184,34 +605,215 @@ impl LogEntity {
     pub fn new(severity: Severity, entity: Arc<RwLock<Write + Send + Sync>>) -> LogEntity {
         LogEntity {
             severity,
-            entity
+            entity,
+            rotation: None,
+            buffer: None,
+            written: 0,
+            opened: SystemTime::now(),
+            flushed: Instant::now(),
+            closed: false
         }
     }
     /// Creates a new `LogEntity` from the specified `severity` and constructing the relative
-    /// log container using the specified file.
+    /// log container using the specified file, which is appended to (see `LogFileMode::Append`).
     pub fn from_filename<P>(severity: Severity, filename: P) -> Result<LogEntity, Error>
         where
             P: AsRef<Path>
     {
-        let file = File::open(filename)?;
+        LogEntity::with_mode(severity, filename, LogFileMode::Append)
+    }
+    /// Creates a new `LogEntity` from the specified `severity` and constructing the relative
+    /// log container using the specified file, opened according to `mode`.
+    pub fn with_mode<P>(severity: Severity, filename: P, mode: LogFileMode) -> Result<LogEntity, Error>
+        where
+            P: AsRef<Path>
+    {
+        let file = mode.open(filename)?;
+        let entity = Arc::new(RwLock::new(file));
+        Ok(LogEntity::new(severity, entity))
+    }
+    /// Creates a new `LogEntity` from the specified `severity` appending to the specified file and
+    /// rotating it according to `rotation`.
+    pub fn with_rotation<P>(severity: Severity, filename: P, rotation: LogRotation) -> Result<LogEntity, Error>
+        where
+            P: AsRef<Path>
+    {
+        let filename = filename.as_ref().to_path_buf();
+        let file = LogFileMode::Append.open(&filename)?;
+        let metadata = file.metadata()?;
+        let written = metadata.len();
+        // The age of a non-empty file counts from its creation, so that it survives restarts.
+        let opened = if written > 0 {
+            metadata.created().or_else(|_| metadata.modified()).unwrap_or_else(|_| SystemTime::now())
+        } else {
+            SystemTime::now()
+        };
         let entity = Arc::new(RwLock::new(file));
         Ok(LogEntity {
             severity,
-            entity
+            entity,
+            rotation: Some((filename, rotation)),
+            buffer: None,
+            written,
+            opened,
+            flushed: Instant::now(),
+            closed: false
         })
     }
+    /// Buffers the information written into the entity according to `buffer`.
+    pub fn with_buffer(mut self, buffer: LogBuffer) -> LogEntity {
+        self.entity = buffered(self.entity, &buffer);
+        self.buffer = Some(buffer);
+        self.flushed = Instant::now();
+        self
+    }
+
+    /// Renames the current log file and the old ones, discarding the oldest file, and reopens a new
+    /// log file.
+    fn rotate(&mut self) -> Result<(), Error> {
+        let (filename, rotation) = match self.rotation {
+            Some((ref filename, rotation)) => (filename.clone(), rotation),
+            None => return Ok(())
+        };
+        let keep = rotation.keep();
+        // Buffered information belongs to the current log file.
+        self.entity.write().unwrap().flush()?;
+
+        if keep == 0 {
+            fs::remove_file(&filename)?;
+        } else {
+            let oldest = rotated_filename(&filename, keep);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for index in (1..keep).rev() {
+                let from = rotated_filename(&filename, index);
+                if from.exists() {
+                    fs::rename(&from, rotated_filename(&filename, index + 1))?;
+                }
+            }
+            fs::rename(&filename, rotated_filename(&filename, 1))?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&filename)?;
+        self.entity = Arc::new(RwLock::new(file));
+        if let Some(buffer) = self.buffer {
+            self.entity = buffered(self.entity.clone(), &buffer);
+        }
+        self.written = 0;
+        self.opened = SystemTime::now();
+
+        Ok(())
+    }
+}
+
+/// Writer forwarding to a shared writer, so that it can be buffered.
+struct SharedWriter(Arc<RwLock<dyn Write + Send + Sync>>);
+
+impl Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.write().unwrap().flush()
+    }
+}
+
+/// Wraps `entity` into a buffer with the capacity defined by `buffer`; if `buffer` defines an
+/// interval, a background thread writes the buffered information whenever it elapses, until the
+/// buffer is dropped.
+fn buffered(entity: Arc<RwLock<dyn Write + Send + Sync>>, buffer: &LogBuffer) -> Arc<RwLock<dyn Write + Send + Sync>> {
+    let writer: Arc<RwLock<dyn Write + Send + Sync>> = Arc::new(RwLock::new(BufWriter::with_capacity(buffer.size().bytes() as usize, SharedWriter(entity))));
+    if let Some(interval) = buffer.interval() {
+        let flushed = Arc::downgrade(&writer);
+        // Without the thread, the buffer is still written when an event is logged after the interval.
+        let _ = thread::Builder::new()
+            .name("mammoth-log-flusher".to_owned())
+            .spawn(move || flush_periodically(flushed, interval));
+    }
+    writer
+}
+
+/// Flushes `writer` every `interval`, until it is dropped.
+fn flush_periodically(writer: Weak<RwLock<dyn Write + Send + Sync>>, interval: Duration) {
+    // Wakes up at least every second, so that the thread ends soon after the buffer is dropped.
+    let step = interval.clamp(Duration::from_millis(10), Duration::from_secs(1));
+    let mut flushed = Instant::now();
+    loop {
+        thread::sleep(step);
+        let writer = match writer.upgrade() {
+            Some(writer) => writer,
+            None => return
+        };
+        if flushed.elapsed() >= interval {
+            if let Ok(mut writer) = writer.write() {
+                let _ = writer.flush();
+            }
+            flushed = Instant::now();
+        }
+    }
+}
+
+/// Obtains the name of the `index`-th rotated log file, i.e. `<filename>.<index>`.
+fn rotated_filename(filename: &Path, index: usize) -> PathBuf {
+    let mut name = OsString::from(filename.as_os_str());
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
 }
 
 impl Logger for LogEntity {
     fn log(&mut self, severity: Severity, desc: &str) {
-        if severity >= self.severity {
+        if severity >= self.severity && !self.closed {
             let datetime = chrono::Local::now();
             let message = format!("{} [{}]: {}\n", datetime.format("%Y-%m-%d %H:%M:%S"), severity, desc);
 
-            let mut writer = self.entity.write().unwrap();
-            writer.write_all(message.as_bytes()).unwrap();
+            // Files are rotated by age before writing, so that they only contain their own period.
+            let expired = self.rotation.as_ref()
+                .and_then(|(_, rotation)| rotation.max_age())
+                .map(|age| self.opened.elapsed().map(|elapsed| elapsed >= age).unwrap_or(false))
+                .unwrap_or(false);
+            if expired {
+                let _ = self.rotate();
+            }
+
+            {
+                let mut writer = self.entity.write().unwrap();
+                writer.write_all(message.as_bytes()).unwrap();
+            }
+            self.written += message.len() as u64;
+
+            let due = self.buffer.as_ref()
+                .map(|buffer| severity >= Severity::Error || buffer.interval().map(|interval| self.flushed.elapsed() >= interval).unwrap_or(false))
+                .unwrap_or(false);
+            if due {
+                let _ = self.flush();
+            }
+
+            let exceeded = self.rotation.as_ref()
+                .and_then(|(_, rotation)| rotation.size())
+                .map(|size| self.written >= size.bytes())
+                .unwrap_or(false);
+            if exceeded {
+                // A failed rotation must not stop logging: keep writing on the current file.
+                let _ = self.rotate();
+            }
         }
     }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.entity.write().unwrap().flush()?;
+        self.flushed = Instant::now();
+        Ok(())
+    }
+
+    /// Flushes the entity and discards the information stored afterwards; the entity itself is
+    /// released once every `LogEntity` sharing it is dropped.
+    fn close(&mut self) -> Result<(), Error> {
+        self.flush()?;
+        self.closed = true;
+        Ok(())
+    }
 }
 
 /// Defines a Validator that validates collections of items implementing the `Id` trait.
@@ -271,14 +873,32 @@ impl<I, V> Validator<Vec<&I>> for IdValidator<I, V>
 
 #[cfg(test)]
 mod tests {
+    use std::borrow::Cow;
+    use std::ffi::OsStr;
     use std::io::{Read, Seek, SeekFrom};
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
     use std::sync::{Arc, RwLock};
+    use std::time::{Duration, Instant};
 
-    use crate::diagnostics::{Logger, LogEntity, PathValidator, PathValidatorKind, Validator};
+    use crate::config::mammoth::LogRotation;
+    use crate::config::path::ConfigPath;
+    use crate::diagnostics::{format_fields, CompositeLogger, Logger, LogEntity, LogFileMode, OptionValidator, PathValidator, PathValidatorKind, PatternValidator, Validator};
     use crate::error::severity::Severity;
     use crate::error::event::Event;
 
+    struct SharedEvents(Arc<RwLock<Vec<Event>>>);
+
+    impl Logger for SharedEvents {
+        fn log(&mut self, sev: Severity, desc: &str) {
+            self.0.write().unwrap().log(sev, desc);
+        }
+
+        fn log_kv(&mut self, sev: Severity, desc: &str, fields: &[(&str, &str)]) {
+            self.0.write().unwrap().log_kv(sev, desc, fields);
+        }
+    }
+
     #[test]
     /// Tests the `LogEntity` structure using a temporary file.
     fn test_logfile() {
@@ -344,6 +964,19 @@ mod tests {
         assert!(validator.validate(&mut events, &Path::new("tests")).is_ok());
     }
 
+    #[test]
+    /// Tests the `PathValidator` of kind `WritableDirectory`.
+    fn test_writable_dir_validator() {
+        let validator = PathValidator(Severity::Error, PathValidatorKind::WritableDirectory);
+        let dir = tempfile::tempdir().unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(validator.validate(&mut events, &dir.path()).is_ok());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+        assert!(validator.validate(&mut events, &Path::new("i_do_not_exist")).is_err());
+        assert!(validator.validate(&mut events, &Path::new("Cargo.toml")).is_err());
+    }
+
     #[test]
     /// Tests the `PathValidator` of kind `FilePath`.
     fn test_file_path_validator() {
@@ -359,4 +992,322 @@ mod tests {
         assert!(validator.validate(&mut events, &Path::new("tests/")).is_err());
         assert!(validator.validate(&mut events, &Path::new("tests")).is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    /// Tests the `PatternValidator` structure.
+    fn test_pattern_validator() {
+        let validator = PatternValidator(Severity::Error, "realm", "[a-z]+");
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(validator.validate(&mut events, &"mammoth").is_ok());
+        assert!(validator.validate(&mut events, &"Mammoth").is_err());
+        assert!(validator.validate(&mut events, &"mammoth 2").is_err());
+        assert_eq!(events.len(), 2);
+        assert!(PatternValidator(Severity::Warning, "realm", "[a-z]+").validate(&mut events, &String::from("42")).is_ok());
+        assert!(PatternValidator(Severity::Warning, "realm", "[a-z").validate(&mut events, &"mammoth").is_err());
+    }
+
+    #[test]
+    /// Tests the validation of the path and string types other than `PathBuf` and `&str`.
+    fn test_validator_types() {
+        let validator = PathValidator(Severity::Error, PathValidatorKind::ExistingFile);
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(validator.validate(&mut events, &ConfigPath::explicit("Cargo.toml")).is_ok());
+        assert!(validator.validate(&mut events, &Cow::Borrowed(Path::new("Cargo.toml"))).is_ok());
+        assert!(validator.validate(&mut events, &PathBuf::from("Cargo.toml").into_boxed_path()).is_ok());
+        assert!(validator.validate(&mut events, &Rc::<Path>::from(Path::new("Cargo.toml"))).is_ok());
+        assert!(validator.validate(&mut events, &OsStr::new("Cargo.toml")).is_ok());
+        assert!(validator.validate(&mut events, &&"i_do_not_exist").is_err());
+
+        let validator = PatternValidator(Severity::Error, "realm", "[a-z]+");
+        assert!(validator.validate(&mut events, &Cow::Borrowed("mammoth")).is_ok());
+        assert!(validator.validate(&mut events, &Rc::<str>::from("mammoth")).is_ok());
+        assert!(validator.validate(&mut events, &&"Mammoth").is_err());
+    }
+
+    #[test]
+    /// Tests the validation of optional values.
+    fn test_option_validator() {
+        let validator = OptionValidator(PathValidator(Severity::Error, PathValidatorKind::ExistingFile));
+        let mut events: Vec<Event> = Vec::new();
+
+        assert!(validator.validate(&mut events, &Some(PathBuf::from("Cargo.toml"))).is_ok());
+        assert!(validator.validate(&mut events, &Some(PathBuf::from("i_do_not_exist"))).is_err());
+        assert!(validator.validate(&mut events, &None::<PathBuf>).is_ok());
+        assert_eq!(events.len(), 1);
+
+        let validator = OptionValidator(PatternValidator(Severity::Error, "realm", "[a-z]+"));
+        assert!(validator.validate(&mut events, &Some(String::from("mammoth"))).is_ok());
+        assert!(validator.validate(&mut events, &Some(Cow::Borrowed("Mammoth"))).is_err());
+        assert!(validator.validate(&mut events, &None::<String>).is_ok());
+    }
+
+    #[test]
+    /// Tests the structured fields of the events.
+    fn test_structured_events() {
+        let mut events: Vec<Event> = Vec::new();
+        events.log_kv(Severity::Warning, "Slow upstream.", &[("host", "example.com"), ("port", "443")]);
+        events.log(Severity::Information, "Unstructured.");
+
+        assert_eq!(events[0].description(), "Slow upstream.");
+        assert_eq!(events[0].field("host"), Some("example.com"));
+        assert_eq!(events[0].field("port"), Some("443"));
+        assert_eq!(events[0].field("path"), None);
+        assert!(events[1].fields().is_empty());
+        assert!(events[0].to_string().ends_with("Slow upstream. host=example.com port=443\n"));
+
+        assert_eq!(format_fields("Request.", &[("path", "/a b"), ("query", "")]), r#"Request. path="/a b" query="""#);
+    }
+
+    #[test]
+    /// Tests the codes of the errors and of the events.
+    fn test_event_codes() {
+        use crate::error::Error;
+        use crate::error::event;
+
+        let errors = [Error::NoHost, Error::NoModsDir, Error::InvalidValue("key".to_owned()), Error::Unknown, Error::Module("failed".to_owned())];
+        let codes: Vec<&str> = errors.iter().map(Error::code).collect();
+        for (i, code) in codes.iter().enumerate() {
+            assert!(code.starts_with("MAM-"));
+            assert!(!codes[i + 1..].contains(code));
+        }
+
+        let event = event::err_error("Invalid configuration.", Error::InvalidValue("mammoth.log.path".to_owned()));
+        assert_eq!(event.code(), Some("MAM-CFG-005"));
+        assert!(event.error().is_some());
+        assert!(event.to_string().ends_with("Invalid configuration. code=MAM-CFG-005\n"));
+        assert_eq!(event::info("No code.").code(), None);
+        assert_eq!(event::info("Custom code.").with_code("APP-001").code(), Some("APP-001"));
+
+        let value = toml::Value::try_from(&event).unwrap();
+        assert_eq!(value.get("code").and_then(|v| v.as_str()), Some("MAM-CFG-005"));
+        assert_eq!(value.get("severity").and_then(|v| v.as_str()), Some("error"));
+        assert_eq!(value.get("error").and_then(|v| v.as_str()), Some("Invalid value for 'mammoth.log.path'."));
+        assert_eq!(value.get("fields").and_then(|v| v.get("code")).and_then(|v| v.as_str()), Some("MAM-CFG-005"));
+        assert!(toml::Value::try_from(event::info("No code.")).unwrap().get("code").is_none());
+
+        let mut events: Vec<Event> = Vec::new();
+        events.log_error(Severity::Warning, "Skipped.", &Error::ModuleDenied("mod_test".to_owned()));
+        assert_eq!(events[0].code(), Some("MAM-MOD-012"));
+    }
+
+    #[test]
+    /// Tests the `CompositeLogger` structure forwarding to every child.
+    fn test_composite_logger() {
+        let first = Arc::new(RwLock::new(Vec::<Event>::new()));
+        let second = Arc::new(RwLock::new(Vec::<Event>::new()));
+        let mut logger = CompositeLogger::new()
+            .with(SharedEvents(first.clone()))
+            .with(SharedEvents(second.clone()));
+
+        assert_eq!(logger.len(), 2);
+        logger.log(Severity::Warning, "Forwarded.");
+        logger.log_kv(Severity::Error, "Structured.", &[("host", "example.com")]);
+
+        for events in [first, second].iter() {
+            let events = events.read().unwrap();
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0].description(), "Forwarded.");
+            assert_eq!(events[1].field("host"), Some("example.com"));
+        }
+        assert!(CompositeLogger::default().is_empty());
+    }
+
+    #[test]
+    /// Tests the minimum severity of each child of the `CompositeLogger` structure.
+    fn test_composite_logger_filter() {
+        let all = Arc::new(RwLock::new(Vec::<Event>::new()));
+        let errors = Arc::new(RwLock::new(Vec::<Event>::new()));
+        let mut logger = CompositeLogger::new()
+            .with(SharedEvents(all.clone()))
+            .with_filtered(Severity::Error, SharedEvents(errors.clone()));
+
+        assert_eq!(logger.severity(), Some(Severity::Debug));
+        logger.log(Severity::Debug, "Debug.");
+        logger.log(Severity::Warning, "Warning.");
+        logger.log_kv(Severity::Error, "Error.", &[("host", "example.com")]);
+        logger.log_kv(Severity::Information, "Information.", &[("host", "example.com")]);
+
+        assert_eq!(all.read().unwrap().len(), 4);
+        let errors = errors.read().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].description(), "Error.");
+        assert_eq!(CompositeLogger::new().with_filtered(Severity::Warning, Vec::<Event>::new()).severity(), Some(Severity::Warning));
+        assert_eq!(CompositeLogger::new().severity(), None);
+    }
+
+    #[test]
+    /// Tests the origin filter of each child of the `CompositeLogger` structure.
+    fn test_composite_logger_origin() {
+        use crate::diagnostics::origin::OriginFilter;
+
+        let all = Arc::new(RwLock::new(Vec::<Event>::new()));
+        let auth = Arc::new(RwLock::new(Vec::<Event>::new()));
+        let mut logger = CompositeLogger::new()
+            .with(SharedEvents(all.clone()))
+            .with_matching(Severity::Debug, OriginFilter::new().with_module("auth"), SharedEvents(auth.clone()));
+
+        logger.log(Severity::Warning, "Untagged.");
+        logger.log_kv(Severity::Warning, "Authenticated.", &[("module", "auth")]);
+        logger.log_kv(Severity::Warning, "Proxied.", &[("module", "proxy")]);
+
+        assert_eq!(all.read().unwrap().len(), 3);
+        let auth = auth.read().unwrap();
+        assert_eq!(auth.len(), 1);
+        assert_eq!(auth[0].description(), "Authenticated.");
+    }
+
+    #[test]
+    /// Tests the buffering of a `LogEntity`.
+    fn test_logfile_buffer() {
+        use crate::config::mammoth::LogBuffer;
+
+        let dir = tempfile::tempdir().unwrap();
+        let filename = dir.path().join("mammoth.log");
+        let mut logger = LogEntity::from_filename(Severity::Debug, &filename).unwrap()
+            .with_buffer(LogBuffer::new(4096).with_interval(Duration::from_secs(3600)));
+
+        logger.log(Severity::Debug, "Buffered.");
+        assert!(std::fs::read_to_string(&filename).unwrap().is_empty());
+        logger.log(Severity::Error, "Flushed.");
+        assert_eq!(std::fs::read_to_string(&filename).unwrap().lines().count(), 2);
+        logger.log(Severity::Information, "Buffered again.");
+        assert_eq!(std::fs::read_to_string(&filename).unwrap().lines().count(), 2);
+        drop(logger);
+        assert_eq!(std::fs::read_to_string(&filename).unwrap().lines().count(), 3);
+
+        let mut logger = LogEntity::from_filename(Severity::Debug, &filename).unwrap()
+            .with_buffer(LogBuffer::new(16));
+        logger.log(Severity::Debug, "Larger than the buffer.");
+        assert_eq!(std::fs::read_to_string(&filename).unwrap().lines().count(), 4);
+    }
+
+    #[test]
+    /// Tests that the buffer of an idle `LogEntity` is written once the interval elapses.
+    fn test_logfile_buffer_interval() {
+        use crate::config::mammoth::LogBuffer;
+
+        let dir = tempfile::tempdir().unwrap();
+        let filename = dir.path().join("mammoth.log");
+        let mut logger = LogEntity::from_filename(Severity::Debug, &filename).unwrap()
+            .with_buffer(LogBuffer::new(4096).with_interval(Duration::from_millis(200)));
+
+        logger.log(Severity::Debug, "Buffered.");
+        assert!(std::fs::read_to_string(&filename).unwrap().is_empty());
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while std::fs::read_to_string(&filename).unwrap().is_empty() {
+            assert!(Instant::now() < deadline, "the buffer was not written within the deadline");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(std::fs::read_to_string(&filename).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    /// Tests the rotation of a buffered `LogEntity`.
+    fn test_logfile_buffer_rotation() {
+        use crate::config::mammoth::LogBuffer;
+
+        let dir = tempfile::tempdir().unwrap();
+        let filename = dir.path().join("mammoth.log");
+        let mut logger = LogEntity::with_rotation(Severity::Debug, &filename, LogRotation::new(100, 1)).unwrap()
+            .with_buffer(LogBuffer::new(4096));
+
+        for _ in 0..3 {
+            logger.log(Severity::Information, "Some information to be rotated.");
+        }
+        assert!(logger.flush().is_ok());
+
+        let rotated = std::fs::read_to_string(dir.path().join("mammoth.log.1")).unwrap();
+        let current = std::fs::read_to_string(&filename).unwrap();
+        assert_eq!(rotated.lines().count() + current.lines().count(), 3);
+        assert!(current.lines().count() < 3);
+    }
+
+    #[test]
+    /// Tests the modes of the log files.
+    fn test_logfile_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        let filename = dir.path().join("logs").join("server").join("mammoth.log");
+
+        let mut logger = LogEntity::from_filename(Severity::Debug, &filename).unwrap();
+        logger.log(Severity::Information, "First.");
+        let mut logger = LogEntity::with_mode(Severity::Debug, &filename, LogFileMode::Append).unwrap();
+        logger.log(Severity::Information, "Second.");
+        assert_eq!(std::fs::read_to_string(&filename).unwrap().lines().count(), 2);
+
+        assert!(LogEntity::with_mode(Severity::Debug, &filename, LogFileMode::Create).is_err());
+        let mut logger = LogEntity::with_mode(Severity::Debug, &filename, LogFileMode::Truncate).unwrap();
+        logger.log(Severity::Information, "Third.");
+        assert!(std::fs::read_to_string(&filename).unwrap().ends_with("Third.\n"));
+        assert_eq!(std::fs::read_to_string(&filename).unwrap().lines().count(), 1);
+
+        let created = dir.path().join("created.log");
+        assert!(LogEntity::with_mode(Severity::Debug, &created, LogFileMode::Create).is_ok());
+        assert!(created.exists());
+    }
+
+    #[test]
+    /// Tests flushing and closing a buffered `LogEntity` through a `CompositeLogger`.
+    fn test_logfile_flush() {
+        use std::io::BufWriter;
+
+        let dir = tempfile::tempdir().unwrap();
+        let filename = dir.path().join("mammoth.log");
+        let file = Arc::new(RwLock::new(BufWriter::new(std::fs::File::create(&filename).unwrap())));
+        let mut logger = CompositeLogger::new().with(LogEntity::new(Severity::Debug, file));
+
+        logger.log(Severity::Information, "Buffered.");
+        assert!(std::fs::read_to_string(&filename).unwrap().is_empty());
+        assert!(logger.flush().is_ok());
+        assert!(std::fs::read_to_string(&filename).unwrap().ends_with("Buffered.\n"));
+
+        assert!(logger.close().is_ok());
+        logger.log(Severity::Information, "Closed.");
+        assert!(logger.flush().is_ok());
+        assert_eq!(std::fs::read_to_string(&filename).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    /// Tests the `LogEntity` rotation using a temporary directory.
+    fn test_logfile_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let filename = dir.path().join("mammoth.log");
+        let rotation = LogRotation::new(64, 2);
+        let mut log_file = LogEntity::with_rotation(Severity::Debug, &filename, rotation).unwrap();
+        let line = "A log line that is long enough to trigger a rotation.";
+
+        log_file.log(Severity::Information, line);
+        assert!(dir.path().join("mammoth.log.1").is_file());
+        assert_eq!(std::fs::metadata(&filename).unwrap().len(), 0);
+
+        log_file.log(Severity::Information, line);
+        log_file.log(Severity::Information, line);
+        assert!(dir.path().join("mammoth.log.1").is_file());
+        assert!(dir.path().join("mammoth.log.2").is_file());
+        assert!(!dir.path().join("mammoth.log.3").exists());
+
+        let rotated = std::fs::read_to_string(dir.path().join("mammoth.log.1")).unwrap();
+        assert!(rotated.ends_with(&format!("{}\n", line)));
+    }
+
+    #[test]
+    /// Tests the `LogEntity` rotation by age using a temporary directory.
+    fn test_logfile_rotation_by_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let filename = dir.path().join("mammoth.log");
+
+        let mut log_file = LogEntity::with_rotation(Severity::Debug, &filename, LogRotation::by_age(3600, 2)).unwrap();
+        log_file.log(Severity::Information, "First line.");
+        log_file.log(Severity::Information, "Second line.");
+        assert!(!dir.path().join("mammoth.log.1").exists());
+        drop(log_file);
+
+        // An age of zero expires the file before every write.
+        let mut log_file = LogEntity::with_rotation(Severity::Debug, &filename, LogRotation::by_age(0, 2)).unwrap();
+        log_file.log(Severity::Information, "Third line.");
+        let rotated = std::fs::read_to_string(dir.path().join("mammoth.log.1")).unwrap();
+        assert!(rotated.ends_with("Second line.\n"));
+        assert!(std::fs::read_to_string(&filename).unwrap().ends_with("Third line.\n"));
+    }
+}
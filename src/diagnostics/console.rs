@@ -0,0 +1,175 @@
+//! Logger writing into the console, with a color for each severity.
+//!
+//! Information up to `Information` is written into the standard output, while warnings and errors
+//! are written into the standard error. Each line contains the time, the severity and the
+//! description of the event; the compact format omits the time and abbreviates the severity.
+//! Colors use the ANSI escape sequences and, unless forced, are only used if the standard output
+//! is a terminal.
+use std::io;
+use std::io::{IsTerminal, Write};
+
+use crate::diagnostics::{format_fields, Logger};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Logger that writes into the standard output and the standard error.
+pub struct ConsoleLogger {
+    severity: Severity,
+    color: bool,
+    compact: bool,
+    out: Box<dyn Write + Send + Sync>,
+    err: Box<dyn Write + Send + Sync>
+}
+
+impl ConsoleLogger {
+    /// Creates a new `ConsoleLogger` writing the information with at least the given `severity`,
+    /// using colors if the standard output is a terminal.
+    pub fn new(severity: Severity) -> ConsoleLogger {
+        ConsoleLogger::with_writers(severity, Box::new(io::stdout()), Box::new(io::stderr()))
+            .with_color(io::stdout().is_terminal())
+    }
+    /// Creates a new `ConsoleLogger` writing into `out` instead of the standard output and into
+    /// `err` instead of the standard error, without colors.
+    pub fn with_writers(severity: Severity, out: Box<dyn Write + Send + Sync>, err: Box<dyn Write + Send + Sync>) -> ConsoleLogger {
+        ConsoleLogger {
+            severity,
+            color: false,
+            compact: false,
+            out,
+            err
+        }
+    }
+    /// Enables or disables the colors.
+    pub fn with_color(mut self, color: bool) -> ConsoleLogger {
+        self.color = color;
+        self
+    }
+    /// Enables or disables the compact format.
+    pub fn with_compact(mut self, compact: bool) -> ConsoleLogger {
+        self.compact = compact;
+        self
+    }
+
+    /// Returns `true` if the logger uses colors and `false` otherwise.
+    pub fn is_colored(&self) -> bool {
+        self.color
+    }
+    /// Returns `true` if the logger uses the compact format and `false` otherwise.
+    pub fn is_compact(&self) -> bool {
+        self.compact
+    }
+    /// Formats a line of the log, without the trailing newline.
+    pub fn format(&self, severity: Severity, desc: &str) -> String {
+        let tag = if self.compact {
+            abbreviation(severity).to_owned()
+        } else {
+            format!("[{}]", severity.to_string().trim_end_matches('\n'))
+        };
+        let tag = if self.color {
+            format!("\x1b[{}m{}\x1b[0m", color_code(severity), tag)
+        } else {
+            tag
+        };
+
+        if self.compact {
+            format!("{} {}", tag, desc)
+        } else {
+            format!("{} {}: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), tag, desc)
+        }
+    }
+}
+
+/// Obtains the ANSI color code of `severity`.
+fn color_code(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Debug => "2",
+        Severity::Information => "32",
+        Severity::Warning => "33",
+        Severity::Error => "31",
+        Severity::Critical => "1;31"
+    }
+}
+
+/// Obtains the abbreviation of `severity` used by the compact format.
+fn abbreviation(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Debug => "DBG",
+        Severity::Information => "INF",
+        Severity::Warning => "WRN",
+        Severity::Error => "ERR",
+        Severity::Critical => "CRT"
+    }
+}
+
+impl Logger for ConsoleLogger {
+    fn log(&mut self, severity: Severity, desc: &str) {
+        if severity >= self.severity {
+            let line = self.format(severity, desc);
+            let writer = if severity >= Severity::Warning { &mut self.err } else { &mut self.out };
+            // As for the other loggers, lines that cannot be written are discarded.
+            let _ = writeln!(writer, "{}", line);
+        }
+    }
+
+    fn log_kv(&mut self, severity: Severity, desc: &str, fields: &[(&str, &str)]) {
+        self.log(severity, &format_fields(desc, fields));
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.out.flush()?;
+        self.err.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use std::sync::{Arc, RwLock};
+
+    use crate::diagnostics::Logger;
+    use crate::diagnostics::console::ConsoleLogger;
+    use crate::error::severity::Severity;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<RwLock<Vec<u8>>>);
+
+    impl Buffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.read().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    /// Tests the formats and the streams of the console logger.
+    fn test_console() {
+        let out = Buffer::default();
+        let err = Buffer::default();
+        let mut logger = ConsoleLogger::with_writers(Severity::Information, Box::new(out.clone()), Box::new(err.clone()))
+            .with_compact(true);
+
+        logger.log(Severity::Debug, "Severity level too low, discard this string.");
+        logger.log(Severity::Information, "Started.");
+        logger.log(Severity::Error, "Failed.");
+        assert_eq!(out.contents(), "INF Started.\n");
+        assert_eq!(err.contents(), "ERR Failed.\n");
+
+        let logger = logger.with_color(true);
+        assert!(logger.is_colored());
+        assert_eq!(logger.format(Severity::Warning, "Colored."), "\x1b[33mWRN\x1b[0m Colored.");
+
+        let logger = logger.with_color(false).with_compact(false);
+        assert!(logger.format(Severity::Critical, "Full.").ends_with(" [CRIT]: Full."));
+        assert!(logger.format(Severity::Debug, "Full.").ends_with(" [DBG ]: Full."));
+    }
+}
@@ -0,0 +1,221 @@
+//! Logger counting the events per severity and per code.
+//!
+//! `CountingLogger` tallies every event it receives, optionally forwarding it to another logger,
+//! so that the counters can be checked after a run (e.g. that no warnings were logged during the
+//! validation) or exported as metrics (see `EventCounts::export`), e.g.
+//! ```rust
+//! use mammoth_setup::diagnostics::Logger;
+//! use mammoth_setup::diagnostics::counting::CountingLogger;
+//! use mammoth_setup::error::severity::Severity;
+//!
+//! let mut logger = CountingLogger::new();
+//! logger.log(Severity::Warning, "Something happened.");
+//!
+//! assert_eq!(logger.snapshot().count(Severity::Warning), 1);
+//! assert_eq!(logger.snapshot().at_least(Severity::Error), 0);
+//! ```
+use std::sync::{Arc, RwLock};
+
+use crate::diagnostics::Logger;
+use crate::error::Error;
+use crate::error::event::CODE_FIELD;
+use crate::error::severity::Severity;
+use crate::metrics::MetricsCollector;
+
+/// Severities, in the order of the counters.
+const SEVERITIES: [Severity; 5] = [Severity::Debug, Severity::Information, Severity::Warning, Severity::Error, Severity::Critical];
+
+/// Structure that contains the number of events per severity and per code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventCounts {
+    severities: [u64; 5],
+    codes: Vec<(String, u64)>
+}
+
+impl EventCounts {
+    /// Obtains the number of events with severity `severity`.
+    pub fn count(&self, severity: Severity) -> u64 {
+        self.severities[index(severity)]
+    }
+    /// Obtains the number of events with at least the given `severity`.
+    pub fn at_least(&self, severity: Severity) -> u64 {
+        self.severities[index(severity)..].iter().sum()
+    }
+    /// Obtains the total number of events.
+    pub fn total(&self) -> u64 {
+        self.severities.iter().sum()
+    }
+    /// Obtains the number of events with the given `code` (see `Error::code`).
+    pub fn code(&self, code: &str) -> u64 {
+        self.codes.iter().find(|(c, _)| c == code).map(|(_, n)| *n).unwrap_or(0)
+    }
+    /// Obtains the number of events per code, in order of first occurrence.
+    pub fn codes(&self) -> Vec<(&str, u64)> {
+        self.codes.iter().map(|(c, n)| (c.as_str(), *n)).collect()
+    }
+    /// Adds the counters to `collector`, as the counter `log_events_total` labelled with the
+    /// `severity` and the counter `log_codes_total` labelled with the `code`.
+    pub fn export(&self, collector: &mut MetricsCollector) {
+        for severity in SEVERITIES.iter() {
+            collector.counter_with("log_events_total", &[("severity", severity.name())], self.count(*severity));
+        }
+        for (code, count) in self.codes.iter() {
+            collector.counter_with("log_codes_total", &[("code", code)], *count);
+        }
+    }
+
+    /// Counts an event with severity `severity` carrying the structured `fields`.
+    fn add(&mut self, severity: Severity, fields: &[(&str, &str)]) {
+        self.severities[index(severity)] += 1;
+        if let Some((_, code)) = fields.iter().find(|(k, _)| *k == CODE_FIELD) {
+            match self.codes.iter_mut().find(|(c, _)| c == code) {
+                Some((_, count)) => *count += 1,
+                None => self.codes.push(((*code).to_owned(), 1))
+            }
+        }
+    }
+}
+
+/// Obtains the index of the counter of `severity`.
+fn index(severity: Severity) -> usize {
+    match severity {
+        Severity::Debug => 0,
+        Severity::Information => 1,
+        Severity::Warning => 2,
+        Severity::Error => 3,
+        Severity::Critical => 4
+    }
+}
+
+/// Handle to the counters of a `CountingLogger`, which can be kept after the logger has been
+/// handed over (e.g. registered as the logger of a `LoadedModuleSet`).
+#[derive(Clone)]
+pub struct CountingHandle(Arc<RwLock<EventCounts>>);
+
+impl CountingHandle {
+    /// Obtains a copy of the current counters.
+    pub fn snapshot(&self) -> EventCounts {
+        self.0.read().unwrap().clone()
+    }
+    /// Sets every counter to zero.
+    pub fn reset(&self) {
+        *self.0.write().unwrap() = EventCounts::default();
+    }
+}
+
+/// Logger that counts the events per severity and per code, optionally forwarding them to
+/// another logger.
+pub struct CountingLogger {
+    counts: CountingHandle,
+    logger: Option<Box<dyn Logger>>
+}
+
+impl CountingLogger {
+    /// Creates a new `CountingLogger` that only counts the events.
+    pub fn new() -> CountingLogger {
+        CountingLogger {
+            counts: CountingHandle(Arc::new(RwLock::new(EventCounts::default()))),
+            logger: None
+        }
+    }
+    /// Creates a new `CountingLogger` that counts the events and forwards them to `logger`.
+    pub fn wrapping<L>(logger: L) -> CountingLogger
+        where
+            L: Logger
+    {
+        CountingLogger {
+            logger: Some(Box::new(logger)),
+            ..CountingLogger::new()
+        }
+    }
+
+    /// Obtains a handle to the counters.
+    pub fn handle(&self) -> CountingHandle {
+        self.counts.clone()
+    }
+    /// Obtains a copy of the current counters.
+    pub fn snapshot(&self) -> EventCounts {
+        self.counts.snapshot()
+    }
+    /// Sets every counter to zero.
+    pub fn reset(&self) {
+        self.counts.reset();
+    }
+}
+
+impl Default for CountingLogger {
+    fn default() -> CountingLogger {
+        CountingLogger::new()
+    }
+}
+
+impl Logger for CountingLogger {
+    fn log(&mut self, severity: Severity, desc: &str) {
+        self.counts.0.write().unwrap().add(severity, &[]);
+        if let Some(ref mut logger) = self.logger {
+            logger.log(severity, desc);
+        }
+    }
+
+    fn log_kv(&mut self, severity: Severity, desc: &str, fields: &[(&str, &str)]) {
+        self.counts.0.write().unwrap().add(severity, fields);
+        if let Some(ref mut logger) = self.logger {
+            logger.log_kv(severity, desc, fields);
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        match self.logger {
+            Some(ref mut logger) => logger.flush(),
+            None => Ok(())
+        }
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        match self.logger {
+            Some(ref mut logger) => logger.close(),
+            None => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::diagnostics::Logger;
+    use crate::diagnostics::counting::CountingLogger;
+    use crate::error::Error;
+    use crate::error::event::Event;
+    use crate::error::severity::Severity;
+    use crate::metrics::MetricsCollector;
+
+    #[test]
+    /// Tests the counters of the counting logger.
+    fn test_counting_logger() {
+        let mut logger = CountingLogger::wrapping(Vec::<Event>::new());
+        let handle = logger.handle();
+
+        logger.log(Severity::Debug, "Debug.");
+        logger.log(Severity::Warning, "Warning.");
+        logger.log_error(Severity::Error, "Invalid value.", &Error::InvalidValue("key".to_owned()));
+        logger.log_error(Severity::Critical, "Invalid value again.", &Error::InvalidValue("key".to_owned()));
+        logger.log_kv(Severity::Warning, "Denied.", &[("code", "MAM-MOD-012")]);
+
+        let counts = handle.snapshot();
+        assert_eq!(counts.total(), 5);
+        assert_eq!(counts.count(Severity::Warning), 2);
+        assert_eq!(counts.count(Severity::Information), 0);
+        assert_eq!(counts.at_least(Severity::Error), 2);
+        assert_eq!(counts.code("MAM-CFG-005"), 2);
+        assert_eq!(counts.codes(), vec![("MAM-CFG-005", 2), ("MAM-MOD-012", 1)]);
+        assert_eq!(counts, logger.snapshot());
+
+        let mut collector = MetricsCollector::new();
+        counts.export(&mut collector);
+        assert_eq!(collector.get("log_events_total", &[("severity", "warning")]).unwrap().value(), 2.0);
+        assert_eq!(collector.get("log_events_total", &[("severity", "critical")]).unwrap().value(), 1.0);
+        assert_eq!(collector.get("log_codes_total", &[("code", "MAM-CFG-005")]).unwrap().value(), 2.0);
+
+        handle.reset();
+        assert_eq!(logger.snapshot().total(), 0);
+    }
+}
@@ -0,0 +1,131 @@
+//! Logger writing into the Windows Event Log.
+//!
+//! Events are reported through `ReportEventW` under an event source named after the `ident` of the
+//! logger; `Debug` and `Information` are reported as information events, `Warning` as warning
+//! events and `Error` and `Critical` as error events. The structured fields of the events, if any
+//! (see `Logger::log_kv`), are appended to the description.
+//!
+//! Since no message file is registered for the event source, the Event Viewer shows the
+//! description of the events as an inserted string; registering the source (e.g. through
+//! `New-EventLog`) is left to the installer of the service.
+//!
+//! This module is only available on Windows, with the `windows` feature.
+use std::ffi::{c_void, OsStr};
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+
+use crate::diagnostics::{format_fields, Logger};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Event type of error events.
+const EVENTLOG_ERROR_TYPE: u16 = 0x0001;
+/// Event type of warning events.
+const EVENTLOG_WARNING_TYPE: u16 = 0x0002;
+/// Event type of information events.
+const EVENTLOG_INFORMATION_TYPE: u16 = 0x0004;
+/// Maximum number of characters of an inserted string accepted by `ReportEventW`.
+const MAX_STRING_LENGTH: usize = 31839;
+
+#[link(name = "advapi32")]
+extern "system" {
+    fn RegisterEventSourceW(server: *const u16, source: *const u16) -> *mut c_void;
+    fn ReportEventW(handle: *mut c_void, event_type: u16, category: u16, event_id: u32, user_sid: *mut c_void,
+                    num_strings: u16, data_size: u32, strings: *const *const u16, data: *mut c_void) -> i32;
+    fn DeregisterEventSource(handle: *mut c_void) -> i32;
+}
+
+/// Logger that writes into the Windows Event Log.
+pub struct EventLogLogger {
+    severity: Severity,
+    ident: String,
+    handle: *mut c_void
+}
+
+// Handles of event sources can be used from any thread.
+unsafe impl Send for EventLogLogger {}
+unsafe impl Sync for EventLogLogger {}
+
+impl EventLogLogger {
+    /// Creates a new `EventLogLogger` writing into the local Event Log under the source `ident`.
+    pub fn new(severity: Severity, ident: &str) -> Result<EventLogLogger, Error> {
+        let source = wide(ident);
+        let handle = unsafe { RegisterEventSourceW(ptr::null(), source.as_ptr()) };
+        if handle.is_null() {
+            Err(io::Error::last_os_error())?;
+        }
+
+        Ok(EventLogLogger {
+            severity,
+            ident: ident.to_owned(),
+            handle
+        })
+    }
+
+    /// Obtains the identifier (i.e. the event source) of the logger.
+    pub fn ident(&self) -> &str {
+        &self.ident
+    }
+}
+
+/// Obtains the event type corresponding to `severity`.
+pub fn event_type(severity: Severity) -> u16 {
+    match severity {
+        Severity::Debug | Severity::Information => EVENTLOG_INFORMATION_TYPE,
+        Severity::Warning => EVENTLOG_WARNING_TYPE,
+        Severity::Error | Severity::Critical => EVENTLOG_ERROR_TYPE
+    }
+}
+
+/// Encodes `s` as a null-terminated UTF-16 string, truncated to the length accepted by the Event Log.
+fn wide(s: &str) -> Vec<u16> {
+    let mut string: Vec<u16> = OsStr::new(s).encode_wide().take(MAX_STRING_LENGTH).collect();
+    string.push(0);
+    string
+}
+
+impl Logger for EventLogLogger {
+    fn log(&mut self, severity: Severity, desc: &str) {
+        if severity >= self.severity {
+            let string = wide(desc);
+            let strings = [string.as_ptr()];
+            // As for syslog, events that cannot be reported are discarded.
+            unsafe {
+                ReportEventW(self.handle, event_type(severity), 0, 0, ptr::null_mut(),
+                             1, 0, strings.as_ptr(), ptr::null_mut());
+            }
+        }
+    }
+
+    fn log_kv(&mut self, severity: Severity, desc: &str, fields: &[(&str, &str)]) {
+        self.log(severity, &format_fields(desc, fields));
+    }
+}
+
+impl Drop for EventLogLogger {
+    fn drop(&mut self) {
+        unsafe {
+            DeregisterEventSource(self.handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::diagnostics::eventlog::{event_type, wide};
+    use crate::error::severity::Severity;
+
+    #[test]
+    /// Tests the event types and the encoding of the inserted strings.
+    fn test_eventlog() {
+        assert_eq!(event_type(Severity::Debug), 0x0004);
+        assert_eq!(event_type(Severity::Information), 0x0004);
+        assert_eq!(event_type(Severity::Warning), 0x0002);
+        assert_eq!(event_type(Severity::Error), 0x0001);
+        assert_eq!(event_type(Severity::Critical), 0x0001);
+
+        assert_eq!(wide("ok"), vec![b'o' as u16, b'k' as u16, 0]);
+        assert_eq!(wide(&"x".repeat(40000)).len(), 31840);
+    }
+}
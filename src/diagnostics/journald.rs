@@ -0,0 +1,157 @@
+//! Logger writing structured entries into the systemd journal.
+//!
+//! Entries are sent to `systemd-journald` through its native protocol, i.e. as a datagram
+//! containing a list of `FIELD=value` pairs; each entry contains the `MESSAGE`, the `PRIORITY`
+//! (mapped from the `Severity` as in syslog), the `SYSLOG_IDENTIFIER`, the `SYSLOG_PID` and the
+//! `MAMMOTH_SEVERITY` fields, followed by the structured fields of the entry, if any (see
+//! `Logger::log_kv`), whose names are converted to the uppercase names accepted by the journal.
+//!
+//! This module is only available with the `journald` feature.
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+use std::process;
+
+use crate::diagnostics::Logger;
+use crate::diagnostics::syslog::severity_code;
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Path of the native journal socket.
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Logger that writes into the systemd journal.
+pub struct JournaldLogger {
+    severity: Severity,
+    ident: String,
+    socket: UnixDatagram
+}
+
+impl JournaldLogger {
+    /// Creates a new `JournaldLogger` writing into the local journal.
+    pub fn new(severity: Severity, ident: &str) -> Result<JournaldLogger, Error> {
+        JournaldLogger::with_socket(severity, ident, JOURNALD_SOCKET)
+    }
+    /// Creates a new `JournaldLogger` writing into the journal listening at `socket`.
+    pub fn with_socket<P>(severity: Severity, ident: &str, socket: P) -> Result<JournaldLogger, Error>
+        where
+            P: AsRef<Path>
+    {
+        let datagram = UnixDatagram::unbound()?;
+        datagram.connect(socket)?;
+
+        Ok(JournaldLogger {
+            severity,
+            ident: ident.to_owned(),
+            socket: datagram
+        })
+    }
+
+    /// Obtains the identifier (i.e. the `SYSLOG_IDENTIFIER` field) of the logger.
+    pub fn ident(&self) -> &str {
+        &self.ident
+    }
+    /// Encodes an entry according to the native journal protocol.
+    pub fn encode(&self, severity: Severity, desc: &str) -> Vec<u8> {
+        self.encode_with_fields(severity, desc, &[])
+    }
+    /// Encodes an entry carrying the structured `fields` according to the native journal protocol.
+    pub fn encode_with_fields(&self, severity: Severity, desc: &str, fields: &[(&str, &str)]) -> Vec<u8> {
+        let mut entry = Vec::new();
+
+        append_field(&mut entry, "MESSAGE", desc);
+        append_field(&mut entry, "PRIORITY", &severity_code(severity).to_string());
+        append_field(&mut entry, "SYSLOG_IDENTIFIER", &self.ident);
+        append_field(&mut entry, "SYSLOG_PID", &process::id().to_string());
+        append_field(&mut entry, "MAMMOTH_SEVERITY", &severity.to_string());
+        for (name, value) in fields {
+            append_field(&mut entry, &field_name(name), value);
+        }
+
+        entry
+    }
+}
+
+/// Converts `name` into a valid name of a journal field, i.e. made of uppercase ASCII letters,
+/// digits and underscores and starting with a letter.
+fn field_name(name: &str) -> String {
+    let name: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("FIELD_{}", name.trim_start_matches('_'))
+    }
+}
+
+/// Appends the field `name` with the given `value` to the `entry`.
+///
+/// Values containing newlines are written in the binary form, i.e. the name followed by a newline,
+/// the length of the value as a little-endian 64-bit integer and the value itself.
+fn append_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+impl Logger for JournaldLogger {
+    fn log(&mut self, severity: Severity, desc: &str) {
+        if severity >= self.severity {
+            // As for syslog, entries that cannot be delivered are discarded.
+            let _ = self.socket.send(&self.encode(severity, desc));
+        }
+    }
+
+    fn log_kv(&mut self, severity: Severity, desc: &str, fields: &[(&str, &str)]) {
+        if severity >= self.severity {
+            let _ = self.socket.send(&self.encode_with_fields(severity, desc, fields));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::os::unix::net::UnixDatagram;
+    use std::time::Duration;
+
+    use crate::diagnostics::Logger;
+    use crate::diagnostics::journald::JournaldLogger;
+    use crate::error::severity::Severity;
+
+    #[test]
+    /// Tests the encoding and delivery of journal entries.
+    fn test_journald() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.socket");
+        let journal = UnixDatagram::bind(&path).unwrap();
+        journal.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        let mut logger = JournaldLogger::with_socket(Severity::Warning, "mammoth", &path).unwrap();
+        assert_eq!(logger.ident(), "mammoth");
+
+        logger.log(Severity::Information, "Severity level too low, discard this string.");
+        logger.log(Severity::Error, "Test string.");
+
+        let mut buffer = [0u8; 1024];
+        let size = journal.recv(&mut buffer).unwrap();
+        let entry = String::from_utf8_lossy(&buffer[..size]);
+
+        assert!(entry.starts_with("MESSAGE=Test string.\n"));
+        assert!(entry.contains("\nPRIORITY=3\n"));
+        assert!(entry.contains("\nSYSLOG_IDENTIFIER=mammoth\n"));
+
+        let entry = logger.encode(Severity::Critical, "Multiple\nlines.");
+        assert!(entry.starts_with(b"MESSAGE\n\x0f\0\0\0\0\0\0\0Multiple\nlines.\nPRIORITY=2\n"));
+
+        let entry = logger.encode_with_fields(Severity::Error, "Test string.", &[("host", "example.com"), ("tls-port", "443"), ("_id", "1")]);
+        let entry = String::from_utf8_lossy(&entry);
+        assert!(entry.ends_with("\nHOST=example.com\nTLS_PORT=443\nFIELD_ID=1\n"));
+    }
+}
@@ -0,0 +1,236 @@
+//! Origin of the events, i.e. the module, the host and the configuration section producing them.
+//!
+//! The origin is carried by the structured fields `module`, `host` and `section` of the events
+//! (see `Logger::log_kv`), so that it is shown by every logger and stored as separate fields by the
+//! loggers able to store structured data.
+//! `OriginLogger` tags every event written through it with its origin, while `OriginFilter`
+//! selects the events forwarded to a log sink (see `CompositeLogger::push_matching`), e.g. to
+//! write the events of a single module into its own log file.
+use crate::diagnostics::{AsyncLoggerReference, Logger};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Name of the field containing the name of the module producing the event.
+pub const MODULE_FIELD: &str = "module";
+/// Name of the field containing the identifier of the host producing the event.
+pub const HOST_FIELD: &str = "host";
+/// Name of the field containing the configuration section producing the event.
+pub const SECTION_FIELD: &str = "section";
+
+/// Structure that describes the origin of an event.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Origin {
+    module: Option<String>,
+    host: Option<String>,
+    section: Option<String>
+}
+
+impl Origin {
+    /// Creates a new, empty `Origin` structure.
+    pub fn new() -> Origin {
+        Origin::default()
+    }
+    /// Obtains the origin described by the structured `fields` of an event.
+    pub fn from_fields(fields: &[(&str, &str)]) -> Origin {
+        let field = |name: &str| fields.iter().find(|(k, _)| *k == name).map(|(_, v)| (*v).to_owned());
+
+        Origin {
+            module: field(MODULE_FIELD),
+            host: field(HOST_FIELD),
+            section: field(SECTION_FIELD)
+        }
+    }
+    /// Sets the name of the module, returning the origin.
+    pub fn with_module(mut self, module: &str) -> Origin {
+        self.module = Some(module.to_owned());
+        self
+    }
+    /// Sets the identifier of the host, returning the origin.
+    pub fn with_host(mut self, host: &str) -> Origin {
+        self.host = Some(host.to_owned());
+        self
+    }
+    /// Sets the configuration section, returning the origin.
+    pub fn with_section(mut self, section: &str) -> Origin {
+        self.section = Some(section.to_owned());
+        self
+    }
+
+    /// Obtains the name of the module, if any.
+    pub fn module(&self) -> Option<&str> {
+        self.module.as_deref()
+    }
+    /// Obtains the identifier of the host, if any.
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+    /// Obtains the configuration section, if any.
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+    /// Returns `true` if the origin is unknown and `false` otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.module.is_none() && self.host.is_none() && self.section.is_none()
+    }
+    /// Obtains the structured fields describing the origin.
+    pub fn fields(&self) -> Vec<(&str, &str)> {
+        let mut fields = Vec::new();
+        if let Some(module) = self.module() { fields.push((MODULE_FIELD, module)); }
+        if let Some(host) = self.host() { fields.push((HOST_FIELD, host)); }
+        if let Some(section) = self.section() { fields.push((SECTION_FIELD, section)); }
+
+        fields
+    }
+}
+
+/// Logger that tags every event with its origin before forwarding it to another logger.
+///
+/// Fields describing the origin already carried by an event are kept, so that the origin set by
+/// the innermost producer takes precedence.
+pub struct OriginLogger {
+    origin: Origin,
+    logger: AsyncLoggerReference
+}
+
+impl OriginLogger {
+    /// Creates a new `OriginLogger` tagging the events with `origin` and forwarding them to `logger`.
+    pub fn new(origin: Origin, logger: AsyncLoggerReference) -> OriginLogger {
+        OriginLogger {
+            origin,
+            logger
+        }
+    }
+
+    /// Obtains the origin the events are tagged with.
+    pub fn origin(&self) -> &Origin {
+        &self.origin
+    }
+}
+
+impl Logger for OriginLogger {
+    fn log(&mut self, severity: Severity, desc: &str) {
+        self.log_kv(severity, desc, &[]);
+    }
+
+    fn log_kv(&mut self, severity: Severity, desc: &str, fields: &[(&str, &str)]) {
+        let mut tagged: Vec<(&str, &str)> = self.origin.fields().into_iter()
+            .filter(|(name, _)| !fields.iter().any(|(k, _)| k == name))
+            .collect();
+        tagged.extend_from_slice(fields);
+
+        self.logger.write().unwrap().log_kv(severity, desc, &tagged);
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.logger.write().unwrap().flush()
+    }
+}
+
+/// Structure that selects events by their origin.
+///
+/// Each non-empty list restricts the events to the ones whose corresponding field is in the list;
+/// an empty filter selects every event.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct OriginFilter {
+    #[serde(default)]
+    modules: Vec<String>,
+    #[serde(default)]
+    hosts: Vec<String>,
+    #[serde(default)]
+    sections: Vec<String>
+}
+
+impl OriginFilter {
+    /// Creates a new `OriginFilter` selecting every event.
+    pub fn new() -> OriginFilter {
+        OriginFilter::default()
+    }
+    /// Selects the events of the module `module` too, returning the filter.
+    pub fn with_module(mut self, module: &str) -> OriginFilter {
+        self.modules.push(module.to_owned());
+        self
+    }
+    /// Selects the events of the host `host` too, returning the filter.
+    pub fn with_host(mut self, host: &str) -> OriginFilter {
+        self.hosts.push(host.to_owned());
+        self
+    }
+    /// Selects the events of the configuration section `section` too, returning the filter.
+    pub fn with_section(mut self, section: &str) -> OriginFilter {
+        self.sections.push(section.to_owned());
+        self
+    }
+
+    /// Obtains the selected modules.
+    pub fn modules(&self) -> &[String] {
+        &self.modules
+    }
+    /// Obtains the selected hosts.
+    pub fn hosts(&self) -> &[String] {
+        &self.hosts
+    }
+    /// Obtains the selected configuration sections.
+    pub fn sections(&self) -> &[String] {
+        &self.sections
+    }
+    /// Returns `true` if the filter selects every event and `false` otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty() && self.hosts.is_empty() && self.sections.is_empty()
+    }
+    /// Returns `true` if the event carrying the structured `fields` is selected and `false`
+    /// otherwise.
+    pub fn matches(&self, fields: &[(&str, &str)]) -> bool {
+        let origin = Origin::from_fields(fields);
+        let select = |list: &[String], value: Option<&str>| {
+            list.is_empty() || value.map(|v| list.iter().any(|item| item == v)).unwrap_or(false)
+        };
+
+        select(&self.modules, origin.module()) && select(&self.hosts, origin.host()) && select(&self.sections, origin.section())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, RwLock};
+
+    use crate::diagnostics::Logger;
+    use crate::diagnostics::origin::{Origin, OriginFilter, OriginLogger};
+    use crate::error::event::Event;
+    use crate::error::severity::Severity;
+
+    #[test]
+    /// Tests the tagging of the events with their origin.
+    fn test_origin_logger() {
+        let events = Arc::new(RwLock::new(Vec::<Event>::new()));
+        let origin = Origin::new().with_module("auth").with_section("mod");
+        let mut logger = OriginLogger::new(origin.clone(), events.clone());
+
+        assert_eq!(logger.origin(), &origin);
+        logger.log(Severity::Information, "Tagged.");
+        logger.log_kv(Severity::Warning, "Nested.", &[("module", "inner"), ("user", "admin")]);
+
+        let events = events.read().unwrap();
+        assert_eq!(events[0].origin(), origin);
+        assert!(events[0].to_string().ends_with("Tagged. module=auth section=mod\n"));
+        assert_eq!(events[1].field("module"), Some("inner"));
+        assert_eq!(events[1].field("section"), Some("mod"));
+        assert_eq!(events[1].field("user"), Some("admin"));
+        assert!(Origin::new().is_empty());
+    }
+
+    #[test]
+    /// Tests the selection of the events by their origin.
+    fn test_origin_filter() {
+        let filter = OriginFilter::new().with_module("auth").with_module("static");
+
+        assert!(OriginFilter::new().matches(&[]));
+        assert!(filter.matches(&[("module", "auth")]));
+        assert!(filter.matches(&[("module", "static"), ("host", "*:80")]));
+        assert!(!filter.matches(&[("module", "proxy")]));
+        assert!(!filter.matches(&[]));
+
+        let filter = filter.with_host("*:80");
+        assert!(filter.matches(&[("module", "static"), ("host", "*:80")]));
+        assert!(!filter.matches(&[("module", "static"), ("host", "*:443")]));
+    }
+}
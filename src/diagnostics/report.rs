@@ -0,0 +1,366 @@
+//! Reports produced by the runtime and stored into the diagnostics system.
+//!
+//! Reports collect information that is only known once the server is running (e.g. the actual
+//! addresses the hosts are bound to or the health of the server) and can be both logged and
+//! serialized.
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+
+use chrono::{DateTime, Local};
+
+use crate::config::HostIdentifier;
+use crate::diagnostics::Logger;
+use crate::error::severity::Severity;
+
+/// Structure that contains the addresses the hosts are actually bound to.
+///
+/// When binding to port `0` or to multiple interfaces, the real address of a host is known only
+/// after the binding has been performed; the runtime should fill this structure, for each host,
+/// with the addresses returned by the operating system.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BoundAddresses {
+    hosts: Vec<BoundHost>
+}
+
+/// Structure that contains the addresses a single host is bound to.
+#[derive(Clone, Debug, Serialize)]
+pub struct BoundHost {
+    host: HostIdentifier,
+    addresses: Vec<SocketAddr>
+}
+
+impl BoundHost {
+    /// Obtains the identifier of the host.
+    pub fn host(&self) -> &HostIdentifier {
+        &self.host
+    }
+    /// Obtains the addresses the host is bound to.
+    pub fn addresses(&self) -> &[SocketAddr] {
+        &self.addresses
+    }
+}
+
+impl BoundAddresses {
+    /// Creates a new, empty `BoundAddresses` structure.
+    pub fn new() -> BoundAddresses {
+        BoundAddresses {
+            hosts: Vec::new()
+        }
+    }
+    /// Records that the host identified by `id` is bound to `address`.
+    pub fn insert(&mut self, id: HostIdentifier, address: SocketAddr) {
+        if let Some(bound) = self.hosts.iter_mut().find(|b| b.host == id) {
+            if !bound.addresses.contains(&address) {
+                bound.addresses.push(address);
+            }
+        } else {
+            self.hosts.push(BoundHost { host: id, addresses: vec![address] });
+        }
+    }
+    /// Obtains the addresses the host identified by `id` is bound to, if any.
+    pub fn get(&self, id: &HostIdentifier) -> Option<&[SocketAddr]> {
+        self.hosts.iter()
+            .find(|b| &b.host == id)
+            .map(|b| b.addresses())
+    }
+    /// Obtains a vector of references to all the bound hosts.
+    pub fn hosts(&self) -> Vec<&BoundHost> {
+        self.hosts.iter().collect()
+    }
+    /// Returns `true` if no address has been recorded and `false` otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+    /// Writes the bound addresses of every host into the `logger`, with `Information` severity.
+    pub fn report(&self, logger: &mut dyn Logger) {
+        for bound in self.hosts.iter() {
+            let addresses: Vec<String> = bound.addresses.iter().map(|a| a.to_string()).collect();
+            let desc = format!("Host '{}' bound to {}.", bound.host, addresses.join(", "));
+            logger.log(Severity::Information, &desc);
+        }
+    }
+}
+
+/// Structure that contains the health information and the metrics of the running server, as a
+/// list of named values.
+///
+/// The runtime decides which values are reported (e.g. `uptime`, `requests` or `healthy`); the
+/// report is used, for instance, to render the status pages.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StatusReport {
+    entries: Vec<(String, String)>
+}
+
+impl StatusReport {
+    /// Creates a new, empty `StatusReport` structure.
+    pub fn new() -> StatusReport {
+        StatusReport {
+            entries: Vec::new()
+        }
+    }
+    /// Sets the value of the entry `name`, replacing the previous value, if any.
+    pub fn insert<V>(&mut self, name: &str, value: V)
+        where
+            V: ToString
+    {
+        let value = value.to_string();
+        if let Some(entry) = self.entries.iter_mut().find(|(n, _)| n == name) {
+            entry.1 = value;
+        } else {
+            self.entries.push((name.to_owned(), value));
+        }
+    }
+    /// Adds an entry `host <id>` for each host in `bound`, listing its addresses.
+    pub fn insert_addresses(&mut self, bound: &BoundAddresses) {
+        for host in bound.hosts() {
+            let addresses: Vec<String> = host.addresses().iter().map(|a| a.to_string()).collect();
+            self.insert(&format!("host {}", host.host()), addresses.join(", "));
+        }
+    }
+    /// Adds the entry `healthy`, telling whether every module in `health` is healthy, and an
+    /// entry `module <name>` for each module in `health`, describing its status.
+    pub fn insert_health(&mut self, health: &HealthReport) {
+        self.insert("healthy", health.is_healthy());
+        for module in health.modules() {
+            let name = match module.host() {
+                Some(host) => format!("module {} ({})", module.name(), host),
+                None => format!("module {}", module.name())
+            };
+            self.insert(&name, module.status());
+        }
+    }
+    /// Obtains the value of the entry `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+    /// Obtains the entries, in insertion order.
+    pub fn entries(&self) -> Vec<(&str, &str)> {
+        self.entries.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect()
+    }
+}
+
+/// Health of a module, as reported through `MammothInterface::on_health`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum HealthStatus {
+    /// The module works as expected.
+    #[default]
+    Healthy,
+    /// The module works with reduced functionality, for the given reason.
+    Degraded(String),
+    /// The module does not work, for the given reason.
+    Unhealthy(String)
+}
+
+impl HealthStatus {
+    /// Returns `true` if the status is `Healthy` and `false` otherwise.
+    pub fn is_healthy(&self) -> bool {
+        *self == HealthStatus::Healthy
+    }
+    /// Obtains the reason of the status, if any.
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            HealthStatus::Healthy => None,
+            HealthStatus::Degraded(reason) | HealthStatus::Unhealthy(reason) => Some(reason)
+        }
+    }
+    fn rank(&self) -> u8 {
+        match self {
+            HealthStatus::Healthy => 0,
+            HealthStatus::Degraded(_) => 1,
+            HealthStatus::Unhealthy(_) => 2
+        }
+    }
+}
+
+impl Display for HealthStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            HealthStatus::Healthy => write!(f, "healthy"),
+            HealthStatus::Degraded(reason) => write!(f, "degraded: {}", reason),
+            HealthStatus::Unhealthy(reason) => write!(f, "unhealthy: {}", reason)
+        }
+    }
+}
+
+/// Structure that contains the health of a single instance of a loaded module.
+#[derive(Clone, Debug)]
+pub struct ModuleHealth {
+    name: String,
+    host: Option<HostIdentifier>,
+    status: HealthStatus,
+    checked_at: DateTime<Local>
+}
+
+impl ModuleHealth {
+    /// Creates a new `ModuleHealth` structure for the module `name`, checked now.
+    pub fn new(name: &str, host: Option<HostIdentifier>, status: HealthStatus) -> ModuleHealth {
+        ModuleHealth {
+            name: name.to_owned(),
+            host,
+            status,
+            checked_at: Local::now()
+        }
+    }
+
+    /// Obtains the name of the module.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Obtains the host owning the instance, if it is a per-host instance.
+    pub fn host(&self) -> Option<&HostIdentifier> {
+        self.host.as_ref()
+    }
+    /// Obtains the status of the module.
+    pub fn status(&self) -> &HealthStatus {
+        &self.status
+    }
+    /// Obtains the moment the status was checked.
+    pub fn checked_at(&self) -> DateTime<Local> {
+        self.checked_at
+    }
+}
+
+/// Structure that contains the health of the loaded modules (see
+/// `LoadedModuleSet::health_report`), e.g. to answer the liveness probes of the server.
+#[derive(Clone, Debug, Default)]
+pub struct HealthReport {
+    modules: Vec<ModuleHealth>
+}
+
+impl HealthReport {
+    /// Creates a new, empty `HealthReport` structure.
+    pub fn new() -> HealthReport {
+        HealthReport {
+            modules: Vec::new()
+        }
+    }
+    /// Adds the health of a module.
+    pub fn push(&mut self, health: ModuleHealth) {
+        self.modules.push(health);
+    }
+    /// Obtains the health of the modules, in loading order.
+    pub fn modules(&self) -> &[ModuleHealth] {
+        &self.modules
+    }
+    /// Obtains the health of the module `name`, i.e. the worst status among its instances, if
+    /// any.
+    pub fn get(&self, name: &str) -> Option<&HealthStatus> {
+        self.modules.iter()
+            .filter(|m| m.name == name)
+            .map(|m| &m.status)
+            .max_by_key(|s| s.rank())
+    }
+    /// Obtains the overall health, i.e. the worst status among the modules; a report without
+    /// modules is healthy.
+    pub fn status(&self) -> HealthStatus {
+        self.modules.iter()
+            .map(|m| &m.status)
+            .max_by_key(|s| s.rank())
+            .cloned()
+            .unwrap_or_default()
+    }
+    /// Returns `true` if every module is healthy and `false` otherwise.
+    pub fn is_healthy(&self) -> bool {
+        self.modules.iter().all(|m| m.status.is_healthy())
+    }
+    /// Writes the modules that are not healthy into the `logger`, with `Warning` severity if
+    /// degraded and `Error` severity if unhealthy.
+    pub fn report(&self, logger: &mut dyn Logger) {
+        for module in self.modules.iter() {
+            let sev = match module.status {
+                HealthStatus::Healthy => continue,
+                HealthStatus::Degraded(_) => Severity::Warning,
+                HealthStatus::Unhealthy(_) => Severity::Error
+            };
+            let desc = format!("Module '{}' is {} (checked at {}).", module.name, module.status, module.checked_at.to_rfc3339());
+            logger.log(sev, &desc);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use crate::config::HostIdentifier;
+    use crate::diagnostics::report::{BoundAddresses, HealthReport, HealthStatus, ModuleHealth, StatusReport};
+    use crate::error::event::Event;
+
+    #[test]
+    /// Tests insertion and retrieval of bound addresses.
+    fn test_bound_addresses() {
+        let mut bound = BoundAddresses::new();
+        let localhost = HostIdentifier::new(0, Some("localhost"));
+        let any = HostIdentifier::new(8080, None);
+        let first: SocketAddr = "127.0.0.1:41234".parse().unwrap();
+        let second: SocketAddr = "[::1]:41234".parse().unwrap();
+
+        assert!(bound.is_empty());
+
+        bound.insert(localhost.clone(), first);
+        bound.insert(localhost.clone(), second);
+        bound.insert(localhost.clone(), first);
+        bound.insert(any.clone(), "0.0.0.0:8080".parse().unwrap());
+
+        assert_eq!(bound.get(&localhost).unwrap(), &[first, second]);
+        assert_eq!(bound.get(&any).unwrap().len(), 1);
+        assert!(bound.get(&HostIdentifier::new(80, None)).is_none());
+        assert_eq!(bound.hosts().len(), 2);
+
+        let mut events: Vec<Event> = Vec::new();
+        bound.report(&mut events);
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    /// Tests the entries of the status report.
+    fn test_status_report() {
+        let mut report = StatusReport::new();
+        let mut bound = BoundAddresses::new();
+        bound.insert(HostIdentifier::new(8080, None), "0.0.0.0:8080".parse().unwrap());
+
+        report.insert("healthy", true);
+        report.insert("requests", 10);
+        report.insert("requests", 11);
+        report.insert_addresses(&bound);
+
+        assert_eq!(report.get("healthy"), Some("true"));
+        assert_eq!(report.get("requests"), Some("11"));
+        assert_eq!(report.get("host *:8080"), Some("0.0.0.0:8080"));
+        assert_eq!(report.entries().len(), 3);
+    }
+
+    #[test]
+    /// Tests the aggregation of the health of the modules.
+    fn test_health_report() {
+        let mut health = HealthReport::new();
+        assert!(health.is_healthy());
+        assert_eq!(health.status(), HealthStatus::Healthy);
+
+        let host = HostIdentifier::new(8080, None);
+        health.push(ModuleHealth::new("mod_auth", None, HealthStatus::Healthy));
+        health.push(ModuleHealth::new("mod_cache", None, HealthStatus::Degraded("store unreachable".to_owned())));
+        health.push(ModuleHealth::new("mod_db", Some(host.clone()), HealthStatus::Healthy));
+        health.push(ModuleHealth::new("mod_db", None, HealthStatus::Unhealthy("no connection".to_owned())));
+
+        assert!(!health.is_healthy());
+        assert_eq!(health.status(), HealthStatus::Unhealthy("no connection".to_owned()));
+        assert_eq!(health.get("mod_db").unwrap().reason(), Some("no connection"));
+        assert_eq!(health.get("mod_auth"), Some(&HealthStatus::Healthy));
+        assert!(health.get("mod_other").is_none());
+        assert!(health.modules()[0].checked_at() <= health.modules()[3].checked_at());
+
+        let mut events: Vec<Event> = Vec::new();
+        health.report(&mut events);
+        assert_eq!(events.len(), 2);
+
+        let mut report = StatusReport::new();
+        report.insert_health(&health);
+        assert_eq!(report.get("healthy"), Some("false"));
+        assert_eq!(report.get("module mod_cache"), Some("degraded: store unreachable"));
+        assert_eq!(report.get("module mod_db (*:8080)"), Some("healthy"));
+    }
+}
@@ -0,0 +1,483 @@
+//! Symbolication of the backtraces generated within modules.
+//!
+//! Modules are usually shipped as stripped dynamic libraries, hence the backtrace of a crash
+//! happening within a module only contains raw addresses.
+//! The `Symbolicator` structure resolves such addresses using the debug symbols of the modules
+//! (configured with the `debug_symbols` key of a `[[mod]]` section) and the `addr2line` tool.
+//!
+//! Addresses are mapped to the loaded libraries by reading the memory map of the current process,
+//! therefore symbolication must happen within the process that generated the backtrace (e.g. in
+//! a panic hook) and is currently only available on Linux.
+//! Since the addresses given to `addr2line` must belong to the address space of the library, the
+//! offsets within the mapped files are converted through the `PT_LOAD` segments of the library.
+use std::convert::TryInto;
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use regex::Regex;
+
+use crate::config::Module;
+use crate::diagnostics::Logger;
+use crate::error::severity::Severity;
+
+/// Structure that describes a library mapped into the memory of the current process.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    start: usize,
+    end: usize,
+    offset: usize,
+    path: PathBuf
+}
+
+/// Structure that describes a loadable (`PT_LOAD`) segment of an ELF file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    offset: usize,
+    vaddr: usize,
+    size: usize
+}
+
+/// Structure that describes a resolved frame of a backtrace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    function: String,
+    location: Option<String>
+}
+
+/// Structure that resolves the addresses of a backtrace using the debug symbols of the modules.
+#[derive(Clone, Debug)]
+pub struct Symbolicator {
+    tool: PathBuf,
+    libraries: Vec<(String, PathBuf, PathBuf)>
+}
+
+impl Mapping {
+    /// Obtains the path of the mapped library.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Returns `true` if the mapping contains `address` and `false` otherwise.
+    pub fn contains(&self, address: usize) -> bool {
+        self.start <= address && address < self.end
+    }
+    /// Obtains the offset of `address` within the mapped library file.
+    pub fn file_offset(&self, address: usize) -> usize {
+        address - self.start + self.offset
+    }
+    /// Obtains the virtual address of `address` within the mapped library, i.e. the address
+    /// expected by `addr2line`, reading the `PT_LOAD` segments of the library.
+    ///
+    /// If the library cannot be read or is not an ELF file, the file offset is returned.
+    pub fn virtual_address(&self, address: usize) -> usize {
+        let offset = self.file_offset(address);
+
+        match read_segments(&self.path) {
+            Some(segments) => virtual_address(&segments, offset),
+            None => offset
+        }
+    }
+}
+
+impl Segment {
+    /// Creates a new `Segment` structure for the `size` bytes at `offset` within the file, loaded
+    /// at the virtual address `vaddr`.
+    pub fn new(offset: usize, vaddr: usize, size: usize) -> Segment {
+        Segment { offset, vaddr, size }
+    }
+    /// Returns `true` if the segment contains the file offset `offset` and `false` otherwise.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.offset <= offset && offset < self.offset + self.size
+    }
+}
+
+impl Frame {
+    /// Obtains the name of the function.
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+    /// Obtains the source location (`file:line`), if known.
+    pub fn location(&self) -> Option<&str> {
+        self.location.as_deref()
+    }
+}
+
+impl Symbolicator {
+    /// Creates a new, empty `Symbolicator` structure using the `addr2line` tool.
+    pub fn new() -> Symbolicator {
+        Symbolicator {
+            tool: PathBuf::from("addr2line"),
+            libraries: Vec::new()
+        }
+    }
+    /// Creates a new `Symbolicator` structure for all the `modules` that specify their debug
+    /// symbols, using `mods_dir` as the default directory of the libraries.
+    pub fn from_modules<P>(modules: &[&Module], mods_dir: P) -> Symbolicator
+        where
+            P: AsRef<Path>
+    {
+        let mut symbolicator = Symbolicator::new();
+
+        for module in modules {
+            if let Some(symbols) = module.debug_symbols() {
+                symbolicator.add(module.name(), module.library_path(mods_dir.as_ref()), symbols);
+            }
+        }
+
+        symbolicator
+    }
+    /// Sets the path of the `addr2line`-compatible tool used to resolve the addresses.
+    pub fn set_tool<P>(&mut self, tool: P)
+        where
+            P: AsRef<Path>
+    {
+        self.tool = tool.as_ref().to_path_buf();
+    }
+    /// Registers the debug `symbols` of the `library` of the module `name`.
+    pub fn add<P, Q>(&mut self, name: &str, library: P, symbols: Q)
+        where
+            P: AsRef<Path>,
+            Q: AsRef<Path>
+    {
+        let library = fs::canonicalize(library.as_ref()).unwrap_or_else(|_| library.as_ref().to_path_buf());
+        self.libraries.push((name.to_owned(), library, symbols.as_ref().to_path_buf()));
+    }
+    /// Returns `true` if no debug symbol has been registered and `false` otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.libraries.is_empty()
+    }
+
+    /// Resolves `address` using the given memory `mappings`.
+    ///
+    /// Returns the name of the module and the resolved frame, if the address belongs to a module
+    /// with registered debug symbols and the frame can be resolved.
+    pub fn resolve_in(&self, mappings: &[Mapping], address: usize) -> Option<(&str, Frame)> {
+        let mapping = mappings.iter().find(|m| m.contains(address))?;
+        let (name, _, symbols) = self.libraries.iter().find(|(_, library, _)| library == mapping.path())?;
+
+        let output = Command::new(&self.tool)
+            .arg("-f")
+            .arg("-C")
+            .arg("-e")
+            .arg(symbols)
+            .arg(format!("{:#x}", mapping.virtual_address(address)))
+            .output()
+            .ok()?;
+        if !output.status.success() { return None; }
+
+        let output = String::from_utf8_lossy(&output.stdout);
+        let mut lines = output.lines();
+        let function = lines.next()?.trim();
+        if function.is_empty() || function == "??" { return None; }
+        let location = lines.next()
+            .map(|l| l.trim())
+            .filter(|l| !l.starts_with("??"))
+            .map(|l| l.to_owned());
+
+        Some((name.as_str(), Frame { function: function.to_owned(), location }))
+    }
+    /// Resolves `address` using the memory mappings of the current process.
+    pub fn resolve(&self, address: usize) -> Option<(&str, Frame)> {
+        self.resolve_in(&current_mappings(), address)
+    }
+    /// Resolves every unresolved frame of the `report` that belongs to a module with registered
+    /// debug symbols, using the given memory `mappings`.
+    ///
+    /// Frames are recognized as lines containing an hexadecimal address (e.g. `0x7f3a2b4c1d2e`)
+    /// followed by `<unknown>`, as in the backtraces generated by the standard library; the
+    /// `<unknown>` placeholder is replaced by the module name, the function and its location.
+    pub fn symbolicate_in(&self, mappings: &[Mapping], report: &str) -> String {
+        lazy_static! {
+            static ref RE_FRAME: Regex = Regex::new(r"0x([0-9a-fA-F]+)(\s*-\s*)<unknown>").unwrap();
+        }
+
+        if self.is_empty() { return report.to_owned(); }
+
+        let lines: Vec<String> = report.lines()
+            .map(|line| {
+                RE_FRAME.replace(line, |caps: &regex::Captures| {
+                    let resolved = usize::from_str_radix(&caps[1], 16).ok()
+                        .and_then(|address| self.resolve_in(mappings, address));
+                    match resolved {
+                        Some((name, frame)) => match frame.location() {
+                            Some(location) => format!("0x{}{}[{}] {}\n             at {}", &caps[1], &caps[2], name, frame.function(), location),
+                            None => format!("0x{}{}[{}] {}", &caps[1], &caps[2], name, frame.function())
+                        },
+                        None => caps[0].to_owned()
+                    }
+                }).into_owned()
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+    /// Resolves every unresolved frame of the `report` using the memory mappings of the current
+    /// process (see `Symbolicator::symbolicate_in`).
+    pub fn symbolicate(&self, report: &str) -> String {
+        if self.is_empty() { return report.to_owned(); }
+        self.symbolicate_in(&current_mappings(), report)
+    }
+    /// Symbolicates the `report` and writes it into the `logger` with the given `severity`.
+    pub fn log(&self, logger: &mut dyn Logger, severity: Severity, report: &str) {
+        logger.log(severity, &self.symbolicate(report));
+    }
+}
+
+impl Default for Symbolicator {
+    fn default() -> Self {
+        Symbolicator::new()
+    }
+}
+
+/// Parses the contents of a `/proc/<pid>/maps` file, keeping only the executable file mappings.
+pub fn parse_mappings(maps: &str) -> Vec<Mapping> {
+    maps.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let range = fields.next()?;
+            let perms = fields.next()?;
+            let offset = fields.next()?;
+            let path = fields.nth(2)?;
+
+            if !perms.contains('x') || !path.starts_with('/') { return None; }
+
+            let mut range = range.split('-');
+            let start = usize::from_str_radix(range.next()?, 16).ok()?;
+            let end = usize::from_str_radix(range.next()?, 16).ok()?;
+            let offset = usize::from_str_radix(offset, 16).ok()?;
+
+            Some(Mapping { start, end, offset, path: PathBuf::from(path) })
+        })
+        .collect()
+}
+
+/// Converts the file `offset` into a virtual address through the given `segments`
+/// (`vaddr = offset - p_offset + p_vaddr`); offsets outside of every segment are returned as is.
+pub fn virtual_address(segments: &[Segment], offset: usize) -> usize {
+    match segments.iter().find(|s| s.contains(offset)) {
+        Some(segment) => offset - segment.offset + segment.vaddr,
+        None => offset
+    }
+}
+
+/// Parses the `PT_LOAD` segments from the `header` of an ELF file and its `program_headers`.
+///
+/// Both 32 and 64 bits, little and big endian files are supported; `None` is returned if `header`
+/// does not describe an ELF file.
+pub fn parse_segments(header: &[u8], program_headers: &[u8]) -> Option<Vec<Segment>> {
+    let layout = ElfLayout::parse(header)?;
+
+    let segments = program_headers.chunks(layout.phentsize)
+        .filter(|ph| ph.len() >= layout.phentsize_min())
+        .filter(|ph| layout.word(&ph[0..4]) == PT_LOAD)
+        .map(|ph| layout.segment(ph))
+        .collect();
+
+    Some(segments)
+}
+
+/// Reads the `PT_LOAD` segments of the ELF file at `path`.
+pub fn read_segments<P>(path: P) -> Option<Vec<Segment>>
+    where
+        P: AsRef<Path>
+{
+    let mut file = File::open(path).ok()?;
+    let mut header = Vec::new();
+    (&mut file).take(64).read_to_end(&mut header).ok()?;
+    let layout = ElfLayout::parse(&header)?;
+
+    let mut program_headers = vec![0u8; layout.phentsize * layout.phnum];
+    file.seek(SeekFrom::Start(layout.phoff as u64)).ok()?;
+    file.read_exact(&mut program_headers).ok()?;
+
+    parse_segments(&header, &program_headers)
+}
+
+const PT_LOAD: u64 = 1;
+
+/// Layout of the program headers of an ELF file.
+struct ElfLayout {
+    is_64: bool,
+    is_le: bool,
+    phoff: usize,
+    phentsize: usize,
+    phnum: usize
+}
+
+impl ElfLayout {
+    fn parse(header: &[u8]) -> Option<ElfLayout> {
+        if header.len() < 52 || &header[0..4] != b"\x7fELF" { return None; }
+        let is_64 = match header[4] { 1 => false, 2 => true, _ => return None };
+        let is_le = match header[5] { 1 => true, 2 => false, _ => return None };
+        if is_64 && header.len() < 64 { return None; }
+
+        let mut layout = ElfLayout { is_64, is_le, phoff: 0, phentsize: 0, phnum: 0 };
+        let (phoff, phentsize, phnum) = if is_64 {
+            (layout.word(&header[0x20..0x28]), layout.word(&header[0x36..0x38]), layout.word(&header[0x38..0x3A]))
+        } else {
+            (layout.word(&header[0x1C..0x20]), layout.word(&header[0x2A..0x2C]), layout.word(&header[0x2C..0x2E]))
+        };
+        layout.phoff = phoff.try_into().ok()?;
+        layout.phentsize = phentsize.try_into().ok()?;
+        layout.phnum = phnum.try_into().ok()?;
+        if layout.phentsize < layout.phentsize_min() { return None; }
+
+        Some(layout)
+    }
+    fn phentsize_min(&self) -> usize {
+        if self.is_64 { 56 } else { 32 }
+    }
+    fn word(&self, bytes: &[u8]) -> u64 {
+        let bytes = bytes.iter().map(|b| u64::from(*b));
+        if self.is_le {
+            bytes.rev().fold(0, |acc, b| (acc << 8) | b)
+        } else {
+            bytes.fold(0, |acc, b| (acc << 8) | b)
+        }
+    }
+    fn segment(&self, ph: &[u8]) -> Segment {
+        let (offset, vaddr, size) = if self.is_64 {
+            (self.word(&ph[8..16]), self.word(&ph[16..24]), self.word(&ph[32..40]))
+        } else {
+            (self.word(&ph[4..8]), self.word(&ph[8..12]), self.word(&ph[16..20]))
+        };
+        Segment::new(offset as usize, vaddr as usize, size as usize)
+    }
+}
+
+/// Obtains the executable file mappings of the current process.
+#[cfg(target_os = "linux")]
+pub fn current_mappings() -> Vec<Mapping> {
+    fs::read_to_string("/proc/self/maps")
+        .map(|maps| parse_mappings(&maps))
+        .unwrap_or_default()
+}
+
+/// Obtains the executable file mappings of the current process.
+#[cfg(not(target_os = "linux"))]
+pub fn current_mappings() -> Vec<Mapping> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use libloading::{Library, Symbol};
+
+    use crate::config::Module;
+    use crate::diagnostics::symbolicate::{current_mappings, parse_mappings, parse_segments, read_segments, virtual_address, Segment, Symbolicator};
+
+    const MAPS: &str = "\
+55d0c0a00000-55d0c0a21000 r--p 00000000 103:02 1311745 /usr/bin/mammoth
+7f3a2b400000-7f3a2b4c2000 r-xp 00012000 103:02 1311746 /srv/mods/mod_test.so
+7f3a2b4c2000-7f3a2b4d0000 rw-p 000d4000 103:02 1311746 /srv/mods/mod_test.so
+7ffd4d5e1000-7ffd4d602000 rw-p 00000000 00:00 0 [stack]";
+
+    #[test]
+    /// Tests parsing of the memory mappings.
+    fn test_parse_mappings() {
+        let mappings = parse_mappings(MAPS);
+
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].path(), Path::new("/srv/mods/mod_test.so"));
+        assert!(mappings[0].contains(0x7f3a2b400000));
+        assert!(mappings[0].contains(0x7f3a2b4c1fff));
+        assert!(!mappings[0].contains(0x7f3a2b4c2000));
+        assert_eq!(mappings[0].file_offset(0x7f3a2b400010), 0x12010);
+    }
+
+    #[test]
+    /// Tests the conversion of file offsets into virtual addresses through the `PT_LOAD` segments.
+    fn test_virtual_address() {
+        // 64 bits, little endian: a single executable segment whose address differs from its
+        // offset, as in the libraries linked with `lld`.
+        let mut header = vec![0u8; 64];
+        header[0..6].copy_from_slice(b"\x7fELF\x02\x01");
+        header[0x20] = 64;
+        header[0x36] = 56;
+        header[0x38] = 2;
+        let mut program_headers = vec![0u8; 112];
+        program_headers[0] = 6;
+        program_headers[56] = 1;
+        program_headers[64..67].copy_from_slice(&[0x20, 0x5b, 0x5c]);
+        program_headers[72..75].copy_from_slice(&[0x20, 0x6b, 0x5c]);
+        program_headers[88..91].copy_from_slice(&[0x00, 0x10, 0x01]);
+
+        let segments = parse_segments(&header, &program_headers).unwrap();
+        assert_eq!(segments, vec![Segment::new(0x5c5b20, 0x5c6b20, 0x11000)]);
+        assert_eq!(virtual_address(&segments, 0x5c5b20), 0x5c6b20);
+        assert_eq!(virtual_address(&segments, 0x5d6b1f), 0x5d7b1f);
+        assert_eq!(virtual_address(&segments, 0x5d6b20), 0x5d6b20);
+        assert_eq!(virtual_address(&segments, 0x10), 0x10);
+
+        // 32 bits, big endian.
+        let mut header = vec![0u8; 52];
+        header[0..6].copy_from_slice(b"\x7fELF\x01\x02");
+        header[0x1F] = 52;
+        header[0x2B] = 32;
+        header[0x2D] = 1;
+        let mut program_headers = vec![0u8; 32];
+        program_headers[3] = 1;
+        program_headers[6] = 0x10;
+        program_headers[10] = 0x20;
+        program_headers[18] = 0x01;
+
+        let segments = parse_segments(&header, &program_headers).unwrap();
+        assert_eq!(segments, vec![Segment::new(0x1000, 0x2000, 0x100)]);
+        assert_eq!(virtual_address(&segments, 0x1010), 0x2010);
+
+        assert!(parse_segments(b"not an ELF file, not an ELF file, not an ELF file...", &[]).is_none());
+        assert!(read_segments("./Cargo.toml").is_none());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    /// Tests the resolution of an address within a real module library.
+    fn test_resolve_library() {
+        let path = "./target/debug/libmod_test.so";
+        let library = Library::new(path).unwrap();
+        let address = unsafe {
+            let symbol: Symbol<unsafe extern "C" fn()> = library.get(b"__mammoth_module").unwrap();
+            *symbol as usize
+        };
+
+        let mappings = current_mappings();
+        let mapping = mappings.iter().find(|m| m.contains(address)).unwrap();
+        let segments = read_segments(mapping.path()).unwrap();
+        assert_eq!(mapping.virtual_address(address), virtual_address(&segments, mapping.file_offset(address)));
+
+        let mut symbolicator = Symbolicator::new();
+        symbolicator.add("mod_test", path, path);
+        let (name, frame) = symbolicator.resolve_in(&mappings, address).unwrap();
+        assert_eq!(name, "mod_test");
+        assert_eq!(frame.function(), "__mammoth_module");
+    }
+
+    #[test]
+    /// Tests that frames that cannot be resolved are left untouched.
+    fn test_symbolicate_unresolved() {
+        let mut symbolicator = Symbolicator::new();
+        let report = "   0: 0x7f3a2b400010 - <unknown>\n   1: 0x55d0c0a00010 - <unknown>";
+
+        assert_eq!(symbolicator.symbolicate_in(&parse_mappings(MAPS), report), report);
+
+        symbolicator.set_tool("./i_do_not_exist");
+        symbolicator.add("mod_test", "/srv/mods/mod_test.so", "/srv/symbols/mod_test.debug");
+        assert!(!symbolicator.is_empty());
+        assert_eq!(symbolicator.symbolicate_in(&parse_mappings(MAPS), report), report);
+    }
+
+    #[test]
+    /// Tests the construction from the module configuration.
+    fn test_from_modules() {
+        let mut with_symbols = Module::new("mod_test").unwrap();
+        with_symbols.set_debug_symbols("./symbols/mod_test.debug");
+        let without_symbols = Module::new("mod_dummy").unwrap();
+
+        assert!(Symbolicator::from_modules(&[&without_symbols], "./mods/").is_empty());
+        assert!(!Symbolicator::from_modules(&[&with_symbols, &without_symbols], "./mods/").is_empty());
+    }
+}
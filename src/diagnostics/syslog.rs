@@ -0,0 +1,233 @@
+//! Logger writing into the system log using the syslog protocol (RFC 5424).
+//!
+//! Messages are sent either to the local syslog daemon (through the `/dev/log` socket, on Unix
+//! systems) or to a remote collector through UDP.
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::net::{ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+use std::process;
+
+use crate::diagnostics::Logger;
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Path of the local syslog socket.
+#[cfg(unix)]
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Describes the syslog facility, i.e. the kind of program that generates the messages.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Facility {
+    Kern,
+    User,
+    Mail,
+    #[default]
+    Daemon,
+    Auth,
+    Syslog,
+    Lpr,
+    News,
+    Uucp,
+    Cron,
+    Authpriv,
+    Ftp,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7
+}
+
+impl Facility {
+    /// Obtains the numerical code of the facility.
+    pub fn code(self) -> u8 {
+        use Facility::*;
+
+        match self {
+            Kern => 0,
+            User => 1,
+            Mail => 2,
+            Daemon => 3,
+            Auth => 4,
+            Syslog => 5,
+            Lpr => 6,
+            News => 7,
+            Uucp => 8,
+            Cron => 9,
+            Authpriv => 10,
+            Ftp => 11,
+            Local0 => 16,
+            Local1 => 17,
+            Local2 => 18,
+            Local3 => 19,
+            Local4 => 20,
+            Local5 => 21,
+            Local6 => 22,
+            Local7 => 23
+        }
+    }
+}
+
+impl Display for Facility {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+/// Obtains the syslog severity code corresponding to the given `Severity`.
+pub fn severity_code(severity: Severity) -> u8 {
+    match severity {
+        Severity::Debug => 7,
+        Severity::Information => 6,
+        Severity::Warning => 4,
+        Severity::Error => 3,
+        Severity::Critical => 2
+    }
+}
+
+#[doc(hidden)]
+enum Transport {
+    #[cfg(unix)]
+    Local(UnixDatagram),
+    Udp(UdpSocket)
+}
+
+/// Logger that writes into the system log.
+pub struct SyslogLogger {
+    severity: Severity,
+    facility: Facility,
+    ident: String,
+    hostname: String,
+    transport: Transport
+}
+
+impl SyslogLogger {
+    /// Creates a new `SyslogLogger` writing into the local syslog daemon.
+    #[cfg(unix)]
+    pub fn new(severity: Severity, facility: Facility, ident: &str) -> Result<SyslogLogger, Error> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(SYSLOG_SOCKET)?;
+
+        Ok(SyslogLogger::with_transport(severity, facility, ident, Transport::Local(socket)))
+    }
+    /// Creates a new `SyslogLogger` writing into the remote collector at `address` through UDP.
+    pub fn udp<A>(severity: Severity, facility: Facility, ident: &str, address: A) -> Result<SyslogLogger, Error>
+        where
+            A: ToSocketAddrs
+    {
+        let address = address.to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::InvalidValue("syslog address".to_owned()))?;
+        let local = if address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(address)?;
+
+        Ok(SyslogLogger::with_transport(severity, facility, ident, Transport::Udp(socket)))
+    }
+
+    #[doc(hidden)]
+    fn with_transport(severity: Severity, facility: Facility, ident: &str, transport: Transport) -> SyslogLogger {
+        SyslogLogger {
+            severity,
+            facility,
+            ident: ident.to_owned(),
+            hostname: hostname(),
+            transport
+        }
+    }
+
+    /// Obtains the facility of the logger.
+    pub fn facility(&self) -> Facility {
+        self.facility
+    }
+    /// Obtains the identifier (i.e. the application name) of the logger.
+    pub fn ident(&self) -> &str {
+        &self.ident
+    }
+    /// Formats a message according to RFC 5424.
+    pub fn format(&self, severity: Severity, desc: &str) -> String {
+        let priority = u16::from(self.facility.code()) * 8 + u16::from(severity_code(severity));
+        let timestamp = chrono::Local::now().to_rfc3339();
+
+        format!("<{}>1 {} {} {} {} - - {}", priority, timestamp, self.hostname, self.ident, process::id(), desc)
+    }
+}
+
+/// Obtains the host name of the machine, or the RFC 5424 nil value if unknown.
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|h| h.trim().to_owned())
+        .ok()
+        .filter(|h| !h.is_empty() && !h.contains(char::is_whitespace))
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+impl Logger for SyslogLogger {
+    fn log(&mut self, severity: Severity, desc: &str) {
+        if severity >= self.severity {
+            let message = self.format(severity, desc);
+
+            // Syslog is a lossy transport: messages that cannot be delivered are discarded.
+            let _ = match self.transport {
+                #[cfg(unix)]
+                Transport::Local(ref socket) => socket.send(message.as_bytes()),
+                Transport::Udp(ref socket) => socket.send(message.as_bytes())
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    use crate::diagnostics::Logger;
+    use crate::diagnostics::syslog::{Facility, SyslogLogger};
+    use crate::error::severity::Severity;
+
+    #[test]
+    /// Tests the RFC 5424 formatting and delivery through UDP.
+    fn test_syslog_udp() {
+        let collector = UdpSocket::bind("127.0.0.1:0").unwrap();
+        collector.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let address = collector.local_addr().unwrap();
+
+        let mut logger = SyslogLogger::udp(Severity::Warning, Facility::Local0, "mammoth", address).unwrap();
+        assert_eq!(logger.facility(), Facility::Local0);
+        assert_eq!(logger.ident(), "mammoth");
+
+        logger.log(Severity::Information, "Severity level too low, discard this string.");
+        logger.log(Severity::Error, "Test string.");
+
+        let mut buffer = [0u8; 1024];
+        let size = collector.recv(&mut buffer).unwrap();
+        let message = String::from_utf8_lossy(&buffer[..size]);
+
+        // local0 (16) * 8 + err (3) = 131
+        assert!(message.starts_with("<131>1 "));
+        assert!(message.contains(" mammoth "));
+        assert!(message.ends_with(" - - Test string."));
+    }
+
+    #[test]
+    /// Tests deserialization of the facility.
+    fn test_facility() {
+        #[derive(Deserialize)]
+        struct Test { facility: Facility }
+
+        let test: Test = toml::from_str(r#"facility = "local7""#).unwrap();
+        assert_eq!(test.facility, Facility::Local7);
+        assert_eq!(test.facility.code(), 23);
+        assert_eq!(test.facility.to_string(), "local7");
+        assert!(toml::from_str::<Test>(r#"facility = "nope""#).is_err());
+    }
+}
@@ -6,45 +6,141 @@ use std::error::Error as ErrorTrait;
 use std::fmt::{Display, Formatter};
 use std::io::Error as IoError;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use openssl::error::ErrorStack as SslError;
 use semver::{Version, VersionReq};
 
+use crate::config::module::ModuleName;
+
 #[derive(Debug)]
 pub enum Error {
+    ChecksumMismatch(PathBuf, String, String),
+    DependencyCycle(Vec<ModuleName>),
     DuplicateItem(String),
     FileNotFound(PathBuf),
     Generic(Box<ErrorTrait + Send + Sync>),
+    InjectedFailure(String),
     InvalidDirectory(PathBuf),
     InvalidFilePath(PathBuf),
     InvalidHostname(String),
+    InvalidModuleAbi(u32),
+    InvalidModuleName(String),
     InvalidModuleVersion(Version, VersionReq),
+    InvalidValue(String),
     Io(IoError),
+    KeyMismatch(PathBuf, PathBuf),
+    LibraryNotFound(ModuleName, Vec<PathBuf>),
+    LockMismatch(PathBuf),
+    MiddlewareCycle(Vec<ModuleName>),
+    MissingDependency(ModuleName, ModuleName),
+    Module(String),
+    ModuleConflict(ModuleName),
+    ModuleDenied(String),
+    ModuleInUse(String),
+    ModuleNotLoaded(String),
+    ModuleTimeout(String, String, Duration),
     NoHost,
     NoModsDir,
+    SandboxViolation(PathBuf),
     SecureBindOnInsecure,
     Ssl(SslError),
     Toml(toml::de::Error),
     Unknown,
+    WasmUnsupported(PathBuf),
+}
+
+impl Error {
+    /// Obtains the stable code of the error, e.g. `MAM-CFG-005`, which can be used by monitoring
+    /// systems to recognize specific conditions.
+    ///
+    /// Codes are made of the `MAM` prefix, a category (`CFG` for the configuration, `GEN` for
+    /// generic errors, `IO` for input and output, `MOD` for the modules and `TLS` for certificates
+    /// and keys) and a number; codes are never reused, even if the corresponding variant is removed.
+    pub fn code(&self) -> &'static str {
+        match &self {
+            Error::ChecksumMismatch(_, _, _) => "MAM-MOD-001",
+            Error::DependencyCycle(_) => "MAM-MOD-002",
+            Error::DuplicateItem(_) => "MAM-CFG-001",
+            Error::FileNotFound(_) => "MAM-IO-001",
+            Error::Generic(_) => "MAM-GEN-001",
+            Error::Io(_) => "MAM-IO-002",
+            Error::InjectedFailure(_) => "MAM-GEN-002",
+            Error::InvalidDirectory(_) => "MAM-CFG-002",
+            Error::InvalidFilePath(_) => "MAM-CFG-003",
+            Error::InvalidHostname(_) => "MAM-CFG-004",
+            Error::InvalidModuleAbi(_) => "MAM-MOD-003",
+            Error::InvalidModuleName(_) => "MAM-MOD-004",
+            Error::InvalidModuleVersion(_, _) => "MAM-MOD-005",
+            Error::InvalidValue(_) => "MAM-CFG-005",
+            Error::KeyMismatch(_, _) => "MAM-TLS-001",
+            Error::LibraryNotFound(_, _) => "MAM-MOD-006",
+            Error::LockMismatch(_) => "MAM-MOD-007",
+            Error::MiddlewareCycle(_) => "MAM-MOD-008",
+            Error::MissingDependency(_, _) => "MAM-MOD-009",
+            Error::Module(_) => "MAM-MOD-010",
+            Error::ModuleConflict(_) => "MAM-MOD-011",
+            Error::ModuleDenied(_) => "MAM-MOD-012",
+            Error::ModuleInUse(_) => "MAM-MOD-013",
+            Error::ModuleNotLoaded(_) => "MAM-MOD-014",
+            Error::ModuleTimeout(..) => "MAM-MOD-015",
+            Error::NoHost => "MAM-CFG-006",
+            Error::NoModsDir => "MAM-CFG-007",
+            Error::SandboxViolation(_) => "MAM-MOD-016",
+            Error::SecureBindOnInsecure => "MAM-TLS-002",
+            Error::Ssl(_) => "MAM-TLS-003",
+            Error::Toml(_) => "MAM-CFG-008",
+            Error::Unknown => "MAM-GEN-000",
+            Error::WasmUnsupported(_) => "MAM-MOD-017"
+        }
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
         match &self {
+            Error::ChecksumMismatch(path, expected, actual) => write!(f, "Checksum mismatch for '{}': expected SHA-256 {}, found {}", path.to_str().unwrap_or(""), expected, actual),
+            Error::DependencyCycle(cycle) => {
+                let cycle: Vec<&str> = cycle.iter().map(|m| m.as_str()).collect();
+                write!(f, "Cyclic module dependency: {}", cycle.join(" -> "))
+            },
             Error::DuplicateItem(name) => write!(f, "Duplicate item: '{}'", name),
             Error::FileNotFound(filename) => write!(f, "File not found: '{}'", filename.to_str().unwrap_or("")),
             Error::Generic(err) => write!(f, "Generic error: {}", err.as_ref()),
             Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::InjectedFailure(point) => write!(f, "Injected failure at '{}'", point),
             Error::InvalidDirectory(dir) => write!(f, "Invalid directory: '{}'", dir.to_str().unwrap_or("")),
             Error::InvalidFilePath(path) => write!(f, "Invalid path: '{}'", path.to_str().unwrap_or("")),
             Error::InvalidHostname(hostname) => write!(f, "Invalid hostname: '{}'", hostname),
+            Error::InvalidModuleAbi(abi) => write!(f, "Invalid module ABI version: {}; expected: {}.", abi, crate::abi::ABI_VERSION),
+            Error::InvalidModuleName(name) => write!(f, "Invalid module name: '{}'", name),
             Error::InvalidModuleVersion(ver, ver_req) => write!(f, "Invalid module version: {}; expected: {}.", ver, ver_req),
+            Error::InvalidValue(key) => write!(f, "Invalid value for '{}'.", key),
+            Error::KeyMismatch(cert, key) => write!(f, "Private key '{}' does not match certificate '{}'", key.to_str().unwrap_or(""), cert.to_str().unwrap_or("")),
+            Error::LibraryNotFound(name, paths) => {
+                let paths: Vec<String> = paths.iter().map(|p| format!("'{}'", p.to_str().unwrap_or(""))).collect();
+                write!(f, "Library of module '{}' not found; tried: {}", name, paths.join(", "))
+            },
+            Error::LockMismatch(path) => write!(f, "Resolved modules differ from lock file: '{}'", path.to_str().unwrap_or("")),
+            Error::MiddlewareCycle(cycle) => {
+                let cycle: Vec<&str> = cycle.iter().map(|m| m.as_str()).collect();
+                write!(f, "Cyclic middleware ordering: {}", cycle.join(" -> "))
+            },
+            Error::MissingDependency(name, dependency) => write!(f, "Module '{}' requires the module '{}', which is not enabled", name, dependency),
+            Error::Module(message) => write!(f, "Module error: {}", message),
+            Error::ModuleConflict(name) => write!(f, "Module '{}' is defined both globally and in a host", name),
+            Error::ModuleDenied(name) => write!(f, "Module '{}' is not allowed by the module policy", name),
+            Error::ModuleInUse(name) => write!(f, "Module '{}' is still in use and cannot be unloaded", name),
+            Error::ModuleNotLoaded(name) => write!(f, "Module '{}' is not loaded", name),
+            Error::ModuleTimeout(name, hook, deadline) => write!(f, "Module '{}' did not return from '{}' within {:?}", name, hook, deadline),
             Error::NoHost => write!(f, "No host specified; one required."),
             Error::NoModsDir => write!(f, "No directory specified for modules; required if modules are enabled."),
+            Error::SandboxViolation(path) => write!(f, "Path outside of the allowed paths: '{}'", path.display()),
             Error::SecureBindOnInsecure => write!(f, "Tried to bind to a secure port without a certificate"),
             Error::Ssl(stack) => write!(f, "SSL error: {}", stack),
             Error::Toml(err) => write!(f, "TOML error: {}", err),
             Error::Unknown => write!(f, "Unknown"),
+            Error::WasmUnsupported(path) => write!(f, "Cannot load the WebAssembly module '{}' without the `wasm` feature", path.to_str().unwrap_or("")),
         }
     }
 }
@@ -52,20 +148,39 @@ impl Display for Error {
 impl ErrorTrait for Error {
     fn description(&self) -> &str {
         match &self {
+            Error::ChecksumMismatch(_, _, _) => "checksum mismatch",
+            Error::DependencyCycle(_) => "dependency cycle",
             Error::DuplicateItem(_) => "duplicate item",
             Error::FileNotFound(_) => "file not found",
             Error::Generic(_) => "generic error",
             Error::Io(_) => "i/o error",
+            Error::InjectedFailure(_) => "injected failure",
             Error::InvalidDirectory(_) => "invalid directory",
             Error::InvalidFilePath(_) => "invalid file path",
             Error::InvalidHostname(_) => "invalid hostname",
+            Error::InvalidModuleAbi(_) => "invalid module abi",
+            Error::InvalidModuleName(_) => "invalid module name",
             Error::InvalidModuleVersion(_, _) => "invalid module version",
+            Error::InvalidValue(_) => "invalid value",
+            Error::KeyMismatch(_, _) => "key mismatch",
+            Error::LibraryNotFound(_, _) => "library not found",
+            Error::LockMismatch(_) => "lock file mismatch",
+            Error::MiddlewareCycle(_) => "middleware cycle",
+            Error::MissingDependency(_, _) => "missing dependency",
+            Error::Module(_) => "module error",
+            Error::ModuleConflict(_) => "module conflict",
+            Error::ModuleDenied(_) => "module denied",
+            Error::ModuleInUse(_) => "module in use",
+            Error::ModuleNotLoaded(_) => "module not loaded",
+            Error::ModuleTimeout(..) => "module timeout",
             Error::NoHost => "no host",
             Error::NoModsDir => "no mods_dir",
+            Error::SandboxViolation(_) => "sandbox violation",
             Error::SecureBindOnInsecure => "secure binding without certificate",
             Error::Ssl(_) => "ssl error",
             Error::Toml(_) => "toml error",
-            Error::Unknown => "unknown"
+            Error::Unknown => "unknown",
+            Error::WasmUnsupported(_) => "wasm unsupported"
         }
     }
 }
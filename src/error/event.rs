@@ -2,15 +2,22 @@ use std::error::Error as ErrorTrait;
 use std::fmt::{Display, Formatter};
 
 use chrono::{DateTime, Local};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 
+use crate::diagnostics::format_fields;
+use crate::diagnostics::origin::Origin;
 use crate::error::severity::Severity;
 use super::Error;
 
+/// Name of the field containing the code of the event (see `Error::code`).
+pub const CODE_FIELD: &str = "code";
+
 pub fn debug(description: &str) -> Event {
     Event {
         timestamp: Local::now(),
         description: description.to_owned(),
         error: None,
+        fields: Vec::new(),
         severity: Severity::Debug
     }
 }
@@ -19,6 +26,7 @@ pub fn info(description: &str) -> Event {
         timestamp: Local::now(),
         description: description.to_owned(),
         error: None,
+        fields: Vec::new(),
         severity: Severity::Information
     }
 }
@@ -27,6 +35,7 @@ pub fn warn(description: &str) -> Event {
         timestamp: Local::now(),
         description: description.to_owned(),
         error: None,
+        fields: Vec::new(),
         severity: Severity::Warning
     }
 }
@@ -35,6 +44,7 @@ pub fn err(description: &str) -> Event {
         timestamp: Local::now(),
         description: description.to_owned(),
         error: None,
+        fields: Vec::new(),
         severity: Severity::Error
     }
 }
@@ -43,48 +53,24 @@ pub fn critical(description: &str) -> Event {
         timestamp: Local::now(),
         description: description.to_owned(),
         error: None,
+        fields: Vec::new(),
         severity: Severity::Critical
     }
 }
 pub fn debug_error(description: &str, err: Error) -> Event {
-    Event {
-        timestamp: Local::now(),
-        description: description.to_owned(),
-        error: Some(err),
-        severity: Severity::Debug
-    }
+    Event::with_error(Severity::Debug, description, err)
 }
 pub fn info_error(description: &str, err: Error) -> Event {
-    Event {
-        timestamp: Local::now(),
-        description: description.to_owned(),
-        error: Some(err),
-        severity: Severity::Information
-    }
+    Event::with_error(Severity::Information, description, err)
 }
 pub fn warn_error(description: &str, err: Error) -> Event {
-    Event {
-        timestamp: Local::now(),
-        description: description.to_owned(),
-        error: Some(err),
-        severity: Severity::Warning
-    }
+    Event::with_error(Severity::Warning, description, err)
 }
 pub fn err_error(description: &str, err: Error) -> Event {
-    Event {
-        timestamp: Local::now(),
-        description: description.to_owned(),
-        error: Some(err),
-        severity: Severity::Error
-    }
+    Event::with_error(Severity::Error, description, err)
 }
 pub fn critical_error(description: &str, err: Error) -> Event {
-    Event {
-        timestamp: Local::now(),
-        description: description.to_owned(),
-        error: Some(err),
-        severity: Severity::Critical
-    }
+    Event::with_error(Severity::Critical, description, err)
 }
 
 #[derive(Debug)]
@@ -92,6 +78,7 @@ pub struct Event {
     pub(in self) timestamp: DateTime<Local>,
     pub(in self) description: String,
     pub(in self) error: Option<Error>,
+    pub(in self) fields: Vec<(String, String)>,
     pub(in self) severity: Severity
 }
 
@@ -101,22 +88,102 @@ impl Event {
             timestamp: Local::now(),
             description: description.to_owned(),
             error: None,
+            fields: Vec::new(),
             severity
         }
     }
+    /// Creates a new `Event` carrying the structured `fields`, as key-value pairs.
+    pub fn with_fields(severity: Severity, description: &str, fields: &[(&str, &str)]) -> Event {
+        Event {
+            timestamp: Local::now(),
+            description: description.to_owned(),
+            error: None,
+            fields: fields.iter().map(|(k, v)| ((*k).to_owned(), (*v).to_owned())).collect(),
+            severity
+        }
+    }
+    /// Creates a new `Event` caused by `error`, carrying the code of the error (see `Error::code`).
     pub fn with_error(severity: Severity, description: &str, error: Error) -> Event {
         Event {
             timestamp: Local::now(),
             description: description.to_owned(),
+            fields: vec![(CODE_FIELD.to_owned(), error.code().to_owned())],
             error: Some(error),
             severity
         }
     }
+    /// Sets the code of the event, returning the event.
+    pub fn with_code(mut self, code: &str) -> Event {
+        self.fields.retain(|(k, _)| k != CODE_FIELD);
+        self.fields.insert(0, (CODE_FIELD.to_owned(), code.to_owned()));
+        self
+    }
+
+    /// Obtains the severity of the event.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+    /// Obtains the description of the event.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+    /// Obtains the code of the event, if any.
+    pub fn code(&self) -> Option<&str> {
+        self.field(CODE_FIELD)
+    }
+    /// Obtains the error that caused the event, if any.
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+    /// Obtains the structured fields of the event, as key-value pairs.
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+    /// Obtains the value of the structured field `key`, if any.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+    /// Obtains the origin of the event, as described by its structured fields.
+    pub fn origin(&self) -> Origin {
+        let fields: Vec<(&str, &str)> = self.fields.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        Origin::from_fields(&fields)
+    }
 }
 
 impl Display for Event {
     fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
-        write!(f, "{} [{}]: {}\n", self.timestamp, self.severity, self.description)
+        let fields: Vec<(&str, &str)> = self.fields.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        write!(f, "{} [{}]: {}\n", self.timestamp, self.severity, format_fields(&self.description, &fields))
+    }
+}
+
+/// Serializes the fields of an event as a map.
+struct FieldMap<'a>(&'a [(String, String)]);
+
+impl<'a> Serialize for FieldMap<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+        S: Serializer {
+        serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+    }
+}
+
+impl Serialize for Event {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+        S: Serializer {
+        let mut event = serializer.serialize_struct("Event", 6)?;
+        event.serialize_field("timestamp", &self.timestamp.to_rfc3339())?;
+        event.serialize_field("severity", &self.severity)?;
+        match self.code() {
+            Some(code) => event.serialize_field("code", code)?,
+            None => event.skip_field("code")?
+        }
+        event.serialize_field("description", &self.description)?;
+        match self.error {
+            Some(ref error) => event.serialize_field("error", &error.to_string())?,
+            None => event.skip_field("error")?
+        }
+        event.serialize_field("fields", &FieldMap(&self.fields))?;
+        event.end()
     }
 }
 
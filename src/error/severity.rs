@@ -1,12 +1,17 @@
-use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
-use serde::de::{Deserialize, Deserializer, Error, Unexpected, Visitor};
+use serde::de::{self, Deserialize, Deserializer, Unexpected, Visitor};
 use serde::ser::{Serialize, Serializer};
 
+use crate::error::Error;
+
 /// Describes the severity of the Log report.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+///
+/// Severities are ordered from the least (`Debug`) to the most severe (`Critical`), so that a
+/// threshold `t` selects the events with severity `s >= t`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub enum Severity {
     /// The log should output every useful and technical information.
     ///
@@ -25,6 +30,35 @@ pub enum Severity {
     Critical
 }
 
+impl Severity {
+    /// Obtains the name of the severity, as in the configuration (e.g. `"warning"`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Debug => "debug",
+            Severity::Information => "information",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Critical => "critical"
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = Error;
+
+    /// Parses a severity from its case-insensitive name.
+    fn from_str(s: &str) -> Result<Severity, Error> {
+        match &s.to_lowercase()[..] {
+            "debug" => Ok(Severity::Debug),
+            "information" => Ok(Severity::Information),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(Error::InvalidValue("severity".to_owned()))
+        }
+    }
+}
+
 /// Case-insensitive visitor for `SeverityReport` deserialization.
 struct SeverityVisitor;
 
@@ -36,17 +70,8 @@ impl<'de> Visitor<'de> for SeverityVisitor {
     }
 
     fn visit_str<E>(self, v: &str) -> Result<Severity, E> where
-        E: Error {
-        let code_str = v.to_lowercase();
-
-        match &code_str[..] {
-            "debug" => Ok(Severity::Debug),
-            "information" => Ok(Severity::Information),
-            "warning" => Ok(Severity::Warning),
-            "error" => Ok(Severity::Error),
-            "critical" => Ok(Severity::Critical),
-            _ => Err(Error::invalid_value(Unexpected::Str(&code_str), &self))
-        }
+        E: de::Error {
+        v.parse().map_err(|_| E::invalid_value(Unexpected::Str(&v.to_lowercase()), &self))
     }
 }
 
@@ -60,13 +85,7 @@ impl<'de> Deserialize<'de> for Severity {
 impl Serialize for Severity {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
         S: Serializer {
-        match &self {
-            Severity::Debug => serializer.serialize_str("debug"),
-            Severity::Information => serializer.serialize_str("information"),
-            Severity::Warning => serializer.serialize_str("warning"),
-            Severity::Error => serializer.serialize_str("error"),
-            Severity::Critical => serializer.serialize_str("critical")
-        }
+        serializer.serialize_str(self.name())
     }
 }
 
@@ -88,31 +107,6 @@ impl Default for Severity {
     }
 }
 
-impl PartialOrd for Severity {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for Severity {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self == other { return Ordering::Equal; }
-
-        use Severity::*;
-
-        match (self, other) {
-            (Debug, _) => Ordering::Less,
-            (Information, Debug) => Ordering::Greater,
-            (Information, _) => Ordering::Less,
-            (Warning, Debug) | (Warning, Information) => Ordering::Greater,
-            (Warning, _) => Ordering::Less,
-            (Error, Critical) => Ordering::Less,
-            (Error, _) => Ordering::Greater,
-            (Critical, _) => Ordering::Greater
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -198,6 +192,20 @@ mod tests {
         assert!(Critical >= Critical);
     }
 
+    #[test]
+    /// Tests parsing and naming of the variants.
+    fn test_from_str() {
+        assert_eq!("debug".parse::<Severity>().unwrap(), Severity::Debug);
+        assert_eq!("Information".parse::<Severity>().unwrap(), Severity::Information);
+        assert_eq!("WARNING".parse::<Severity>().unwrap(), Severity::Warning);
+        assert!("dummy".parse::<Severity>().is_err());
+
+        for severity in [Severity::Debug, Severity::Information, Severity::Warning, Severity::Error, Severity::Critical].iter() {
+            assert_eq!(severity.name().parse::<Severity>().unwrap(), *severity);
+        }
+        assert_eq!(Severity::Warning.max(Severity::Error), Severity::Error);
+    }
+
     #[test]
     #[should_panic]
     /// Tests deserialization of an invalid variant.
@@ -0,0 +1,316 @@
+//! Request and response abstraction through which the modules take part in the handling of the
+//! requests (requires the `http` feature).
+//!
+//! Every request served by a host goes through the `MammothInterface::on_request` hook of the
+//! middleware modules of the host, in the order of its middleware chain, and then of its handler
+//! modules, until a module answers it (see `ConfigurationFile::request_chain_for`); the response
+//! then goes back through the `MammothInterface::on_response` hook of the modules that received
+//! the request, in reverse order (see `LoadedModuleSet::handle_request`), e.g.
+//! ```rust,ignore
+//! fn on_request(&self, request: &mut Request) -> Option<Response> {
+//!     match request.headers().get("Authorization") {
+//!         Some(_) => None,
+//!         None => Some(Response::new(401))
+//!     }
+//! }
+//! ```
+//! Requests and responses are independent of the underlying HTTP implementation, so that they can
+//! cross the boundary of the module libraries (see `abi::RawHttpMessage`): their heads are
+//! serialized as TOML, while their bodies are passed as raw bytes.
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// Method of a request.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Method {
+    Connect,
+    Delete,
+    Get,
+    Head,
+    Options,
+    Patch,
+    Post,
+    Put,
+    Trace
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match self {
+            Method::Connect => "CONNECT",
+            Method::Delete => "DELETE",
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Options => "OPTIONS",
+            Method::Patch => "PATCH",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Trace => "TRACE"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Method {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "CONNECT" => Ok(Method::Connect),
+            "DELETE" => Ok(Method::Delete),
+            "GET" => Ok(Method::Get),
+            "HEAD" => Ok(Method::Head),
+            "OPTIONS" => Ok(Method::Options),
+            "PATCH" => Ok(Method::Patch),
+            "POST" => Ok(Method::Post),
+            "PUT" => Ok(Method::Put),
+            "TRACE" => Ok(Method::Trace),
+            _ => Err(Error::InvalidValue("method".to_owned()))
+        }
+    }
+}
+
+/// Structure that contains the headers of a request or a response, in order; names are compared
+/// case-insensitively.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Headers(Vec<(String, String)>);
+
+impl Headers {
+    /// Creates a new, empty `Headers` structure.
+    pub fn new() -> Headers {
+        Headers(Vec::new())
+    }
+
+    /// Obtains the value of the first header named `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    /// Obtains the values of every header named `name`, in order.
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.0.iter()
+            .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+    /// Returns `true` if at least one header is named `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+    /// Sets the header `name` to `value`, replacing every header with the same name.
+    pub fn insert(&mut self, name: &str, value: &str) {
+        self.remove(name);
+        self.append(name, value);
+    }
+    /// Adds the header `name` with `value`, keeping the headers with the same name.
+    pub fn append(&mut self, name: &str, value: &str) {
+        self.0.push((name.to_owned(), value.to_owned()));
+    }
+    /// Removes every header named `name`.
+    pub fn remove(&mut self, name: &str) {
+        self.0.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+    }
+    /// Obtains the headers as name-value pairs, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+    /// Obtains the number of headers.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    /// Returns `true` if there are no headers.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Structure that contains a request received by a host.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Request {
+    method: Method,
+    path: String,
+    query: Option<String>,
+    headers: Headers,
+    #[serde(skip)]
+    body: Vec<u8>
+}
+
+impl Request {
+    /// Creates a new `Request` structure without headers and body, given its method and target,
+    /// i.e. its path optionally followed by `?` and the query string.
+    pub fn new(method: Method, target: &str) -> Request {
+        let (path, query) = match target.find('?') {
+            Some(index) => (&target[..index], Some(target[index + 1..].to_owned())),
+            None => (target, None)
+        };
+
+        Request {
+            method,
+            path: path.to_owned(),
+            query,
+            headers: Headers::new(),
+            body: Vec::new()
+        }
+    }
+
+    /// Obtains the method of the request.
+    pub fn method(&self) -> Method {
+        self.method
+    }
+    /// Obtains the path of the request, without the query string.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    /// Sets the path of the request, e.g. to rewrite it.
+    pub fn set_path(&mut self, path: &str) {
+        self.path = path.to_owned();
+    }
+    /// Obtains the query string of the request, if any.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+    /// Sets the query string of the request.
+    pub fn set_query(&mut self, query: Option<&str>) {
+        self.query = query.map(str::to_owned);
+    }
+    /// Obtains the headers of the request.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+    /// Obtains the headers of the request, to modify them.
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+    /// Obtains the body of the request.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+    /// Sets the body of the request.
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = body;
+    }
+
+    /// Serializes the head of the request, i.e. everything but its body.
+    pub(crate) fn encode_head(&self) -> String {
+        toml::to_string(self).unwrap_or_default()
+    }
+    /// Reconstructs a request given its serialized head and its body.
+    pub(crate) fn decode(head: &str, body: Vec<u8>) -> Result<Request, Error> {
+        let mut request: Request = toml::from_str(head)?;
+        request.body = body;
+        Ok(request)
+    }
+}
+
+/// Structure that contains the response to a request.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Response {
+    status: u16,
+    headers: Headers,
+    #[serde(skip)]
+    body: Vec<u8>
+}
+
+impl Response {
+    /// Creates a new `Response` structure without headers and body, given its status code.
+    pub fn new(status: u16) -> Response {
+        Response {
+            status,
+            headers: Headers::new(),
+            body: Vec::new()
+        }
+    }
+    /// Creates a new `Response` structure with status 200, given its body and the value of its
+    /// `Content-Type` header.
+    pub fn with_body(body: Vec<u8>, content_type: &str) -> Response {
+        let mut response = Response::new(200);
+        response.headers.insert("Content-Type", content_type);
+        response.body = body;
+        response
+    }
+
+    /// Obtains the status code of the response.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+    /// Sets the status code of the response.
+    pub fn set_status(&mut self, status: u16) {
+        self.status = status;
+    }
+    /// Obtains the headers of the response.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+    /// Obtains the headers of the response, to modify them.
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+    /// Obtains the body of the response.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+    /// Sets the body of the response.
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = body;
+    }
+
+    /// Serializes the head of the response, i.e. everything but its body.
+    pub(crate) fn encode_head(&self) -> String {
+        toml::to_string(self).unwrap_or_default()
+    }
+    /// Reconstructs a response given its serialized head and its body.
+    pub(crate) fn decode(head: &str, body: Vec<u8>) -> Result<Response, Error> {
+        let mut response: Response = toml::from_str(head)?;
+        response.body = body;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::http::{Headers, Method, Request, Response};
+
+    #[test]
+    /// Tests the headers of requests and responses.
+    fn test_headers() {
+        let mut headers = Headers::new();
+        headers.append("Accept", "text/html");
+        headers.append("accept", "application/json");
+        headers.insert("Content-Type", "text/plain");
+
+        assert_eq!(headers.get("ACCEPT"), Some("text/html"));
+        assert_eq!(headers.get_all("Accept"), vec!["text/html", "application/json"]);
+        assert!(headers.contains("content-type"));
+        headers.insert("accept", "*/*");
+        assert_eq!(headers.get_all("Accept"), vec!["*/*"]);
+        headers.remove("Content-Type");
+        assert_eq!(headers.iter().collect::<Vec<_>>(), vec![("accept", "*/*")]);
+        assert_eq!(headers.len(), 1);
+    }
+
+    #[test]
+    /// Tests the serialization of requests and responses.
+    fn test_encoding() {
+        let mut request = Request::new(Method::Post, "/login?next=/home");
+        request.headers_mut().insert("Host", "example.com");
+        request.set_body(vec![0, 159, 146, 150]);
+        assert_eq!(request.path(), "/login");
+        assert_eq!(request.query(), Some("next=/home"));
+        assert_eq!(Request::decode(&request.encode_head(), request.body().to_vec()).unwrap(), request);
+        assert_eq!(Request::new(Method::Get, "/").query(), None);
+
+        let response = Response::with_body(b"hello".to_vec(), "text/plain");
+        assert_eq!(response.status(), 200);
+        assert_eq!(Response::decode(&response.encode_head(), response.body().to_vec()).unwrap(), response);
+        assert!(Response::decode("status = \"ok\"", Vec::new()).is_err());
+
+        assert_eq!("PATCH".parse::<Method>().unwrap(), Method::Patch);
+        assert_eq!(Method::Options.to_string(), "OPTIONS");
+        assert!("get".parse::<Method>().is_err());
+    }
+}
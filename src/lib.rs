@@ -3,25 +3,52 @@ extern crate lazy_static;
 #[macro_use]
 extern crate serde_derive;
 
+pub mod abi;
+pub mod bus;
 pub mod config;
 pub mod diagnostics;
 pub mod error;
+#[cfg(feature = "http")]
+pub mod http;
 pub mod loaded;
+pub mod metadata;
+pub mod metrics;
+pub mod sandbox;
+pub mod testing;
 pub mod version;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 use std::any::Any;
 
+#[cfg(feature = "actix")]
+use actix_web::web::ServiceConfig;
+
+use toml::Value;
+
+use crate::bus::BusHandle;
 use crate::diagnostics::{Log, Logger};
+use crate::diagnostics::report::HealthStatus;
 use crate::error::Error;
+use crate::metrics::MetricsCollector;
+use crate::sandbox::SandboxedFs;
+#[cfg(feature = "http")]
+use crate::http::{Request, Response};
 
 pub mod prelude {
     #[cfg(feature = "mammoth_module")]
-    pub use mammoth_macro::mammoth_module;
+    pub use mammoth_macro::{mammoth_module, Log, Validate};
 
-    pub use crate::MammothInterface;
+    pub use crate::{Handler, MammothInterface, Middleware, Service};
+    pub use crate::bus::{BusHandle, Message};
     pub use crate::error::Error;
     pub use crate::error::severity::Severity;
+    #[cfg(feature = "http")]
+    pub use crate::http::{Method, Request, Response};
     pub use crate::diagnostics::{Log, Logger, AsyncLoggerReference};
+    pub use crate::diagnostics::report::HealthStatus;
+    pub use crate::metrics::MetricsCollector;
+    pub use crate::sandbox::SandboxedFs;
 
     pub use toml::Value;
     pub use semver;
@@ -29,20 +56,72 @@ pub mod prelude {
 
 /// Trait that contains the functions that should be implemented by a module or a handler.
 pub trait MammothInterface: Any + Send + Sync + Log {
+    /// Function that is called when the module is loaded, before `on_load`, with a handle to the
+    /// message bus shared by the loaded modules (see `bus`).
+    ///
+    /// Modules interacting with other modules should keep the handle: dropping it cancels the
+    /// subscriptions made through it.
+    fn on_bus(&self, _: BusHandle) {}
+    /// Function that is called when the module is loaded, before `on_load`, with the handle
+    /// through which the module should access the files under the paths it is allowed to access
+    /// (see `sandbox`); the handle denies every access if the module declares no allowed paths.
+    fn on_sandbox(&self, _: SandboxedFs) {}
     /// Function that is called when the library is loaded.
     fn on_load(&self) {}
-    // FOR_LATER: load Actix crate and uncomment the following.
-    // /// Function that is called during the construction of the server.
-    // ///
-    // /// It should output a "factory" function that can be used in `App::configure()`.
-    // fn on_factory(&self, _cfg: &mut ServiceConfig) {}
+    /// Function that is called during the construction of the server, so that the module can
+    /// register its routes and services (requires the `actix` feature; see
+    /// `LoadedModuleSet::apply`).
+    ///
+    /// Library modules are only called if they are built by the same compiler and against the
+    /// same version of `mammoth-setup` as the server (see `abi::FACTORY_FINGERPRINT`).
+    #[cfg(feature = "actix")]
+    fn on_factory(&self, _: &mut ServiceConfig) {}
 
-    // FOR_LATER: Add Middleware support.
-    // FOR_LATER: Add support for interaction between interfaces.
+    /// Function that is called for every request served by a host the module is enabled for,
+    /// before the request reaches the handler modules (requires the `http` feature).
+    ///
+    /// Middleware modules may rewrite the request and pass it on by returning `None`, or answer it
+    /// directly; handler modules answer the requests they serve.
+    #[cfg(feature = "http")]
+    fn on_request(&self, _: &mut Request) -> Option<Response> { None }
+    /// Function that is called with the response to a request that the module received through
+    /// `on_request`, so that middleware modules may rewrite it (requires the `http` feature).
+    #[cfg(feature = "http")]
+    fn on_response(&self, _: &Request, _: &mut Response) {}
 
     /// Function that is called when the server is validating the configuration.
     fn on_validation(&self, _: &mut Logger) -> Result<(), Error>;
 
+    /// Function that is called after all the modules are loaded and validated, before the server
+    /// starts.
+    ///
+    /// Heavy initialization should be deferred here rather than performed in `on_load`.
+    fn on_start(&self) {}
+
+    /// Function that is called to check the health of the module while the server is running
+    /// (see `LoadedModuleSet::health_report`); it should return quickly.
+    fn on_health(&self) -> HealthStatus { HealthStatus::Healthy }
+
+    /// Function that is called to collect the metrics of the module while the server is running
+    /// (see `LoadedModuleSet::collect_metrics`); it should return quickly.
+    fn on_metrics(&self, _: &mut MetricsCollector) {}
+
+    /// Function that is called when the configuration is reloaded, with the new configuration of
+    /// the module (an empty table if the module has no configuration).
+    fn on_config_reload(&self, _: &Value) {}
+
     /// Function that is called when the server is shut down.
     fn on_shutdown(&self) {}
-}
\ No newline at end of file
+}
+
+/// Trait implemented by the modules that terminate requests, producing their responses (see
+/// `ModuleKind::Handler`).
+pub trait Handler: MammothInterface {}
+
+/// Trait implemented by the modules that wrap the requests terminated by other modules (see
+/// `ModuleKind::Middleware`).
+pub trait Middleware: MammothInterface {}
+
+/// Trait implemented by the modules that run alongside the server without taking part in the
+/// handling of requests (see `ModuleKind::Service`).
+pub trait Service: MammothInterface {}
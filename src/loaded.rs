@@ -1,2 +1,6 @@
 pub mod library;
-
+pub mod lock;
+pub mod registry;
+pub mod shared;
+pub mod tls;
+pub mod watchdog;
\ No newline at end of file
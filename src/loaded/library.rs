@@ -1,18 +1,66 @@
+use std::env;
+use std::fs;
+use std::panic;
+use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
+use std::process;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
+#[cfg(feature = "actix")]
+use actix_web::App;
+#[cfg(feature = "actix")]
+use actix_web::dev::{ServiceFactory, ServiceRequest};
+#[cfg(feature = "actix")]
+use actix_web::web::ServiceConfig;
 use libloading::Library;
+use toml::Value;
 
 use crate::MammothInterface;
-use crate::config::module::DYLIB_EXT;
+use crate::abi;
+use crate::bus::MessageBus;
+use crate::config::HostIdentifier;
+use crate::config::module::{load_order, Instancing, LibraryNames, LoadPolicy, Module, ModuleName};
 use crate::error::Error;
-use crate::diagnostics::Id;
+use crate::error::event::Event;
+use crate::error::severity::Severity;
+#[cfg(feature = "http")]
+use crate::http::{Request, Response};
+use crate::diagnostics::{AsyncLoggerReference, Id, Logger};
+use crate::diagnostics::report::{HealthReport, HealthStatus, ModuleHealth};
+use crate::loaded::watchdog;
+use crate::metadata::{ModuleKind, ModuleMetadata};
+use crate::metrics::MetricsCollector;
+use crate::version::CompatibilityPolicy;
 
 pub struct LoadedLibrary {
     pub path: PathBuf,
     pub library: Library
 }
 
+impl LoadedLibrary {
+    /// Loads the library at `path`; its shutdown hook (see `abi::library_shutdown`) runs when the
+    /// structure is dropped, so only the owners of the modules of the library (i.e. a
+    /// `LoadedModuleSet` or a `ModuleHarness`) hold one: a library is inspected through a plain
+    /// `Library` instead.
+    pub fn open<P>(path: P) -> Result<LoadedLibrary, Error>
+        where
+            P: AsRef<Path>
+    {
+        let path = path.as_ref();
+        let library = Library::new(path)?;
+
+        Ok(LoadedLibrary { path: path.to_path_buf(), library })
+    }
+}
+
+impl Drop for LoadedLibrary {
+    fn drop(&mut self) {
+        abi::library_shutdown(&self.library);
+    }
+}
+
 impl Id for LoadedLibrary {
     type Identifier = PathBuf;
 
@@ -23,14 +71,33 @@ impl Id for LoadedLibrary {
 
 #[allow(dead_code)]
 pub struct LoadedModule {
-    pub(in self) library: Arc<String>,
-    pub(in self) interface: Arc<Box<MammothInterface>>
+    pub(in self) library: Arc<ModuleName>,
+    pub(in self) interface: Arc<Box<MammothInterface>>,
+    pub(in self) metadata: Option<ModuleMetadata>,
+    // The host of a per-host instance, `None` for shared instances.
+    pub(in self) owner: Option<HostIdentifier>,
+    pub(in self) kind: ModuleKind,
+    // Declared after the interface, so that the library containing its code outlives it.
+    pub(in self) source: Option<Arc<LoadedLibrary>>
 }
 
+/// Structure that contains the loaded modules, along with the libraries containing their code.
+///
+/// Module interfaces are `Send` and `Sync` (see `MammothInterface`), and so is the set; use a
+/// `SharedModuleSet` to call into the modules from several threads.
 pub struct LoadedModuleSet {
     default_path: PathBuf,
-    libraries: Vec<Arc<LoadedLibrary>>,
-    modules: Vec<Arc<LoadedModule>>
+    policy: CompatibilityPolicy,
+    library_names: LibraryNames,
+    module_policy: LoadPolicy,
+    timeout: Option<Duration>,
+    environment: Option<Value>,
+    logger: Option<AsyncLoggerReference>,
+    bus: MessageBus,
+    // Fields are dropped in declaration order: the modules must be dropped before the libraries
+    // containing their code are unloaded.
+    modules: Vec<Arc<LoadedModule>>,
+    libraries: Vec<Arc<LoadedLibrary>>
 }
 
 impl LoadedModuleSet {
@@ -40,38 +107,552 @@ impl LoadedModuleSet {
     {
         LoadedModuleSet {
             default_path: default_path.as_ref().to_path_buf(),
+            policy: CompatibilityPolicy::default(),
+            library_names: LibraryNames::default(),
+            module_policy: LoadPolicy::new(),
+            timeout: Some(watchdog::DEFAULT_TIMEOUT),
+            environment: None,
+            logger: None,
+            bus: MessageBus::new(),
             libraries: Vec::new(),
             modules: Vec::new()
         }
     }
 
+    /// Obtains the compatibility policy used to check the versions of the loaded modules.
+    pub fn policy(&self) -> &CompatibilityPolicy {
+        &self.policy
+    }
+    /// Sets the compatibility policy used to check the versions of the modules loaded from now on.
+    pub fn set_policy(&mut self, policy: CompatibilityPolicy) {
+        self.policy = policy;
+    }
+    /// Obtains the file names of the libraries searched in the default directory.
+    pub fn library_names(&self) -> &LibraryNames {
+        &self.library_names
+    }
+    /// Sets the file names of the libraries searched in the default directory from now on.
+    pub fn set_library_names(&mut self, names: LibraryNames) {
+        self.library_names = names;
+    }
+
+    /// Obtains the policy restricting which modules can be loaded.
+    pub fn module_policy(&self) -> &LoadPolicy {
+        &self.module_policy
+    }
+    /// Sets the policy restricting which modules can be loaded from now on.
+    pub fn set_module_policy(&mut self, policy: LoadPolicy) {
+        self.module_policy = policy;
+    }
+    /// Obtains how long the set waits for a module to return from `on_load`, `on_validation` and
+    /// `on_shutdown` (see `loaded::watchdog`); `None` if it waits indefinitely.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+    /// Sets how long the set waits for a module to return from its lifecycle calls from now on.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+    /// Obtains the environment passed to the constructors of the modules (see
+    /// `abi::current_environment`), if any.
+    pub fn environment(&self) -> Option<&Value> {
+        self.environment.as_ref()
+    }
+    /// Sets the environment passed to the constructors of the modules loaded or reloaded from now
+    /// on (see `ConfigurationFile::environment_for`).
+    ///
+    /// As their configuration, shared instances keep the environment they are first constructed
+    /// with.
+    pub fn set_environment(&mut self, environment: Option<Value>) {
+        self.environment = environment;
+    }
+    /// Obtains the logger registered on the modules, if any.
+    pub fn logger(&self) -> Option<&AsyncLoggerReference> {
+        self.logger.as_ref()
+    }
+    /// Sets the logger registered on the modules loaded or reloaded from now on, before they are
+    /// notified through `on_load` (see `Log::register_logger`).
+    pub fn set_logger(&mut self, logger: AsyncLoggerReference) {
+        self.logger = Some(logger);
+    }
+
+    /// Obtains the message bus shared by the loaded modules (see `MammothInterface::on_bus`).
+    pub fn bus(&self) -> &MessageBus {
+        &self.bus
+    }
+
     pub fn load<P>(&mut self, path: P) -> Result<Arc<LoadedLibrary>, Error>
         where
             P: AsRef<Path>
     {
         let path = path.as_ref();
-        let lib = self.libraries.iter().find(|e| e.path == path);
+        let lib = self.libraries.iter().rev().find(|e| e.path == path);
 
         if let Some(lib) = lib {
             Ok(lib.clone())
         } else {
-            let library = Library::new(path)?;
-            let path = path.to_path_buf();
-            let loaded = Arc::new(LoadedLibrary { path, library });
+            let loaded = Arc::new(LoadedLibrary::open(path)?);
             self.libraries.push(loaded.clone());
             Ok(loaded)
         }
     }
 
-    pub fn lib_path(&self, name: &str) -> PathBuf
+    /// Loads `modules`, ordering them so that every module is loaded after the modules it requires
+    /// (see `config::module::load_order`).
+    pub fn load_all(&mut self, modules: &[&Module]) -> Result<(), Error> {
+        let default_path = self.default_path.clone();
+        let names = self.library_names.clone();
+        for module in load_order(modules, |m| m.requirements_in(&default_path, &names))? {
+            module.load_into(self)?;
+        }
+
+        Ok(())
+    }
+
+    /// Obtains the path of the library of the module `name` within the default directory, using
+    /// the file names of the set (see `set_library_names`).
+    pub fn lib_path(&self, name: &ModuleName) -> PathBuf
     {
-        self.default_path.join(name.to_owned() + DYLIB_EXT)
+        self.library_names.resolve(&self.default_path, name)
+    }
+
+    /// Loads `modules` for the host `host` as by `load_all`, skipping the modules that are
+    /// already loaded for it: a shared module (see `Instancing::Shared`) is loaded once for every
+    /// host, while a per-host module gets a new instance for each host.
+    ///
+    /// Optional modules (see `Module::is_required`) that fail to load are skipped; use
+    /// `load_for_host_with` to be warned about them.
+    pub fn load_for_host(&mut self, host: &HostIdentifier, modules: &[&Module]) -> Result<(), Error> {
+        self.load_for_host_with(host, modules, &mut Vec::<Event>::new())
+    }
+    /// Loads `modules` for the host `host` as by `load_for_host`, logging a warning into `logger`
+    /// for every optional module that fails to load.
+    pub fn load_for_host_with(&mut self, host: &HostIdentifier, modules: &[&Module], logger: &mut dyn Logger) -> Result<(), Error> {
+        let default_path = self.default_path.clone();
+        let names = self.library_names.clone();
+        for module in load_order(modules, |m| m.requirements_in(&default_path, &names))? {
+            let owner = match module.instancing() {
+                Instancing::Shared => None,
+                Instancing::PerHost => Some(host)
+            };
+            if self.modules.iter().any(|m| m.library.as_ref() == module.module_name() && m.owner.as_ref() == owner) {
+                continue;
+            }
+            let libraries = self.libraries.len();
+            match module.load_into_for(self, owner) {
+                Err(err) if !module.is_required() => {
+                    let desc = format!("Optional module '{}' of host '{}' cannot be loaded and is skipped: {}.", module.name(), host, err);
+                    logger.log_error(Severity::Warning, &desc, &err);
+                    // Unloads the library of the skipped module unless something else uses it.
+                    let loaded = self.libraries.split_off(libraries);
+                    self.libraries.extend(loaded.into_iter().filter(|l| Arc::strong_count(l) > 1));
+                },
+                result => result?
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn insert(&mut self, name: &str, interface: Arc<Box<MammothInterface>>) {
+    /// Inserts the module `name` constructed from `source` (`None` for WebAssembly modules), along
+    /// with the metadata exported by its library, if any; the kind of the module is the one
+    /// declared in its metadata, if any, or else `ModuleKind::Service`.
+    pub fn insert(&mut self, name: &ModuleName, interface: Arc<Box<MammothInterface>>, metadata: Option<ModuleMetadata>, source: Option<Arc<LoadedLibrary>>) {
+        let kind = metadata.as_ref().and_then(|m| m.kind()).unwrap_or(ModuleKind::Service);
+        self.insert_for(name, interface, metadata, source, None, kind);
+    }
+    /// Inserts the module `name` of kind `kind` as by `insert`, as the instance owned by `owner` if
+    /// any (see `Instancing::PerHost`).
+    pub fn insert_for(&mut self, name: &ModuleName, interface: Arc<Box<dyn MammothInterface>>, metadata: Option<ModuleMetadata>, source: Option<Arc<LoadedLibrary>>, owner: Option<HostIdentifier>, kind: ModuleKind) {
         self.modules.push(Arc::new(LoadedModule{
-            library: Arc::new(name.to_owned()),
-            interface
+            library: Arc::new(name.clone()),
+            interface,
+            metadata,
+            owner,
+            kind,
+            source
         }));
     }
-}
\ No newline at end of file
+
+    /// Obtains the names of the loaded modules, in loading order.
+    pub fn module_names(&self) -> Vec<&str> {
+        self.modules.iter().map(|m| m.library.as_str()).collect()
+    }
+
+    /// Obtains the metadata of the loaded module `name`.
+    ///
+    /// Returns `None` if the module is not loaded or its library does not describe itself.
+    pub fn metadata(&self, name: &str) -> Option<&ModuleMetadata> {
+        self.modules.iter()
+            .find(|m| m.library.as_str() == name)
+            .and_then(|m| m.metadata.as_ref())
+    }
+    /// Obtains the kind of the loaded module `name` (see `Module::effective_kind`).
+    pub fn kind(&self, name: &str) -> Option<ModuleKind> {
+        self.modules.iter()
+            .find(|m| m.library.as_str() == name)
+            .map(|m| m.kind)
+    }
+    /// Obtains the names of the loaded modules of kind `kind`, in loading order.
+    pub fn modules_of_kind(&self, kind: ModuleKind) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for module in self.modules.iter().filter(|m| m.kind == kind) {
+            if !names.contains(&module.library.as_str()) {
+                names.push(module.library.as_str());
+            }
+        }
+        names
+    }
+    /// Obtains the first instance of the loaded module `name`, in loading order.
+    pub fn instance(&self, name: &str) -> Option<Arc<Box<dyn MammothInterface>>> {
+        self.modules.iter()
+            .find(|m| m.library.as_str() == name)
+            .map(|m| m.interface.clone())
+    }
+    /// Obtains the instance of the loaded module `name` serving the host `host`: its per-host
+    /// instance if any, the shared one otherwise.
+    pub fn instance_for(&self, name: &str, host: &HostIdentifier) -> Option<Arc<Box<dyn MammothInterface>>> {
+        let mut instances = self.modules.iter().filter(|m| m.library.as_str() == name);
+        instances.clone().find(|m| m.owner.as_ref() == Some(host))
+            .or_else(|| instances.find(|m| m.owner.is_none()))
+            .map(|m| m.interface.clone())
+    }
+
+    /// Lets every loaded module register its routes and services into `cfg` through
+    /// `on_factory`, in loading order (requires the `actix` feature).
+    ///
+    /// The routes registered by a library module run code of the library, so the application
+    /// they are registered into must be dropped before the module is unloaded.
+    #[cfg(feature = "actix")]
+    pub fn configure(&self, cfg: &mut ServiceConfig) {
+        for module in self.modules.iter() {
+            module.interface.on_factory(cfg);
+        }
+    }
+    /// Applies the factory of every loaded module to `app` (see `configure`), e.g.
+    /// `HttpServer::new(move || mod_set.read().apply(App::new()))`.
+    #[cfg(feature = "actix")]
+    pub fn apply<T>(&self, app: App<T>) -> App<T>
+        where
+            T: ServiceFactory<ServiceRequest, Config = (), Error = actix_web::Error, InitError = ()>
+    {
+        app.configure(|cfg| self.configure(cfg))
+    }
+    /// Handles `request` for the host `host` through the loaded modules named in `chain` (see
+    /// `ConfigurationFile::request_chain_for`), returning its response (requires the `http`
+    /// feature).
+    ///
+    /// The request goes through `on_request` of the modules in order, until one of them answers
+    /// it; the response then goes through `on_response` of the modules that received the request,
+    /// in reverse order. Requests that no module answers get a `404` response.
+    #[cfg(feature = "http")]
+    pub fn handle_request(&self, host: &HostIdentifier, chain: &[&str], mut request: Request) -> Response {
+        let mut visited = Vec::new();
+        let mut response = None;
+        for interface in chain.iter().filter_map(|name| self.instance_for(name, host)) {
+            response = interface.on_request(&mut request);
+            visited.push(interface);
+            if response.is_some() {
+                break;
+            }
+        }
+
+        let mut response = response.unwrap_or_else(|| Response::new(404));
+        for interface in visited.iter().rev() {
+            interface.on_response(&request, &mut response);
+        }
+        response
+    }
+
+    /// Notifies every loaded module that all the modules are loaded and validated, in loading
+    /// order.
+    pub fn start(&self) {
+        for module in self.modules.iter() {
+            module.interface.on_start();
+        }
+    }
+
+    /// Checks the health of every instance of the loaded modules through `on_health`, in loading
+    /// order; instances that panic are reported as unhealthy.
+    pub fn health_report(&self) -> HealthReport {
+        let mut report = HealthReport::new();
+        for module in self.modules.iter() {
+            let status = panic::catch_unwind(AssertUnwindSafe(|| module.interface.on_health()))
+                .unwrap_or_else(|_| HealthStatus::Unhealthy("module panicked during health check".to_owned()));
+            report.push(ModuleHealth::new(module.library.as_str(), module.owner.clone(), status));
+        }
+        report
+    }
+
+    /// Collects the metrics of every instance of the loaded modules through `on_metrics`, in
+    /// loading order, labelling their samples with the name of the module and, for per-host
+    /// instances, with the host; instances that panic contribute no sample.
+    pub fn collect_metrics(&self) -> MetricsCollector {
+        let mut collector = MetricsCollector::new();
+        for module in self.modules.iter() {
+            let samples = panic::catch_unwind(AssertUnwindSafe(|| {
+                let mut samples = MetricsCollector::new();
+                module.interface.on_metrics(&mut samples);
+                samples
+            })).unwrap_or_default();
+
+            let host = module.owner.as_ref().map(|h| h.to_string());
+            let mut labels = vec![("module", module.library.as_str())];
+            if let Some(ref host) = host {
+                labels.push(("host", host.as_str()));
+            }
+            collector.merge(samples, &labels);
+        }
+        collector
+    }
+
+    /// Notifies the loaded modules that the configuration is reloaded, in loading order, passing
+    /// to each one the configuration of the module with the same name in `modules`.
+    ///
+    /// Loaded modules that are not in `modules` are not notified.
+    pub fn reload_config(&self, modules: &[&Module]) {
+        for module in self.modules.iter() {
+            if let Some(config) = modules.iter().find(|m| m.module_name() == module.library.as_ref()) {
+                let value = config.effective_config().unwrap_or_else(|| Value::Table(Default::default()));
+                module.interface.on_config_reload(&value);
+            }
+        }
+    }
+    /// Notifies the loaded modules serving the host `host` that the configuration is reloaded, as
+    /// by `reload_config`; per-host instances of other hosts are not notified.
+    pub fn reload_config_for(&self, host: &HostIdentifier, modules: &[&Module]) {
+        for module in self.modules.iter().filter(|m| m.owner.is_none() || m.owner.as_ref() == Some(host)) {
+            if let Some(config) = modules.iter().find(|m| m.module_name() == module.library.as_ref()) {
+                let value = config.effective_config().unwrap_or_else(|| Value::Table(Default::default()));
+                module.interface.on_config_reload(&value);
+            }
+        }
+    }
+
+    /// Unloads every instance of the module `name`, along with its library if no other module
+    /// uses it.
+    ///
+    /// Unloading follows this protocol, preventing any code of the library from being used after
+    /// the library itself is unloaded:
+    /// 1. if any instance of the module is still referenced outside of the set, nothing is
+    ///    unloaded and `Error::ModuleInUse` is returned;
+    /// 2. every instance is notified through `on_shutdown`, in reverse loading order, waiting for
+    ///    at most the timeout of the set for each of them;
+    /// 3. the instances are dropped, together with their interfaces;
+    /// 4. the library is dropped (and thus unloaded) once no module of the set references it and
+    ///    no `Arc<LoadedLibrary>` obtained through `load` is still alive.
+    pub fn unload(&mut self, name: &str) -> Result<(), Error> {
+        let instances: Vec<&Arc<LoadedModule>> = self.modules.iter()
+            .filter(|m| m.library.as_str() == name)
+            .collect();
+        if instances.is_empty() {
+            return Err(Error::ModuleNotLoaded(name.to_owned()));
+        }
+        if instances.iter().any(|m| Arc::strong_count(m) > 1 || Arc::strong_count(&m.interface) > 1) {
+            return Err(Error::ModuleInUse(name.to_owned()));
+        }
+
+        let (unloaded, kept): (Vec<_>, Vec<_>) = self.modules.drain(..)
+            .partition(|m| m.library.as_str() == name);
+        self.modules = kept;
+
+        let mut result = Ok(());
+        for module in unloaded.iter().rev() {
+            result = result.and(notify_shutdown(module, self.timeout));
+        }
+        let sources: Vec<*const LoadedLibrary> = unloaded.iter().filter_map(|m| m.source.as_ref()).map(Arc::as_ptr).collect();
+        drop(unloaded);
+
+        self.libraries.retain(|l| !sources.contains(&Arc::as_ptr(l)) || Arc::strong_count(l) > 1);
+
+        result
+    }
+
+    /// Replaces every instance of the loaded module `name` with an instance of `module`, so that
+    /// a module can be upgraded without restarting the server.
+    ///
+    /// The library of `module` is copied to a temporary file, checked against the checksum of
+    /// `module` (see `Module::verify_checksum`) and loaded from there, side by side with the
+    /// current one even if the path is the same; it is recorded under its original path (see
+    /// `library_paths`). The new instances are checked against
+    /// the compatibility policy and validated (`on_validation`) before the swap: if anything
+    /// fails, the current instances are left untouched. After the swap, the new instances are
+    /// notified through `on_start` and the old ones are unloaded as by `unload`. Per-host
+    /// instances are replaced by instances constructed for the same host.
+    ///
+    /// If an old instance does not return from `on_shutdown` in time, the reload is completed
+    /// anyway and `Error::ModuleTimeout` is returned.
+    ///
+    /// What the new instances log while being validated is written into the logger of the set, if
+    /// any (see `set_logger`); use `reload_with` to collect it instead.
+    pub fn reload(&mut self, name: &str, module: &Module) -> Result<(), Error> {
+        let mut events: Vec<Event> = Vec::new();
+        let result = self.reload_with(name, module, &mut events);
+        if let Some(logger) = self.logger() {
+            let mut logger = logger.write().unwrap();
+            for event in events.iter() {
+                let fields: Vec<(&str, &str)> = event.fields().iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+                logger.log_kv(event.severity(), event.description(), &fields);
+            }
+        }
+        result
+    }
+    /// Reloads the module `name` as by `reload`, logging into `logger` what the new instances log
+    /// while being validated.
+    pub fn reload_with(&mut self, name: &str, module: &Module, logger: &mut dyn Logger) -> Result<(), Error> {
+        let positions: Vec<usize> = self.modules.iter()
+            .enumerate()
+            .filter(|(_, m)| m.library.as_str() == name)
+            .map(|(i, _)| i)
+            .collect();
+        if positions.is_empty() {
+            return Err(Error::ModuleNotLoaded(name.to_owned()));
+        }
+        if positions.iter().any(|&i| Arc::strong_count(&self.modules[i]) > 1 || Arc::strong_count(&self.modules[i].interface) > 1) {
+            return Err(Error::ModuleInUse(name.to_owned()));
+        }
+
+        let lib_path = match module.location() {
+            Some(path) => path.to_path_buf(),
+            None => self.lib_path(module.module_name())
+        };
+        if module.is_static() {
+            self.module_policy.check(module.name(), None)?;
+        } else {
+            self.module_policy.check(module.name(), Some(&lib_path))?;
+        }
+        let source = if module.is_static() {
+            None
+        } else if module.is_wasm() {
+            module.verify_checksum(&lib_path)?;
+            None
+        } else {
+            Some(Arc::new(stage(&lib_path, module)?))
+        };
+
+        let mut replacements: Vec<Arc<LoadedModule>> = Vec::new();
+        for &i in positions.iter() {
+            match instantiate_validated(self, module, source.as_ref(), self.modules[i].owner.as_ref(), logger) {
+                Ok(replacement) => replacements.push(replacement),
+                Err(err) => {
+                    for replacement in replacements.iter().rev() {
+                        let _ = notify_shutdown(replacement, self.timeout);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        let mut replaced: Vec<Arc<LoadedModule>> = Vec::new();
+        for (&i, replacement) in positions.iter().zip(replacements) {
+            replaced.push(std::mem::replace(&mut self.modules[i], replacement));
+        }
+        self.libraries.extend(source);
+        for &i in positions.iter() {
+            self.modules[i].interface.on_start();
+        }
+
+        let mut result = Ok(());
+        for module in replaced.iter().rev() {
+            result = result.and(notify_shutdown(module, self.timeout));
+        }
+        let sources: Vec<*const LoadedLibrary> = replaced.iter().filter_map(|m| m.source.as_ref()).map(Arc::as_ptr).collect();
+        drop(replaced);
+
+        self.libraries.retain(|l| !sources.contains(&Arc::as_ptr(l)) || Arc::strong_count(l) > 1);
+
+        result
+    }
+
+    /// Obtains the paths of the loaded libraries.
+    pub fn library_paths(&self) -> Vec<&Path> {
+        self.libraries.iter().map(|l| l.path.as_path()).collect()
+    }
+
+    /// Notifies every loaded module that the server is shutting down, in reverse loading order,
+    /// then flushes the logger, if any, so that the events logged during the shutdown are stored.
+    ///
+    /// Every module is notified even if some of them do not return in time; fails with the first
+    /// `Error::ModuleTimeout` if any.
+    pub fn shutdown(&self) -> Result<(), Error> {
+        let mut result = Ok(());
+        for module in self.modules.iter().rev() {
+            result = result.and(notify_shutdown(module, self.timeout));
+        }
+        if let Some(logger) = self.logger() {
+            result = result.and(logger.write().unwrap().flush());
+        }
+        result
+    }
+}
+
+/// Notifies `module` through `on_shutdown`, waiting for at most `timeout` (see `watchdog`) and
+/// keeping the instance and its library alive until the call returns.
+fn notify_shutdown(module: &LoadedModule, timeout: Option<Duration>) -> Result<(), Error> {
+    let interface = module.interface.clone();
+    let source = module.source.clone();
+    watchdog::with_deadline(module.library.as_str(), "on_shutdown", timeout, move || {
+        interface.on_shutdown();
+        drop(interface);
+        drop(source);
+    })
+}
+
+/// Constructs `module` for `owner`, if any, from `source` (or through the `StaticModuleRegistry`
+/// or from its WebAssembly file if `None`) and validates it, shutting it down if the validation
+/// fails; lifecycle calls wait for at most `timeout`, and what the module logs while being
+/// validated is logged into `logger`.
+fn instantiate_validated(mod_set: &LoadedModuleSet, module: &Module, source: Option<&Arc<LoadedLibrary>>, owner: Option<&HostIdentifier>, logger: &mut dyn Logger) -> Result<Arc<LoadedModule>, Error> {
+    let timeout = mod_set.timeout;
+    let (interface, metadata) = match source {
+        Some(source) => module.instantiate(&source.library, owner, mod_set)?,
+        None => match module.instantiate_static(owner, mod_set) {
+            Some(instance) => instance,
+            None => module.instantiate_wasm(owner, mod_set)?
+        }
+    };
+    module.notify_load(&interface, source.cloned(), timeout)?;
+
+    let loaded = Arc::new(LoadedModule {
+        library: Arc::new(module.module_name().clone()),
+        interface,
+        kind: module.effective_kind(metadata.as_ref()),
+        metadata,
+        owner: owner.cloned(),
+        source: source.cloned()
+    });
+    match watchdog::validate(module.name(), loaded.interface.clone(), loaded.source.clone(), timeout, logger) {
+        Ok(()) => Ok(loaded),
+        Err(err @ Error::ModuleTimeout(..)) => Err(err),
+        Err(err) => {
+            let _ = notify_shutdown(&loaded, timeout);
+            Err(err)
+        }
+    }
+}
+
+/// Loads the library at `path` from a temporary copy, so that the dynamic loader does not reuse
+/// an already loaded version of the same file; the copy is checked against the checksum of
+/// `module`, so that the library loaded is the one verified.
+///
+/// The copy is removed as soon as the library is loaded; the library is recorded under `path`.
+fn stage(path: &Path, module: &Module) -> Result<LoadedLibrary, Error> {
+    static STAGED: AtomicUsize = AtomicUsize::new(0);
+
+    let file_name = path.file_name()
+        .ok_or_else(|| Error::InvalidFilePath(path.to_path_buf()))?
+        .to_string_lossy();
+    let dir = env::temp_dir().join("mammoth-reload");
+    fs::create_dir_all(&dir)?;
+    let staged = dir.join(format!("{}-{}-{}", process::id(), STAGED.fetch_add(1, Ordering::SeqCst), file_name));
+
+    fs::copy(path, &staged)?;
+    let library = match module.verify_checksum(&staged) {
+        Ok(()) => Library::new(&staged).map_err(Error::from),
+        Err(Error::ChecksumMismatch(_, expected, actual)) => Err(Error::ChecksumMismatch(path.to_path_buf(), expected, actual)),
+        Err(err) => Err(err)
+    };
+    let _ = fs::remove_file(&staged);
+
+    Ok(LoadedLibrary { path: path.to_path_buf(), library: library? })
+}
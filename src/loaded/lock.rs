@@ -0,0 +1,396 @@
+//! The `LockFile` structure pins the exact modules resolved at startup.
+//!
+//! After a successful startup, the embedding server records the resolved path, version and hash
+//! of every module into a `mammoth.lock` file; when started in locked mode (e.g. with a `--locked`
+//! command line flag), the server refuses to start if any module differs from the lock file,
+//! guaranteeing reproducible deployments.
+//!
+//! # Example
+//! ```toml
+//! [[module]]
+//! name = "mod_test"
+//! path = "./mods/mod_test.so"
+//! version = "0.0.1"
+//! sha256 = "3b4f..."
+//! ```
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use openssl::sha::sha256;
+
+use crate::config::ConfigurationFile;
+use crate::diagnostics::{Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Default name of the lock file.
+pub const LOCK_FILE: &str = "mammoth.lock";
+
+/// Describes how the lock file is used at startup.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LockMode {
+    /// The lock file is (re)generated from the resolved modules.
+    Update,
+    /// The resolved modules must match the existing lock file.
+    Locked
+}
+
+/// Structure that describes a module pinned in the lock file.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LockedModule {
+    name: String,
+    path: PathBuf,
+    version: String,
+    sha256: String
+}
+
+/// Structure that contains all the modules pinned in the lock file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LockFile {
+    #[serde(rename = "module", default)]
+    modules: Vec<LockedModule>
+}
+
+impl LockedModule {
+    /// Creates a new `LockedModule` structure given the module `name`, the `path` of its library
+    /// and its `version`, computing the hash of the library.
+    pub fn new<P>(name: &str, path: P, version: &str) -> Result<LockedModule, Error>
+        where
+            P: AsRef<Path>
+    {
+        let contents = fs::read(path.as_ref())?;
+        let sha256 = sha256(&contents).iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        Ok(LockedModule {
+            name: name.to_owned(),
+            path: path.as_ref().to_path_buf(),
+            version: version.to_owned(),
+            sha256
+        })
+    }
+    /// Obtains the name of the module.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Obtains the path of the library of the module.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Obtains the version of the module.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+    /// Obtains the SHA-256 hash of the library of the module, as an hexadecimal string.
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+}
+
+impl LockFile {
+    /// Creates a new, empty `LockFile` structure.
+    pub fn new() -> LockFile {
+        LockFile { modules: Vec::new() }
+    }
+    /// Creates a `LockFile` structure containing the modules effectively enabled in the given
+    /// `configuration`, whose libraries are searched through `[mammoth].library_names`.
+    ///
+    /// Built-in modules (see `Module::is_static`) are compiled into the server, so they are not
+    /// locked; WebAssembly modules that do not declare their version are locked by their hash
+    /// only, with an empty version.
+    pub fn generate(configuration: &ConfigurationFile) -> Result<LockFile, Error> {
+        let mut lock = LockFile::new();
+        let mods_dir = match configuration.mammoth().mods_dir() {
+            Some(mods_dir) => mods_dir,
+            None => return Ok(lock)
+        };
+        let names = configuration.mammoth().library_names();
+
+        for host in configuration.hosts() {
+            for effective in configuration.modules_for(&host.identifier()) {
+                let module = effective.module();
+                if module.is_static() { continue; }
+                let path = module.library_in(mods_dir, names).path().to_path_buf();
+                if lock.modules.iter().any(|m| m.name == module.name() && m.path == path) { continue; }
+
+                let version = match module.version_in(mods_dir, names) {
+                    Ok(version) => version.to_string(),
+                    Err(Error::Module(_)) if module.is_wasm() => String::new(),
+                    Err(err) => return Err(err)
+                };
+                lock.insert(LockedModule::new(module.name(), path, &version)?);
+            }
+        }
+
+        Ok(lock)
+    }
+    /// Creates a `LockFile` structure given a lock file.
+    pub fn from_file<P>(path: P) -> Result<LockFile, Error>
+        where
+            P: AsRef<Path>
+    {
+        fs::read_to_string(path)?.parse()
+    }
+    /// Writes the lock file at `path`.
+    pub fn write<P>(&self, path: P) -> Result<(), Error>
+        where
+            P: AsRef<Path>
+    {
+        let contents = toml::to_string(self)
+            .map_err(|err| Error::Generic(Box::new(err)))?;
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+    /// Obtains a vector of references to the pinned modules.
+    pub fn modules(&self) -> Vec<&LockedModule> {
+        self.modules.iter().collect()
+    }
+    /// Adds a pinned module.
+    pub fn insert(&mut self, module: LockedModule) {
+        self.modules.push(module);
+    }
+    /// Obtains a description of every difference between the current lock file and `other`.
+    ///
+    /// Entries are matched by name and path, since the same module can be resolved to different
+    /// libraries for different hosts; a locked entry without a match is reported as resolved to
+    /// another path if `other` has an unmatched entry with the same name.
+    pub fn differences(&self, other: &LockFile) -> Vec<String> {
+        let mut differences = Vec::new();
+        let unmatched = |entries: &[LockedModule], against: &[LockedModule], name: &str| -> Vec<PathBuf> {
+            entries.iter()
+                .filter(|m| m.name == name && !against.iter().any(|a| a.name == m.name && a.path == m.path))
+                .map(|m| m.path.clone())
+                .collect()
+        };
+
+        for locked in self.modules.iter() {
+            match other.modules.iter().find(|m| m.name == locked.name && m.path == locked.path) {
+                None => match unmatched(&other.modules, &self.modules, &locked.name).first() {
+                    Some(path) => differences.push(format!("Module '{}' resolved to '{}' instead of locked '{}'.", locked.name, path.display(), locked.path.display())),
+                    None => differences.push(format!("Module '{}' is locked but not resolved.", locked.name))
+                },
+                Some(resolved) => {
+                    if resolved.version != locked.version {
+                        differences.push(format!("Module '{}' has version {} instead of locked {}.", locked.name, resolved.version, locked.version));
+                    }
+                    if resolved.sha256 != locked.sha256 {
+                        differences.push(format!("Module '{}' has a different hash than the locked one.", locked.name));
+                    }
+                }
+            }
+        }
+        for resolved in other.modules.iter() {
+            let locked = self.modules.iter().any(|m| m.name == resolved.name && m.path == resolved.path);
+            if !locked && unmatched(&self.modules, &other.modules, &resolved.name).is_empty() {
+                differences.push(format!("Module '{}' is resolved but not locked.", resolved.name));
+            }
+        }
+
+        differences
+    }
+}
+
+impl FromStr for LockFile {
+    type Err = Error;
+
+    fn from_str(contents: &str) -> Result<LockFile, Error> {
+        Ok(toml::from_str(contents)?)
+    }
+}
+
+/// Checks the modules of the `configuration` against the lock file at `path`.
+///
+/// In `LockMode::Update` the lock file is (re)written with the resolved modules; in
+/// `LockMode::Locked` the function fails with `Error::LockMismatch` if the lock file does not exist
+/// or if the resolved modules differ from the lock file.
+pub fn check<P>(logger: &mut dyn Logger, configuration: &ConfigurationFile, path: P, mode: LockMode) -> Result<LockFile, Error>
+    where
+        P: AsRef<Path>
+{
+    let path = path.as_ref();
+    let resolved = LockFile::generate(configuration)?;
+
+    match mode {
+        LockMode::Update => resolved.write(path)?,
+        LockMode::Locked => {
+            if !path.exists() {
+                let desc = format!("Lock file '{}' not found; required in locked mode.", path.display());
+                logger.log(Severity::Critical, &desc);
+                Err(Error::LockMismatch(path.to_path_buf()))?;
+            }
+            let locked = LockFile::from_file(path)?;
+            path.to_path_buf().validate(logger, &(locked, resolved.clone()))?;
+        }
+    }
+
+    Ok(resolved)
+}
+
+impl Validator<(LockFile, LockFile)> for PathBuf {
+    fn validate(&self, logger: &mut dyn Logger, item: &(LockFile, LockFile)) -> Result<(), Error> {
+        let (locked, resolved) = item;
+        let differences = locked.differences(resolved);
+
+        for difference in differences.iter() {
+            logger.log(Severity::Critical, difference);
+        }
+        if !differences.is_empty() {
+            Err(Error::LockMismatch(self.clone()))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use semver::Version;
+
+    use crate::MammothInterface;
+    use crate::config::{ConfigurationFile, Module};
+    use crate::diagnostics::{AsyncLoggerReference, Log, Logger};
+    use crate::error::Error;
+    use crate::error::event::Event;
+    use crate::loaded::lock::{check, LockedModule, LockFile, LockMode};
+    use crate::loaded::registry::StaticModuleRegistry;
+    use crate::metadata::ModuleMetadata;
+
+    struct LockedBuiltin;
+
+    impl Log for LockedBuiltin {
+        fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            None
+        }
+    }
+
+    impl MammothInterface for LockedBuiltin {
+        fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    /// Tests hashing, serialization and comparison of lock files.
+    fn test_lock_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = dir.path().join("mod_test.so");
+        fs::write(&library, b"abc").unwrap();
+
+        let module = LockedModule::new("mod_test", &library, "0.0.1").unwrap();
+        assert_eq!(module.sha256(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let mut lock = LockFile::new();
+        lock.insert(module);
+        lock.write(dir.path().join("mammoth.lock")).unwrap();
+        let read = LockFile::from_file(dir.path().join("mammoth.lock")).unwrap();
+        assert_eq!(read, lock);
+        assert!(lock.differences(&read).is_empty());
+
+        fs::write(&library, b"abd").unwrap();
+        let mut changed = LockFile::new();
+        changed.insert(LockedModule::new("mod_test", &library, "0.0.2").unwrap());
+        assert_eq!(lock.differences(&changed).len(), 2);
+        assert_eq!(lock.differences(&LockFile::new()).len(), 1);
+        assert_eq!(LockFile::new().differences(&lock).len(), 1);
+    }
+
+    #[test]
+    /// Tests the comparison of lock files pinning the same module at several paths.
+    fn test_lock_file_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let (first, second) = (dir.path().join("first.so"), dir.path().join("second.so"));
+        fs::write(&first, b"abc").unwrap();
+        fs::write(&second, b"abd").unwrap();
+
+        let mut lock = LockFile::new();
+        lock.insert(LockedModule::new("mod_test", &first, "0.0.1").unwrap());
+        lock.insert(LockedModule::new("mod_test", &second, "0.0.2").unwrap());
+        let mut reversed = LockFile::new();
+        reversed.insert(LockedModule::new("mod_test", &second, "0.0.2").unwrap());
+        reversed.insert(LockedModule::new("mod_test", &first, "0.0.1").unwrap());
+        assert!(lock.differences(&reversed).is_empty());
+
+        let mut changed = LockFile::new();
+        changed.insert(LockedModule::new("mod_test", &first, "0.0.1").unwrap());
+        changed.insert(LockedModule::new("mod_test", &second, "0.0.3").unwrap());
+        assert_eq!(lock.differences(&changed), vec!["Module 'mod_test' has version 0.0.3 instead of locked 0.0.2."]);
+
+        let third = dir.path().join("third.so");
+        fs::write(&third, b"abd").unwrap();
+        let mut moved = LockFile::new();
+        moved.insert(LockedModule::new("mod_test", &first, "0.0.1").unwrap());
+        moved.insert(LockedModule::new("mod_test", &third, "0.0.2").unwrap());
+        assert_eq!(lock.differences(&moved).len(), 1);
+        assert!(lock.differences(&moved)[0].contains("third.so"));
+
+        let mut single = LockFile::new();
+        single.insert(LockedModule::new("mod_test", &first, "0.0.1").unwrap());
+        assert_eq!(lock.differences(&single), vec!["Module 'mod_test' is locked but not resolved."]);
+        assert_eq!(single.differences(&lock), vec!["Module 'mod_test' is resolved but not locked."]);
+    }
+
+    #[test]
+    /// Tests the update and locked modes.
+    fn test_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mammoth.lock");
+        let configuration = ConfigurationFile::from_str("[mammoth]\n[[host]]\nlisten = 8080").unwrap();
+        let mut events: Vec<Event> = Vec::new();
+
+        match check(&mut events, &configuration, &path, LockMode::Locked) {
+            Err(Error::LockMismatch(_)) => {},
+            _ => panic!("Should be 'LockMismatch' error.")
+        }
+
+        check(&mut events, &configuration, &path, LockMode::Update).unwrap();
+        check(&mut events, &configuration, &path, LockMode::Locked).unwrap();
+
+        fs::write(&path, "[[module]]\nname = \"mod_test\"\npath = \"mod_test.so\"\nversion = \"0.0.1\"\nsha256 = \"00\"").unwrap();
+        assert!(check(&mut events, &configuration, &path, LockMode::Locked).is_err());
+    }
+
+    #[test]
+    /// Tests that built-in modules are not locked.
+    fn test_generate_builtin() {
+        StaticModuleRegistry::global().write().unwrap()
+            .register_with_metadata(ModuleMetadata::new("mod_locked").with_version("1.2.3"), |_| Box::new(LockedBuiltin)).unwrap();
+        let toml = "[mammoth]\nmods_dir = \"./nonexistent\"\n[[host]]\nlisten = 8080\n[[mod]]\nname = \"mod_locked\"";
+        let configuration = ConfigurationFile::from_str(toml).unwrap();
+
+        assert_eq!(Module::new("mod_locked").unwrap().version("./nonexistent").unwrap(), Version::new(1, 2, 3));
+        assert!(LockFile::generate(&configuration).unwrap().modules().is_empty());
+
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_locked");
+    }
+
+    #[test]
+    /// Tests that the libraries are searched through the configured file names.
+    fn test_generate_library_names() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::copy("./target/debug/libmod_test.so", dir.path().join("mod_test.plugin")).unwrap();
+        let toml = format!(r#"
+        [mammoth]
+        mods_dir = "{}"
+        library_names = ["{{name}}.plugin"]
+
+        [[host]]
+        listen = 8080
+
+        [[mod]]
+        name = "mod_test"
+        "#, dir.path().display());
+        let configuration = ConfigurationFile::from_str(&toml).unwrap();
+
+        let lock = LockFile::generate(&configuration).unwrap();
+        assert_eq!(lock.modules().len(), 1);
+        assert_eq!(lock.modules()[0].path(), dir.path().join("mod_test.plugin"));
+        assert_eq!(lock.modules()[0].version(), "0.0.1");
+    }
+}
@@ -0,0 +1,557 @@
+//! The `StaticModuleRegistry` contains the modules compiled into the server binary.
+//!
+//! First-party modules can be statically linked rather than shipped as dynamic libraries: the
+//! server registers a factory for each of them in the global registry (see
+//! `StaticModuleRegistry::global`) before loading the configuration, e.g.
+//! ```rust,ignore
+//! StaticModuleRegistry::global().write().unwrap()
+//!     .register("mod_auth", |config| Box::new(AuthModule::new(config)))?;
+//! ```
+//! A `[[mod]]` whose name is registered is constructed through its factory, without touching the
+//! filesystem (see `Module::load_into`); registered names thus take precedence over libraries.
+//! Built-in modules are compiled together with the server, so their version is not checked.
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use toml::Value;
+
+use crate::MammothInterface;
+use crate::config::module::ModuleName;
+use crate::error::Error;
+use crate::metadata::ModuleMetadata;
+
+lazy_static! {
+    static ref REGISTRY: RwLock<StaticModuleRegistry> = RwLock::new(StaticModuleRegistry::new());
+}
+
+/// Function that constructs a built-in module given its configuration.
+pub type ModuleFactory = dyn Fn(Option<&Value>) -> Box<dyn MammothInterface> + Send + Sync;
+
+struct StaticModule {
+    factory: Arc<ModuleFactory>,
+    metadata: Option<ModuleMetadata>
+}
+
+/// Structure that contains the factories of the built-in modules, by name.
+#[derive(Default)]
+pub struct StaticModuleRegistry {
+    modules: BTreeMap<ModuleName, StaticModule>
+}
+
+impl StaticModuleRegistry {
+    /// Creates a new, empty `StaticModuleRegistry` structure.
+    pub fn new() -> StaticModuleRegistry {
+        StaticModuleRegistry {
+            modules: BTreeMap::new()
+        }
+    }
+    /// Obtains the registry consulted when loading modules.
+    pub fn global() -> &'static RwLock<StaticModuleRegistry> {
+        &REGISTRY
+    }
+
+    /// Registers the built-in module `name`, constructed through `factory`.
+    ///
+    /// Fails if `name` is not a valid module name or is already registered.
+    pub fn register<F>(&mut self, name: &str, factory: F) -> Result<(), Error>
+        where
+            F: Fn(Option<&Value>) -> Box<dyn MammothInterface> + Send + Sync + 'static
+    {
+        self.insert(ModuleName::new(name)?, None, Arc::new(factory))
+    }
+    /// Registers the built-in module described by `metadata`, constructed through `factory`.
+    ///
+    /// Fails if the name of the module is not valid or is already registered.
+    pub fn register_with_metadata<F>(&mut self, metadata: ModuleMetadata, factory: F) -> Result<(), Error>
+        where
+            F: Fn(Option<&Value>) -> Box<dyn MammothInterface> + Send + Sync + 'static
+    {
+        self.insert(ModuleName::new(metadata.name())?, Some(metadata), Arc::new(factory))
+    }
+    fn insert(&mut self, name: ModuleName, metadata: Option<ModuleMetadata>, factory: Arc<ModuleFactory>) -> Result<(), Error> {
+        if self.modules.contains_key(&name) {
+            return Err(Error::DuplicateItem(name.to_string()));
+        }
+        self.modules.insert(name, StaticModule { factory, metadata });
+
+        Ok(())
+    }
+    /// Removes the built-in module `name`, returning `true` if it was registered.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.modules.remove(name).is_some()
+    }
+
+    /// Returns `true` if the built-in module `name` is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.modules.contains_key(name)
+    }
+    /// Obtains the names of the registered modules, in alphabetical order.
+    pub fn names(&self) -> Vec<&str> {
+        self.modules.keys().map(|name| name.as_str()).collect()
+    }
+    /// Obtains the metadata of the built-in module `name`, if registered with metadata.
+    pub fn metadata(&self, name: &str) -> Option<&ModuleMetadata> {
+        self.modules.get(name).and_then(|m| m.metadata.as_ref())
+    }
+    /// Obtains the factory of the built-in module `name`, if registered.
+    pub fn factory(&self, name: &str) -> Option<Arc<ModuleFactory>> {
+        self.modules.get(name).map(|m| m.factory.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[cfg(feature = "actix")]
+    use actix_web::{test, web, App, HttpResponse};
+    #[cfg(feature = "actix")]
+    use actix_web::rt::System;
+    #[cfg(feature = "actix")]
+    use actix_web::web::ServiceConfig;
+    use toml::Value;
+
+    use crate::MammothInterface;
+    use crate::abi;
+    use crate::config::{HostIdentifier, Module};
+    use crate::config::module::{Instancing, LibraryNames, ModuleValidator};
+    use crate::diagnostics::{AsyncLoggerReference, Log, Logger, Validator};
+    use crate::diagnostics::report::HealthStatus;
+    use crate::error::Error;
+    use crate::error::event::Event;
+    #[cfg(feature = "http")]
+    use crate::http::{Method, Request, Response};
+    use crate::loaded::library::LoadedModuleSet;
+    use crate::loaded::registry::StaticModuleRegistry;
+    use crate::metadata::{ModuleKind, ModuleMetadata};
+    use crate::metrics::MetricsCollector;
+    use crate::version::CompatibilityPolicy;
+
+    struct BuiltinModule {
+        config: Option<Value>
+    }
+
+    impl Log for BuiltinModule {
+        fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            None
+        }
+    }
+
+    impl MammothInterface for BuiltinModule {
+        fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+            match self.config {
+                Some(Value::String(ref s)) if s == "test_error" => Err(Error::Unknown),
+                _ => Ok(())
+            }
+        }
+    }
+
+    fn builtin(config: Option<&Value>) -> Box<dyn MammothInterface> {
+        Box::new(BuiltinModule { config: config.cloned() })
+    }
+
+    #[test]
+    /// Tests the registration of built-in modules.
+    fn test_register() {
+        let mut registry = StaticModuleRegistry::new();
+        registry.register("mod_builtin", builtin).unwrap();
+        registry.register_with_metadata(ModuleMetadata::new("mod_described"), builtin).unwrap();
+
+        assert!(registry.register("mod_builtin", builtin).is_err());
+        assert!(registry.register("mod builtin", builtin).is_err());
+        assert_eq!(registry.names(), vec!["mod_builtin", "mod_described"]);
+        assert!(registry.metadata("mod_builtin").is_none());
+        assert_eq!(registry.metadata("mod_described").map(|m| m.name()), Some("mod_described"));
+
+        let module = (registry.factory("mod_builtin").unwrap())(Some(&Value::from("test_error")));
+        let mut events: Vec<Event> = Vec::new();
+        assert!(module.on_validation(&mut events).is_err());
+
+        assert!(registry.unregister("mod_builtin"));
+        assert!(!registry.contains("mod_builtin"));
+    }
+
+    fn host_aware(_: Option<&Value>) -> Box<dyn MammothInterface> {
+        Box::new(BuiltinModule { config: abi::current_host().map(|h| Value::from(h.to_string())) })
+    }
+
+    #[test]
+    /// Tests the shared and per-host instances of a module enabled on several hosts.
+    fn test_instancing() {
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_instanced", host_aware).unwrap();
+        let first = HostIdentifier::new(8080, None);
+        let second = HostIdentifier::new(8081, None);
+
+        let shared = Module::new("mod_instanced").unwrap();
+        assert_eq!(shared.instancing(), Instancing::Shared);
+        let mut lms = LoadedModuleSet::new("./nonexistent");
+        lms.load_for_host(&first, &[&shared]).unwrap();
+        lms.load_for_host(&second, &[&shared]).unwrap();
+        assert_eq!(lms.module_names(), vec!["mod_instanced"]);
+        assert!(lms.instance_for("mod_instanced", &second).is_some());
+        lms.unload("mod_instanced").unwrap();
+
+        let mut per_host = Module::new("mod_instanced").unwrap();
+        per_host.set_instancing(Instancing::PerHost);
+        lms.load_for_host(&first, &[&per_host]).unwrap();
+        lms.load_for_host(&second, &[&per_host]).unwrap();
+        lms.load_for_host(&second, &[&per_host]).unwrap();
+        assert_eq!(lms.module_names(), vec!["mod_instanced", "mod_instanced"]);
+        assert!(!Arc::ptr_eq(&lms.instance_for("mod_instanced", &first).unwrap(), &lms.instance_for("mod_instanced", &second).unwrap()));
+        assert!(lms.instance_for("mod_instanced", &HostIdentifier::new(8082, None)).is_none());
+
+        lms.reload("mod_instanced", &per_host).unwrap();
+        assert_eq!(lms.module_names(), vec!["mod_instanced", "mod_instanced"]);
+        assert!(lms.instance_for("mod_instanced", &first).is_some());
+
+        lms.unload("mod_instanced").unwrap();
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_instanced");
+    }
+
+    struct ProbedModule {
+        status: Option<HealthStatus>
+    }
+
+    impl Log for ProbedModule {
+        fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            None
+        }
+    }
+
+    impl MammothInterface for ProbedModule {
+        fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn on_health(&self) -> HealthStatus {
+            self.status.clone().expect("health check failed")
+        }
+
+        fn on_metrics(&self, collector: &mut MetricsCollector) {
+            let healthy = self.status.as_ref().expect("metrics unavailable").is_healthy();
+            collector.gauge("healthy", if healthy { 1.0 } else { 0.0 });
+        }
+    }
+
+    fn probed(config: Option<&Value>) -> Box<dyn MammothInterface> {
+        let status = match config.and_then(Value::as_str) {
+            Some("degraded") => Some(HealthStatus::Degraded("slow".to_owned())),
+            Some("panic") => None,
+            _ => Some(HealthStatus::Healthy)
+        };
+        Box::new(ProbedModule { status })
+    }
+
+    #[test]
+    /// Tests the health report of the loaded modules.
+    fn test_health_report() {
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_probed", probed).unwrap();
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_probed_panic", probed).unwrap();
+        let host = HostIdentifier::new(8080, None);
+        let mut per_host = Module::with_config("mod_probed", true, Value::from("degraded")).unwrap();
+        per_host.set_instancing(Instancing::PerHost);
+
+        let mut lms = LoadedModuleSet::new("./nonexistent");
+        lms.load_all(&[&Module::new("mod_probed").unwrap(), &Module::with_config("mod_probed_panic", true, Value::from("panic")).unwrap()]).unwrap();
+        lms.load_for_host(&host, &[&per_host]).unwrap();
+
+        let report = lms.health_report();
+        assert_eq!(report.modules().len(), 3);
+        assert_eq!(report.modules()[0].status(), &HealthStatus::Healthy);
+        assert_eq!(report.modules()[1].status(), &HealthStatus::Unhealthy("module panicked during health check".to_owned()));
+        assert_eq!(report.modules()[2].host(), Some(&host));
+        assert_eq!(report.get("mod_probed"), Some(&HealthStatus::Degraded("slow".to_owned())));
+        assert!(!report.is_healthy());
+
+        lms.unload("mod_probed").unwrap();
+        lms.unload("mod_probed_panic").unwrap();
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_probed");
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_probed_panic");
+    }
+
+    #[test]
+    /// Tests the collection of the metrics of the loaded modules.
+    fn test_collect_metrics() {
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_metered", probed).unwrap();
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_metered_panic", probed).unwrap();
+        let host = HostIdentifier::new(8080, None);
+        let mut per_host = Module::with_config("mod_metered", true, Value::from("degraded")).unwrap();
+        per_host.set_instancing(Instancing::PerHost);
+
+        let mut lms = LoadedModuleSet::new("./nonexistent");
+        lms.load_all(&[&Module::new("mod_metered").unwrap(), &Module::with_config("mod_metered_panic", true, Value::from("panic")).unwrap()]).unwrap();
+        lms.load_for_host(&host, &[&per_host]).unwrap();
+
+        let metrics = lms.collect_metrics();
+        assert_eq!(metrics.samples().len(), 2);
+        assert_eq!(metrics.samples()[0].labels(), vec![("module", "mod_metered")]);
+        assert_eq!(metrics.get("healthy", &[("module", "mod_metered"), ("host", &host.to_string())]).unwrap().value(), 0.0);
+        assert!(metrics.get("healthy", &[("module", "mod_metered_panic")]).is_none());
+
+        lms.unload("mod_metered").unwrap();
+        lms.unload("mod_metered_panic").unwrap();
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_metered");
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_metered_panic");
+    }
+
+    struct StuckModule {
+        hook: String
+    }
+
+    impl StuckModule {
+        fn call(&self, hook: &str) {
+            if self.hook == hook {
+                thread::sleep(Duration::from_millis(300));
+            }
+        }
+    }
+
+    impl Log for StuckModule {
+        fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            None
+        }
+    }
+
+    impl MammothInterface for StuckModule {
+        fn on_load(&self) {
+            self.call("on_load");
+        }
+
+        fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+            self.call("on_validation");
+            Ok(())
+        }
+
+        fn on_shutdown(&self) {
+            self.call("on_shutdown");
+        }
+    }
+
+    fn stuck(config: Option<&Value>) -> Box<dyn MammothInterface> {
+        let hook = config.and_then(Value::as_str).unwrap_or_default().to_owned();
+        Box::new(StuckModule { hook })
+    }
+
+    #[test]
+    /// Tests the deadlines of the lifecycle calls of the modules.
+    fn test_lifecycle_timeout() {
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_stuck", stuck).unwrap();
+        let timeout = Some(Duration::from_millis(20));
+        let mut lms = LoadedModuleSet::new("./nonexistent");
+        lms.set_timeout(timeout);
+
+        match lms.load_all(&[&Module::with_config("mod_stuck", true, Value::from("on_load")).unwrap()]) {
+            Err(Error::ModuleTimeout(name, hook, _)) => assert_eq!((name.as_str(), hook.as_str()), ("mod_stuck", "on_load")),
+            _ => panic!("Should be 'ModuleTimeout' error.")
+        }
+        assert!(lms.module_names().is_empty());
+
+        let mut events: Vec<Event> = Vec::new();
+        let validation = Module::with_config("mod_stuck", true, Value::from("on_validation")).unwrap();
+        let validator = ModuleValidator(Path::new("./nonexistent"), &CompatibilityPolicy::default(), &LibraryNames::default(), timeout, None);
+        assert!(validator.validate(&mut events, &validation).is_err());
+        assert_eq!(events.len(), 1);
+
+        lms.load_all(&[&Module::with_config("mod_stuck", true, Value::from("on_shutdown")).unwrap()]).unwrap();
+        assert!(lms.shutdown().is_err());
+        thread::sleep(Duration::from_millis(400));
+        assert!(lms.unload("mod_stuck").is_err());
+        assert!(lms.module_names().is_empty());
+
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_stuck");
+    }
+
+    #[test]
+    /// Tests the kinds of the loaded modules.
+    fn test_kind() {
+        StaticModuleRegistry::global().write().unwrap()
+            .register_with_metadata(ModuleMetadata::new("mod_handler").with_kind(ModuleKind::Handler), builtin).unwrap();
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_undeclared", builtin).unwrap();
+
+        let mods_dir = PathBuf::from("./nonexistent");
+        let mut events: Vec<Event> = Vec::new();
+        let mut handler = Module::new("mod_handler").unwrap();
+        assert!(mods_dir.validate(&mut events, &handler).is_ok());
+        handler.set_kind(ModuleKind::Middleware);
+        assert!(mods_dir.validate(&mut events, &handler).is_err());
+        handler.set_kind(ModuleKind::Handler);
+        assert!(mods_dir.validate(&mut events, &handler).is_ok());
+
+        let mut undeclared = Module::new("mod_undeclared").unwrap();
+        assert_eq!(undeclared.effective_kind(None), ModuleKind::Service);
+        undeclared.set_kind(ModuleKind::Middleware);
+        assert!(mods_dir.validate(&mut events, &undeclared).is_ok());
+
+        let mut lms = LoadedModuleSet::new("./nonexistent");
+        lms.load_all(&[&Module::new("mod_handler").unwrap(), &undeclared]).unwrap();
+        assert_eq!(lms.kind("mod_handler"), Some(ModuleKind::Handler));
+        assert_eq!(lms.kind("mod_undeclared"), Some(ModuleKind::Middleware));
+        assert_eq!(lms.modules_of_kind(ModuleKind::Middleware), vec!["mod_undeclared"]);
+        assert!(lms.modules_of_kind(ModuleKind::Service).is_empty());
+
+        lms.unload("mod_handler").unwrap();
+        lms.unload("mod_undeclared").unwrap();
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_handler");
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_undeclared");
+    }
+
+    #[cfg(feature = "http")]
+    struct HttpModule {
+        answer: bool
+    }
+
+    #[cfg(feature = "http")]
+    impl Log for HttpModule {
+        fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            None
+        }
+    }
+
+    #[cfg(feature = "http")]
+    impl MammothInterface for HttpModule {
+        fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn on_request(&self, request: &mut Request) -> Option<Response> {
+            request.headers_mut().append("Via", if self.answer { "handler" } else { "middleware" });
+            if self.answer {
+                Some(Response::with_body(request.headers().get_all("Via").join(", ").into_bytes(), "text/plain"))
+            } else {
+                None
+            }
+        }
+
+        fn on_response(&self, _: &Request, response: &mut Response) {
+            response.headers_mut().append("Via", if self.answer { "handler" } else { "middleware" });
+        }
+    }
+
+    #[cfg(feature = "http")]
+    fn http_module(config: Option<&Value>) -> Box<dyn MammothInterface> {
+        Box::new(HttpModule { answer: config == Some(&Value::from("answer")) })
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    /// Tests the handling of requests through the loaded modules.
+    fn test_handle_request() {
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_http_middleware", http_module).unwrap();
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_http_handler", http_module).unwrap();
+        let host = HostIdentifier::new(8080, None);
+        let middleware = Module::new("mod_http_middleware").unwrap();
+        let handler = Module::with_config("mod_http_handler", true, Value::from("answer")).unwrap();
+
+        let mut lms = LoadedModuleSet::new("./nonexistent");
+        lms.load_for_host(&host, &[&middleware, &handler]).unwrap();
+
+        let response = lms.handle_request(&host, &["mod_http_middleware", "mod_http_handler"], Request::new(Method::Get, "/"));
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.body(), b"middleware, handler");
+        assert_eq!(response.headers().get_all("Via"), vec!["handler", "middleware"]);
+
+        let response = lms.handle_request(&host, &["mod_http_middleware", "mod_unknown"], Request::new(Method::Get, "/"));
+        assert_eq!(response.status(), 404);
+        assert_eq!(response.headers().get_all("Via"), vec!["middleware"]);
+
+        lms.unload("mod_http_middleware").unwrap();
+        lms.unload("mod_http_handler").unwrap();
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_http_middleware");
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_http_handler");
+    }
+
+    #[cfg(feature = "actix")]
+    struct RouteModule;
+
+    #[cfg(feature = "actix")]
+    impl Log for RouteModule {
+        fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            None
+        }
+    }
+
+    #[cfg(feature = "actix")]
+    impl MammothInterface for RouteModule {
+        fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn on_factory(&self, cfg: &mut ServiceConfig) {
+            cfg.route("/hello", web::get().to(|| async { HttpResponse::Ok().body("hello") }));
+        }
+    }
+
+    #[cfg(feature = "actix")]
+    fn route_module(_: Option<&Value>) -> Box<dyn MammothInterface> {
+        Box::new(RouteModule)
+    }
+
+    #[test]
+    #[cfg(feature = "actix")]
+    /// Tests the registration of the routes of the loaded modules into an application.
+    fn test_apply() {
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_routes", route_module).unwrap();
+        let mut lms = LoadedModuleSet::new("./nonexistent");
+        lms.load_all(&[&Module::new("mod_routes").unwrap()]).unwrap();
+
+        System::new().block_on(async {
+            let app = test::init_service(lms.apply(App::new())).await;
+            let response = test::call_service(&app, test::TestRequest::get().uri("/hello").to_request()).await;
+            assert!(response.status().is_success());
+            assert_eq!(test::read_body(response).await, "hello");
+
+            let response = test::call_service(&app, test::TestRequest::get().uri("/other").to_request()).await;
+            assert_eq!(response.status().as_u16(), 404);
+        });
+
+        lms.unload("mod_routes").unwrap();
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_routes");
+    }
+
+    #[test]
+    /// Tests that built-in modules are loaded without a library.
+    fn test_builtin_load() {
+        StaticModuleRegistry::global().write().unwrap()
+            .register_with_metadata(ModuleMetadata::new("mod_registered"), builtin).unwrap();
+
+        let module = Module::new("mod_registered").unwrap();
+        assert!(module.is_static());
+        assert_eq!(module.inspect("./nonexistent").unwrap().name(), "mod_registered");
+
+        let mut lms = LoadedModuleSet::new("./nonexistent");
+        module.load_into(&mut lms).unwrap();
+        assert_eq!(lms.module_names(), vec!["mod_registered"]);
+        assert!(lms.library_paths().is_empty());
+
+        let mods_dir = PathBuf::from("./nonexistent");
+        let mut events: Vec<Event> = Vec::new();
+        assert!(mods_dir.validate(&mut events, &module).is_ok());
+        assert!(mods_dir.validate(&mut events, &Module::with_config("mod_registered", true, Value::from("test_error")).unwrap()).is_err());
+
+        lms.unload("mod_registered").unwrap();
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_registered");
+    }
+}
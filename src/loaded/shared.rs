@@ -0,0 +1,187 @@
+//! The `SharedModuleSet` makes a `LoadedModuleSet` available to many threads at once.
+//!
+//! A web server calls into the modules from many worker threads, while the modules themselves are
+//! loaded, reloaded and unloaded from a single control thread. The `SharedModuleSet` is a
+//! cloneable handle to a set behind a `RwLock`: workers obtain the instances they need through
+//! `instance` or `instance_for`, which only hold the lock for the lookup, e.g.
+//! ```rust,ignore
+//! let modules = SharedModuleSet::new(mod_set);
+//! let worker = modules.clone();
+//! thread::spawn(move || {
+//!     if let Some(auth) = worker.instance_for("mod_auth", &host) {
+//!         // ...
+//!     }
+//! });
+//! ```
+//! Instances obtained this way keep the module in use: `unload` and `reload` fail with
+//! `Error::ModuleInUse` until they are dropped.
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::MammothInterface;
+use crate::config::HostIdentifier;
+use crate::config::module::Module;
+use crate::diagnostics::report::HealthReport;
+use crate::error::Error;
+use crate::loaded::library::LoadedModuleSet;
+use crate::metrics::MetricsCollector;
+
+/// Cloneable handle to a `LoadedModuleSet` shared among threads.
+#[derive(Clone)]
+pub struct SharedModuleSet {
+    inner: Arc<RwLock<LoadedModuleSet>>
+}
+
+impl SharedModuleSet {
+    /// Creates a new `SharedModuleSet` structure sharing `mod_set`.
+    pub fn new(mod_set: LoadedModuleSet) -> SharedModuleSet {
+        SharedModuleSet {
+            inner: Arc::new(RwLock::new(mod_set))
+        }
+    }
+
+    /// Locks the set for reading.
+    pub fn read(&self) -> RwLockReadGuard<'_, LoadedModuleSet> {
+        self.inner.read().unwrap()
+    }
+    /// Locks the set for writing, e.g. to load new modules.
+    pub fn write(&self) -> RwLockWriteGuard<'_, LoadedModuleSet> {
+        self.inner.write().unwrap()
+    }
+
+    /// Obtains the first instance of the loaded module `name` (see `LoadedModuleSet::instance`).
+    pub fn instance(&self, name: &str) -> Option<Arc<Box<dyn MammothInterface>>> {
+        self.read().instance(name)
+    }
+    /// Obtains the instance of the loaded module `name` serving the host `host` (see
+    /// `LoadedModuleSet::instance_for`).
+    pub fn instance_for(&self, name: &str, host: &HostIdentifier) -> Option<Arc<Box<dyn MammothInterface>>> {
+        self.read().instance_for(name, host)
+    }
+    /// Obtains the names of the loaded modules, in loading order.
+    pub fn module_names(&self) -> Vec<String> {
+        self.read().module_names().into_iter().map(str::to_owned).collect()
+    }
+
+    /// Loads `modules` for the host `host` (see `LoadedModuleSet::load_for_host`).
+    pub fn load_for_host(&self, host: &HostIdentifier, modules: &[&Module]) -> Result<(), Error> {
+        self.write().load_for_host(host, modules)
+    }
+    /// Notifies every loaded module that all the modules are loaded and validated.
+    pub fn start(&self) {
+        self.read().start();
+    }
+    /// Notifies the loaded modules serving the host `host` that the configuration is reloaded
+    /// (see `LoadedModuleSet::reload_config_for`).
+    pub fn reload_config_for(&self, host: &HostIdentifier, modules: &[&Module]) {
+        self.read().reload_config_for(host, modules);
+    }
+    /// Unloads every instance of the module `name` (see `LoadedModuleSet::unload`).
+    pub fn unload(&self, name: &str) -> Result<(), Error> {
+        self.write().unload(name)
+    }
+    /// Replaces every instance of the loaded module `name` with an instance of `module` (see
+    /// `LoadedModuleSet::reload`).
+    pub fn reload(&self, name: &str, module: &Module) -> Result<(), Error> {
+        self.write().reload(name, module)
+    }
+    /// Checks the health of the loaded modules (see `LoadedModuleSet::health_report`).
+    pub fn health_report(&self) -> HealthReport {
+        self.read().health_report()
+    }
+    /// Collects the metrics of the loaded modules (see `LoadedModuleSet::collect_metrics`).
+    pub fn collect_metrics(&self) -> MetricsCollector {
+        self.read().collect_metrics()
+    }
+    /// Notifies every loaded module that the server is shutting down (see
+    /// `LoadedModuleSet::shutdown`).
+    pub fn shutdown(&self) -> Result<(), Error> {
+        self.read().shutdown()
+    }
+}
+
+impl From<LoadedModuleSet> for SharedModuleSet {
+    fn from(mod_set: LoadedModuleSet) -> SharedModuleSet {
+        SharedModuleSet::new(mod_set)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    use toml::Value;
+
+    use crate::MammothInterface;
+    use crate::config::{HostIdentifier, Module};
+    use crate::diagnostics::{AsyncLoggerReference, Log, Logger};
+    use crate::error::Error;
+    use crate::error::event::Event;
+    use crate::loaded::library::LoadedModuleSet;
+    use crate::loaded::registry::StaticModuleRegistry;
+    use crate::loaded::shared::SharedModuleSet;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingModule;
+
+    impl Log for CountingModule {
+        fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            None
+        }
+    }
+
+    impl MammothInterface for CountingModule {
+        fn on_validation(&self, _: &mut dyn Logger) -> Result<(), Error> {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn counting(_: Option<&Value>) -> Box<dyn MammothInterface> {
+        Box::new(CountingModule)
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    /// Tests the use of a shared module set from several threads.
+    fn test_shared_set() {
+        assert_send_sync::<LoadedModuleSet>();
+        assert_send_sync::<SharedModuleSet>();
+
+        StaticModuleRegistry::global().write().unwrap()
+            .register("mod_shared", counting).unwrap();
+        let host = HostIdentifier::new(8080, None);
+        let module = Module::new("mod_shared").unwrap();
+
+        let shared = SharedModuleSet::new(LoadedModuleSet::new("./nonexistent"));
+        shared.load_for_host(&host, &[&module]).unwrap();
+        shared.start();
+
+        let workers: Vec<_> = (0..4).map(|_| {
+            let shared = shared.clone();
+            let host = host.clone();
+            thread::spawn(move || {
+                for _ in 0..25 {
+                    let instance = shared.instance_for("mod_shared", &host).unwrap();
+                    let mut events: Vec<Event> = Vec::new();
+                    instance.on_validation(&mut events).unwrap();
+                }
+            })
+        }).collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(CALLS.load(Ordering::SeqCst), 100);
+
+        let instance = shared.instance("mod_shared").unwrap();
+        assert!(shared.unload("mod_shared").is_err());
+        drop(instance);
+        shared.unload("mod_shared").unwrap();
+        assert!(shared.module_names().is_empty());
+        StaticModuleRegistry::global().write().unwrap().unregister("mod_shared");
+    }
+}
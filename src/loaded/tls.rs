@@ -0,0 +1,135 @@
+//! The `TlsWatcher` structure keeps the `SslAcceptor` of a secure binding up to date with its
+//! certificate and key files.
+//!
+//! The watcher does not spawn any thread: the embedding server is expected to call `poll`
+//! periodically (e.g. every few seconds); when any of the TLS files of the binding has changed, the
+//! acceptor is rebuilt and swapped in, so that new connections use the new certificate while the
+//! established ones are unaffected.
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use openssl::ssl::SslAcceptor;
+
+use crate::config::port::Binding;
+use crate::diagnostics::Logger;
+use crate::error::Error;
+
+/// Structure that rebuilds the `SslAcceptor` of a binding whenever its TLS files change.
+pub struct TlsWatcher {
+    binding: Binding,
+    acceptor: Arc<RwLock<SslAcceptor>>,
+    stamps: Vec<(PathBuf, Option<SystemTime>)>
+}
+
+impl TlsWatcher {
+    /// Creates a new `TlsWatcher` structure for `binding`, building its first acceptor.
+    ///
+    /// Fails if the binding is not secure or if its acceptor cannot be built.
+    pub fn new(binding: &Binding) -> Result<TlsWatcher, Error> {
+        let acceptor = binding.ssl_acceptor()?;
+
+        Ok(TlsWatcher {
+            binding: binding.clone(),
+            acceptor: Arc::new(RwLock::new(acceptor)),
+            stamps: stamps(binding)
+        })
+    }
+
+    /// Obtains the binding watched.
+    pub fn binding(&self) -> &Binding {
+        &self.binding
+    }
+    /// Obtains the current acceptor.
+    pub fn acceptor(&self) -> SslAcceptor {
+        self.acceptor.read().unwrap().clone()
+    }
+    /// Obtains a shared reference to the current acceptor, which is updated by `poll`.
+    pub fn handle(&self) -> Arc<RwLock<SslAcceptor>> {
+        self.acceptor.clone()
+    }
+    /// Returns `true` if any of the TLS files changed since the last (successful or failed) reload.
+    pub fn changed(&self) -> bool {
+        stamps(&self.binding) != self.stamps
+    }
+    /// Reloads the acceptor if any of the TLS files changed, logging the outcome through `logger`;
+    /// returns `true` if the acceptor has been replaced.
+    ///
+    /// If the new files are invalid (e.g. a certificate has been written but its key not yet),
+    /// the previous acceptor is kept and the reload is attempted again when the files change.
+    pub fn poll(&mut self, logger: &mut dyn Logger) -> Result<bool, Error> {
+        let current = stamps(&self.binding);
+        if current == self.stamps {
+            return Ok(false);
+        }
+        self.stamps = current;
+
+        let acceptor = self.binding.reload_tls(logger)?;
+        *self.acceptor.write().unwrap() = acceptor;
+
+        Ok(true)
+    }
+}
+
+/// Obtains the last modification time of every TLS file of `binding`.
+fn stamps(binding: &Binding) -> Vec<(PathBuf, Option<SystemTime>)> {
+    binding.tls_files().into_iter()
+        .map(|p| (p.to_path_buf(), fs::metadata(p).and_then(|m| m.modified()).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+
+    use crate::config::port::Binding;
+    use crate::error::event::Event;
+    use crate::loaded::tls::TlsWatcher;
+
+    /// Copies `from` into `to`, making sure that the modification time changes.
+    fn replace(from: &str, to: &std::path::Path) {
+        fs::copy(from, to).unwrap();
+        let file = fs::File::options().write(true).open(to).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+    }
+
+    #[test]
+    /// Tests the reload of the acceptor when the certificate changes.
+    fn test_poll() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert = dir.path().join("cert.pem");
+        let key = dir.path().join("key.pem");
+        fs::copy("./tests/test_cert.pem", &cert).unwrap();
+        fs::copy("./tests/test_key.pem", &key).unwrap();
+
+        let mut watcher = TlsWatcher::new(&Binding::with_security(8443, &cert, &key)).unwrap();
+        let mut events: Vec<Event> = Vec::new();
+        let handle = watcher.handle();
+
+        assert!(!watcher.changed());
+        assert!(!watcher.poll(&mut events).unwrap());
+
+        replace("./tests/test_leaf_cert.pem", &cert);
+        replace("./tests/test_leaf_key.pem", &key);
+        assert!(watcher.changed());
+        assert!(watcher.poll(&mut events).unwrap());
+        assert_eq!(events.len(), 1);
+        assert!(!watcher.poll(&mut events).unwrap());
+        assert!(handle.read().is_ok());
+
+        fs::write(&cert, "not a certificate").unwrap();
+        let file = fs::File::options().write(true).open(&cert).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(120)).unwrap();
+        assert!(watcher.poll(&mut events).is_err());
+        assert_eq!(events.len(), 2);
+        assert!(!watcher.poll(&mut events).unwrap());
+    }
+
+    #[test]
+    /// Tests that insecure bindings cannot be watched.
+    fn test_insecure() {
+        assert!(TlsWatcher::new(&Binding::new(8080)).is_err());
+    }
+}
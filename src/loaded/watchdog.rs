@@ -0,0 +1,151 @@
+//! Deadlines on the lifecycle calls of the modules.
+//!
+//! `on_load`, `on_validation` and `on_shutdown` are executed on a watchdog thread while the caller
+//! waits for at most the configured deadline (see `Mammoth::module_timeout`): a module that does
+//! not return in time results in `Error::ModuleTimeout`, so that a single stuck module cannot hang
+//! the startup or the shutdown of the server. The stuck call is abandoned rather than interrupted:
+//! the watchdog thread keeps the instance, and the library containing its code, alive until the
+//! call eventually returns.
+use std::panic;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+
+use crate::MammothInterface;
+use crate::diagnostics::Logger;
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Deadline of the lifecycle calls if the configuration does not specify one.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Executes `f` on a watchdog thread, waiting for at most `deadline` (or indefinitely if `None`).
+///
+/// Fails with `Error::ModuleTimeout` if `f` does not return in time, naming the `module` and the
+/// `hook` being called; panics of `f` are propagated to the caller.
+pub fn with_deadline<T, F>(module: &str, hook: &str, deadline: Option<Duration>, f: F) -> Result<T, Error>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static
+{
+    let deadline = match deadline {
+        Some(deadline) => deadline,
+        None => return Ok(f())
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let watched = thread::Builder::new()
+        .name(format!("mammoth-watchdog-{}", module))
+        .spawn(move || { let _ = sender.send(f()); })?;
+
+    match receiver.recv_timeout(deadline) {
+        Ok(value) => Ok(value),
+        Err(RecvTimeoutError::Timeout) => Err(Error::ModuleTimeout(module.to_owned(), hook.to_owned(), deadline)),
+        Err(RecvTimeoutError::Disconnected) => match watched.join() {
+            Err(payload) => panic::resume_unwind(payload),
+            Ok(()) => Err(Error::Unknown)
+        }
+    }
+}
+
+/// Calls `on_validation` on `interface` as by `with_deadline`, keeping `keep_alive` (e.g. the
+/// library of the module) until the call returns, and forwards what the module logs into `logger`.
+///
+/// A timeout is logged with `Critical` severity.
+pub fn validate<K>(module: &str, interface: Arc<Box<dyn MammothInterface>>, keep_alive: K, deadline: Option<Duration>, logger: &mut dyn Logger) -> Result<(), Error>
+    where
+        K: Send + 'static
+{
+    let result = with_deadline(module, "on_validation", deadline, move || {
+        let mut events = Buffer(Vec::new());
+        let result = interface.on_validation(&mut events);
+        drop(interface);
+        drop(keep_alive);
+        (result, events)
+    });
+
+    match result {
+        Ok((result, events)) => {
+            for (sev, desc) in events.0 {
+                logger.log(sev, &desc);
+            }
+            result
+        },
+        Err(err) => {
+            logger.log_error(Severity::Critical, &format!("{}.", err), &err);
+            Err(err)
+        }
+    }
+}
+
+/// Logger collecting what a module logs on the watchdog thread.
+struct Buffer(Vec<(Severity, String)>);
+
+impl Logger for Buffer {
+    fn log(&mut self, sev: Severity, desc: &str) {
+        self.0.push((sev, desc.to_owned()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::panic;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::MammothInterface;
+    use crate::diagnostics::{AsyncLoggerReference, Log, Logger};
+    use crate::error::Error;
+    use crate::error::event::Event;
+    use crate::error::severity::Severity;
+    use crate::loaded::watchdog::{validate, with_deadline};
+
+    struct SlowModule(Duration);
+
+    impl Log for SlowModule {
+        fn register_logger(&mut self, _: AsyncLoggerReference) {}
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            None
+        }
+    }
+
+    impl MammothInterface for SlowModule {
+        fn on_validation(&self, logger: &mut dyn Logger) -> Result<(), Error> {
+            thread::sleep(self.0);
+            logger.log(Severity::Warning, "Validated slowly.");
+            Ok(())
+        }
+    }
+
+    #[test]
+    /// Tests calls exceeding their deadline.
+    fn test_deadline() {
+        assert_eq!(with_deadline("mod_fast", "on_load", Some(Duration::from_secs(5)), || 42).unwrap(), 42);
+        assert_eq!(with_deadline("mod_fast", "on_load", None, || 42).unwrap(), 42);
+        match with_deadline("mod_stuck", "on_shutdown", Some(Duration::from_millis(20)), || thread::sleep(Duration::from_secs(1))) {
+            Err(err @ Error::ModuleTimeout(..)) => assert_eq!(err.to_string(), "Module 'mod_stuck' did not return from 'on_shutdown' within 20ms"),
+            _ => panic!("Should be 'ModuleTimeout' error.")
+        }
+        let panicked = panic::catch_unwind(|| with_deadline("mod_panic", "on_load", Some(Duration::from_secs(5)), || panic!("load failed")));
+        assert!(panicked.is_err());
+    }
+
+    #[test]
+    /// Tests the validation of a module with a deadline.
+    fn test_validate() {
+        let mut events: Vec<Event> = Vec::new();
+        let fast: Arc<Box<dyn MammothInterface>> = Arc::new(Box::new(SlowModule(Duration::from_millis(0))));
+        validate("mod_fast", fast, (), Some(Duration::from_secs(5)), &mut events).unwrap();
+        assert_eq!(events.len(), 1);
+
+        let slow: Arc<Box<dyn MammothInterface>> = Arc::new(Box::new(SlowModule(Duration::from_secs(1))));
+        let keep_alive = Arc::new(());
+        assert!(validate("mod_slow", slow, keep_alive.clone(), Some(Duration::from_millis(20)), &mut events).is_err());
+        assert_eq!(events.len(), 2);
+        assert_eq!(Arc::strong_count(&keep_alive), 2);
+    }
+}
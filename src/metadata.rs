@@ -0,0 +1,230 @@
+//! The `ModuleMetadata` structure describes a module library, as exported through its function
+//! table (see `abi::ModuleVTable`).
+//!
+//! The `mammoth_module` attribute generates the export from the package of the module (name of
+//! the crate, description, authors and license), along with the capabilities listed in the
+//! attribute, e.g. `#[mammoth_module(constructor_fn, capabilities(validation, shutdown))]`, and the
+//! modules it requires, e.g. `requires(mod_auth, "mod-sessions")`, and its kind, e.g.
+//! `kind(handler)`.
+//! This allows operators to inspect a library before enabling it (see `Module::inspect`).
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Capabilities that a module can declare in its metadata.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Capability {
+    /// The module performs some work when loaded (`MammothInterface::on_load`).
+    Load,
+    /// The module validates its configuration (`MammothInterface::on_validation`).
+    Validation,
+    /// The module performs some work when the server starts (`MammothInterface::on_start`).
+    Start,
+    /// The module reacts to the reloads of the configuration
+    /// (`MammothInterface::on_config_reload`).
+    Reload,
+    /// The module performs some work when the server shuts down (`MammothInterface::on_shutdown`).
+    Shutdown,
+    /// The module writes to the logger of the server (`Log`).
+    Logging
+}
+
+impl Capability {
+    /// Obtains the name of the capability, as written in the `mammoth_module` attribute.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Capability::Load => "load",
+            Capability::Validation => "validation",
+            Capability::Start => "start",
+            Capability::Reload => "reload",
+            Capability::Shutdown => "shutdown",
+            Capability::Logging => "logging"
+        }
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Describes the role of a module in the handling of the requests.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleKind {
+    /// The module terminates requests, producing their responses (see `Handler`).
+    Handler,
+    /// The module wraps the requests terminated by other modules, e.g. to authenticate or
+    /// compress them (see `Middleware`).
+    Middleware,
+    /// The module runs alongside the server without taking part in the handling of requests,
+    /// e.g. a cache or a metrics exporter (see `Service`).
+    Service
+}
+
+impl ModuleKind {
+    /// Obtains the name of the kind, as written in the configuration.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModuleKind::Handler => "handler",
+            ModuleKind::Middleware => "middleware",
+            ModuleKind::Service => "service"
+        }
+    }
+}
+
+impl Display for ModuleKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Structure that describes a module library.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ModuleMetadata {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    license: Option<String>,
+    #[serde(default)]
+    capabilities: Vec<Capability>,
+    #[serde(default)]
+    requires: Vec<String>,
+    kind: Option<ModuleKind>,
+    version: Option<String>
+}
+
+impl ModuleMetadata {
+    /// Creates a new `ModuleMetadata` structure for the module `name`, without any other
+    /// information.
+    pub fn new(name: &str) -> ModuleMetadata {
+        ModuleMetadata {
+            name: name.to_owned(),
+            description: None,
+            authors: Vec::new(),
+            license: None,
+            capabilities: Vec::new(),
+            requires: Vec::new(),
+            kind: None,
+            version: None
+        }
+    }
+    /// Creates a new `ModuleMetadata` structure from the information of a Cargo package, where
+    /// empty fields are considered missing and `authors` is a colon-separated list (as in the
+    /// `CARGO_PKG_AUTHORS` environment variable).
+    pub fn from_package(name: &str, description: &str, authors: &str, license: &str, capabilities: &[Capability], requires: &[&str]) -> ModuleMetadata {
+        let non_empty = |s: &str| if s.is_empty() { None } else { Some(s.to_owned()) };
+        let mut capabilities = capabilities.to_vec();
+        capabilities.sort();
+        capabilities.dedup();
+
+        ModuleMetadata {
+            name: name.to_owned(),
+            description: non_empty(description),
+            authors: authors.split(':').filter(|a| !a.is_empty()).map(|a| a.to_owned()).collect(),
+            license: non_empty(license),
+            capabilities,
+            requires: requires.iter().map(|r| (*r).to_owned()).collect(),
+            kind: None,
+            version: None
+        }
+    }
+    /// Declares the kind of the module.
+    pub fn with_kind(mut self, kind: ModuleKind) -> ModuleMetadata {
+        self.kind = Some(kind);
+        self
+    }
+    /// Declares the version of the module, for the modules that are not built against
+    /// `mammoth-setup` (i.e. built-in and WebAssembly modules, see `Module::version`).
+    pub fn with_version(mut self, version: &str) -> ModuleMetadata {
+        self.version = Some(version.to_owned());
+        self
+    }
+
+    /// Obtains the name of the module.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Obtains the description of the module, if any.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+    /// Obtains the authors of the module.
+    pub fn authors(&self) -> Vec<&str> {
+        self.authors.iter().map(|a| a.as_str()).collect()
+    }
+    /// Obtains the license of the module, if any.
+    pub fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+    /// Obtains the capabilities declared by the module.
+    pub fn capabilities(&self) -> &[Capability] {
+        &self.capabilities
+    }
+    /// Obtains the names of the modules required by the module.
+    pub fn requires(&self) -> Vec<&str> {
+        self.requires.iter().map(|r| r.as_str()).collect()
+    }
+    /// Obtains the kind declared by the module, if any.
+    pub fn kind(&self) -> Option<ModuleKind> {
+        self.kind
+    }
+    /// Obtains the version declared by the module, if any.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+    /// Returns `true` if the module declares the capability `capability`.
+    pub fn has_capability(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+impl Display for ModuleMetadata {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(ref license) = self.license {
+            write!(f, " ({})", license)?;
+        }
+        if let Some(ref description) = self.description {
+            write!(f, ": {}", description)?;
+        }
+        if !self.capabilities.is_empty() {
+            let capabilities: Vec<&str> = self.capabilities.iter().map(|c| c.as_str()).collect();
+            write!(f, " [{}]", capabilities.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::metadata::{Capability, ModuleKind, ModuleMetadata};
+
+    #[test]
+    /// Tests the metadata built from the information of a Cargo package.
+    fn test_from_package() {
+        let metadata = ModuleMetadata::from_package("mod_test", "", "Alice <alice@example.com>:Bob", "MIT", &[Capability::Shutdown, Capability::Validation], &["mod_auth"]);
+
+        assert_eq!(metadata.description(), None);
+        assert_eq!(metadata.authors(), vec!["Alice <alice@example.com>", "Bob"]);
+        assert_eq!(metadata.capabilities(), &[Capability::Validation, Capability::Shutdown]);
+        assert!(!metadata.has_capability(Capability::Load));
+        assert_eq!(metadata.requires(), vec!["mod_auth"]);
+        assert_eq!(metadata.to_string(), "mod_test (MIT) [validation, shutdown]");
+        assert_eq!(metadata.kind(), None);
+    }
+
+    #[test]
+    /// Tests the kind declared in the metadata.
+    fn test_kind() {
+        let metadata = ModuleMetadata::new("mod_test").with_kind(ModuleKind::Middleware);
+        let serialized = toml::to_string(&metadata).unwrap();
+
+        assert!(serialized.contains("kind = \"middleware\""));
+        assert_eq!(toml::from_str::<ModuleMetadata>(&serialized).unwrap().kind(), Some(ModuleKind::Middleware));
+        assert_eq!(toml::from_str::<ModuleMetadata>("name = \"mod_test\"").unwrap().kind(), None);
+    }
+}
@@ -0,0 +1,234 @@
+//! Counters and gauges exposed by the loaded modules.
+//!
+//! The server periodically asks the modules for their metrics through
+//! `MammothInterface::on_metrics`, handing them a `MetricsCollector`, e.g.
+//! ```rust,ignore
+//! fn on_metrics(&self, collector: &mut MetricsCollector) {
+//!     collector.counter("logins_total", self.logins.load(Ordering::Relaxed));
+//!     collector.gauge_with("sessions", &[("state", "active")], self.active() as f64);
+//! }
+//! ```
+//! The samples of every module are aggregated by `LoadedModuleSet::collect_metrics`, labelled
+//! with the name of the module (and the host of per-host instances), and can then be logged or
+//! rendered in the Prometheus text format to be scraped.
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::diagnostics::Logger;
+use crate::error::severity::Severity;
+
+/// Type of a metric.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    /// A value that only increases, e.g. the number of served requests.
+    Counter,
+    /// A value that can increase and decrease, e.g. the number of open connections.
+    Gauge
+}
+
+impl Display for MetricKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            MetricKind::Counter => write!(f, "counter"),
+            MetricKind::Gauge => write!(f, "gauge")
+        }
+    }
+}
+
+/// Structure that contains a single value of a metric.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Sample {
+    name: String,
+    kind: MetricKind,
+    value: f64,
+    #[serde(default)]
+    labels: Vec<(String, String)>
+}
+
+impl Sample {
+    /// Obtains the name of the metric.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Obtains the type of the metric.
+    pub fn kind(&self) -> MetricKind {
+        self.kind
+    }
+    /// Obtains the value of the metric.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+    /// Obtains the value of the label `name`, if any.
+    pub fn label(&self, name: &str) -> Option<&str> {
+        self.labels.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+    /// Obtains the labels of the sample, in order.
+    pub fn labels(&self) -> Vec<(&str, &str)> {
+        self.labels.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect()
+    }
+}
+
+impl Display for Sample {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.labels.is_empty() {
+            let labels: Vec<String> = self.labels.iter()
+                .map(|(n, v)| format!("{}=\"{}\"", n, v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")))
+                .collect();
+            write!(f, "{{{}}}", labels.join(","))?;
+        }
+        write!(f, " {}", self.value)
+    }
+}
+
+/// Structure through which the modules report their metrics.
+///
+/// Names of metrics and labels may only contain ASCII letters, digits and underscores, and may
+/// not start with a digit; other characters are replaced with underscores.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct MetricsCollector {
+    samples: Vec<Sample>
+}
+
+impl MetricsCollector {
+    /// Creates a new, empty `MetricsCollector` structure.
+    pub fn new() -> MetricsCollector {
+        MetricsCollector {
+            samples: Vec::new()
+        }
+    }
+
+    /// Records the counter `name` with `value`.
+    pub fn counter(&mut self, name: &str, value: u64) {
+        self.record(name, MetricKind::Counter, &[], value as f64);
+    }
+    /// Records the counter `name` with `value`, given its labels.
+    pub fn counter_with(&mut self, name: &str, labels: &[(&str, &str)], value: u64) {
+        self.record(name, MetricKind::Counter, labels, value as f64);
+    }
+    /// Records the gauge `name` with `value`.
+    pub fn gauge(&mut self, name: &str, value: f64) {
+        self.record(name, MetricKind::Gauge, &[], value);
+    }
+    /// Records the gauge `name` with `value`, given its labels.
+    pub fn gauge_with(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.record(name, MetricKind::Gauge, labels, value);
+    }
+    fn record(&mut self, name: &str, kind: MetricKind, labels: &[(&str, &str)], value: f64) {
+        self.samples.push(Sample {
+            name: sanitize(name),
+            kind,
+            value,
+            labels: labels.iter().map(|(n, v)| (sanitize(n), (*v).to_owned())).collect()
+        });
+    }
+
+    /// Moves the samples of `other` into this collector, adding `labels` in front of their own.
+    pub fn merge(&mut self, other: MetricsCollector, labels: &[(&str, &str)]) {
+        for mut sample in other.samples {
+            let mut merged: Vec<(String, String)> = labels.iter()
+                .map(|(n, v)| (sanitize(n), (*v).to_owned()))
+                .collect();
+            merged.append(&mut sample.labels);
+            sample.labels = merged;
+            self.samples.push(sample);
+        }
+    }
+
+    /// Obtains the recorded samples, in order.
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+    /// Obtains the first sample of the metric `name` whose labels include `labels`, if any.
+    pub fn get(&self, name: &str, labels: &[(&str, &str)]) -> Option<&Sample> {
+        self.samples.iter()
+            .filter(|s| s.name == name)
+            .find(|s| labels.iter().all(|(n, v)| s.label(n) == Some(*v)))
+    }
+    /// Returns `true` if no sample has been recorded and `false` otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Renders the samples in the Prometheus text format, grouped by metric.
+    pub fn render(&self) -> String {
+        let mut names: Vec<(&str, MetricKind)> = Vec::new();
+        for sample in self.samples.iter() {
+            if !names.iter().any(|(n, _)| *n == sample.name) {
+                names.push((&sample.name, sample.kind));
+            }
+        }
+
+        let mut output = String::new();
+        for (name, kind) in names {
+            output.push_str(&format!("# TYPE {} {}\n", name, kind));
+            for sample in self.samples.iter().filter(|s| s.name == name) {
+                output.push_str(&format!("{}\n", sample));
+            }
+        }
+        output
+    }
+    /// Writes every sample into the `logger`, with `Information` severity.
+    pub fn report(&self, logger: &mut dyn Logger) {
+        for sample in self.samples.iter() {
+            logger.log(Severity::Information, &format!("Metric {}.", sample));
+        }
+    }
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .enumerate()
+        .map(|(i, c)| if c.is_ascii_alphabetic() || c == '_' || (i > 0 && c.is_ascii_digit()) { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::event::Event;
+    use crate::metrics::{MetricKind, MetricsCollector};
+
+    #[test]
+    /// Tests recording and aggregating samples.
+    fn test_collector() {
+        let mut module = MetricsCollector::new();
+        module.counter("logins_total", 3);
+        module.gauge_with("sessions", &[("state", "active")], 2.5);
+        module.counter("failed-logins", 1);
+
+        let mut collector = MetricsCollector::new();
+        assert!(collector.is_empty());
+        collector.merge(module, &[("module", "mod_auth")]);
+
+        let sample = collector.get("sessions", &[("state", "active")]).unwrap();
+        assert_eq!(sample.kind(), MetricKind::Gauge);
+        assert_eq!(sample.value(), 2.5);
+        assert_eq!(sample.labels(), vec![("module", "mod_auth"), ("state", "active")]);
+        assert!(collector.get("failed_logins", &[("module", "mod_auth")]).is_some());
+        assert!(collector.get("logins_total", &[("module", "mod_other")]).is_none());
+
+        let mut events: Vec<Event> = Vec::new();
+        collector.report(&mut events);
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    /// Tests the rendering and the serialization of the samples.
+    fn test_render() {
+        let mut collector = MetricsCollector::new();
+        collector.counter_with("requests_total", &[("host", "a\"b")], 10);
+        collector.gauge("1connections", 4.0);
+        collector.counter_with("requests_total", &[("host", "c")], 5);
+
+        assert_eq!(collector.render(), "# TYPE requests_total counter\n\
+            requests_total{host=\"a\\\"b\"} 10\n\
+            requests_total{host=\"c\"} 5\n\
+            # TYPE _connections gauge\n\
+            _connections 4\n");
+        let serialized = toml::to_string(&collector).unwrap();
+        assert_eq!(toml::from_str::<MetricsCollector>(&serialized).unwrap(), collector);
+    }
+}
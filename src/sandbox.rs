@@ -0,0 +1,269 @@
+//! Filesystem sandbox of the modules.
+//!
+//! A `[[mod]]` can restrict the files its module is expected to access, e.g.
+//! ```toml
+//! [[mod]]
+//! name = "mod_upload"
+//! allowed_paths = ["/var/lib/mammoth/uploads", "/etc/mammoth/upload.d"]
+//! config = { target = "/var/lib/mammoth/uploads" }
+//! ```
+//! The validation of the configuration then fails if the configuration of the module references an
+//! absolute path outside of its allowed paths, and the module receives a `SandboxedFs` handle
+//! through `MammothInterface::on_sandbox`, through which it should perform its file accesses.
+//!
+//! The sandbox is a defense-in-depth measure: modules run in the process of the server and can
+//! still access the filesystem directly, but well-behaved third-party modules going through the
+//! handle cannot be tricked into accessing files outside of their allowed paths.
+use std::env;
+use std::fs;
+use std::fs::{File, OpenOptions, ReadDir};
+use std::path::{Component, Path, PathBuf};
+
+use toml::Value;
+
+use crate::error::Error;
+
+/// Handle through which a module accesses the files under its allowed paths.
+///
+/// Relative paths are resolved against the first allowed path; a sandbox without allowed paths
+/// denies every access.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SandboxedFs {
+    roots: Vec<PathBuf>
+}
+
+impl SandboxedFs {
+    /// Creates a new `SandboxedFs` structure given the allowed paths; relative paths are relative
+    /// to the working directory of the server.
+    pub fn new<P>(roots: &[P]) -> SandboxedFs
+        where
+            P: AsRef<Path>
+    {
+        let cwd = env::current_dir().unwrap_or_default();
+
+        SandboxedFs {
+            roots: roots.iter().map(|root| normalize(&cwd.join(root))).collect()
+        }
+    }
+
+    /// Obtains the allowed paths.
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+    /// Returns `true` if `path` lies under one of the allowed paths and `false` otherwise.
+    ///
+    /// Paths are also checked after resolving the symbolic links of their existing ancestors.
+    pub fn allows<P>(&self, path: P) -> bool
+        where
+            P: AsRef<Path>
+    {
+        self.resolve(path).is_ok()
+    }
+    /// Obtains the absolute path of `path`, checking that it lies under one of the allowed paths.
+    ///
+    /// Fails with `Error::SandboxViolation` otherwise.
+    pub fn resolve<P>(&self, path: P) -> Result<PathBuf, Error>
+        where
+            P: AsRef<Path>
+    {
+        let path = path.as_ref();
+        let violation = || Error::SandboxViolation(path.to_path_buf());
+        let absolute = match self.roots.first() {
+            Some(root) => normalize(&root.join(path)),
+            None => Err(violation())?
+        };
+
+        if !self.roots.iter().any(|root| absolute.starts_with(root)) {
+            Err(violation())?;
+        }
+        let canonical = canonicalize(&absolute);
+        let inside = self.roots.iter()
+            .map(|root| canonicalize(root))
+            .any(|root| canonical.starts_with(root));
+        if !inside {
+            Err(violation())?;
+        }
+
+        Ok(absolute)
+    }
+    /// Obtains the absolute paths referenced by `config` that lie outside of the allowed paths.
+    pub fn violations(&self, config: &Value) -> Vec<PathBuf> {
+        let mut violations = Vec::new();
+        collect_paths(config, &mut |path| {
+            if !self.allows(path) {
+                violations.push(path.to_path_buf());
+            }
+        });
+        violations
+    }
+
+    /// Opens the file at `path` in read-only mode.
+    pub fn open<P>(&self, path: P) -> Result<File, Error>
+        where
+            P: AsRef<Path>
+    {
+        Ok(File::open(self.resolve(path)?)?)
+    }
+    /// Opens the file at `path` with the given options.
+    pub fn open_with<P>(&self, path: P, options: &OpenOptions) -> Result<File, Error>
+        where
+            P: AsRef<Path>
+    {
+        Ok(options.open(self.resolve(path)?)?)
+    }
+    /// Creates the file at `path`, truncating it if it exists.
+    pub fn create<P>(&self, path: P) -> Result<File, Error>
+        where
+            P: AsRef<Path>
+    {
+        Ok(File::create(self.resolve(path)?)?)
+    }
+    /// Reads the whole content of the file at `path`.
+    pub fn read<P>(&self, path: P) -> Result<Vec<u8>, Error>
+        where
+            P: AsRef<Path>
+    {
+        Ok(fs::read(self.resolve(path)?)?)
+    }
+    /// Reads the whole content of the file at `path` as a string.
+    pub fn read_to_string<P>(&self, path: P) -> Result<String, Error>
+        where
+            P: AsRef<Path>
+    {
+        Ok(fs::read_to_string(self.resolve(path)?)?)
+    }
+    /// Writes `contents` into the file at `path`, replacing its content.
+    pub fn write<P, C>(&self, path: P, contents: C) -> Result<(), Error>
+        where
+            P: AsRef<Path>,
+            C: AsRef<[u8]>
+    {
+        Ok(fs::write(self.resolve(path)?, contents)?)
+    }
+    /// Obtains the entries of the directory at `path`.
+    pub fn read_dir<P>(&self, path: P) -> Result<ReadDir, Error>
+        where
+            P: AsRef<Path>
+    {
+        Ok(fs::read_dir(self.resolve(path)?)?)
+    }
+    /// Creates the directory at `path` along with its missing parents.
+    pub fn create_dir_all<P>(&self, path: P) -> Result<(), Error>
+        where
+            P: AsRef<Path>
+    {
+        Ok(fs::create_dir_all(self.resolve(path)?)?)
+    }
+    /// Removes the file at `path`.
+    pub fn remove_file<P>(&self, path: P) -> Result<(), Error>
+        where
+            P: AsRef<Path>
+    {
+        Ok(fs::remove_file(self.resolve(path)?)?)
+    }
+}
+
+/// Removes the `.` and `..` components of `path` without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => { normalized.pop(); },
+            other => normalized.push(other.as_os_str())
+        }
+    }
+    normalized
+}
+
+/// Resolves the symbolic links of the longest existing ancestor of the normalized `path`.
+fn canonicalize(path: &Path) -> PathBuf {
+    for ancestor in path.ancestors() {
+        if let Ok(canonical) = ancestor.canonicalize() {
+            let rest = path.strip_prefix(ancestor).unwrap_or(Path::new(""));
+            return if rest.as_os_str().is_empty() { canonical } else { canonical.join(rest) };
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Calls `f` for every string of `value` that is an absolute path.
+fn collect_paths<F>(value: &Value, f: &mut F)
+    where
+        F: FnMut(&Path)
+{
+    match value {
+        Value::String(s) if Path::new(s).is_absolute() => f(Path::new(s)),
+        Value::Array(values) => values.iter().for_each(|v| collect_paths(v, f)),
+        Value::Table(table) => table.values().for_each(|v| collect_paths(v, f)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+
+    use toml::Value;
+
+    use crate::error::Error;
+    use crate::sandbox::SandboxedFs;
+
+    #[test]
+    /// Tests the resolution of paths inside and outside the sandbox.
+    fn test_resolve() {
+        let root = env::temp_dir().join("mammoth_test_sandbox");
+        let sandbox = SandboxedFs::new(&[&root]);
+
+        assert_eq!(sandbox.resolve("data/file.txt").unwrap(), root.join("data/file.txt"));
+        assert!(sandbox.allows(root.join("./data/../file.txt")));
+        assert!(!sandbox.allows("../outside.txt"));
+        assert!(!sandbox.allows("/etc/passwd"));
+        match sandbox.resolve("/etc/passwd") {
+            Err(Error::SandboxViolation(path)) => assert_eq!(path.to_str(), Some("/etc/passwd")),
+            other => panic!("unexpected result: {:?}", other)
+        }
+        assert!(!SandboxedFs::default().allows("file.txt"));
+    }
+
+    #[test]
+    /// Tests file accesses through the sandbox.
+    fn test_access() {
+        let root = env::temp_dir().join("mammoth_test_sandbox_access");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let sandbox = SandboxedFs::new(&[&root]);
+
+        sandbox.create_dir_all("cache").unwrap();
+        sandbox.write("cache/entry", "hello").unwrap();
+        assert_eq!(sandbox.read_to_string("cache/entry").unwrap(), "hello");
+        assert_eq!(sandbox.read_dir("cache").unwrap().count(), 1);
+        assert!(sandbox.write("../escaped", "hello").is_err());
+        assert!(!root.parent().unwrap().join("escaped").exists());
+        sandbox.remove_file("cache/entry").unwrap();
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(env::temp_dir(), root.join("link")).unwrap();
+            assert!(!sandbox.allows("link/file.txt"));
+            assert!(!sandbox.allows("link/missing/file.txt"));
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    /// Tests the detection of configured paths outside the sandbox.
+    fn test_violations() {
+        let sandbox = SandboxedFs::new(&["/var/lib/mammoth"]);
+        let config: Value = toml::from_str(r#"
+        target = "/var/lib/mammoth/uploads"
+        relative = "uploads"
+        [backup]
+        paths = ["/var/lib/mammoth/backup", "/etc/shadow"]
+        "#).unwrap();
+
+        assert_eq!(sandbox.violations(&config), vec![std::path::PathBuf::from("/etc/shadow")]);
+    }
+}
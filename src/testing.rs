@@ -0,0 +1,9 @@
+//! Utilities to test embedders and modules of Mammoth.
+//!
+//! Some of the utilities of this module are only available with the corresponding features (e.g.
+//! `chaos`).
+
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod harness;
+pub mod integration;
@@ -0,0 +1,139 @@
+//! Injection of failures at defined points of the startup, for chaos testing.
+//!
+//! Failures can be injected either through the API (see `inject`) or through the `MAMMOTH_CHAOS`
+//! environment variable, containing a comma-separated list of failure points, e.g.
+//! ```text
+//! MAMMOTH_CHAOS=config_parse,module_load:mod_test,tls_setup,logger_init
+//! ```
+//! Whenever the execution reaches an injected failure point, the corresponding operation fails
+//! with `Error::InjectedFailure`.
+//!
+//! This module is only available with the `chaos` feature.
+use std::env;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use crate::error::Error;
+
+/// Name of the environment variable containing the injected failure points.
+pub const CHAOS_VAR: &str = "MAMMOTH_CHAOS";
+
+lazy_static! {
+    static ref INJECTED: RwLock<Vec<FailurePoint>> = RwLock::new(Vec::new());
+}
+
+/// Describes a point of the startup at which a failure can be injected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FailurePoint {
+    /// Parsing of the configuration.
+    ConfigParse,
+    /// Loading of the module with the given name.
+    ModuleLoad(String),
+    /// Construction of the TLS acceptor of a secure binding.
+    TlsSetup,
+    /// Construction of the loggers.
+    LoggerInit
+}
+
+impl Display for FailurePoint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            FailurePoint::ConfigParse => write!(f, "config_parse"),
+            FailurePoint::ModuleLoad(name) => write!(f, "module_load:{}", name),
+            FailurePoint::TlsSetup => write!(f, "tls_setup"),
+            FailurePoint::LoggerInit => write!(f, "logger_init")
+        }
+    }
+}
+
+impl FromStr for FailurePoint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<FailurePoint, Error> {
+        match s.trim() {
+            "config_parse" => Ok(FailurePoint::ConfigParse),
+            "tls_setup" => Ok(FailurePoint::TlsSetup),
+            "logger_init" => Ok(FailurePoint::LoggerInit),
+            s if s.starts_with("module_load:") => Ok(FailurePoint::ModuleLoad(s["module_load:".len()..].to_owned())),
+            _ => Err(Error::InvalidValue(CHAOS_VAR.to_owned()))
+        }
+    }
+}
+
+/// Parses a comma-separated list of failure points, ignoring the invalid ones.
+pub fn parse(points: &str) -> Vec<FailurePoint> {
+    points.split(',')
+        .filter(|p| !p.trim().is_empty())
+        .filter_map(|p| p.parse().ok())
+        .collect()
+}
+
+/// Injects a failure at the given `point`.
+pub fn inject(point: FailurePoint) {
+    INJECTED.write().unwrap().push(point);
+}
+/// Removes the failure injected through the API at the given `point`, if any.
+pub fn remove(point: &FailurePoint) {
+    INJECTED.write().unwrap().retain(|p| p != point);
+}
+/// Removes all the failures injected through the API.
+pub fn clear() {
+    INJECTED.write().unwrap().clear();
+}
+/// Returns `true` if a failure is injected at the given `point` and `false` otherwise.
+pub fn is_injected(point: &FailurePoint) -> bool {
+    if INJECTED.read().unwrap().contains(point) { return true; }
+
+    env::var(CHAOS_VAR)
+        .map(|points| parse(&points).contains(point))
+        .unwrap_or(false)
+}
+/// Fails with `Error::InjectedFailure` if a failure is injected at the given `point`.
+pub fn check(point: FailurePoint) -> Result<(), Error> {
+    if is_injected(&point) {
+        Err(Error::InjectedFailure(point.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::Module;
+    use crate::error::Error;
+    use crate::loaded::library::LoadedModuleSet;
+    use crate::testing::chaos::{check, inject, is_injected, parse, remove, FailurePoint};
+
+    #[test]
+    /// Tests parsing of the failure points.
+    fn test_parse() {
+        let points = parse("config_parse, module_load:mod_test,,nope,tls_setup,logger_init");
+
+        assert_eq!(points, vec![
+            FailurePoint::ConfigParse,
+            FailurePoint::ModuleLoad("mod_test".to_owned()),
+            FailurePoint::TlsSetup,
+            FailurePoint::LoggerInit
+        ]);
+        assert_eq!(points[1].to_string(), "module_load:mod_test");
+    }
+
+    #[test]
+    /// Tests injection of a module load failure through the API.
+    fn test_inject() {
+        let point = FailurePoint::ModuleLoad("mod_chaos".to_owned());
+        assert!(check(point.clone()).is_ok());
+
+        inject(point.clone());
+        assert!(is_injected(&point));
+        match Module::new("mod_chaos").unwrap().load_into(&mut LoadedModuleSet::new("./target/debug/")) {
+            Err(Error::InjectedFailure(desc)) => assert_eq!(desc, "module_load:mod_chaos"),
+            _ => panic!("Should be 'InjectedFailure' error.")
+        }
+
+        remove(&point);
+        assert!(!is_injected(&point));
+    }
+}
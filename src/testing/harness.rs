@@ -0,0 +1,273 @@
+//! Harness to test a single module outside of a server.
+//!
+//! `ModuleHarness` wraps either a module library (see `ModuleHarness::load`) or an interface
+//! constructed in-process (see `ModuleHarness::new`), prepares it as a `LoadedModuleSet` does
+//! (registering a capturing logger, then calling `on_bus` with a handle to a bus owned by the
+//! harness and `on_sandbox`) and drives its lifecycle (`on_load`, `on_validation` and
+//! `on_shutdown`), so that the authors of a module can test it without dealing with the `abi`
+//! module. The shutdown hook of the library, if any, runs when the harness is dropped.
+//! Every event logged by the module, both through its registered logger and during the validation,
+//! is captured and can be checked through the assertion helpers.
+//!
+//! # Example
+//! ```rust,no_run
+//! use mammoth_setup::error::severity::Severity;
+//! use mammoth_setup::testing::harness::ModuleHarness;
+//!
+//! let harness = ModuleHarness::load("./target/debug/libmod_test.so", None).unwrap();
+//!
+//! harness.run().unwrap();
+//! harness.assert_logged(Severity::Debug, "Test module loaded.");
+//! harness.assert_no_errors();
+//! ```
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+use toml::Value;
+
+use crate::MammothInterface;
+use crate::abi;
+use crate::abi::ForeignModule;
+use crate::bus::MessageBus;
+use crate::error::Error;
+use crate::error::event::Event;
+use crate::error::severity::Severity;
+use crate::loaded::library::LoadedLibrary;
+use crate::sandbox::SandboxedFs;
+
+/// Structure that drives the lifecycle of a single module, capturing its events.
+pub struct ModuleHarness {
+    // Declared before `library`, so that the instance is dropped before its library is unloaded.
+    interface: Box<dyn MammothInterface>,
+    library: Option<LoadedLibrary>,
+    bus: MessageBus,
+    events: Arc<RwLock<Vec<Event>>>
+}
+
+impl ModuleHarness {
+    /// Creates a new `ModuleHarness` driving `interface`, denying it access to every path.
+    pub fn new(interface: Box<dyn MammothInterface>) -> ModuleHarness {
+        ModuleHarness::with_sandbox(interface, SandboxedFs::new::<PathBuf>(&[]))
+    }
+    /// Creates a new `ModuleHarness` driving `interface`, preparing it with `sandbox` as the
+    /// handle to the paths it is allowed to access (see `MammothInterface::on_sandbox`).
+    pub fn with_sandbox(mut interface: Box<dyn MammothInterface>, sandbox: SandboxedFs) -> ModuleHarness {
+        let events = Arc::new(RwLock::new(Vec::new()));
+        let bus = MessageBus::new();
+        interface.register_logger(events.clone());
+        interface.on_bus(bus.handle());
+        interface.on_sandbox(sandbox);
+
+        ModuleHarness {
+            interface,
+            library: None,
+            bus,
+            events
+        }
+    }
+    /// Creates a new `ModuleHarness` driving the module of the library at `path`, constructed with
+    /// `config`, if any, and denied access to every path.
+    ///
+    /// Fails if the library cannot be loaded, if it exports an incompatible module or if the
+    /// construction of the module fails.
+    pub fn load<P>(path: P, config: Option<&Value>) -> Result<ModuleHarness, Error>
+        where
+            P: AsRef<Path>
+    {
+        ModuleHarness::load_with_sandbox(path, config, SandboxedFs::new::<PathBuf>(&[]))
+    }
+    /// Creates a new `ModuleHarness` driving the module of the library at `path` as by `load`,
+    /// preparing it with `sandbox` (see `with_sandbox`).
+    pub fn load_with_sandbox<P>(path: P, config: Option<&Value>, sandbox: SandboxedFs) -> Result<ModuleHarness, Error>
+        where
+            P: AsRef<Path>
+    {
+        let library = LoadedLibrary::open(path)?;
+        let interface = {
+            let vtable = abi::vtable(&library.library)?;
+            abi::check_host_version(vtable)?;
+            unsafe { ForeignModule::new(vtable, config)? }
+        };
+        let mut harness = ModuleHarness::with_sandbox(Box::new(interface), sandbox);
+        harness.library = Some(library);
+
+        Ok(harness)
+    }
+
+    /// Obtains the driven interface.
+    pub fn interface(&self) -> &dyn MammothInterface {
+        self.interface.as_ref()
+    }
+    /// Obtains the bus the module is connected to, e.g. to publish messages to the module.
+    pub fn bus(&self) -> &MessageBus {
+        &self.bus
+    }
+    /// Obtains the events captured so far.
+    pub fn events(&self) -> RwLockReadGuard<'_, Vec<Event>> {
+        self.events.read().unwrap()
+    }
+    /// Notifies the module through `on_load`.
+    pub fn load_module(&self) {
+        self.interface.on_load();
+    }
+    /// Validates the module through `on_validation`, capturing the logged events.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut events: Vec<Event> = Vec::new();
+        let result = self.interface.on_validation(&mut events);
+        self.events.write().unwrap().append(&mut events);
+
+        result
+    }
+    /// Notifies the module through `on_shutdown`.
+    pub fn shutdown(&self) {
+        self.interface.on_shutdown();
+    }
+    /// Drives the whole lifecycle of the module, i.e. `on_load`, `on_validation` and
+    /// `on_shutdown`, returning the result of the validation.
+    ///
+    /// The module is shut down even if the validation fails.
+    pub fn run(&self) -> Result<(), Error> {
+        self.load_module();
+        let result = self.validate();
+        self.shutdown();
+
+        result
+    }
+
+    /// Asserts that an event with severity `severity` and containing `description` was captured.
+    ///
+    /// # Panics
+    /// If no such event was captured, listing the captured events.
+    pub fn assert_logged(&self, severity: Severity, description: &str) {
+        let events = self.events();
+        if !events.iter().any(|e| e.severity() == severity && e.description().contains(description)) {
+            panic!("No event [{}] containing '{}' was logged; logged events:\n{}", severity, description, list(&events));
+        }
+    }
+    /// Asserts that no event with severity `Error` or higher was captured.
+    ///
+    /// # Panics
+    /// If such an event was captured, listing the captured events.
+    pub fn assert_no_errors(&self) {
+        let events = self.events();
+        if events.iter().any(|e| e.severity() >= Severity::Error) {
+            panic!("Errors were logged; logged events:\n{}", list(&events));
+        }
+    }
+}
+
+/// Lists `events`, one per line.
+fn list(events: &[Event]) -> String {
+    events.iter().map(|e| format!("  [{}] {}", e.severity(), e.description())).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use toml::Value;
+
+    use crate::MammothInterface;
+    use crate::bus::{BusHandle, Message};
+    use crate::diagnostics::{AsyncLoggerReference, Log, Logger};
+    use crate::error::Error;
+    use crate::error::severity::Severity;
+    use crate::sandbox::SandboxedFs;
+    use crate::testing::harness::ModuleHarness;
+
+    #[derive(Default)]
+    struct HarnessedModule {
+        logger: Option<AsyncLoggerReference>,
+        bus: Mutex<Option<BusHandle>>
+    }
+
+    impl Log for HarnessedModule {
+        fn register_logger(&mut self, logger: AsyncLoggerReference) {
+            self.logger = Some(logger);
+        }
+
+        fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+            self.logger.clone()
+        }
+    }
+
+    impl MammothInterface for HarnessedModule {
+        fn on_bus(&self, bus: BusHandle) {
+            let logger = self.retrieve_logger();
+            bus.subscribe("ping", move |_| {
+                if let Some(ref logger) = logger {
+                    logger.write().unwrap().log(Severity::Information, "Pinged.");
+                }
+            });
+            *self.bus.lock().unwrap() = Some(bus);
+        }
+
+        fn on_sandbox(&self, sandbox: SandboxedFs) {
+            self.log(Severity::Debug, &format!("Sandboxed with {} allowed paths.", sandbox.roots().len()));
+        }
+
+        fn on_load(&self) {
+            self.log(Severity::Information, "Loaded.");
+        }
+
+        fn on_validation(&self, logger: &mut dyn Logger) -> Result<(), Error> {
+            logger.log(Severity::Warning, "Validated.");
+            Ok(())
+        }
+
+        fn on_shutdown(&self) {
+            self.log(Severity::Information, "Shut down.");
+        }
+    }
+
+    #[test]
+    /// Tests the harness on an in-process interface.
+    fn test_harness() {
+        let harness = ModuleHarness::new(Box::new(HarnessedModule::default()));
+
+        assert!(harness.run().is_ok());
+        assert_eq!(harness.events().len(), 4);
+        harness.assert_logged(Severity::Debug, "Sandboxed with 0 allowed paths.");
+        harness.assert_logged(Severity::Information, "Loaded");
+        harness.assert_logged(Severity::Warning, "Validated.");
+        harness.assert_logged(Severity::Information, "Shut down.");
+        harness.assert_no_errors();
+    }
+
+    #[test]
+    /// Tests that the harness prepares the interface with the bus and the sandbox.
+    fn test_harness_preparation() {
+        let sandbox = SandboxedFs::new(&["./tests"]);
+        let harness = ModuleHarness::with_sandbox(Box::new(HarnessedModule::default()), sandbox);
+
+        assert_eq!(harness.bus().subscribers("ping"), 1);
+        assert_eq!(harness.bus().publish(&Message::new("ping", &1).unwrap()), 1);
+        harness.assert_logged(Severity::Information, "Pinged.");
+        harness.assert_logged(Severity::Debug, "Sandboxed with 1 allowed paths.");
+    }
+
+    #[test]
+    #[should_panic]
+    /// Tests that `assert_logged` fails if the event was not captured.
+    fn test_harness_assertion() {
+        let harness = ModuleHarness::new(Box::new(HarnessedModule::default()));
+
+        harness.assert_logged(Severity::Information, "Loaded.");
+    }
+
+    #[test]
+    /// Tests the harness on the `mod_test` library.
+    fn test_harness_library() {
+        let harness = ModuleHarness::load("./target/debug/libmod_test.so", None).unwrap();
+        assert!(harness.run().is_ok());
+        harness.assert_logged(Severity::Debug, "Test module loaded.");
+        harness.assert_logged(Severity::Debug, "Test module unloaded.");
+
+        let config = Value::from("test_error");
+        let harness = ModuleHarness::load("./target/debug/libmod_test.so", Some(&config)).unwrap();
+        assert!(harness.run().is_err());
+        harness.assert_logged(Severity::Debug, "Error tested successfully!");
+
+        assert!(ModuleHarness::load("./target/debug/libmod_nonexistent.so", None).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! End-to-end test harness that boots a miniature Mammoth.
+//!
+//! `TestServer::start` runs the whole startup pipeline on a configuration string: it parses and
+//! validates the configuration, loads the modules of every host through the real loader, binds
+//! every host to an ephemeral port on the loopback interface (the configured ports are only used
+//! to group the hosts that share a listener) and finally starts the modules.
+//! `TestServer::reload` notifies the modules of a new configuration, as on a reload.
+//! The harness can be used both to test this crate and by embedders to smoke-test their
+//! configurations against the actual loader.
+//!
+//! # Example
+//! ```rust
+//! use mammoth_setup::testing::integration::TestServer;
+//!
+//! let server = TestServer::start(r#"
+//! [mammoth]
+//!
+//! [[host]]
+//! listen = 8080
+//! "#).unwrap();
+//!
+//! assert_eq!(server.addresses().hosts().len(), 1);
+//! server.shutdown().unwrap();
+//! ```
+use std::net::{SocketAddr, TcpListener};
+
+use crate::config::{ConfigurationFile, HostIdentifier};
+use crate::diagnostics::Validator;
+use crate::diagnostics::report::BoundAddresses;
+use crate::error::Error;
+use crate::error::event::Event;
+use crate::loaded::library::LoadedModuleSet;
+
+/// Structure that contains a running miniature Mammoth.
+pub struct TestServer {
+    configuration: ConfigurationFile,
+    events: Vec<Event>,
+    modules: LoadedModuleSet,
+    listeners: Vec<(u16, TcpListener)>,
+    addresses: BoundAddresses
+}
+
+impl TestServer {
+    /// Starts a new `TestServer` given the TOML configuration string `toml`.
+    ///
+    /// Fails with the first error encountered by the pipeline.
+    pub fn start(toml: &str) -> Result<TestServer, Error> {
+        let configuration = ConfigurationFile::from_str(toml)?;
+        let mut events: Vec<Event> = Vec::new();
+        ().validate(&mut events, &configuration)?;
+
+        let mods_dir = configuration.mammoth().mods_dir()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default();
+        let mut modules = LoadedModuleSet::new(mods_dir);
+        let mut listeners: Vec<(u16, TcpListener)> = Vec::new();
+        let mut addresses = BoundAddresses::new();
+
+        for host in configuration.hosts() {
+            let id = host.identifier();
+            configuration.load_modules_for(&id, &mut modules, &mut events)?;
+
+            let port = host.binding().port();
+            let address = match listeners.iter().find(|(p, _)| *p == port) {
+                Some((_, listener)) => listener.local_addr()?,
+                None => {
+                    let listener = TcpListener::bind("127.0.0.1:0")?;
+                    let address = listener.local_addr()?;
+                    listeners.push((port, listener));
+                    address
+                }
+            };
+            addresses.insert(id, address);
+        }
+        modules.start();
+
+        Ok(TestServer {
+            configuration,
+            events,
+            modules,
+            listeners,
+            addresses
+        })
+    }
+
+    /// Obtains the parsed configuration.
+    pub fn configuration(&self) -> &ConfigurationFile {
+        &self.configuration
+    }
+    /// Obtains the events logged during the startup.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+    /// Obtains the names of the loaded modules.
+    pub fn modules(&self) -> Vec<&str> {
+        self.modules.module_names()
+    }
+    /// Obtains the addresses the hosts are bound to.
+    pub fn addresses(&self) -> &BoundAddresses {
+        &self.addresses
+    }
+    /// Obtains the address the host identified by `id` is bound to, if any.
+    pub fn address_of(&self, id: &HostIdentifier) -> Option<SocketAddr> {
+        self.addresses.get(id).and_then(|a| a.first().cloned())
+    }
+    /// Reloads the configuration given the TOML configuration string `toml`, notifying the loaded
+    /// modules.
+    ///
+    /// Modules and listeners are not changed. If the new configuration is invalid, the current one
+    /// is kept.
+    pub fn reload(&mut self, toml: &str) -> Result<(), Error> {
+        let configuration = ConfigurationFile::from_str(toml)?;
+        ().validate(&mut self.events, &configuration)?;
+
+        for host in configuration.hosts() {
+            configuration.reload_modules_for(&host.identifier(), &self.modules);
+        }
+        self.configuration = configuration;
+
+        Ok(())
+    }
+    /// Shuts down the server, notifying the modules and closing the listeners.
+    ///
+    /// Fails if a module does not return in time (see `LoadedModuleSet::shutdown`); the listeners
+    /// are closed anyway.
+    pub fn shutdown(self) -> Result<(), Error> {
+        drop(self.listeners);
+        self.modules.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpStream;
+
+    use crate::config::HostIdentifier;
+    use crate::error::Error;
+    use crate::testing::integration::TestServer;
+
+    #[test]
+    /// Tests the pipeline with two hosts sharing the same port.
+    fn test_start() {
+        let server = TestServer::start(r#"
+        [mammoth]
+
+        [[host]]
+        hostname = "localhost"
+        listen = 8080
+
+        [[host]]
+        listen = 8080
+
+        [[host]]
+        listen = 8088
+        "#).unwrap();
+
+        let localhost = server.address_of(&HostIdentifier::new(8080, Some("localhost"))).unwrap();
+        let any = server.address_of(&HostIdentifier::new(8080, None)).unwrap();
+        let other = server.address_of(&HostIdentifier::new(8088, None)).unwrap();
+
+        assert_eq!(localhost, any);
+        assert_ne!(localhost, other);
+        assert!(server.modules().is_empty());
+        assert!(TcpStream::connect(other).is_ok());
+
+        server.shutdown().unwrap();
+        assert!(TcpStream::connect(other).is_err());
+    }
+
+    #[test]
+    /// Tests the pipeline loading the `mod_test` module.
+    fn test_start_with_module() {
+        let server = TestServer::start(r#"
+        [mammoth]
+        mods_dir = "./target/debug/"
+
+        [[host]]
+        listen = 8080
+
+        [[mod]]
+        name = "mod_test"
+        location = "./target/debug/libmod_test.so"
+        "#).unwrap();
+
+        assert_eq!(server.modules(), vec!["mod_test"]);
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    /// Tests reloading the configuration of a running server.
+    fn test_reload() {
+        let mut server = TestServer::start(r#"
+        [mammoth]
+        mods_dir = "./target/debug/"
+
+        [[host]]
+        listen = 8080
+
+        [[mod]]
+        name = "mod_test"
+        location = "./target/debug/libmod_test.so"
+        "#).unwrap();
+
+        server.reload(r#"
+        [mammoth]
+        mods_dir = "./target/debug/"
+
+        [[host]]
+        listen = 8080
+
+        [[mod]]
+        name = "mod_test"
+        location = "./target/debug/libmod_test.so"
+        config = "reloaded"
+        "#).unwrap();
+        assert_eq!(server.configuration().mods()[0].config().and_then(|c| c.as_str()), Some("reloaded"));
+
+        assert!(server.reload("[mammoth]").is_err());
+        assert_eq!(server.configuration().mods()[0].config().and_then(|c| c.as_str()), Some("reloaded"));
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    /// Tests that the pipeline stops at the first error.
+    fn test_start_error() {
+        match TestServer::start("[mammoth]") {
+            Err(Error::Toml(_)) => {},
+            _ => panic!("Should be 'Toml' error.")
+        }
+        match TestServer::start("host = []\n[mammoth]") {
+            Err(Error::NoHost) => {},
+            _ => panic!("Should be 'NoHost' error.")
+        }
+    }
+}
@@ -1,13 +1,319 @@
+//! Version information and module compatibility checks.
+//!
+//! Whether a module can be loaded depends on a `CompatibilityPolicy`, which is read from the
+//! `[mammoth.compatibility]` section of the configuration or, if not specified, defaults to
+//! accepting every version that respects `COMPATIBILITY_STRING`.
+//!
+//! # Example
+//! ```toml
+//! [mammoth.compatibility]
+//! requirement = "~0.0.0"
+//! deny = ["0.0.3"]
+//!
+//! [mammoth.compatibility.modules.mod_legacy]
+//! allow = ["0.0.0-rc.1"]
+//! ```
+use std::collections::BTreeMap;
+use std::fmt;
+
 use semver::{Version, VersionReq};
 
-// FOR_LATER: find a better way to make compatibility check.
+use crate::config::module::ModuleName;
+use crate::diagnostics::{Logger, Validator};
+use crate::error::Error;
+use crate::error::severity::Severity;
+
+/// Default requisite that the version of a module must respect.
 pub const COMPATIBILITY_STRING: &str = "~0.0.0";
 
 pub fn version() -> Version {
     Version::parse(env!("CARGO_PKG_VERSION")).unwrap()
 }
 
+/// Returns `true` if `version` respects the default requisite `COMPATIBILITY_STRING`.
 pub fn compatible(version: &Version) -> bool {
     let req = VersionReq::parse(COMPATIBILITY_STRING).unwrap();
     req.matches(version)
-}
\ No newline at end of file
+}
+
+/// Checks whether the `version` of the module `name` can be loaded according to `policy`.
+///
+/// Rules are evaluated in the following order, and the first one that applies decides:
+/// 1. versions denied for the module, then versions denied globally;
+/// 2. versions allowed for the module, then versions allowed globally;
+/// 3. the requisite of the module, if overridden, or else the global requisite.
+pub fn check(policy: &CompatibilityPolicy, name: &str, version: &Version) -> CompatVerdict {
+    let module = policy.modules.get(name);
+
+    if module.is_some_and(|m| m.deny.contains(version)) {
+        return CompatVerdict::Denied(format!("version {} is denied for module '{}'", version, name));
+    }
+    if policy.deny.contains(version) {
+        return CompatVerdict::Denied(format!("version {} is globally denied", version));
+    }
+    if module.is_some_and(|m| m.allow.contains(version)) {
+        return CompatVerdict::Allowed(format!("version {} is explicitly allowed for module '{}'", version, name));
+    }
+    if policy.allow.contains(version) {
+        return CompatVerdict::Allowed(format!("version {} is explicitly allowed", version));
+    }
+
+    let requirement = policy.requirement_for(name);
+    let scope = if module.and_then(|m| m.requirement.as_ref()).is_some() { "module" } else { "global" };
+    if requirement.matches(version) {
+        CompatVerdict::Compatible(format!("version {} respects the {} requisite {}", version, scope, requirement))
+    } else {
+        CompatVerdict::Incompatible(format!("version {} does not respect the {} requisite {}", version, scope, requirement))
+    }
+}
+
+/// Outcome of a compatibility check, along with the reason that led to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompatVerdict {
+    /// The version respects the requisite.
+    Compatible(String),
+    /// The version is explicitly allowed, regardless of the requisite.
+    Allowed(String),
+    /// The version is explicitly denied, regardless of the requisite.
+    Denied(String),
+    /// The version does not respect the requisite.
+    Incompatible(String)
+}
+
+impl CompatVerdict {
+    /// Returns `true` if the module can be loaded.
+    pub fn is_compatible(&self) -> bool {
+        matches!(self, CompatVerdict::Compatible(_) | CompatVerdict::Allowed(_))
+    }
+    /// Obtains the reason of the verdict.
+    pub fn reason(&self) -> &str {
+        match self {
+            CompatVerdict::Compatible(reason) => reason,
+            CompatVerdict::Allowed(reason) => reason,
+            CompatVerdict::Denied(reason) => reason,
+            CompatVerdict::Incompatible(reason) => reason
+        }
+    }
+}
+
+impl fmt::Display for CompatVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompatVerdict::Compatible(reason) => write!(f, "compatible: {}", reason),
+            CompatVerdict::Allowed(reason) => write!(f, "allowed: {}", reason),
+            CompatVerdict::Denied(reason) => write!(f, "denied: {}", reason),
+            CompatVerdict::Incompatible(reason) => write!(f, "incompatible: {}", reason)
+        }
+    }
+}
+
+/// Structure that defines which module versions can be loaded.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct CompatibilityPolicy {
+    #[serde(default = "default_requirement")]
+    requirement: VersionReq,
+    #[serde(default = "default_versions")]
+    allow: Vec<Version>,
+    #[serde(default = "default_versions")]
+    deny: Vec<Version>,
+    #[serde(default = "default_modules")]
+    modules: BTreeMap<ModuleName, ModulePolicy>
+}
+
+/// Structure that defines the overrides of a `CompatibilityPolicy` for a single module.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct ModulePolicy {
+    requirement: Option<VersionReq>,
+    #[serde(default = "default_versions")]
+    allow: Vec<Version>,
+    #[serde(default = "default_versions")]
+    deny: Vec<Version>
+}
+
+#[doc(hidden)]
+fn default_requirement() -> VersionReq { VersionReq::parse(COMPATIBILITY_STRING).unwrap() }
+#[doc(hidden)]
+fn default_versions() -> Vec<Version> { Vec::new() }
+#[doc(hidden)]
+fn default_modules() -> BTreeMap<ModuleName, ModulePolicy> { BTreeMap::new() }
+
+impl CompatibilityPolicy {
+    /// Creates a new `CompatibilityPolicy` accepting the versions that respect `requirement`.
+    pub fn new(requirement: VersionReq) -> CompatibilityPolicy {
+        CompatibilityPolicy {
+            requirement,
+            allow: Vec::new(),
+            deny: Vec::new(),
+            modules: BTreeMap::new()
+        }
+    }
+
+    /// Obtains the global requisite.
+    pub fn requirement(&self) -> &VersionReq {
+        &self.requirement
+    }
+    /// Obtains the requisite of the module `name`, i.e. its own requisite if overridden and the
+    /// global requisite otherwise.
+    pub fn requirement_for(&self, name: &str) -> &VersionReq {
+        self.modules.get(name)
+            .and_then(|m| m.requirement.as_ref())
+            .unwrap_or(&self.requirement)
+    }
+    /// Obtains the globally allowed versions.
+    pub fn allowed(&self) -> &[Version] {
+        &self.allow
+    }
+    /// Obtains the globally denied versions.
+    pub fn denied(&self) -> &[Version] {
+        &self.deny
+    }
+    /// Obtains the overrides of the module `name`, if any.
+    pub fn module(&self, name: &str) -> Option<&ModulePolicy> {
+        self.modules.get(name)
+    }
+    /// Obtains the names of the modules having overrides.
+    pub fn module_names(&self) -> Vec<&ModuleName> {
+        self.modules.keys().collect()
+    }
+
+    /// Sets the global requisite.
+    pub fn set_requirement(&mut self, requirement: VersionReq) {
+        self.requirement = requirement;
+    }
+    /// Allows `version` for every module.
+    pub fn allow(&mut self, version: Version) {
+        self.allow.push(version);
+    }
+    /// Denies `version` for every module.
+    pub fn deny(&mut self, version: Version) {
+        self.deny.push(version);
+    }
+    /// Sets the overrides of the module `name`.
+    pub fn set_module(&mut self, name: ModuleName, policy: ModulePolicy) {
+        self.modules.insert(name, policy);
+    }
+    /// Removes the overrides of the module `name`.
+    pub fn remove_module(&mut self, name: &str) {
+        self.modules.remove(name);
+    }
+}
+
+impl Default for CompatibilityPolicy {
+    fn default() -> Self {
+        CompatibilityPolicy::new(default_requirement())
+    }
+}
+
+impl ModulePolicy {
+    /// Creates a new, empty `ModulePolicy` structure.
+    pub fn new() -> ModulePolicy {
+        ModulePolicy::default()
+    }
+
+    /// Obtains the requisite of the module, if overridden.
+    pub fn requirement(&self) -> Option<&VersionReq> {
+        self.requirement.as_ref()
+    }
+    /// Obtains the versions allowed for the module.
+    pub fn allowed(&self) -> &[Version] {
+        &self.allow
+    }
+    /// Obtains the versions denied for the module.
+    pub fn denied(&self) -> &[Version] {
+        &self.deny
+    }
+
+    /// Overrides the requisite of the module.
+    pub fn set_requirement(&mut self, requirement: VersionReq) {
+        self.requirement = Some(requirement);
+    }
+    /// Allows `version` for the module.
+    pub fn allow(&mut self, version: Version) {
+        self.allow.push(version);
+    }
+    /// Denies `version` for the module.
+    pub fn deny(&mut self, version: Version) {
+        self.deny.push(version);
+    }
+}
+
+impl Validator<CompatibilityPolicy> for () {
+    fn validate(&self, logger: &mut dyn Logger, item: &CompatibilityPolicy) -> Result<(), Error> {
+        for version in item.allow.iter().filter(|v| item.deny.contains(v)) {
+            let desc = format!("Version {} is both allowed and denied by the compatibility policy; denying.", version);
+            logger.log(Severity::Warning, &desc);
+        }
+        for (name, module) in item.modules.iter() {
+            for version in module.allow.iter().filter(|v| module.deny.contains(v) || item.deny.contains(v)) {
+                let desc = format!("Version {} is both allowed and denied for module '{}'; denying.", version, name);
+                logger.log(Severity::Warning, &desc);
+            }
+        }
+        if !item.allow.is_empty() || item.modules.values().any(|m| !m.allow.is_empty()) {
+            logger.log(Severity::Information, "Compatibility policy allows specific module versions regardless of the requisite.");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use semver::{Version, VersionReq};
+
+    use crate::diagnostics::Validator;
+    use crate::error::event::Event;
+    use crate::version::{check, CompatibilityPolicy, CompatVerdict};
+
+    #[test]
+    /// Tests the default policy.
+    fn test_default_policy() {
+        let policy = CompatibilityPolicy::default();
+
+        assert!(check(&policy, "mod_test", &Version::parse("0.0.1").unwrap()).is_compatible());
+        assert!(!check(&policy, "mod_test", &Version::parse("0.1.0").unwrap()).is_compatible());
+    }
+
+    #[test]
+    /// Tests the per-module overrides and the allow and deny lists.
+    fn test_check() {
+        let policy: CompatibilityPolicy = toml::from_str(r#"
+        deny = ["0.0.3"]
+
+        [modules.mod_legacy]
+        requirement = ">=0.0.0, <0.2.0"
+        allow = ["0.2.1"]
+
+        [modules.mod_broken]
+        deny = ["0.0.1"]
+        "#).unwrap();
+        let v = |s: &str| Version::parse(s).unwrap();
+
+        assert_eq!(policy.requirement_for("mod_legacy"), &VersionReq::parse(">=0.0.0, <0.2.0").unwrap());
+        assert_eq!(policy.requirement_for("mod_test"), policy.requirement());
+
+        assert!(matches!(check(&policy, "mod_test", &v("0.0.1")), CompatVerdict::Compatible(_)));
+        assert!(matches!(check(&policy, "mod_test", &v("0.0.3")), CompatVerdict::Denied(_)));
+        assert!(matches!(check(&policy, "mod_legacy", &v("0.1.0")), CompatVerdict::Compatible(_)));
+        assert!(matches!(check(&policy, "mod_legacy", &v("0.2.1")), CompatVerdict::Allowed(_)));
+        assert!(matches!(check(&policy, "mod_legacy", &v("0.2.0")), CompatVerdict::Incompatible(_)));
+        assert!(matches!(check(&policy, "mod_legacy", &v("0.0.3")), CompatVerdict::Denied(_)));
+        assert!(matches!(check(&policy, "mod_broken", &v("0.0.1")), CompatVerdict::Denied(_)));
+        assert!(check(&policy, "mod_broken", &v("0.0.1")).reason().contains("mod_broken"));
+
+        assert!(toml::from_str::<CompatibilityPolicy>("requirement = \"not a version\"").is_err());
+        assert!(toml::from_str::<CompatibilityPolicy>("[modules.\"../evil\"]").is_err());
+    }
+
+    #[test]
+    /// Tests the validation of a policy.
+    fn test_validate() {
+        let mut events: Vec<Event> = Vec::new();
+        let mut policy = CompatibilityPolicy::default();
+
+        policy.allow(Version::parse("0.1.0").unwrap());
+        policy.deny(Version::parse("0.1.0").unwrap());
+        assert!(().validate(&mut events, &policy).is_ok());
+        assert!(!events.is_empty());
+    }
+}
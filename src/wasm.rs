@@ -0,0 +1,497 @@
+//! Support for modules compiled to WebAssembly, available with the `wasm` feature.
+//!
+//! A module whose `location` points to a `.wasm` file is executed by an interpreter instead of
+//! being loaded as a native library: the module can only access its own linear memory and the
+//! functions imported from the `mammoth` namespace, so that untrusted modules cannot crash or
+//! take over the server. Every call into the module is limited to `FUEL_PER_CALL` units of fuel
+//! (roughly, executed instructions) and the memory of the module to `MEMORY_LIMIT` bytes.
+//!
+//! # Interface
+//! Strings are passed as a pointer into the memory of the module and a length, both `i32`.
+//!
+//! The module must export:
+//! - `memory`, its linear memory;
+//! - `mammoth_abi_version() -> i32`, returning `WASM_ABI_VERSION`;
+//! - `mammoth_alloc(len: i32) -> i32`, allocating `len` bytes in which the server writes a string;
+//! - `mammoth_construct(config_ptr: i32, config_len: i32) -> i32`, constructing the module given
+//!   its configuration, host and environment serialized as TOML (see `abi::encode_context`) and
+//!   returning zero on success.
+//!
+//! The module may export the lifecycle hooks, which are otherwise no-ops:
+//! - `mammoth_on_load()`, `mammoth_on_start()` and `mammoth_on_shutdown()`;
+//! - `mammoth_on_validation() -> i32`, returning zero if the configuration is valid;
+//! - `mammoth_on_config_reload(config_ptr: i32, config_len: i32)`;
+//! - `mammoth_metadata()`, writing the metadata of the module serialized as TOML.
+//!
+//! The server provides the following imports:
+//! - `mammoth.log(severity: i32, ptr: i32, len: i32)`, logging a message, where the severity goes
+//!   from `0` (debug) to `4` (critical);
+//! - `mammoth.write(ptr: i32, len: i32)`, writing the output of the current call, i.e. the error
+//!   message of `mammoth_construct` and `mammoth_on_validation` or the metadata.
+//!
+//! Strings outside of the memory of the module trap the call. The messages and the output of a
+//! single call are limited to `MAX_BUFFERED` bytes each: further messages are discarded and the
+//! output is truncated.
+//!
+//! WebAssembly modules do not have access to the message bus (see `bus`) yet.
+
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use toml::Value;
+use wasmi::{Caller, Config, Engine, Extern, Instance, Linker, Store, StoreLimits, StoreLimitsBuilder};
+use wasmi::core::Trap;
+
+use crate::MammothInterface;
+use crate::abi;
+use crate::config::HostIdentifier;
+use crate::diagnostics::{AsyncLoggerReference, Log, Logger};
+use crate::error::Error;
+use crate::error::severity::Severity;
+use crate::metadata::ModuleMetadata;
+
+/// Version of the interface between the server and the WebAssembly modules.
+pub const WASM_ABI_VERSION: i32 = 1;
+/// Fuel available to every call into a module.
+pub const FUEL_PER_CALL: u64 = 100_000_000;
+/// Maximum size, in bytes, of the linear memory of a module.
+pub const MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+/// Maximum size, in bytes, of the messages and of the output buffered during a single call.
+pub const MAX_BUFFERED: usize = 1024 * 1024;
+
+struct HostState {
+    limits: StoreLimits,
+    output: String,
+    events: Vec<(Severity, String)>,
+    events_size: usize,
+    discarded: usize
+}
+
+struct Runtime {
+    store: Store<HostState>,
+    instance: Instance
+}
+
+impl Runtime {
+    fn new(path: &Path) -> Result<Runtime, Error> {
+        let bytes = fs::read(path)?;
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = wasmi::Module::new(&engine, &bytes[..]).map_err(wasm_error)?;
+
+        let state = HostState {
+            limits: StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build(),
+            output: String::new(),
+            events: Vec::new(),
+            events_size: 0,
+            discarded: 0
+        };
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+
+        let mut linker: Linker<HostState> = Linker::new(&engine);
+        linker.func_wrap("mammoth", "log", |mut caller: Caller<'_, HostState>, sev: i32, ptr: i32, len: i32| {
+            let remaining = MAX_BUFFERED.saturating_sub(caller.data().events_size);
+            let desc = read_string(&caller, ptr, len, remaining)?;
+            let state = caller.data_mut();
+            if len as u32 as usize > remaining {
+                state.discarded += 1;
+            } else {
+                state.events_size += desc.len();
+                state.events.push((severity(sev), desc));
+            }
+            Ok(())
+        }).map_err(wasm_error)?;
+        linker.func_wrap("mammoth", "write", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let remaining = MAX_BUFFERED.saturating_sub(caller.data().output.len());
+            let s = read_string(&caller, ptr, len, remaining)?;
+            caller.data_mut().output.push_str(&s);
+            Ok(())
+        }).map_err(wasm_error)?;
+
+        store.add_fuel(FUEL_PER_CALL).map_err(wasm_error)?;
+        let instance = linker.instantiate(&mut store, &module).map_err(wasm_error)?
+            .start(&mut store).map_err(wasm_error)?;
+        let mut runtime = Runtime { store, instance };
+
+        let version = runtime.call::<(), i32>("mammoth_abi_version", ())?
+            .ok_or_else(|| Error::Module("missing export 'mammoth_abi_version'".to_owned()))?;
+        if version != WASM_ABI_VERSION {
+            return Err(Error::InvalidModuleAbi(version as u32));
+        }
+
+        Ok(runtime)
+    }
+
+    /// Calls the exported function `name`, if any, with a fresh amount of fuel.
+    fn call<P, R>(&mut self, name: &str, params: P) -> Result<Option<R>, Error>
+        where
+            P: wasmi::WasmParams,
+            R: wasmi::WasmResults
+    {
+        let func = match self.instance.get_typed_func::<P, R>(&self.store, name) {
+            Ok(func) => func,
+            Err(_) if self.instance.get_export(&self.store, name).is_none() => return Ok(None),
+            Err(err) => return Err(wasm_error(err))
+        };
+        self.refuel();
+        self.store.data_mut().output.clear();
+
+        func.call(&mut self.store, params)
+            .map(Some)
+            .map_err(wasm_error)
+    }
+
+    /// Writes `s` into a buffer allocated by the module, returning its pointer and length.
+    fn write_string(&mut self, s: &str) -> Result<(i32, i32), Error> {
+        if s.is_empty() {
+            return Ok((0, 0));
+        }
+        let len = s.len() as i32;
+        let ptr = self.call::<i32, i32>("mammoth_alloc", len)?
+            .ok_or_else(|| Error::Module("missing export 'mammoth_alloc'".to_owned()))?;
+        let memory = self.instance.get_memory(&self.store, "memory")
+            .ok_or_else(|| Error::Module("missing export 'memory'".to_owned()))?;
+        memory.write(&mut self.store, ptr as u32 as usize, s.as_bytes()).map_err(wasm_error)?;
+
+        Ok((ptr, len))
+    }
+
+    fn refuel(&mut self) {
+        let remaining = self.store.consume_fuel(0).unwrap_or(0);
+        if remaining < FUEL_PER_CALL {
+            let _ = self.store.add_fuel(FUEL_PER_CALL - remaining);
+        }
+    }
+
+    fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.store.data_mut().output)
+    }
+
+    fn take_events(&mut self) -> Vec<(Severity, String)> {
+        let state = self.store.data_mut();
+        let mut events = std::mem::take(&mut state.events);
+        if state.discarded > 0 {
+            let desc = format!("Discarded {} messages of the WebAssembly module exceeding {} bytes.", state.discarded, MAX_BUFFERED);
+            events.push((Severity::Warning, desc));
+        }
+        state.events_size = 0;
+        state.discarded = 0;
+
+        events
+    }
+}
+
+/// Instance of a module compiled to WebAssembly, seen by the server as a `MammothInterface`.
+pub struct WasmModule {
+    runtime: Mutex<Runtime>,
+    logger: Option<AsyncLoggerReference>
+}
+
+impl WasmModule {
+    /// Constructs the module at `path` given its configuration.
+    pub fn new<P>(path: P, config: Option<&Value>) -> Result<WasmModule, Error>
+        where
+            P: AsRef<Path>
+    {
+        WasmModule::for_host(path, config, None)
+    }
+    /// Constructs the module at `path` given its configuration and the host it is constructed
+    /// for, if any.
+    pub fn for_host<P>(path: P, config: Option<&Value>, host: Option<&HostIdentifier>) -> Result<WasmModule, Error>
+        where
+            P: AsRef<Path>
+    {
+        WasmModule::with_context(path, config, host, None)
+    }
+    /// Constructs the module at `path` given its configuration, the host it is constructed for,
+    /// if any, and its environment (see `abi::current_environment`).
+    pub fn with_context<P>(path: P, config: Option<&Value>, host: Option<&HostIdentifier>, environment: Option<&Value>) -> Result<WasmModule, Error>
+        where
+            P: AsRef<Path>
+    {
+        let mut runtime = Runtime::new(path.as_ref())?;
+        let (ptr, len) = runtime.write_string(&abi::encode_context(config, host, environment))?;
+        let status = runtime.call::<(i32, i32), i32>("mammoth_construct", (ptr, len))?
+            .ok_or_else(|| Error::Module("missing export 'mammoth_construct'".to_owned()))?;
+        if status != 0 {
+            let message = runtime.take_output();
+            return Err(Error::Module(if message.is_empty() { "module construction failed".to_owned() } else { message }));
+        }
+
+        Ok(WasmModule {
+            runtime: Mutex::new(runtime),
+            logger: None
+        })
+    }
+
+    /// Obtains the metadata of the module at `path`, without constructing it.
+    pub fn metadata<P>(path: P) -> Result<ModuleMetadata, Error>
+        where
+            P: AsRef<Path>
+    {
+        let mut runtime = Runtime::new(path.as_ref())?;
+        runtime.call::<(), ()>("mammoth_metadata", ())?
+            .ok_or_else(|| Error::Module("missing export 'mammoth_metadata'".to_owned()))?;
+
+        Ok(toml::from_str(&runtime.take_output())?)
+    }
+
+    /// Calls the hook `name`, logging the messages of the module and any failure.
+    fn hook<P>(&self, name: &str, params: P)
+        where
+            P: wasmi::WasmParams
+    {
+        let mut runtime = self.runtime.lock().unwrap();
+        let result = runtime.call::<P, ()>(name, params);
+        for (sev, desc) in runtime.take_events() {
+            self.log(sev, &desc);
+        }
+        if let Err(err) = result {
+            self.log(Severity::Error, &format!("WebAssembly module failed in '{}': {}.", name, err));
+        }
+    }
+}
+
+impl Log for WasmModule {
+    fn register_logger(&mut self, logger: AsyncLoggerReference) {
+        self.logger = Some(logger);
+    }
+
+    fn retrieve_logger(&self) -> Option<AsyncLoggerReference> {
+        self.logger.clone()
+    }
+}
+
+impl MammothInterface for WasmModule {
+    fn on_load(&self) {
+        self.hook("mammoth_on_load", ());
+    }
+
+    fn on_validation(&self, logger: &mut dyn Logger) -> Result<(), Error> {
+        let mut runtime = self.runtime.lock().unwrap();
+        let result = runtime.call::<(), i32>("mammoth_on_validation", ());
+        for (sev, desc) in runtime.take_events() {
+            logger.log(sev, &desc);
+        }
+
+        match result? {
+            Some(0) | None => Ok(()),
+            Some(_) => {
+                let message = runtime.take_output();
+                Err(Error::Module(if message.is_empty() { "validation failed".to_owned() } else { message }))
+            }
+        }
+    }
+
+    fn on_start(&self) {
+        self.hook("mammoth_on_start", ());
+    }
+
+    fn on_config_reload(&self, config: &Value) {
+        let written = self.runtime.lock().unwrap().write_string(&abi::encode_config(Some(config)));
+        match written {
+            Ok(params) => self.hook("mammoth_on_config_reload", params),
+            Err(err) => self.log(Severity::Error, &format!("WebAssembly module failed in 'mammoth_on_config_reload': {}.", err))
+        }
+    }
+
+    fn on_shutdown(&self) {
+        self.hook("mammoth_on_shutdown", ());
+    }
+}
+
+/// Reads the string at `ptr` of length `len` from the memory of the module, truncated to `max`
+/// bytes; the whole string must lie inside the memory, which is checked before copying anything.
+#[doc(hidden)]
+fn read_string(caller: &Caller<'_, HostState>, ptr: i32, len: i32, max: usize) -> Result<String, Trap> {
+    let memory = caller.get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Trap::new("missing export 'memory'"))?;
+    let start = ptr as u32 as usize;
+    let end = start.checked_add(len as u32 as usize)
+        .ok_or_else(|| Trap::new("string out of bounds"))?;
+    let buffer = memory.data(caller).get(start..end)
+        .ok_or_else(|| Trap::new("string out of bounds"))?;
+    let buffer = &buffer[..buffer.len().min(max)];
+
+    Ok(String::from_utf8_lossy(buffer).into_owned())
+}
+
+#[doc(hidden)]
+fn severity(code: i32) -> Severity {
+    match code {
+        0 => Severity::Debug,
+        1 => Severity::Information,
+        2 => Severity::Warning,
+        3 => Severity::Error,
+        _ => Severity::Critical
+    }
+}
+
+#[doc(hidden)]
+fn wasm_error<E: std::fmt::Display>(err: E) -> Error {
+    Error::Module(format!("WebAssembly: {}", err))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use std::sync::{Arc, RwLock};
+
+    use semver::Version;
+    use tempfile::NamedTempFile;
+    use toml::Value;
+
+    use crate::MammothInterface;
+    use crate::config::Module;
+    use crate::diagnostics::{Log, Logger};
+    use crate::error::Error;
+    use crate::error::event::Event;
+    use crate::error::severity::Severity;
+    use crate::loaded::library::LoadedModuleSet;
+    use crate::metadata::Capability;
+    use crate::wasm::{MAX_BUFFERED, WasmModule};
+
+    const MODULE: &str = r#"
+    (module
+        (import "mammoth" "log" (func $log (param i32 i32 i32)))
+        (import "mammoth" "write" (func $write (param i32 i32)))
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+        (global $config_len (mut i32) (i32.const 0))
+        (data (i32.const 0) "started")
+        (data (i32.const 16) "invalid config")
+        (data (i32.const 32) "name = \"mod_wasm\"\ncapabilities = [\"validation\", \"start\"]\nversion = \"0.1.0\"\n")
+        (func (export "mammoth_abi_version") (result i32) i32.const 1)
+        (func (export "mammoth_alloc") (param $len i32) (result i32)
+            global.get $heap
+            global.get $heap local.get $len i32.add global.set $heap)
+        (func (export "mammoth_construct") (param i32 i32) (result i32)
+            local.get 1 global.set $config_len
+            i32.const 0)
+        (func (export "mammoth_on_validation") (result i32)
+            global.get $config_len i32.eqz
+            if (result i32)
+                i32.const 0
+            else
+                i32.const 16 i32.const 14 call $write
+                i32.const 1
+            end)
+        (func (export "mammoth_on_start")
+            i32.const 1 i32.const 0 i32.const 7 call $log)
+        (func (export "mammoth_metadata")
+            i32.const 32 i32.const 75 call $write)
+        (func (export "mammoth_on_shutdown")
+            (loop $forever br $forever)))
+    "#;
+
+    const FLOODING_MODULE: &str = r#"
+    (module
+        (import "mammoth" "log" (func $log (param i32 i32 i32)))
+        (import "mammoth" "write" (func $write (param i32 i32)))
+        (memory (export "memory") 1)
+        (func (export "mammoth_abi_version") (result i32) i32.const 1)
+        (func (export "mammoth_construct") (param i32 i32) (result i32) i32.const 0)
+        (func (export "mammoth_on_load")
+            i32.const 1 i32.const 0 i32.const 0x7fffffff call $log)
+        (func (export "mammoth_on_start")
+            (local $i i32)
+            (loop $again
+                i32.const 0 i32.const 0 i32.const 65536 call $log
+                local.get $i i32.const 1 i32.add local.tee $i
+                i32.const 20 i32.lt_u br_if $again))
+        (func (export "mammoth_on_validation") (result i32)
+            (local $i i32)
+            (loop $again
+                i32.const 0 i32.const 65536 call $write
+                local.get $i i32.const 1 i32.add local.tee $i
+                i32.const 20 i32.lt_u br_if $again)
+            i32.const 1))
+    "#;
+
+    fn module_file() -> NamedTempFile {
+        wasm_file(MODULE)
+    }
+
+    fn wasm_file(source: &str) -> NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(".wasm").tempfile().unwrap();
+        file.write_all(&wat::parse_str(source).unwrap()).unwrap();
+        file
+    }
+
+    #[test]
+    /// Tests the lifecycle hooks of a WebAssembly module.
+    fn test_wasm_module() {
+        let file = module_file();
+        let mut module = WasmModule::new(file.path(), None).unwrap();
+        let events: Arc<RwLock<dyn Logger>> = Arc::new(RwLock::new(Vec::<Event>::new()));
+        module.register_logger(events.clone());
+
+        let mut validation: Vec<Event> = Vec::new();
+        assert!(module.on_validation(&mut validation).is_ok());
+        module.on_start();
+        module.on_shutdown();
+
+        let metadata = WasmModule::metadata(file.path()).unwrap();
+        assert_eq!(metadata.name(), "mod_wasm");
+        assert!(metadata.has_capability(Capability::Start));
+
+        let invalid = WasmModule::new(file.path(), Some(&Value::from("test_error"))).unwrap();
+        match invalid.on_validation(&mut validation) {
+            Err(Error::Module(message)) => assert_eq!(message, "invalid config"),
+            _ => panic!("Should be 'Module' error generated in module validation.")
+        }
+    }
+
+    #[test]
+    /// Tests that the strings passed by a module are bounded by its memory and by `MAX_BUFFERED`.
+    fn test_wasm_oversized_strings() {
+        let file = wasm_file(FLOODING_MODULE);
+        let mut module = WasmModule::new(file.path(), None).unwrap();
+        let events = Arc::new(RwLock::new(Vec::<Event>::new()));
+        module.register_logger(events.clone());
+
+        // A string larger than the memory traps the call instead of being allocated.
+        module.on_load();
+        assert_eq!(events.read().unwrap().len(), 1);
+        let event = events.read().unwrap()[0].to_string();
+        assert!(event.contains(&format!("[{}]", Severity::Error)));
+        assert!(event.contains("out of bounds"));
+
+        module.on_start();
+        let events = events.read().unwrap();
+        assert_eq!(events.len(), 1 + MAX_BUFFERED / 65536 + 1);
+        assert!(events.last().unwrap().to_string().contains(&format!("[{}]", Severity::Warning)));
+
+        let mut validation: Vec<Event> = Vec::new();
+        match module.on_validation(&mut validation) {
+            Err(Error::Module(message)) => assert_eq!(message.len(), MAX_BUFFERED),
+            _ => panic!("Should be 'Module' error generated in module validation.")
+        }
+    }
+
+    #[test]
+    /// Tests that WebAssembly modules are loaded through their location.
+    fn test_wasm_load() {
+        let file = module_file();
+        let mut module = Module::new("mod_wasm").unwrap();
+        module.set_location(file.path());
+        assert!(module.is_wasm());
+        assert_eq!(module.inspect("./").unwrap().name(), "mod_wasm");
+        assert_eq!(module.version("./").unwrap(), Version::new(0, 1, 0));
+
+        let mut lms = LoadedModuleSet::new("./");
+        module.load_into(&mut lms).unwrap();
+        lms.start();
+        assert_eq!(lms.module_names(), vec!["mod_wasm"]);
+        assert!(lms.metadata("mod_wasm").is_some());
+        assert!(lms.library_paths().is_empty());
+
+        // `on_shutdown` never returns, but runs out of fuel.
+        lms.unload("mod_wasm").unwrap();
+        assert!(lms.module_names().is_empty());
+    }
+}